@@ -0,0 +1,191 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, toml_to_field_string, AppConfig, ConfigScope, ENV_FIELD_MAP};
+use crate::preset::{self, Preset};
+
+/// Version of the bundle format written by `cgen config export`
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A shareable snapshot of the configuration: settings that differ from the defaults, keyed
+/// like the global config, and optionally the saved presets
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Bundle {
+    pub bundle_version: u32,
+    #[serde(default)]
+    pub config: toml::Table,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<Preset>,
+}
+
+/// A value in the bundle that differs from what is already configured
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Setting key or `preset "<name>"`
+    pub label: String,
+    pub current: String,
+    pub imported: String,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub settings: usize,
+    pub presets: usize,
+    pub kept: usize,
+}
+
+/// Snapshot `cfg` (and `presets`, if given). API keys are left out unless `include_keys`.
+pub fn export(cfg: &AppConfig, presets: Option<&[Preset]>, include_keys: bool) -> Result<Bundle> {
+    let toml::Value::Table(mut table) =
+        toml::Value::try_from(cfg).context("Failed to serialize config")?
+    else {
+        bail!("Failed to serialize config");
+    };
+    let toml::Value::Table(defaults) =
+        toml::Value::try_from(AppConfig::default()).context("Failed to serialize config")?
+    else {
+        bail!("Failed to serialize config");
+    };
+    table.remove("config_version");
+    table.retain(|key, value| defaults.get(key) != Some(value));
+    if !include_keys {
+        table.remove("api_key");
+    }
+
+    let presets = presets
+        .unwrap_or_default()
+        .iter()
+        .map(|p| {
+            let mut p = p.clone();
+            p.id = 0; // IDs are reassigned on import
+            if !include_keys {
+                p.fields.api_key = String::new();
+            }
+            p
+        })
+        .collect();
+
+    Ok(Bundle {
+        bundle_version: BUNDLE_VERSION,
+        config: table,
+        presets,
+    })
+}
+
+pub fn to_toml(bundle: &Bundle) -> Result<String> {
+    toml::to_string_pretty(bundle).context("Failed to serialize bundle")
+}
+
+/// Parse a bundle, rejecting files written by a newer cgen and settings this build does not know
+pub fn parse(content: &str) -> Result<Bundle> {
+    let bundle: Bundle = toml::from_str(content).context("Failed to parse bundle")?;
+    if bundle.bundle_version > BUNDLE_VERSION {
+        bail!(
+            "Bundle version {} is newer than this cgen supports ({BUNDLE_VERSION}); update cgen first",
+            bundle.bundle_version
+        );
+    }
+    for key in bundle.config.keys() {
+        if !ENV_FIELD_MAP.iter().any(|(_, field)| field == key) {
+            bail!("Unknown setting `{key}` in bundle");
+        }
+    }
+    Ok(bundle)
+}
+
+/// Write a bundle into the global config and presets file. Settings and presets that are new
+/// or identical are applied directly; for each conflict `resolve` decides whether the imported
+/// value replaces the current one.
+pub fn import(
+    bundle: &Bundle,
+    mut resolve: impl FnMut(&Conflict) -> Result<bool>,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    let current = config::settings_in(ConfigScope::Global)?;
+
+    for (suffix, field) in ENV_FIELD_MAP {
+        let Some(value) = bundle.config.get(*field) else {
+            continue;
+        };
+        let imported = toml_to_field_string(value);
+        if let Some(existing) = current.get(*suffix) {
+            // The global config stores a credential reference, not the key
+            let existing = if *suffix == "API_KEY" {
+                crate::credentials::resolve(existing)?
+            } else {
+                existing.clone()
+            };
+            if existing == imported {
+                continue;
+            }
+            let conflict = Conflict {
+                label: field.to_string(),
+                current: display_value(suffix, &existing),
+                imported: display_value(suffix, &imported),
+            };
+            if !resolve(&conflict)? {
+                summary.kept += 1;
+                continue;
+            }
+        }
+        config::save_setting(suffix, &imported, ConfigScope::Global)
+            .with_context(|| format!("Invalid value for `{field}` in bundle"))?;
+        summary.settings += 1;
+    }
+
+    if bundle.presets.is_empty() {
+        return Ok(summary);
+    }
+    let mut file = preset::load_presets()?;
+    for imported in &bundle.presets {
+        let mut fields = imported.fields.clone();
+        match file.presets.iter_mut().find(|p| p.name == imported.name) {
+            Some(existing) => {
+                // A bundle exported without keys keeps the key already stored here
+                if fields.api_key.is_empty() {
+                    fields.api_key = existing.fields.api_key.clone();
+                }
+                if existing.fields == fields {
+                    continue;
+                }
+                let conflict = Conflict {
+                    label: format!("preset \"{}\"", imported.name),
+                    current: preset_summary(&existing.fields),
+                    imported: preset_summary(&fields),
+                };
+                if !resolve(&conflict)? {
+                    summary.kept += 1;
+                    continue;
+                }
+                existing.fields = fields;
+            }
+            None => {
+                let id = preset::create_preset(&mut file, Some(imported.name.clone()), fields);
+                preset::set_tags(&mut file, id, imported.tags.clone());
+            }
+        }
+        summary.presets += 1;
+    }
+    if summary.presets > 0 {
+        preset::save_presets(&file)?;
+    }
+    Ok(summary)
+}
+
+fn display_value(suffix: &str, value: &str) -> String {
+    if suffix == "API_KEY" && !value.is_empty() {
+        "********".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn preset_summary(fields: &preset::LlmPresetFields) -> String {
+    let url = if fields.api_url.is_empty() {
+        String::new()
+    } else {
+        format!(" @ {}", fields.api_url)
+    };
+    format!("{}/{}{url}", fields.provider, fields.model)
+}
@@ -1,23 +1,80 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
 use inquire::Select;
+use rusqlite::{params, Connection, TransactionBehavior};
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::config::AppConfig;
+
+/// A commit cgen generated. Entries written before the full message was stored only have
+/// `hash` and `message_preview`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CachedCommit {
     pub hash: String,
+    #[serde(default)]
     pub message_preview: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// ISO 8601, UTC
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub branch: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub provider: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub model: String,
+    /// Fallback preset that generated the message, if the primary provider failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
 }
 
+impl CachedCommit {
+    /// A record for `message` committed as `hash` now
+    pub fn new(hash: &str, message: &str) -> Self {
+        Self {
+            hash: hash.into(),
+            message_preview: message
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(80)
+                .collect(),
+            message: message.into(),
+            timestamp: crate::timestamp::iso8601(crate::timestamp::now_unix()),
+            ..Self::default()
+        }
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..7.min(self.hash.len())]
+    }
+
+    /// Provider and model, with the fallback preset if one was used
+    pub fn generated_by(&self) -> Option<String> {
+        if self.model.is_empty() {
+            return None;
+        }
+        let by = format!("{}/{}", self.provider, self.model);
+        Some(match &self.fallback {
+            Some(name) => format!("{by} (fallback: {name})"),
+            None => by,
+        })
+    }
+}
+
+/// A repository's tracked commits, oldest first
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RepoCache {
     pub repo_path: String,
     pub commits: Vec<CachedCommit>,
 }
 
+/// `index.toml` of the TOML cache used before `history.db`; imported on first use
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CacheIndex {
     pub repos: Vec<CacheIndexEntry>,
@@ -29,7 +86,7 @@ pub struct CacheIndexEntry {
     pub cache_file: String,
 }
 
-fn cache_dir() -> Option<PathBuf> {
+pub(crate) fn cache_dir() -> Option<PathBuf> {
     crate::config::global_config_path().map(|p| {
         p.parent()
             .expect("global config path should have a parent")
@@ -37,220 +94,655 @@ fn cache_dir() -> Option<PathBuf> {
     })
 }
 
-fn repo_path_hash(path: &str) -> String {
-    let mut hasher = DefaultHasher::new();
-    path.hash(&mut hasher);
-    format!("{:016x}", hasher.finish())
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS commits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    message_preview TEXT NOT NULL DEFAULT '',
+    message TEXT NOT NULL DEFAULT '',
+    timestamp TEXT NOT NULL DEFAULT '',
+    branch TEXT NOT NULL DEFAULT '',
+    provider TEXT NOT NULL DEFAULT '',
+    model TEXT NOT NULL DEFAULT '',
+    fallback TEXT
+);
+CREATE INDEX IF NOT EXISTS commits_by_repo ON commits (repo_path, id);
+";
+
+/// Bytes a row takes up, as counted against `ACR_CACHE_MAX_BYTES`
+const ROW_SIZE: &str = "length(CAST(repo_path AS BLOB)) + length(CAST(hash AS BLOB)) \
+    + length(CAST(message_preview AS BLOB)) + length(CAST(message AS BLOB)) \
+    + length(CAST(timestamp AS BLOB)) + length(CAST(branch AS BLOB)) \
+    + length(CAST(provider AS BLOB)) + length(CAST(model AS BLOB)) \
+    + ifnull(length(CAST(fallback AS BLOB)), 0)";
+
+/// Open `history.db`, creating it (and importing a TOML cache left by older versions) if
+/// needed. WAL mode and a busy timeout let several cgen processes write at once.
+fn open_db() -> Result<Connection> {
+    let dir = cache_dir().context("Could not determine cache directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join("history.db");
+    let conn =
+        Connection::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.execute_batch(SCHEMA)
+        .context("Failed to create history database")?;
+    import_toml_cache(&conn, &dir)?;
+    Ok(conn)
 }
 
-fn index_path() -> Option<PathBuf> {
-    cache_dir().map(|d| d.join("index.toml"))
+/// Repo cache files of the TOML cache are named after a 16-hex-digit hash of the repo path
+fn is_repo_cache_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+        && path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|s| s.len() == 16 && s.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
-fn load_index() -> Result<CacheIndex> {
-    let path = match index_path() {
-        Some(p) => p,
-        None => return Ok(CacheIndex::default()),
-    };
-    if !path.exists() {
-        return Ok(CacheIndex::default());
+/// Move the commits of a TOML cache into the database and delete its files
+fn import_toml_cache(conn: &Connection, dir: &Path) -> Result<()> {
+    let index_path = dir.join("index.toml");
+    if !index_path.exists() {
+        return Ok(());
+    }
+    let tx = rusqlite::Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    // Another process may have imported it while this one waited for the lock
+    if !index_path.exists() {
+        return Ok(());
     }
-    let content = std::fs::read_to_string(&path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
-    let idx: CacheIndex =
-        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
-    Ok(idx)
+    let content = std::fs::read_to_string(&index_path)
+        .with_context(|| format!("Failed to read {}", index_path.display()))?;
+    let index: CacheIndex = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", index_path.display()))?;
+    for entry in &index.repos {
+        let path = dir.join(&entry.cache_file);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let cache: RepoCache = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        for commit in &cache.commits {
+            insert_commit(&tx, &entry.repo_path, commit)?;
+        }
+    }
+    tx.commit().context("Failed to import history cache")?;
+
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if is_repo_cache_file(&path) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    std::fs::remove_file(&index_path)
+        .with_context(|| format!("Failed to remove {}", index_path.display()))
 }
 
-fn save_index(index: &CacheIndex) -> Result<()> {
-    let dir = cache_dir().context("Could not determine cache directory")?;
-    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
-    let path = dir.join("index.toml");
-    let content = toml::to_string_pretty(index).context("Failed to serialize cache index")?;
-    let tmp_path = path.with_extension("toml.tmp");
-    std::fs::write(&tmp_path, &content)
-        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
-    std::fs::rename(&tmp_path, &path)
-        .with_context(|| format!("Failed to rename temp file to {}", path.display()))?;
+fn insert_commit(conn: &Connection, repo_path: &str, commit: &CachedCommit) -> Result<()> {
+    conn.execute(
+        "INSERT INTO commits (repo_path, hash, message_preview, message, timestamp, branch, \
+         provider, model, fallback) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            repo_path,
+            commit.hash,
+            commit.message_preview,
+            commit.message,
+            commit.timestamp,
+            commit.branch,
+            commit.provider,
+            commit.model,
+            commit.fallback,
+        ],
+    )
+    .context("Failed to record commit")?;
     Ok(())
 }
 
-fn load_repo_cache(repo_path: &str) -> Result<RepoCache> {
-    let dir = match cache_dir() {
-        Some(d) => d,
-        None => {
-            return Ok(RepoCache {
-                repo_path: repo_path.into(),
-                commits: Vec::new(),
+/// A commit from the `hash` … `fallback` columns, starting at column `first`
+fn commit_from_row(row: &rusqlite::Row, first: usize) -> rusqlite::Result<CachedCommit> {
+    Ok(CachedCommit {
+        hash: row.get(first)?,
+        message_preview: row.get(first + 1)?,
+        message: row.get(first + 2)?,
+        timestamp: row.get(first + 3)?,
+        branch: row.get(first + 4)?,
+        provider: row.get(first + 5)?,
+        model: row.get(first + 6)?,
+        fallback: row.get(first + 7)?,
+    })
+}
+
+/// A repository's tracked commits, oldest first
+pub fn load_repo_cache(repo_path: &str) -> Result<RepoCache> {
+    let conn = open_db()?;
+    let mut stmt = conn.prepare(
+        "SELECT hash, message_preview, message, timestamp, branch, provider, model, fallback \
+         FROM commits WHERE repo_path = ?1 ORDER BY id",
+    )?;
+    let commits = stmt
+        .query_map([repo_path], |row| commit_from_row(row, 0))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read commit history")?;
+    Ok(RepoCache {
+        repo_path: repo_path.into(),
+        commits,
+    })
+}
+
+/// Repositories with tracked commits, most recently used first
+pub fn tracked_repos() -> Result<Vec<String>> {
+    let conn = open_db()?;
+    let mut stmt =
+        conn.prepare("SELECT repo_path FROM commits GROUP BY repo_path ORDER BY MAX(id) DESC")?;
+    let repos = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .context("Failed to read commit history")?;
+    Ok(repos)
+}
+
+/// A tracked repository with its most recent commit
+#[derive(Debug, Clone)]
+pub struct RepoSummary {
+    pub repo_path: String,
+    pub commits: usize,
+    pub last: CachedCommit,
+}
+
+/// Every tracked repository with its latest commit, most recently used first
+pub fn repo_summaries() -> Result<Vec<RepoSummary>> {
+    let conn = open_db()?;
+    let mut stmt = conn.prepare(
+        "SELECT c.repo_path, n.count, c.hash, c.message_preview, c.message, c.timestamp, \
+         c.branch, c.provider, c.model, c.fallback FROM commits c \
+         JOIN (SELECT COUNT(*) AS count, MAX(id) AS last FROM commits GROUP BY repo_path) n \
+         ON c.id = n.last ORDER BY c.id DESC",
+    )?;
+    let repos = stmt
+        .query_map([], |row| {
+            Ok(RepoSummary {
+                repo_path: row.get(0)?,
+                commits: row.get::<_, i64>(1)? as usize,
+                last: commit_from_row(row, 2)?,
             })
-        }
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read commit history")?;
+    Ok(repos)
+}
+
+pub fn record_commit(repo_path: &str, commit: CachedCommit) -> Result<()> {
+    insert_commit(&open_db()?, repo_path, &commit)
+}
+
+/// Repo-relative path of the shared, committed history file (`ACR_REPO_HISTORY`)
+pub const REPO_HISTORY_FILE: &str = ".cgen/history.toml";
+
+/// A generated commit as recorded in `.cgen/history.toml`. Only what a reviewer needs to tell
+/// which commits were generated and by what; never prompts, diffs or keys.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RepoHistoryEntry {
+    pub hash: String,
+    pub subject: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub branch: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+/// Contents of `.cgen/history.toml`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RepoHistoryFile {
+    #[serde(default)]
+    pub commits: Vec<RepoHistoryEntry>,
+}
+
+/// Append `commit` to the repository's `.cgen/history.toml`. Entries are appended rather than
+/// rewritten so concurrent branches merge cleanly.
+pub fn append_repo_history(repo_root: &str, commit: &CachedCommit) -> Result<()> {
+    let path = Path::new(repo_root).join(REPO_HISTORY_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let entry = RepoHistoryFile {
+        commits: vec![RepoHistoryEntry {
+            hash: commit.hash.clone(),
+            subject: commit.message_preview.clone(),
+            timestamp: commit.timestamp.clone(),
+            branch: commit.branch.clone(),
+            provider: commit.provider.clone(),
+            model: commit.model.clone(),
+            fallback: commit.fallback.clone(),
+        }],
     };
-    let hash = repo_path_hash(repo_path);
-    let path = dir.join(format!("{hash}.toml"));
-    if !path.exists() {
-        return Ok(RepoCache {
-            repo_path: repo_path.into(),
-            commits: Vec::new(),
-        });
-    }
-    let content = std::fs::read_to_string(&path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
-    let cache: RepoCache =
-        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
-    Ok(cache)
-}
-
-fn save_repo_cache(cache: &RepoCache) -> Result<()> {
-    let dir = cache_dir().context("Could not determine cache directory")?;
-    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
-    let hash = repo_path_hash(&cache.repo_path);
-    let path = dir.join(format!("{hash}.toml"));
-    let content = toml::to_string_pretty(cache).context("Failed to serialize repo cache")?;
-    let tmp_path = path.with_extension("toml.tmp");
-    std::fs::write(&tmp_path, &content)
-        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
-    std::fs::rename(&tmp_path, &path)
-        .with_context(|| format!("Failed to rename temp file to {}", path.display()))?;
-    Ok(())
+    let block = toml::to_string(&entry).context("Failed to serialize history entry")?;
+    let separator = if file_is_empty(&path) { "" } else { "\n" };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(format!("{separator}{block}").as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn file_is_empty(path: &Path) -> bool {
+    std::fs::metadata(path).map_or(true, |m| m.len() == 0)
 }
 
-pub fn record_commit(repo_path: &str, hash: &str, message_preview: &str) -> Result<()> {
-    let mut index = load_index()?;
-    let cache_file = format!("{}.toml", repo_path_hash(repo_path));
+/// Bounds on the history cache; 0 means unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub max_commits_per_repo: usize,
+    pub max_bytes: u64,
+}
 
-    if !index.repos.iter().any(|e| e.repo_path == repo_path) {
-        index.repos.push(CacheIndexEntry {
-            repo_path: repo_path.into(),
-            cache_file,
-        });
-        save_index(&index)?;
+impl CacheLimits {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            max_commits_per_repo: cfg.cache_max_commits,
+            max_bytes: cfg.cache_max_bytes as u64,
+        }
     }
+}
 
-    let mut cache = load_repo_cache(repo_path)?;
-    cache.commits.push(CachedCommit {
-        hash: hash.into(),
-        message_preview: message_preview.into(),
-    });
-    save_repo_cache(&cache)?;
-    Ok(())
+/// What a `prune` removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    pub repos_removed: usize,
+    pub commits_removed: usize,
+    pub bytes_freed: u64,
+}
+
+fn history_size(conn: &Connection) -> Result<u64> {
+    let size: i64 = conn.query_row(
+        &format!("SELECT ifnull(SUM({ROW_SIZE}), 0) FROM commits"),
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(size as u64)
+}
+
+fn count(conn: &Connection, sql: &str) -> Result<usize> {
+    let n: i64 = conn.query_row(sql, [], |row| row.get(0))?;
+    Ok(n as usize)
+}
+
+/// Bring the history cache within `limits`: drop each repo's oldest commits past the per-repo
+/// cap, then, while the cache is over the size cap, drop the least recently used repo (or the
+/// oldest commits of the last one left). With `drop_missing_repos`, repos whose directory no
+/// longer exists are removed first.
+pub fn prune(limits: &CacheLimits, drop_missing_repos: bool) -> Result<PruneSummary> {
+    let conn = open_db()?;
+    let tx = rusqlite::Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+    let repos_before = count(&tx, "SELECT COUNT(DISTINCT repo_path) FROM commits")?;
+    let commits_before = count(&tx, "SELECT COUNT(*) FROM commits")?;
+    let size_before = history_size(&tx)?;
+
+    if drop_missing_repos {
+        let repos = {
+            let mut stmt = tx.prepare("SELECT DISTINCT repo_path FROM commits")?;
+            let repos = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            repos
+        };
+        for repo in repos.iter().filter(|r| !Path::new(r).exists()) {
+            tx.execute("DELETE FROM commits WHERE repo_path = ?1", [repo])?;
+        }
+    }
+
+    if limits.max_commits_per_repo > 0 {
+        tx.execute(
+            "DELETE FROM commits WHERE id IN (SELECT id FROM (SELECT id, ROW_NUMBER() OVER \
+             (PARTITION BY repo_path ORDER BY id DESC) AS n FROM commits) WHERE n > ?1)",
+            [limits.max_commits_per_repo as i64],
+        )?;
+    }
+
+    if limits.max_bytes > 0 {
+        while history_size(&tx)? > limits.max_bytes {
+            if count(&tx, "SELECT COUNT(DISTINCT repo_path) FROM commits")? > 1 {
+                tx.execute(
+                    "DELETE FROM commits WHERE repo_path = (SELECT repo_path FROM commits \
+                     GROUP BY repo_path ORDER BY MAX(id) LIMIT 1)",
+                    [],
+                )?;
+                continue;
+            }
+            // A single repo over the cap keeps the newest commits that fit
+            let mut stmt = tx.prepare(&format!("SELECT {ROW_SIZE} FROM commits ORDER BY id"))?;
+            let sizes = stmt
+                .query_map([], |row| row.get::<_, i64>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut excess = history_size(&tx)? - limits.max_bytes;
+            let drop = sizes
+                .iter()
+                .take_while(|size| {
+                    let more = excess > 0;
+                    excess = excess.saturating_sub(**size as u64);
+                    more
+                })
+                .count();
+            tx.execute(
+                "DELETE FROM commits WHERE id IN (SELECT id FROM commits ORDER BY id LIMIT ?1)",
+                [drop as i64],
+            )?;
+        }
+    }
+
+    let summary = PruneSummary {
+        repos_removed: repos_before - count(&tx, "SELECT COUNT(DISTINCT repo_path) FROM commits")?,
+        commits_removed: commits_before - count(&tx, "SELECT COUNT(*) FROM commits")?,
+        bytes_freed: size_before - history_size(&tx)?,
+    };
+    tx.commit().context("Failed to prune history cache")?;
+    // Compacting rewrites the whole file, so only the explicit `cgen cache prune` does it
+    if summary.commits_removed > 0 && drop_missing_repos {
+        conn.execute_batch("VACUUM")
+            .context("Failed to compact history database")?;
+    }
+    Ok(summary)
+}
+
+/// Delete the whole commit history cache. Usage and metrics files are kept. Returns how many
+/// repositories had history.
+pub fn clear() -> Result<usize> {
+    let conn = open_db()?;
+    let repos = count(&conn, "SELECT COUNT(DISTINCT repo_path) FROM commits")?;
+    conn.execute("DELETE FROM commits", [])
+        .context("Failed to clear history cache")?;
+    conn.execute_batch("VACUUM")
+        .context("Failed to compact history database")?;
+    Ok(repos)
 }
 
 pub fn get_head_hash() -> Result<String> {
-    let output = std::process::Command::new("git")
-        .args(["rev-parse", "HEAD"])
-        .output()
-        .context("Failed to run git rev-parse HEAD")?;
-    if !output.status.success() {
-        anyhow::bail!(
-            "git rev-parse HEAD failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+    crate::git::head_hash()
+}
+
+/// A tracked commit and the repository it was made in
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub repo_path: String,
+    pub commit: CachedCommit,
+}
+
+/// Narrows the commits `cgen history` lists
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Case-insensitive text the hash or message (or, across repos, the repo path) contains
+    pub text: Option<String>,
+    /// Branch the commit was made on
+    pub branch: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none() && self.branch.is_none()
     }
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-fn show_repo_commits(cache: &RepoCache) -> Result<()> {
-    if cache.commits.is_empty() {
+/// Tracked commits matching `filter`, oldest first. With `repo_path` only that repo is
+/// searched; otherwise repo paths match the text too.
+pub fn search(filter: &HistoryFilter, repo_path: Option<&str>) -> Result<Vec<HistoryEntry>> {
+    let conn = open_db()?;
+    let mut stmt = conn.prepare(
+        "SELECT repo_path, hash, message_preview, message, timestamp, branch, provider, model, \
+         fallback FROM commits \
+         WHERE (?2 IS NULL OR repo_path = ?2) \
+         AND (?3 IS NULL OR branch = ?3) \
+         AND (?1 IS NULL OR instr(lower(hash), lower(?1)) > 0 \
+         OR instr(lower(message_preview), lower(?1)) > 0 \
+         OR instr(lower(message), lower(?1)) > 0 \
+         OR (?2 IS NULL AND instr(lower(repo_path), lower(?1)) > 0)) \
+         ORDER BY id",
+    )?;
+    let entries = stmt
+        .query_map(params![filter.text, repo_path, filter.branch], |row| {
+            Ok(HistoryEntry {
+                repo_path: row.get(0)?,
+                commit: commit_from_row(row, 1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to search commit history")?;
+    Ok(entries)
+}
+
+fn show_repo_commits(cache: &RepoCache) -> Result<Option<String>> {
+    let entries: Vec<HistoryEntry> = cache
+        .commits
+        .iter()
+        .map(|commit| HistoryEntry {
+            repo_path: cache.repo_path.clone(),
+            commit: commit.clone(),
+        })
+        .collect();
+    show_commits(&entries, false)
+}
+
+const SHOW_COMMIT: &str = "Show full commit";
+const REUSE_MESSAGE: &str = "Commit staged changes with this message";
+const COPY_MESSAGE: &str = "Copy message to clipboard";
+const BACK: &str = "Back";
+
+/// Pick from `entries`, newest first, and act on the chosen commit; `show_repo` prefixes each
+/// entry with its repository. Returns the message to reuse for a new commit, if one was chosen.
+fn show_commits(entries: &[HistoryEntry], show_repo: bool) -> Result<Option<String>> {
+    if entries.is_empty() {
         println!("{}", "No tracked commits for this repository.".dimmed());
-        return Ok(());
+        return Ok(None);
     }
 
     loop {
-        let mut options: Vec<String> = cache
-            .commits
+        let mut options: Vec<String> = entries
             .iter()
             .rev()
-            .map(|c| {
-                let short = if c.hash.len() >= 7 {
-                    &c.hash[..7]
-                } else {
-                    &c.hash
-                };
-                format!("{} {}", short, c.message_preview)
+            .map(|entry| {
+                let c = &entry.commit;
+                let mut line = c.short_hash().to_string();
+                if show_repo {
+                    line = format!("{} {line}", entry.repo_path);
+                }
+                if let Some(date) = c.timestamp.get(..10) {
+                    line = format!("{line} {date}");
+                }
+                if !c.branch.is_empty() {
+                    line = format!("{line} [{}]", c.branch);
+                }
+                format!("{line} {}", c.message_preview)
             })
             .collect();
-        options.push("Back".into());
+        options.push(BACK.into());
 
-        let choice = match Select::new("Select commit to view:", options.clone()).prompt() {
+        let choice = match Select::new("Select commit to view:", options)
+            .with_scorer(&crate::ui::fuzzy_score)
+            .raw_prompt()
+        {
             Ok(c) => c,
             Err(_) => break,
         };
 
-        if choice == "Back" {
+        if choice.index == entries.len() {
             break;
         }
 
-        let idx = options.iter().position(|o| o == &choice).unwrap();
-        let commit = &cache.commits[cache.commits.len() - 1 - idx];
+        let entry = &entries[entries.len() - 1 - choice.index];
+        print_commit_details(&entry.commit);
+        print_commit_status(entry);
+        if let Some(message) = commit_actions(entry)? {
+            return Ok(Some(message));
+        }
+    }
+    Ok(None)
+}
 
-        let status = std::process::Command::new("git")
-            .args(["show", &commit.hash])
-            .status();
+/// Offer what to do with a selected commit until the user goes back
+fn commit_actions(entry: &HistoryEntry) -> Result<Option<String>> {
+    let commit = &entry.commit;
+    // Entries recorded before full messages were stored only have the subject
+    let message = if commit.message.is_empty() {
+        &commit.message_preview
+    } else {
+        &commit.message
+    };
+    loop {
+        let options = vec![SHOW_COMMIT, REUSE_MESSAGE, COPY_MESSAGE, BACK];
+        match Select::new("What do you want to do?", options).prompt() {
+            Ok(SHOW_COMMIT) => show_commit(entry),
+            Ok(REUSE_MESSAGE) => return Ok(Some(message.clone())),
+            Ok(COPY_MESSAGE) => match crate::ui::copy_to_clipboard(message) {
+                Ok(()) => println!("  {}", "Message copied to clipboard.".green()),
+                Err(e) => println!("  {} {:#}", "error:".red().bold(), e),
+            },
+            _ => return Ok(None),
+        }
+    }
+}
 
-        match status {
-            Ok(s) if !s.success() => {
-                println!(
-                    "  {} Could not show commit {} (it may have been garbage collected)",
-                    "error:".red().bold(),
-                    &commit.hash[..7.min(commit.hash.len())]
-                );
-            }
-            Err(e) => {
-                println!("  {} {}", "error:".red().bold(), e);
+/// Run `git show` on the commit, printing the recorded message when git no longer has it
+fn show_commit(entry: &HistoryEntry) {
+    let commit = &entry.commit;
+    let mut cmd = crate::git::command();
+    if crate::ui::pager_disabled() {
+        cmd.arg("--no-pager");
+    }
+    let status = cmd
+        .args(["-C", &entry.repo_path, "show", &commit.hash])
+        .status();
+
+    match status {
+        Ok(s) if !s.success() => {
+            println!(
+                "  {} Could not show commit {} (it may have been garbage collected)",
+                "error:".red().bold(),
+                commit.short_hash()
+            );
+            if !commit.message.is_empty() {
+                println!("\n{}\n", commit.message);
             }
-            _ => {}
         }
+        Err(e) => {
+            println!("  {} {}", "error:".red().bold(), e);
+        }
+        _ => {}
     }
-    Ok(())
 }
 
-pub fn interactive_history() -> Result<()> {
-    match crate::git::find_repo_root() {
-        Ok(repo_root) => {
-            let cache = load_repo_cache(&repo_root)?;
-            show_repo_commits(&cache)?;
+/// Which branches still contain the commit and its diffstat, or a note that the repo no
+/// longer has it
+fn print_commit_status(entry: &HistoryEntry) {
+    let hash = &entry.commit.hash;
+    let Ok(stat) = crate::git::commit_stat(&entry.repo_path, hash) else {
+        println!(
+            "  {}",
+            "Not in the repository anymore (undone, rewritten or garbage collected)".yellow()
+        );
+        return;
+    };
+    let branches = crate::git::branches_containing(&entry.repo_path, hash).unwrap_or_default();
+    let on = if branches.is_empty() {
+        "no branch (undone or rewritten)".yellow().to_string()
+    } else {
+        branches.join(", ")
+    };
+    println!("  {} {}", "On:".dimmed(), on);
+    if !stat.is_empty() {
+        println!();
+        for line in stat.lines() {
+            println!("  {line}");
         }
-        Err(_) => {
-            let index = load_index()?;
-            if index.repos.is_empty() {
-                println!("{}", "No tracked repositories found.".dimmed());
-                return Ok(());
-            }
+    }
+    println!();
+}
 
-            let options: Vec<String> = index.repos.iter().map(|e| e.repo_path.clone()).collect();
-            if let Ok(repo_path) = Select::new("Select repository:", options).prompt() {
-                let cache = load_repo_cache(&repo_path)?;
-                show_repo_commits(&cache)?;
-            }
+/// What cgen recorded about a commit, beyond what `git show` prints
+fn print_commit_details(commit: &CachedCommit) {
+    let details = [
+        ("Generated", commit.timestamp.clone()),
+        ("Branch", commit.branch.clone()),
+        ("Model", commit.generated_by().unwrap_or_default()),
+    ];
+    println!();
+    for (label, value) in details {
+        if !value.is_empty() {
+            println!("  {} {}", format!("{label}:").dimmed(), value);
         }
     }
-    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_repo_path_hash_deterministic() {
-        let h1 = repo_path_hash("/home/user/project");
-        let h2 = repo_path_hash("/home/user/project");
-        assert_eq!(h1, h2);
-        assert_eq!(h1.len(), 16);
+/// Choose a tracked repository, most recently used first, each shown with its latest commit.
+/// Typing fuzzy-matches the repository paths.
+fn pick_repo() -> Result<Option<String>> {
+    let repos = repo_summaries()?;
+    if repos.is_empty() {
+        println!("{}", "No tracked repositories found.".dimmed());
+        return Ok(None);
     }
 
-    #[test]
-    fn test_repo_path_hash_different_paths() {
-        let h1 = repo_path_hash("/home/user/project-a");
-        let h2 = repo_path_hash("/home/user/project-b");
-        assert_ne!(h1, h2);
+    let options: Vec<String> = repos
+        .iter()
+        .map(|r| {
+            let date = r.last.timestamp.get(..10).unwrap_or("");
+            let latest = format!("{date} {}", r.last.message_preview);
+            format!(
+                "{}  {}",
+                r.repo_path,
+                format!("({} commits, latest {})", r.commits, latest.trim()).dimmed()
+            )
+        })
+        .collect();
+    let scorer = |input: &str, _: &String, _: &str, idx: usize| {
+        Select::<String>::DEFAULT_SCORER(input, &repos[idx].repo_path, &repos[idx].repo_path, idx)
+    };
+    let choice = Select::new("Select repository:", options)
+        .with_scorer(&scorer)
+        .with_page_size(15)
+        .with_help_message("Type to fuzzy-filter repository paths")
+        .raw_prompt();
+    Ok(choice.ok().map(|c| repos[c.index].repo_path.clone()))
+}
+
+/// Browse tracked commits: the current repo's, or pick a repo first outside one. With a
+/// filter, only matching commits are listed, across all repos when outside one. Returns the
+/// message the user chose to reuse for a new commit.
+pub fn interactive_history(filter: &HistoryFilter) -> Result<Option<String>> {
+    let repo_root = crate::git::repo_identity().ok();
+    if !filter.is_empty() {
+        let entries = search(filter, repo_root.as_deref())?;
+        if entries.is_empty() {
+            println!("{}", "No tracked commits match the filter.".dimmed());
+            return Ok(None);
+        }
+        return show_commits(&entries, repo_root.is_none());
+    }
+    match repo_root {
+        Some(repo_root) => show_repo_commits(&load_repo_cache(&repo_root)?),
+        None => match pick_repo()? {
+            Some(repo_path) => show_repo_commits(&load_repo_cache(&repo_path)?),
+            None => Ok(None),
+        },
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn test_cached_commit_serde() {
         let commit = CachedCommit {
             hash: "abc123def456".into(),
             message_preview: "feat: add login".into(),
+            ..CachedCommit::default()
         };
         let toml_str = toml::to_string(&commit).unwrap();
         let parsed: CachedCommit = toml::from_str(&toml_str).unwrap();
@@ -266,10 +758,12 @@ mod tests {
                 CachedCommit {
                     hash: "aaa".into(),
                     message_preview: "first".into(),
+                    ..CachedCommit::default()
                 },
                 CachedCommit {
                     hash: "bbb".into(),
                     message_preview: "second".into(),
+                    ..CachedCommit::default()
                 },
             ],
         };
@@ -311,6 +805,7 @@ mod tests {
         let commit = CachedCommit {
             hash: "abc123".into(),
             message_preview: "test commit".into(),
+            ..CachedCommit::default()
         };
         let cloned = commit.clone();
         assert_eq!(commit.hash, cloned.hash);
@@ -335,6 +830,7 @@ mod tests {
             commits: vec![CachedCommit {
                 hash: "abc".into(),
                 message_preview: "msg".into(),
+                ..CachedCommit::default()
             }],
         };
         let cloned = cache.clone();
@@ -355,22 +851,13 @@ mod tests {
     }
 
     #[test]
-    fn test_repo_path_hash_consistency() {
-        // Same path should always produce same hash
-        let path = "/some/long/path/to/repository";
-        let hash1 = repo_path_hash(path);
-        let hash2 = repo_path_hash(path);
-        let hash3 = repo_path_hash(path);
-        assert_eq!(hash1, hash2);
-        assert_eq!(hash2, hash3);
-    }
-
-    #[test]
-    fn test_repo_path_hash_format() {
-        let hash = repo_path_hash("/test/path");
-        // Should be 16 hex characters
-        assert_eq!(hash.len(), 16);
-        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    fn test_is_repo_cache_file() {
+        assert!(is_repo_cache_file(Path::new("/c/a1b2c3d4e5f67890.toml")));
+        assert!(!is_repo_cache_file(Path::new("/c/index.toml")));
+        assert!(!is_repo_cache_file(Path::new("/c/usage.toml")));
+        assert!(!is_repo_cache_file(Path::new(
+            "/c/a1b2c3d4e5f67890.toml.tmp"
+        )));
     }
 
     #[test]
@@ -381,14 +868,17 @@ mod tests {
                 CachedCommit {
                     hash: "aaa111".into(),
                     message_preview: "first".into(),
+                    ..CachedCommit::default()
                 },
                 CachedCommit {
                     hash: "bbb222".into(),
                     message_preview: "second".into(),
+                    ..CachedCommit::default()
                 },
                 CachedCommit {
                     hash: "ccc333".into(),
                     message_preview: "third".into(),
+                    ..CachedCommit::default()
                 },
             ],
         };
@@ -1,14 +1,22 @@
 use std::collections::HashSet;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use inquire::{Select, Text};
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ConfigScope};
 use crate::preset::LlmPresetFields;
 use crate::ui;
 
+/// `--color` values
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
 #[derive(Parser, Debug)]
 #[command(
     name = "cgen",
@@ -20,18 +28,86 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Command>,
 
+    /// Run in the repository at PATH instead of the current directory (like `git -C`)
+    #[arg(short = 'C', long = "repo", value_name = "PATH")]
+    pub repo: Option<std::path::PathBuf>,
+
+    /// Print long output directly instead of through `$PAGER` (like `git --no-pager`)
+    #[arg(long)]
+    pub no_pager: bool,
+
+    /// When to color output: auto (off when NO_COLOR is set or stdout is not a terminal),
+    /// always or never
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Same as --color=never
+    #[arg(long, conflicts_with = "color")]
+    pub no_color: bool,
+
     /// Generate and print commit message without creating a commit
     #[arg(long)]
     pub dry_run: bool,
 
-    /// Print the final system prompt sent to the LLM (without diff payload)
+    /// Print the HTTP request (URL, headers with secrets masked, JSON body) that would be sent
+    /// for the staged changes, without sending it
     #[arg(long)]
-    pub verbose: bool,
+    pub dry_run_request: bool,
+
+    /// Print the final system prompt sent to the LLM (without diff payload); give it twice
+    /// (`-vv`) to also log provider requests, responses and fallback decisions to stderr
+    #[arg(short = 'v', long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Append a debug log (config layers, git commands, provider attempts and errors) to PATH
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<std::path::PathBuf>,
+
+    /// Print only the final message and errors: no staged-file tree, notes or progress
+    #[arg(short = 'q', long, conflicts_with = "verbose")]
+    pub quiet: bool,
 
     /// Create a semantic version tag after a successful commit
     #[arg(long)]
     pub tag: bool,
 
+    /// Append the configured CI-skip token (e.g. `[skip ci]`) to the message
+    #[arg(long)]
+    pub skip_ci: bool,
+
+    /// Review the message on a full-screen view next to the staged diff (`ACR_REVIEW_TUI`)
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Page the diff that will be sent before calling the LLM (`ACR_SHOW_DIFF`)
+    #[arg(long)]
+    pub show_diff: bool,
+
+    /// Append a prompt snippet file to the system prompt (repeatable)
+    #[arg(long, value_name = "PATH")]
+    pub prompt_file: Vec<std::path::PathBuf>,
+
+    /// Leave files matching a glob out of the diff sent to the LLM, in addition to
+    /// ACR_DIFF_EXCLUDE_GLOBS (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Sampling seed for reproducible messages on providers that support it (overrides ACR_SEED)
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Apply a named prompt profile from profiles.toml (overrides ACR_PROFILE)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Allow a commit with nothing staged, e.g. to trigger CI (passed on to `git commit`)
+    #[arg(long)]
+    pub allow_empty: bool,
+
+    /// Why the empty commit is made; sent to the LLM in place of the diff
+    #[arg(long, value_name = "TEXT", requires = "allow_empty")]
+    pub context: Option<String>,
+
     /// Extra arguments forwarded to `git commit`
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub extra_args: Vec<String>,
@@ -39,32 +115,275 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
-    /// Open interactive configuration editor
-    Config,
-    /// Undo latest commit (soft reset)
-    Undo,
+    /// Open interactive configuration editor, or read and write single settings
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// Undo latest commit (soft reset: its changes stay staged)
+    Undo {
+        /// Undo the last N commits at once, listing them before confirming
+        #[arg(short = 'n', long = "count", value_name = "N", default_value_t = 1,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Also unstage the changes, keeping them in the working tree (`git reset --mixed`)
+        #[arg(long, conflicts_with = "discard")]
+        mixed: bool,
+        /// Throw the changes away, with any other uncommitted change (`git reset --hard`)
+        #[arg(long)]
+        discard: bool,
+    },
+    /// Put back the commit undone by the last `cgen undo`, if nothing changed since
+    Redo,
     /// Generate message from existing commit diff and rewrite commit message
     Alter {
         /// One hash: rewrite that commit from its own diff. Two hashes: use older..newer diff and rewrite newer.
         #[arg(value_name = "HASH", num_args = 1..=2)]
         commits: Vec<String>,
+        /// Rewrite history even on a protected branch (`ACR_PROTECTED_BRANCHES`)
+        #[arg(long)]
+        force: bool,
     },
     /// Update cgen to the latest version
-    Update,
+    Update {
+        /// Stop announcing the latest version instead of installing it
+        #[arg(long, conflicts_with = "snooze")]
+        skip: bool,
+        /// Silence the update warning for DAYS days instead of installing
+        #[arg(long, value_name = "DAYS")]
+        snooze: Option<u64>,
+    },
     /// Print the LLM system prompt without running anything
-    Prompt,
+    Prompt {
+        /// Also show the filtered staged diff and the request body that would be sent
+        #[arg(long)]
+        diff: bool,
+    },
     /// View commit history generated by cgen
-    History,
+    History {
+        /// Only list commits whose hash or message contains TEXT (and, outside a repo, whose
+        /// repository path does)
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+        /// Only list commits made on BRANCH (the current branch when no name is given)
+        #[arg(long, value_name = "BRANCH", num_args = 0..=1, default_missing_value = "")]
+        branch: Option<String>,
+    },
+    /// Prune or clear the commit history cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Show local usage metrics per provider and model (see ACR_USAGE_METRICS)
+    Stats {
+        /// Delete the recorded metrics
+        #[arg(long)]
+        clear: bool,
+    },
     /// Manage LLM presets (save, load, rename, duplicate, delete, export/import)
-    Preset,
+    Preset {
+        #[command(subcommand)]
+        action: Option<PresetAction>,
+    },
     /// Configure LLM fallback order
     Fallback,
+    /// Manage named prompt profiles (create, delete, set default)
+    Profile,
+    /// Check commit messages against Conventional Commits (exits 1 on violations)
+    Lint {
+        /// Commit or range to check (e.g. `HEAD`, `main..HEAD`). Defaults to HEAD.
+        #[arg(value_name = "REF_RANGE")]
+        range: Option<String>,
+        /// Read a single message from stdin instead of git history
+        #[arg(long, conflicts_with_all = ["range", "fix"])]
+        stdin: bool,
+        /// Regenerate non-conforming messages via `alter`
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Answer JSON-RPC requests from editor integrations (generate, lint, list presets)
+    Serve {
+        /// Read one request per line from stdin and write responses to stdout
+        #[arg(long, required = true)]
+        stdio: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Drop repositories that no longer exist and apply ACR_CACHE_MAX_COMMITS/ACR_CACHE_MAX_BYTES
+    Prune,
+    /// Delete the whole commit history cache
+    Clear,
+}
+
+/// Non-interactive config access for scripts and dotfile managers. Keys may be written as
+/// `model`, `MODEL` or `ACR_MODEL`.
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the value of a setting (the effective value unless a scope is given)
+    Get {
+        key: String,
+        #[command(flatten)]
+        scope: ScopeArgs,
+        /// Print the API key in plain text instead of masked
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Validate and save a setting (global config unless --local or --env)
+    Set {
+        key: String,
+        value: String,
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Print settings as KEY=value lines (effective values unless a scope is given)
+    List {
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Check every config layer and report the file and key of each problem
+    Validate,
+    /// Remove a setting so lower layers apply again (global config unless --local or --env)
+    Unset {
+        key: String,
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Write the effective configuration to a shareable TOML bundle
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+        /// Include saved presets
+        #[arg(long)]
+        presets: bool,
+        /// Include API keys in plain text
+        #[arg(long)]
+        keys: bool,
+    },
+    /// Apply a bundle from `cgen config export` to the global config and presets
+    Import {
+        #[arg(value_name = "PATH")]
+        path: std::path::PathBuf,
+        /// Keep current values when they differ from the bundle
+        #[arg(long, conflicts_with = "overwrite")]
+        keep: bool,
+        /// Replace current values with the bundle's without asking
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+/// Share presets as TOML files
+#[derive(Subcommand, Debug)]
+pub enum PresetAction {
+    /// Write presets as TOML (all of them unless names or ids are given)
+    Export {
+        #[arg(value_name = "PRESET")]
+        presets: Vec<String>,
+        /// Write to a file instead of stdout
+        #[arg(long, short, visible_alias = "output", value_name = "PATH")]
+        out: Option<std::path::PathBuf>,
+        /// Include API keys in plain text
+        #[arg(long)]
+        keys: bool,
+    },
+    /// Add presets from a TOML file or an https URL, skipping ones already saved
+    Import {
+        #[arg(value_name = "PATH|URL")]
+        source: String,
+    },
+    /// Send a tiny request with each preset and report OK, latency or the error (all presets
+    /// unless names or ids are given; exits 1 if any fails)
+    Test {
+        #[arg(value_name = "PRESET")]
+        presets: Vec<String>,
+    },
+}
+
+/// Which config file a `cgen config` action reads or writes
+#[derive(Args, Debug, Default)]
+#[group(multiple = false)]
+pub struct ScopeArgs {
+    /// The repository .cgen.toml
+    #[arg(long)]
+    pub local: bool,
+    /// The committed team defaults in .cgen/config.toml
+    #[arg(long)]
+    pub team: bool,
+    /// Your uncommitted overrides in .cgen/config.local.toml
+    #[arg(long)]
+    pub personal: bool,
+    /// The repository .env
+    #[arg(long)]
+    pub env: bool,
+    /// The global config file
+    #[arg(long)]
+    pub global: bool,
+}
+
+impl ScopeArgs {
+    /// The selected file, if any flag was given
+    pub fn scope(&self) -> Option<ConfigScope> {
+        if self.local {
+            Some(ConfigScope::Repo)
+        } else if self.team {
+            Some(ConfigScope::Team)
+        } else if self.personal {
+            Some(ConfigScope::Personal)
+        } else if self.env {
+            Some(ConfigScope::Env)
+        } else if self.global {
+            Some(ConfigScope::Global)
+        } else {
+            None
+        }
+    }
 }
 
 pub fn parse() -> Cli {
     Cli::parse()
 }
 
+/// Edit a list of glob patterns one entry at a time: select an entry to remove it, or add a
+/// new one. Returns the comma-separated list, or `None` when cancelled.
+fn edit_glob_list(title: &str, globs: &[String]) -> Option<String> {
+    let mut globs = globs.to_vec();
+    let add = "+ Add pattern".to_string();
+    let done = "Done".to_string();
+    loop {
+        let mut choices = vec![add.clone()];
+        choices.extend(globs.iter().map(|g| format!("- {g}")));
+        choices.push(done.clone());
+        let choice = Select::new(title, choices)
+            .with_scorer(&ui::fuzzy_score)
+            .with_help_message(
+                "Select a pattern to remove it (e.g., *.lock, dist/**); type to filter",
+            )
+            .prompt()
+            .ok()?;
+        if choice == done {
+            return Some(globs.join(","));
+        }
+        if choice == add {
+            let Ok(pattern) = Text::new("Glob pattern:").prompt() else {
+                continue;
+            };
+            let pattern = pattern.trim().to_string();
+            if pattern.is_empty() || globs.contains(&pattern) {
+                continue;
+            }
+            match glob::Pattern::new(&pattern) {
+                Ok(_) => globs.push(pattern),
+                Err(e) => println!("  {} {}", "error:".red().bold(), e),
+            }
+        } else if let Some(pattern) = choice.strip_prefix("- ") {
+            globs.retain(|g| g != pattern);
+        }
+    }
+}
+
 /// What happens when a menu entry is selected
 enum MenuAction {
     ToggleGroup(&'static str),
@@ -78,9 +397,13 @@ enum MenuAction {
     Search,
 }
 
-pub fn interactive_config(global: bool) -> Result<()> {
+pub fn interactive_config(target: ConfigScope) -> Result<()> {
     let mut cfg = AppConfig::load()?;
-    let scope = if global { "global" } else { "local" };
+    let scope = match target {
+        ConfigScope::Global => "global",
+        ConfigScope::Team => "team",
+        ConfigScope::Repo | ConfigScope::Personal | ConfigScope::Env => "local",
+    };
 
     println!("\n{}  {} configuration\n", "cgen".cyan().bold(), scope);
 
@@ -116,12 +439,13 @@ pub fn interactive_config(global: bool) -> Result<()> {
         }
 
         let groups = cfg.grouped_fields();
+        let g = crate::ui::glyphs();
         let mut actions: Vec<MenuAction> = Vec::new();
         let mut labels: Vec<String> = Vec::new();
 
         for group in &groups {
             let group_open = expanded.contains(group.name);
-            let arrow = if group_open { "\u{25BC}" } else { "\u{25B6}" };
+            let arrow = if group_open { g.open } else { g.closed };
             labels.push(format!("{} {}", arrow, group.name.bright_white().bold()));
             actions.push(MenuAction::ToggleGroup(group.name));
 
@@ -132,11 +456,7 @@ pub fn interactive_config(global: bool) -> Result<()> {
             let has_subgroups = !group.subgroups.is_empty();
             for (i, (display_name, suffix, val)) in group.fields.iter().enumerate() {
                 let is_last = !has_subgroups && i == group.fields.len() - 1;
-                let conn = if is_last {
-                    "\u{2514}\u{2500}\u{2500}"
-                } else {
-                    "\u{251C}\u{2500}\u{2500}"
-                };
+                let conn = if is_last { g.last } else { g.branch };
                 let mut field_label = format!("  {} {:<22} {}", conn, display_name, val.dimmed());
                 if show_descriptions {
                     let desc = crate::config::field_description(suffix);
@@ -151,12 +471,8 @@ pub fn interactive_config(global: bool) -> Result<()> {
             for (sg_idx, sg) in group.subgroups.iter().enumerate() {
                 let is_last_sg = sg_idx == group.subgroups.len() - 1;
                 let sg_open = expanded.contains(sg.name);
-                let sg_arrow = if sg_open { "\u{25BC}" } else { "\u{25B6}" };
-                let sg_conn = if is_last_sg {
-                    "\u{2514}\u{2500}\u{2500}"
-                } else {
-                    "\u{251C}\u{2500}\u{2500}"
-                };
+                let sg_arrow = if sg_open { g.open } else { g.closed };
+                let sg_conn = if is_last_sg { g.last } else { g.branch };
                 labels.push(format!(
                     "  {} {} {}",
                     sg_conn,
@@ -169,14 +485,10 @@ pub fn interactive_config(global: bool) -> Result<()> {
                     continue;
                 }
 
-                let pipe = if is_last_sg { " " } else { "\u{2502}" };
+                let pipe = if is_last_sg { " " } else { g.pipe };
                 for (f_idx, (display_name, suffix, val)) in sg.fields.iter().enumerate() {
                     let is_last_field = f_idx == sg.fields.len() - 1;
-                    let f_conn = if is_last_field {
-                        "\u{2514}\u{2500}\u{2500}"
-                    } else {
-                        "\u{251C}\u{2500}\u{2500}"
-                    };
+                    let f_conn = if is_last_field { g.last } else { g.branch };
                     let mut field_label = format!(
                         "  {}   {} {:<22} {}",
                         pipe,
@@ -247,6 +559,7 @@ pub fn interactive_config(global: bool) -> Result<()> {
         all_labels.push("Exit without saving".red().to_string());
 
         let selection = Select::new("Edit a setting:", all_labels)
+            .with_scorer(&ui::fuzzy_score)
             .with_page_size(22)
             .with_starting_cursor(starting_cursor)
             .with_formatter(&|opt| ui::strip_tree_chars(opt.value))
@@ -265,16 +578,23 @@ pub fn interactive_config(global: bool) -> Result<()> {
                 }
             }
 
-            if global {
-                cfg.save_global()?;
-                let path = crate::config::global_config_path()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_default();
-                println!("\n{} Saved to {}", "done!".green().bold(), path.dimmed());
-            } else {
-                cfg.save_local()?;
-                println!("\n{} Saved to {}", "done!".green().bold(), ".env".dimmed());
-            }
+            let path = match target {
+                ConfigScope::Global => {
+                    cfg.save_global()?;
+                    crate::config::global_config_path()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default()
+                }
+                ConfigScope::Team | ConfigScope::Repo | ConfigScope::Personal => {
+                    cfg.save_repo(target)?;
+                    target.repo_file().unwrap_or_default().to_string()
+                }
+                ConfigScope::Env => {
+                    cfg.save_local()?;
+                    ".env".to_string()
+                }
+            };
+            println!("\n{} Saved to {}", "done!".green().bold(), path.dimmed());
             break;
         }
         if selection.contains("Exit without saving") {
@@ -320,7 +640,7 @@ pub fn interactive_config(global: bool) -> Result<()> {
                     }
                     if *suffix == "PROVIDER" {
                         let default_model = crate::provider::default_model_for(&val);
-                        cfg.set_field("MODEL", default_model)?;
+                        cfg.set_field("MODEL", &default_model)?;
                         if default_model.is_empty() {
                             println!(
                                 "  {} Model cleared (set it manually)",
@@ -397,24 +717,11 @@ pub fn interactive_config(global: bool) -> Result<()> {
 fn edit_field(suffix: &str, cfg: &AppConfig) -> Option<String> {
     match suffix {
         "PROVIDER" => {
-            let choices = vec![
-                "gemini",
-                "openai",
-                "anthropic",
-                "groq",
-                "grok",
-                "deepseek",
-                "openrouter",
-                "mistral",
-                "together",
-                "fireworks",
-                "perplexity",
-                "lm_studio",
-                "(custom)",
-            ];
+            let mut choices = crate::provider::provider_names();
+            choices.push("(custom)".to_string());
             match Select::new("Provider:", choices).prompt() {
-                Ok("(custom)") => Text::new("Custom provider name:").prompt().ok(),
-                Ok(v) => Some(v.to_string()),
+                Ok(v) if v == "(custom)" => Text::new("Custom provider name:").prompt().ok(),
+                Ok(v) => Some(v),
                 Err(_) => None,
             }
         }
@@ -446,6 +753,34 @@ fn edit_field(suffix: &str, cfg: &AppConfig) -> Option<String> {
                 .ok()
                 .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
         }
+        "REVIEW_TUI" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Review on a full-screen diff view:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "SHOW_DIFF" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Show the diff before generating:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "ASCII_UI" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Draw the UI with ASCII only:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "TEMPLATE_COMMANDS" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Run $(command) substitutions:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
         "POST_COMMIT_PUSH" => {
             let choices = vec!["ask", "always", "never"];
             Select::new("Post-commit push behavior:", choices)
@@ -473,6 +808,14 @@ fn edit_field(suffix: &str, cfg: &AppConfig) -> Option<String> {
             )
             .prompt()
             .ok(),
+        "WARN_STAGED_FILES_MAX_LINES" => Text::new("Warn threshold (changed lines):")
+            .with_help_message("Added plus removed lines in the diff sent to the LLM; 0 disables")
+            .prompt()
+            .ok(),
+        "WARN_STAGED_FILES_MAX_BYTES" => Text::new("Warn threshold (diff bytes):")
+            .with_help_message("Size of the diff sent to the LLM; 0 disables")
+            .prompt()
+            .ok(),
         "CONFIRM_NEW_VERSION" => {
             let choices = vec!["enabled", "disabled"];
             Select::new("Confirm new semantic version tag:", choices)
@@ -487,6 +830,13 @@ fn edit_field(suffix: &str, cfg: &AppConfig) -> Option<String> {
                 .ok()
                 .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
         }
+        "UPDATE_CHANNEL" => {
+            let choices = vec!["stable", "beta", "nightly"];
+            Select::new("Update channel:", choices)
+                .prompt()
+                .ok()
+                .map(|v| v.to_string())
+        }
         "FALLBACK_ENABLED" => {
             let choices = vec!["enabled", "disabled"];
             Select::new("Enable LLM fallback on failure:", choices)
@@ -501,15 +851,225 @@ fn edit_field(suffix: &str, cfg: &AppConfig) -> Option<String> {
                 .ok()
                 .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
         }
-        "API_KEY" => Text::new("API Key:")
-            .with_help_message("Your LLM provider API key")
+        "REPO_HISTORY" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Append generated commits to .cgen/history.toml:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "EXCLUDE_LFS" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Leave Git LFS files out of the LLM diff:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "WARN_DEFAULT_BRANCH" => {
+            let choices = vec!["enabled", "disabled"];
+            Select::new("Note commits made directly to the default branch:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "AUTO_SET_UPSTREAM" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Set the upstream on a branch's first push without asking:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "PRE_COMMIT_HOOK" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Run the pre-commit hook before generating:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "AUTO_STASH" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new(
+                "Stash uncommitted changes around alter and undo --discard:",
+                choices,
+            )
+            .prompt()
+            .ok()
+            .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "USAGE_METRICS" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Record local usage metrics for `cgen stats`:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "MESSAGE_STYLE" => {
+            let choices = vec!["default", "detailed"];
+            Select::new("Message style:", choices)
+                .prompt()
+                .ok()
+                .map(|v| v.to_string())
+        }
+        "ROUTING" => Select::new("Preset routing:", crate::routing::POLICIES.to_vec())
+            .with_help_message("Which preset from the routing list each run uses; cheapest picks by price and diff size")
+            .prompt()
+            .ok()
+            .map(|v| v.to_string()),
+        "BUDGET_ACTION" => {
+            let choices = vec!["confirm", "warn"];
+            Select::new("When a run would exceed the budget:", choices)
+                .prompt()
+                .ok()
+                .map(|v| v.to_string())
+        }
+        "REASONING_EFFORT" => {
+            let choices = vec!["(provider default)", "minimal", "low", "medium", "high"];
+            Select::new("Reasoning effort:", choices)
+                .prompt()
+                .ok()
+                .map(|v| {
+                    if v == "(provider default)" {
+                        String::new()
+                    } else {
+                        v.to_string()
+                    }
+                })
+        }
+        "POST_PROCESS" => {
+            let choices = vec!["enabled", "disabled"];
+            Select::new("Post-process generated messages:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "SUBJECT_MAX_LENGTH" => Text::new("Subject max length:")
+            .with_help_message("Integer value; 0 disables the limit")
+            .with_default(&cfg.subject_max_length.to_string())
+            .prompt()
+            .ok(),
+        "LOWERCASE_SUBJECT" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Lowercase subject description:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "ALLOWED_TYPES" => Text::new("Allowed types:")
+            .with_help_message("Comma-separated (e.g., feat,fix,chore); leave empty to allow any")
+            .with_default(&cfg.allowed_types.join(","))
+            .prompt()
+            .ok(),
+        "PUSH_REMOTE" => Text::new("Push remotes:")
+            .with_help_message("Comma-separated remote names (e.g., origin,upstream); leave empty for git's default")
+            .with_default(&cfg.push_remote.join(","))
+            .prompt()
+            .ok(),
+        "PROTECTED_BRANCHES" => Text::new("Protected branches:")
+            .with_help_message(
+                "Comma-separated globs (e.g., main,release/*); @default is the remote's default branch",
+            )
+            .with_default(&cfg.protected_branches.join(","))
+            .prompt()
+            .ok(),
+        "ALLOWED_SCOPES" => Text::new("Allowed scopes:")
+            .with_help_message("Comma-separated (e.g., api,cli,docs); leave empty to allow any")
+            .with_default(&cfg.allowed_scopes.join(","))
+            .prompt()
+            .ok(),
+        "SCOPE_MAP" => Text::new("Scope map:")
+            .with_help_message(
+                "Comma-separated glob=scope pairs (e.g., crates/api/**=api,docs/**=docs)",
+            )
+            .with_default(&cfg.scope_map.join(","))
+            .prompt()
+            .ok(),
+        "FALLBACK_POLICY" => Text::new("Fallback policy:")
+            .with_help_message(
+                "Comma-separated status=fallback|retry|fail rules (e.g., 401=fail,429=retry,5xx=fallback)",
+            )
+            .with_default(&cfg.fallback_policy)
             .prompt()
             .ok(),
-        "DIFF_EXCLUDE_GLOBS" => Text::new("Diff Exclude Globs:")
-            .with_help_message("Comma-separated glob patterns (e.g., *.json,*.lock,*.png)")
-            .with_default(&cfg.diff_exclude_globs.join(","))
+        "AUTO_SCOPE" => {
+            let choices = vec!["enabled", "disabled"];
+            Select::new("Detect scope from workspace packages:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "GITMOJI_DETERMINISTIC" => {
+            let choices = vec!["enabled", "disabled"];
+            Select::new("Map gitmoji from commit type:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "SKIP_CI" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Append CI-skip token:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "SKIP_CI_TOKEN" => {
+            let choices = vec!["[skip ci]", "[ci skip]", "***NO_CI***"];
+            Select::new("CI-skip token:", choices)
+                .prompt()
+                .ok()
+                .map(|v| v.to_string())
+        }
+        "TYPE_TEMPLATES" => Text::new("Per-type templates:")
+            .with_help_message(
+                "type=template pairs separated by ; (e.g., fix=$msg\\n\\nBug: $ticket;feat=$msg)",
+            )
+            .with_default(&crate::config::format_type_templates(&cfg.type_templates))
+            .prompt()
+            .ok(),
+        "PROMPT_INCLUDE" => Text::new("Prompt snippets to include:")
+            .with_help_message(
+                "Comma-separated file-name globs (e.g., team-*,style); leave empty for all",
+            )
+            .with_default(&cfg.prompt_include.join(","))
+            .prompt()
+            .ok(),
+        "PROMPT_EXCLUDE" => Text::new("Prompt snippets to exclude:")
+            .with_help_message("Comma-separated file-name globs (e.g., draft,*-old)")
+            .with_default(&cfg.prompt_exclude.join(","))
+            .prompt()
+            .ok(),
+        "PROFILE" => {
+            let mut choices = vec!["(none)".to_string()];
+            if let Ok(file) = crate::profile::load_profiles() {
+                choices.extend(file.profiles.keys().cloned());
+            }
+            Select::new("Prompt profile:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "(none)" { String::new() } else { v })
+        }
+        "PRESET" => {
+            let mut choices = vec!["(none)".to_string()];
+            if let Ok(file) = crate::preset::load_presets() {
+                choices.extend(file.presets.into_iter().map(|p| p.name));
+            }
+            Select::new("Preset:", choices)
+                .with_scorer(&ui::fuzzy_score)
+                .prompt()
+                .ok()
+                .map(|v| if v == "(none)" { String::new() } else { v })
+        }
+        "ENCRYPT_SECRETS" => {
+            let choices = vec!["disabled", "enabled"];
+            Select::new("Encrypt stored API keys:", choices)
+                .prompt()
+                .ok()
+                .map(|v| if v == "enabled" { "1" } else { "0" }.to_string())
+        }
+        "API_KEY" => Text::new("API Key:")
+            .with_help_message("Your LLM provider API key")
             .prompt()
             .ok(),
+        "DIFF_EXCLUDE_GLOBS" => edit_glob_list("Diff Exclude Globs:", &cfg.diff_exclude_globs),
         _ => {
             let fields = cfg.fields_display();
             let field = fields.iter().find(|(_, s, _)| *s == suffix);
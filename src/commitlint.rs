@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+/// Config files commitlint looks for, in its own lookup order
+const COMMITLINT_FILES: &[&str] = &[
+    ".commitlintrc",
+    ".commitlintrc.json",
+    ".commitlintrc.yaml",
+    ".commitlintrc.yml",
+    ".commitlintrc.js",
+    ".commitlintrc.cjs",
+    ".commitlintrc.mjs",
+    ".commitlintrc.ts",
+    ".commitlintrc.cts",
+    "commitlint.config.js",
+    "commitlint.config.cjs",
+    "commitlint.config.mjs",
+    "commitlint.config.ts",
+    "commitlint.config.cts",
+];
+
+/// Types enforced by `@commitlint/config-conventional`
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+/// The subset of commitlint rules cgen understands
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitlintRules {
+    /// Allowed commit types (`type-enum`); empty means any
+    pub type_enum: Vec<String>,
+    /// Allowed scopes (`scope-enum`); empty means any
+    pub scope_enum: Vec<String>,
+    /// Maximum header length (`header-max-length` / `subject-max-length`)
+    pub header_max_length: Option<usize>,
+    /// Maximum body line length (`body-max-line-length`)
+    pub body_max_line_length: Option<usize>,
+    /// Whether `subject-case` requires a lowercase description
+    pub lowercase_subject: bool,
+}
+
+impl CommitlintRules {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Locate and parse the commitlint config in the current repository, if any
+pub fn load_for_repo() -> Option<CommitlintRules> {
+    let root = crate::git::find_repo_root().ok()?;
+    load_from_dir(Path::new(&root))
+}
+
+/// Parse the first commitlint config file found in `dir`
+pub fn load_from_dir(dir: &Path) -> Option<CommitlintRules> {
+    let path = find_config_file(dir)?;
+    let content = std::fs::read_to_string(path).ok()?;
+    let rules = parse_rules(&content);
+    (!rules.is_empty()).then_some(rules)
+}
+
+fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    COMMITLINT_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Extract the supported rules from a commitlint config. JSON, flow-style YAML and JS/TS
+/// object literals are understood; rules written as YAML block lists are ignored.
+pub fn parse_rules(content: &str) -> CommitlintRules {
+    let mut rules = CommitlintRules::default();
+
+    if content.contains("config-conventional") {
+        rules.type_enum = CONVENTIONAL_TYPES.iter().map(|s| s.to_string()).collect();
+        rules.header_max_length = Some(100);
+        rules.body_max_line_length = Some(100);
+        rules.lowercase_subject = true;
+    }
+
+    if let Some(rule) = find_rule(content, "type-enum") {
+        if let Some(values) = rule.enabled_always_list() {
+            rules.type_enum = values;
+        } else if rule.is_disabled() {
+            rules.type_enum.clear();
+        }
+    }
+    if let Some(rule) = find_rule(content, "scope-enum") {
+        if let Some(values) = rule.enabled_always_list() {
+            rules.scope_enum = values;
+        } else if rule.is_disabled() {
+            rules.scope_enum.clear();
+        }
+    }
+    for name in ["header-max-length", "subject-max-length"] {
+        if let Some(rule) = find_rule(content, name) {
+            if rule.is_disabled() {
+                rules.header_max_length = None;
+            } else if let Some(n) = rule.number_value() {
+                rules.header_max_length = Some(rules.header_max_length.map_or(n, |m| m.min(n)));
+            }
+        }
+    }
+    if let Some(rule) = find_rule(content, "body-max-line-length") {
+        rules.body_max_line_length = if rule.is_disabled() {
+            None
+        } else {
+            rule.number_value().or(rules.body_max_line_length)
+        };
+    }
+    if let Some(rule) = find_rule(content, "subject-case") {
+        rules.lowercase_subject = !rule.is_disabled() && rule.requires_lowercase();
+    }
+
+    rules
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<Value>),
+}
+
+/// A rule in commitlint's `[level, applicable, value]` form
+#[derive(Debug)]
+struct Rule(Vec<Value>);
+
+impl Rule {
+    fn is_disabled(&self) -> bool {
+        matches!(self.0.first(), Some(Value::Scalar(level)) if level == "0")
+    }
+
+    fn applicable(&self) -> &str {
+        match self.0.get(1) {
+            Some(Value::Scalar(s)) => s,
+            _ => "always",
+        }
+    }
+
+    fn values(&self) -> Vec<String> {
+        match self.0.get(2) {
+            Some(Value::List(items)) => items
+                .iter()
+                .filter_map(|v| match v {
+                    Value::Scalar(s) => Some(s.clone()),
+                    Value::List(_) => None,
+                })
+                .collect(),
+            Some(Value::Scalar(s)) => vec![s.clone()],
+            None => Vec::new(),
+        }
+    }
+
+    fn enabled_always_list(&self) -> Option<Vec<String>> {
+        if self.is_disabled() || self.applicable() != "always" {
+            return None;
+        }
+        let values = self.values();
+        (!values.is_empty()).then_some(values)
+    }
+
+    fn number_value(&self) -> Option<usize> {
+        match self.0.get(2) {
+            Some(Value::Scalar(s)) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn requires_lowercase(&self) -> bool {
+        let values = self.values();
+        let has = |name: &str| values.iter().any(|v| v == name);
+        match self.applicable() {
+            "always" => has("lower-case"),
+            "never" => has("sentence-case") || has("upper-case") || has("start-case"),
+            _ => false,
+        }
+    }
+}
+
+/// Find `name: [ ... ]` (key quoted or bare) and parse the array that follows
+fn find_rule(content: &str, name: &str) -> Option<Rule> {
+    for quote in ["\"", "'", ""] {
+        let key = format!("{quote}{name}{quote}");
+        let mut search = content;
+        while let Some(idx) = search.find(&key) {
+            let rest = search[idx + key.len()..].trim_start();
+            if let Some(after_colon) = rest.strip_prefix(':') {
+                let mut chars = after_colon.trim_start().chars().peekable();
+                if chars.peek() == Some(&'[') {
+                    if let Some(Value::List(items)) = parse_value(&mut chars) {
+                        return Some(Rule(items));
+                    }
+                }
+            }
+            search = &search[idx + key.len()..];
+        }
+    }
+    None
+}
+
+fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Value> {
+    skip_ws(chars);
+    match *chars.peek()? {
+        '[' => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                skip_ws(chars);
+                match *chars.peek()? {
+                    ']' => {
+                        chars.next();
+                        return Some(Value::List(items));
+                    }
+                    ',' => {
+                        chars.next();
+                    }
+                    _ => items.push(parse_value(chars)?),
+                }
+            }
+        }
+        q @ ('"' | '\'' | '`') => {
+            chars.next();
+            let mut s = String::new();
+            for c in chars.by_ref() {
+                if c == q {
+                    return Some(Value::Scalar(s));
+                }
+                s.push(c);
+            }
+            None
+        }
+        _ => {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == ']' || c.is_whitespace() {
+                    break;
+                }
+                s.push(c);
+                chars.next();
+            }
+            if s.is_empty() {
+                return None;
+            }
+            Some(Value::Scalar(resolve_level(&s)))
+        }
+    }
+}
+
+/// Map `RuleConfigSeverity.Error`-style identifiers to numeric levels
+fn resolve_level(token: &str) -> String {
+    match token.rsplit('.').next().unwrap_or(token) {
+        "Disabled" => "0".into(),
+        "Warning" => "1".into(),
+        "Error" => "2".into(),
+        other => other.to_string(),
+    }
+}
+
+fn skip_ws(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
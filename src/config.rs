@@ -1,8 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
+use crate::commitlint::CommitlintRules;
+use crate::gitmoji::GitmojiSet;
+use crate::snippet::PromptSnippet;
+
 pub struct FieldSubgroup {
     pub name: &'static str,
     pub fields: Vec<(&'static str, &'static str, String)>,
@@ -22,6 +26,9 @@ it into a commit message. Use the present tense. Use english for the commit mess
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// Schema version of the global config file; files without it are version 0
+    #[serde(default)]
+    pub config_version: u32,
     #[serde(default = "default_provider")]
     pub provider: String,
     #[serde(default = "default_model")]
@@ -54,6 +61,10 @@ pub struct AppConfig {
     pub warn_staged_files_enabled: bool,
     #[serde(default = "default_warn_staged_files_threshold")]
     pub warn_staged_files_threshold: usize,
+    #[serde(default = "default_warn_staged_files_max_lines")]
+    pub warn_staged_files_max_lines: usize,
+    #[serde(default = "default_warn_staged_files_max_bytes")]
+    pub warn_staged_files_max_bytes: usize,
     #[serde(default = "default_true")]
     pub confirm_new_version: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -64,6 +75,150 @@ pub struct AppConfig {
     pub track_generated_commits: bool,
     #[serde(default = "default_diff_exclude_globs")]
     pub diff_exclude_globs: Vec<String>,
+    #[serde(default = "default_message_style")]
+    pub message_style: String,
+    #[serde(default = "default_true")]
+    pub post_process: bool,
+    #[serde(default = "default_subject_max_length")]
+    pub subject_max_length: usize,
+    #[serde(default)]
+    pub lowercase_subject: bool,
+    /// Rules read from the repository's commitlint config (not persisted)
+    #[serde(skip)]
+    pub commitlint: Option<CommitlintRules>,
+    /// Custom gitmoji set from `gitmoji.toml` in the config directory (not persisted)
+    #[serde(skip)]
+    pub gitmoji_set: Option<GitmojiSet>,
+    /// Prompt snippets from the config and repository `.cgen/` directories (not persisted)
+    #[serde(skip)]
+    pub prompt_snippets: Vec<PromptSnippet>,
+    /// Whether any config layer set the locale explicitly (not persisted)
+    #[serde(skip)]
+    pub locale_configured: bool,
+    /// Request overrides of the applied preset (not persisted)
+    #[serde(skip)]
+    pub preset_overrides: crate::preset::PresetOverrides,
+    /// [`COMMAND_SETTINGS`] last set by a repository layer, whose `$(command)`s never run
+    /// (not persisted)
+    #[serde(skip)]
+    pub repo_command_settings: Vec<String>,
+    #[serde(default)]
+    pub allowed_types: Vec<String>,
+    #[serde(default)]
+    pub allowed_scopes: Vec<String>,
+    #[serde(default)]
+    pub scope_map: Vec<String>,
+    #[serde(default = "default_true")]
+    pub auto_scope: bool,
+    #[serde(default = "default_true")]
+    pub gitmoji_deterministic: bool,
+    #[serde(default)]
+    pub skip_ci: bool,
+    #[serde(default = "default_skip_ci_token")]
+    pub skip_ci_token: String,
+    #[serde(default)]
+    pub type_templates: BTreeMap<String, String>,
+    #[serde(default)]
+    pub prompt_include: Vec<String>,
+    #[serde(default)]
+    pub prompt_exclude: Vec<String>,
+    #[serde(default)]
+    pub profile: String,
+    /// Encrypt API keys written to config.toml and presets.toml (global-only)
+    #[serde(default)]
+    pub encrypt_secrets: bool,
+    /// Largest request body in bytes; bigger diffs are truncated to fit (0 = no limit)
+    #[serde(default = "default_max_payload_bytes")]
+    pub max_payload_bytes: usize,
+    /// Context window of the model in tokens, overriding the built-in model table (0 = look up
+    /// the model)
+    #[serde(default = "default_context_window")]
+    pub context_window: usize,
+    /// Reasoning effort sent to reasoning models (`minimal`, `low`, `medium`, `high`; empty =
+    /// provider default)
+    #[serde(default)]
+    pub reasoning_effort: String,
+    /// Sampling seed sent to providers that support one, for reproducible output (empty = off)
+    #[serde(default)]
+    pub seed: String,
+    /// Monthly LLM spending budget in USD (empty = off); usage is only tracked while it is set
+    #[serde(default)]
+    pub monthly_budget_usd: String,
+    /// What happens when a run would exceed the budget: `warn` or `confirm`
+    #[serde(default = "default_budget_action")]
+    pub budget_action: String,
+    /// Record per-run metrics locally for `cgen stats` (opt-in, never sent anywhere)
+    #[serde(default)]
+    pub usage_metrics: bool,
+    /// Most commits kept in the history cache per repository (0 = unlimited)
+    #[serde(default = "default_cache_max_commits")]
+    pub cache_max_commits: usize,
+    /// Largest total size of the history cache in bytes (0 = unlimited)
+    #[serde(default = "default_cache_max_bytes")]
+    pub cache_max_bytes: usize,
+    /// Also append generated commits to the repository's `.cgen/history.toml`
+    #[serde(default)]
+    pub repo_history: bool,
+    /// Stash uncommitted changes around `alter`
+    #[serde(default)]
+    pub auto_stash: bool,
+    /// Drop Git LFS pointer files from the LLM diff instead of summarizing them
+    #[serde(default)]
+    pub exclude_lfs: bool,
+    /// Run the pre-commit hook before generating and restage the files it modifies
+    #[serde(default)]
+    pub pre_commit_hook: bool,
+    /// Branch glob patterns that need confirmation to commit or push and refuse `alter`
+    #[serde(default)]
+    pub protected_branches: Vec<String>,
+    /// Remotes to push to, in order; empty pushes the default remote
+    #[serde(default)]
+    pub push_remote: Vec<String>,
+    /// Run `git push -u` for branches without an upstream without asking
+    #[serde(default)]
+    pub auto_set_upstream: bool,
+    /// Note when committing directly to the remote's default branch
+    #[serde(default = "default_true")]
+    pub warn_default_branch: bool,
+    /// Releases `cgen update` considers: `stable`, `beta` or `nightly`. Global only.
+    #[serde(default = "default_update_channel")]
+    pub update_channel: String,
+    /// Hours a cached update check stays fresh; 0 checks on every run
+    #[serde(default = "default_update_check_hours")]
+    pub update_check_hours: usize,
+    /// Review on a full-screen diff + message screen instead of the menu
+    #[serde(default)]
+    pub review_tui: bool,
+    /// Page the diff that will be sent and confirm before calling the LLM
+    #[serde(default)]
+    pub show_diff: bool,
+    /// Seconds before an LLM request is abandoned; 0 waits indefinitely
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: usize,
+    /// Draw trees, arrows and the spinner with ASCII only
+    #[serde(default)]
+    pub ascii_ui: bool,
+    /// Run `$(command)` substitutions in templates, URLs and headers (global only, since
+    /// repository files could otherwise run commands)
+    #[serde(default)]
+    pub template_commands: bool,
+    /// Preset (name or id) applied when the layer setting it is loaded, so a repository can
+    /// pick its provider without switching by hand
+    #[serde(default)]
+    pub preset: String,
+    /// How each run picks a preset from the `[routing]` list in presets.toml (see `routing`)
+    #[serde(default = "default_routing")]
+    pub routing: String,
+    /// Comma-separated `status=action` rules deciding whether an HTTP error falls back, retries
+    /// or fails (see `provider::error_action`)
+    #[serde(default = "default_fallback_policy")]
+    pub fallback_policy: String,
+    /// Fallback presets tried after the primary fails; 0 tries the whole order
+    #[serde(default = "default_fallback_max_attempts")]
+    pub fallback_max_attempts: usize,
+    /// Minutes a fallback preset is skipped after it fails (see `cooldown`); 0 turns this off
+    #[serde(default = "default_fallback_cooldown_mins")]
+    pub fallback_cooldown_mins: usize,
 }
 
 fn default_provider() -> String {
@@ -93,6 +248,12 @@ fn default_gitmoji_format() -> String {
 fn default_warn_staged_files_threshold() -> usize {
     20
 }
+fn default_warn_staged_files_max_lines() -> usize {
+    2000
+}
+fn default_warn_staged_files_max_bytes() -> usize {
+    200_000
+}
 fn default_diff_exclude_globs() -> Vec<String> {
     vec![
         "*.json",
@@ -117,10 +278,68 @@ fn default_diff_exclude_globs() -> Vec<String> {
     .map(String::from)
     .collect()
 }
+fn default_message_style() -> String {
+    "default".into()
+}
+fn default_subject_max_length() -> usize {
+    72
+}
+fn default_skip_ci_token() -> String {
+    "[skip ci]".into()
+}
+
+fn default_max_payload_bytes() -> usize {
+    0
+}
+
+fn default_context_window() -> usize {
+    0
+}
+
+fn default_budget_action() -> String {
+    "confirm".into()
+}
+
+fn default_cache_max_commits() -> usize {
+    1000
+}
+
+fn default_cache_max_bytes() -> usize {
+    10000000
+}
+
+fn default_update_channel() -> String {
+    "stable".into()
+}
+
+fn default_update_check_hours() -> usize {
+    24
+}
+
+fn default_request_timeout_secs() -> usize {
+    120
+}
+
+fn default_routing() -> String {
+    "none".into()
+}
+
+fn default_fallback_policy() -> String {
+    "401=fail,403=fail".into()
+}
+
+fn default_fallback_max_attempts() -> usize {
+    0
+}
+
+fn default_fallback_cooldown_mins() -> usize {
+    0
+}
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             provider: default_provider(),
             model: default_model(),
             api_key: String::new(),
@@ -137,17 +356,70 @@ impl Default for AppConfig {
             suppress_tool_output: false,
             warn_staged_files_enabled: true,
             warn_staged_files_threshold: default_warn_staged_files_threshold(),
+            warn_staged_files_max_lines: default_warn_staged_files_max_lines(),
+            warn_staged_files_max_bytes: default_warn_staged_files_max_bytes(),
             confirm_new_version: true,
             auto_update: None,
             fallback_enabled: true,
             track_generated_commits: true,
             diff_exclude_globs: default_diff_exclude_globs(),
+            message_style: default_message_style(),
+            post_process: true,
+            subject_max_length: default_subject_max_length(),
+            lowercase_subject: false,
+            commitlint: None,
+            gitmoji_set: None,
+            prompt_snippets: Vec::new(),
+            locale_configured: false,
+            preset_overrides: Default::default(),
+            repo_command_settings: Vec::new(),
+            allowed_types: Vec::new(),
+            allowed_scopes: Vec::new(),
+            scope_map: Vec::new(),
+            auto_scope: true,
+            gitmoji_deterministic: true,
+            skip_ci: false,
+            skip_ci_token: default_skip_ci_token(),
+            type_templates: BTreeMap::new(),
+            prompt_include: Vec::new(),
+            prompt_exclude: Vec::new(),
+            profile: String::new(),
+            encrypt_secrets: false,
+            max_payload_bytes: default_max_payload_bytes(),
+            context_window: default_context_window(),
+            reasoning_effort: String::new(),
+            seed: String::new(),
+            monthly_budget_usd: String::new(),
+            budget_action: default_budget_action(),
+            usage_metrics: false,
+            cache_max_commits: default_cache_max_commits(),
+            cache_max_bytes: default_cache_max_bytes(),
+            repo_history: false,
+            auto_stash: false,
+            exclude_lfs: false,
+            pre_commit_hook: false,
+            protected_branches: Vec::new(),
+            push_remote: Vec::new(),
+            auto_set_upstream: false,
+            warn_default_branch: true,
+            update_channel: default_update_channel(),
+            update_check_hours: default_update_check_hours(),
+            review_tui: false,
+            show_diff: false,
+            request_timeout_secs: default_request_timeout_secs(),
+            ascii_ui: false,
+            template_commands: false,
+            preset: String::new(),
+            routing: default_routing(),
+            fallback_policy: default_fallback_policy(),
+            fallback_max_attempts: default_fallback_max_attempts(),
+            fallback_cooldown_mins: default_fallback_cooldown_mins(),
         }
     }
 }
 
 /// Map of ACR_ env var suffix → struct field name
-const ENV_FIELD_MAP: &[(&str, &str)] = &[
+pub const ENV_FIELD_MAP: &[(&str, &str)] = &[
     ("PROVIDER", "provider"),
     ("MODEL", "model"),
     ("API_KEY", "api_key"),
@@ -164,35 +436,127 @@ const ENV_FIELD_MAP: &[(&str, &str)] = &[
     ("SUPPRESS_TOOL_OUTPUT", "suppress_tool_output"),
     ("WARN_STAGED_FILES_ENABLED", "warn_staged_files_enabled"),
     ("WARN_STAGED_FILES_THRESHOLD", "warn_staged_files_threshold"),
+    ("WARN_STAGED_FILES_MAX_LINES", "warn_staged_files_max_lines"),
+    ("WARN_STAGED_FILES_MAX_BYTES", "warn_staged_files_max_bytes"),
     ("CONFIRM_NEW_VERSION", "confirm_new_version"),
     ("AUTO_UPDATE", "auto_update"),
     ("FALLBACK_ENABLED", "fallback_enabled"),
     ("TRACK_GENERATED_COMMITS", "track_generated_commits"),
     ("DIFF_EXCLUDE_GLOBS", "diff_exclude_globs"),
+    ("MESSAGE_STYLE", "message_style"),
+    ("POST_PROCESS", "post_process"),
+    ("SUBJECT_MAX_LENGTH", "subject_max_length"),
+    ("LOWERCASE_SUBJECT", "lowercase_subject"),
+    ("ALLOWED_TYPES", "allowed_types"),
+    ("ALLOWED_SCOPES", "allowed_scopes"),
+    ("SCOPE_MAP", "scope_map"),
+    ("AUTO_SCOPE", "auto_scope"),
+    ("GITMOJI_DETERMINISTIC", "gitmoji_deterministic"),
+    ("SKIP_CI", "skip_ci"),
+    ("SKIP_CI_TOKEN", "skip_ci_token"),
+    ("TYPE_TEMPLATES", "type_templates"),
+    ("PROMPT_INCLUDE", "prompt_include"),
+    ("PROMPT_EXCLUDE", "prompt_exclude"),
+    ("PROFILE", "profile"),
+    ("ENCRYPT_SECRETS", "encrypt_secrets"),
+    ("MAX_PAYLOAD_BYTES", "max_payload_bytes"),
+    ("CONTEXT_WINDOW", "context_window"),
+    ("REASONING_EFFORT", "reasoning_effort"),
+    ("SEED", "seed"),
+    ("MONTHLY_BUDGET_USD", "monthly_budget_usd"),
+    ("BUDGET_ACTION", "budget_action"),
+    ("USAGE_METRICS", "usage_metrics"),
+    ("CACHE_MAX_COMMITS", "cache_max_commits"),
+    ("CACHE_MAX_BYTES", "cache_max_bytes"),
+    ("REPO_HISTORY", "repo_history"),
+    ("AUTO_STASH", "auto_stash"),
+    ("EXCLUDE_LFS", "exclude_lfs"),
+    ("PRE_COMMIT_HOOK", "pre_commit_hook"),
+    ("PROTECTED_BRANCHES", "protected_branches"),
+    ("PUSH_REMOTE", "push_remote"),
+    ("AUTO_SET_UPSTREAM", "auto_set_upstream"),
+    ("WARN_DEFAULT_BRANCH", "warn_default_branch"),
+    ("UPDATE_CHANNEL", "update_channel"),
+    ("UPDATE_CHECK_HOURS", "update_check_hours"),
+    ("REVIEW_TUI", "review_tui"),
+    ("SHOW_DIFF", "show_diff"),
+    ("REQUEST_TIMEOUT_SECS", "request_timeout_secs"),
+    ("ASCII_UI", "ascii_ui"),
+    ("TEMPLATE_COMMANDS", "template_commands"),
+    ("PRESET", "preset"),
+    ("ROUTING", "routing"),
+    ("FALLBACK_POLICY", "fallback_policy"),
+    ("FALLBACK_MAX_ATTEMPTS", "fallback_max_attempts"),
+    ("FALLBACK_COOLDOWN_MINS", "fallback_cooldown_mins"),
 ];
 
 impl AppConfig {
-    /// Load config with layered resolution: defaults → global TOML → local .env → env vars
+    /// Load config with layered resolution: defaults → global TOML → matching directory
+    /// profiles → repository TOML files (team, `.cgen.toml`, personal) → local .env → env vars
+    #[tracing::instrument(level = "debug", name = "config_load", skip_all)]
     pub fn load() -> Result<Self> {
         let mut cfg = Self::default();
 
         // Layer 1: Global TOML
         if let Some(path) = global_config_path() {
             if path.exists() {
-                let content = std::fs::read_to_string(&path)
-                    .with_context(|| format!("Failed to read {}", path.display()))?;
-                let file_cfg: AppConfig = toml::from_str(&content)
+                let mut table = load_global_table(&path)?;
+                let sections = take_directory_profiles(&mut table);
+                crate::ui::log_event!(
+                    "config: {} sets {}",
+                    path.display(),
+                    table.keys().cloned().collect::<Vec<_>>().join(", ")
+                );
+                cfg.locale_configured |= table.contains_key("locale");
+                let file_cfg: AppConfig = toml::Value::Table(table)
+                    .try_into()
                     .with_context(|| format!("Failed to parse {}", path.display()))?;
                 cfg.merge_from(&file_cfg);
+                // The global file always holds provider settings, so its preset replaces them
+                if !file_cfg.preset.is_empty() {
+                    cfg.apply_preset_setting(&file_cfg.preset, "config.toml")?;
+                }
+                cfg.apply_directory_profiles(&sections)?;
             }
         }
 
-        // Layer 2: Local .env (in git repo root)
+        // Layer 2: Repository TOML files, then local .env (in git repo root)
         if let Ok(root) = crate::git::find_repo_root() {
+            for scope in REPO_LAYERS {
+                let Some(file) = scope.repo_file() else {
+                    continue;
+                };
+                let repo_path = PathBuf::from(&root).join(file);
+                if !repo_path.exists() {
+                    continue;
+                }
+                let mut settings = table_settings(&read_table(&repo_path)?);
+                if scope == ConfigScope::Team {
+                    // The team file is committed: it may neither hold a key nor say where the
+                    // user's key is sent
+                    settings.retain(|suffix, value| team_may_set(suffix, value));
+                }
+                let env_map: HashMap<String, String> = settings
+                    .into_iter()
+                    .map(|(suffix, value)| (format!("ACR_{suffix}"), value))
+                    .collect();
+                cfg.locale_configured |= env_map.contains_key("ACR_LOCALE");
+                log_layer(&repo_path.display().to_string(), &env_map);
+                cfg.apply_layer_preset(&env_map, file)?;
+                let before = cfg.command_setting_values();
+                cfg.apply_env_map(&env_map, true);
+                cfg.track_command_settings(&before, true);
+            }
+
             let env_path = PathBuf::from(&root).join(".env");
             if env_path.exists() {
                 let env_map = parse_dotenv(&env_path)?;
+                cfg.locale_configured |= env_map.contains_key("ACR_LOCALE");
+                log_layer(&env_path.display().to_string(), &env_map);
+                cfg.apply_layer_preset(&env_map, ".env")?;
+                let before = cfg.command_setting_values();
                 cfg.apply_env_map(&env_map, true);
+                cfg.track_command_settings(&before, true);
             }
         }
 
@@ -204,12 +568,81 @@ impl AppConfig {
                 env_map.insert(key, val);
             }
         }
+        cfg.locale_configured |= env_map.contains_key("ACR_LOCALE");
+        log_layer("the environment", &env_map);
+        cfg.apply_layer_preset(&env_map, "the environment")?;
+        let before = cfg.command_setting_values();
         cfg.apply_env_map(&env_map, false);
+        cfg.track_command_settings(&before, false);
         cfg.ensure_valid_locale()?;
+        cfg.api_key =
+            crate::credentials::resolve(&cfg.api_key).context("Failed to resolve the API key")?;
+
+        // Repository commitlint rules tighten generation and linting
+        cfg.commitlint = crate::commitlint::load_for_repo();
+        cfg.gitmoji_set = crate::gitmoji::load_custom_set()?;
+        cfg.prompt_snippets = crate::snippet::load_layered(&cfg);
+        crate::ui::log_event!(
+            "config: resolved provider {}, model {}, API key {}",
+            cfg.provider,
+            cfg.model,
+            if cfg.api_key.is_empty() {
+                "not set"
+            } else {
+                "set"
+            }
+        );
 
         Ok(cfg)
     }
 
+    fn command_setting_values(&self) -> Vec<Option<String>> {
+        COMMAND_SETTINGS
+            .iter()
+            .map(|suffix| self.setting_value(suffix))
+            .collect()
+    }
+
+    /// Record which [`COMMAND_SETTINGS`] a layer changed from their `before` values: a
+    /// repository layer marks them, a trusted layer clears the mark
+    fn track_command_settings(&mut self, before: &[Option<String>], from_repo: bool) {
+        for (suffix, old) in COMMAND_SETTINGS.iter().zip(before) {
+            if self.setting_value(suffix) == *old {
+                continue;
+            }
+            self.repo_command_settings.retain(|s| s != suffix);
+            if from_repo {
+                self.repo_command_settings.push(suffix.to_string());
+            }
+        }
+    }
+
+    /// Whether `$(command)`s in the value of `suffix` may run: `ACR_TEMPLATE_COMMANDS` is on and
+    /// the value comes from the global config or the environment, not from a repository file a
+    /// clone could ship
+    pub fn commands_allowed(&self, suffix: &str) -> bool {
+        self.template_commands && !self.repo_command_settings.iter().any(|s| s == suffix)
+    }
+
+    /// Apply the preset a layer names in `ACR_PRESET`, before the layer's own settings so its
+    /// provider keys can still adjust the preset
+    fn apply_layer_preset(&mut self, map: &HashMap<String, String>, source: &str) -> Result<()> {
+        match map.get("ACR_PRESET").map(|name| name.trim()) {
+            Some(name) if !name.is_empty() => self.apply_preset_setting(name, source),
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the saved preset called `name` (or with that id), set by `source`
+    fn apply_preset_setting(&mut self, name: &str, source: &str) -> Result<()> {
+        let presets = crate::preset::load_presets()?;
+        let preset = crate::preset::find_preset(&presets, name)
+            .with_context(|| format!("Preset '{name}' used by {source} not found"))?;
+        crate::ui::log_event!("config: {source} applies preset '{}'", preset.name);
+        crate::preset::apply_preset_to_config(self, preset);
+        Ok(())
+    }
+
     fn merge_from(&mut self, other: &AppConfig) {
         if !other.provider.is_empty() {
             self.provider = other.provider.clone();
@@ -247,6 +680,8 @@ impl AppConfig {
         self.suppress_tool_output = other.suppress_tool_output;
         self.warn_staged_files_enabled = other.warn_staged_files_enabled;
         self.warn_staged_files_threshold = other.warn_staged_files_threshold;
+        self.warn_staged_files_max_lines = other.warn_staged_files_max_lines;
+        self.warn_staged_files_max_bytes = other.warn_staged_files_max_bytes;
         self.confirm_new_version = other.confirm_new_version;
         if other.auto_update.is_some() {
             self.auto_update = other.auto_update;
@@ -256,6 +691,89 @@ impl AppConfig {
         if !other.diff_exclude_globs.is_empty() {
             self.diff_exclude_globs = other.diff_exclude_globs.clone();
         }
+        if !other.message_style.is_empty() {
+            self.message_style = normalize_message_style(&other.message_style);
+        }
+        self.post_process = other.post_process;
+        self.subject_max_length = other.subject_max_length;
+        self.lowercase_subject = other.lowercase_subject;
+        if !other.allowed_types.is_empty() {
+            self.allowed_types = other.allowed_types.clone();
+        }
+        if !other.allowed_scopes.is_empty() {
+            self.allowed_scopes = other.allowed_scopes.clone();
+        }
+        if !other.scope_map.is_empty() {
+            self.scope_map = other.scope_map.clone();
+        }
+        self.auto_scope = other.auto_scope;
+        self.gitmoji_deterministic = other.gitmoji_deterministic;
+        self.skip_ci = other.skip_ci;
+        if !other.skip_ci_token.is_empty() {
+            self.skip_ci_token = other.skip_ci_token.clone();
+        }
+        if !other.type_templates.is_empty() {
+            self.type_templates = other.type_templates.clone();
+        }
+        if !other.prompt_include.is_empty() {
+            self.prompt_include = other.prompt_include.clone();
+        }
+        if !other.prompt_exclude.is_empty() {
+            self.prompt_exclude = other.prompt_exclude.clone();
+        }
+        if !other.profile.is_empty() {
+            self.profile = other.profile.clone();
+        }
+        self.encrypt_secrets = other.encrypt_secrets;
+        self.max_payload_bytes = other.max_payload_bytes;
+        self.context_window = other.context_window;
+        if !other.reasoning_effort.is_empty() {
+            self.reasoning_effort = other.reasoning_effort.clone();
+        }
+        if !other.seed.is_empty() {
+            self.seed = other.seed.clone();
+        }
+        if !other.monthly_budget_usd.is_empty() {
+            self.monthly_budget_usd = other.monthly_budget_usd.clone();
+        }
+        if !other.budget_action.is_empty() {
+            self.budget_action = other.budget_action.clone();
+        }
+        self.usage_metrics = other.usage_metrics;
+        self.cache_max_commits = other.cache_max_commits;
+        self.cache_max_bytes = other.cache_max_bytes;
+        self.repo_history = other.repo_history;
+        self.auto_stash = other.auto_stash;
+        self.exclude_lfs = other.exclude_lfs;
+        self.pre_commit_hook = other.pre_commit_hook;
+        if !other.protected_branches.is_empty() {
+            self.protected_branches = other.protected_branches.clone();
+        }
+        if !other.push_remote.is_empty() {
+            self.push_remote = other.push_remote.clone();
+        }
+        self.auto_set_upstream = other.auto_set_upstream;
+        self.warn_default_branch = other.warn_default_branch;
+        if !other.update_channel.is_empty() {
+            self.update_channel = other.update_channel.clone();
+        }
+        self.update_check_hours = other.update_check_hours;
+        self.review_tui = other.review_tui;
+        self.show_diff = other.show_diff;
+        self.request_timeout_secs = other.request_timeout_secs;
+        self.ascii_ui = other.ascii_ui;
+        self.template_commands = other.template_commands;
+        if !other.preset.is_empty() {
+            self.preset = other.preset.clone();
+        }
+        if !other.routing.is_empty() {
+            self.routing = other.routing.clone();
+        }
+        if !other.fallback_policy.is_empty() {
+            self.fallback_policy = other.fallback_policy.clone();
+        }
+        self.fallback_max_attempts = other.fallback_max_attempts;
+        self.fallback_cooldown_mins = other.fallback_cooldown_mins;
     }
 
     fn apply_env_map(&mut self, map: &HashMap<String, String>, from_local: bool) {
@@ -291,14 +809,20 @@ impl AppConfig {
                         self.warn_staged_files_threshold =
                             parse_usize_or_default(val, default_warn_staged_files_threshold());
                     }
+                    "WARN_STAGED_FILES_MAX_LINES" => {
+                        self.warn_staged_files_max_lines =
+                            parse_usize_or_default(val, default_warn_staged_files_max_lines());
+                    }
+                    "WARN_STAGED_FILES_MAX_BYTES" => {
+                        self.warn_staged_files_max_bytes =
+                            parse_usize_or_default(val, default_warn_staged_files_max_bytes());
+                    }
                     "CONFIRM_NEW_VERSION" => {
                         self.confirm_new_version = val == "1" || val.eq_ignore_ascii_case("true")
                     }
-                    "AUTO_UPDATE" => {
-                        // auto_update is global-only; skip when reading from local .env
-                        if !from_local {
-                            self.auto_update = Some(val == "1" || val.eq_ignore_ascii_case("true"));
-                        }
+                    // auto_update is global-only; skip when reading from local .env
+                    "AUTO_UPDATE" if !from_local => {
+                        self.auto_update = Some(val == "1" || val.eq_ignore_ascii_case("true"));
                     }
                     "FALLBACK_ENABLED" => {
                         self.fallback_enabled = val == "1" || val.eq_ignore_ascii_case("true");
@@ -314,6 +838,108 @@ impl AppConfig {
                             .filter(|s| !s.is_empty())
                             .collect();
                     }
+                    "MESSAGE_STYLE" => self.message_style = normalize_message_style(val),
+                    "POST_PROCESS" => {
+                        self.post_process = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "SUBJECT_MAX_LENGTH" => {
+                        self.subject_max_length =
+                            parse_usize_or_default(val, default_subject_max_length());
+                    }
+                    "LOWERCASE_SUBJECT" => {
+                        self.lowercase_subject = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "ALLOWED_TYPES" => self.allowed_types = parse_list(val),
+                    "ALLOWED_SCOPES" => self.allowed_scopes = parse_list(val),
+                    "SCOPE_MAP" => self.scope_map = parse_list(val),
+                    "AUTO_SCOPE" => {
+                        self.auto_scope = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "GITMOJI_DETERMINISTIC" => {
+                        self.gitmoji_deterministic = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "SKIP_CI" => {
+                        self.skip_ci = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "SKIP_CI_TOKEN" => self.skip_ci_token = val.clone(),
+                    "TYPE_TEMPLATES" => self.type_templates = parse_type_templates(val),
+                    "PROMPT_INCLUDE" => self.prompt_include = parse_list(val),
+                    "PROMPT_EXCLUDE" => self.prompt_exclude = parse_list(val),
+                    "PROFILE" => self.profile = val.trim().to_string(),
+                    // encrypt_secrets is global-only; skip when reading from local .env
+                    "ENCRYPT_SECRETS" if !from_local => {
+                        self.encrypt_secrets = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "MAX_PAYLOAD_BYTES" => {
+                        self.max_payload_bytes =
+                            parse_usize_or_default(val, default_max_payload_bytes());
+                    }
+                    "CONTEXT_WINDOW" => {
+                        self.context_window = parse_usize_or_default(val, default_context_window());
+                    }
+                    "REASONING_EFFORT" => self.reasoning_effort = val.trim().to_string(),
+                    "SEED" => self.seed = val.trim().to_string(),
+                    "MONTHLY_BUDGET_USD" => self.monthly_budget_usd = val.trim().to_string(),
+                    "BUDGET_ACTION" => self.budget_action = val.trim().to_string(),
+                    "USAGE_METRICS" => {
+                        self.usage_metrics = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "CACHE_MAX_COMMITS" => {
+                        self.cache_max_commits =
+                            parse_usize_or_default(val, default_cache_max_commits());
+                    }
+                    "CACHE_MAX_BYTES" => {
+                        self.cache_max_bytes =
+                            parse_usize_or_default(val, default_cache_max_bytes());
+                    }
+                    "REPO_HISTORY" => {
+                        self.repo_history = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "AUTO_STASH" => {
+                        self.auto_stash = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "EXCLUDE_LFS" => {
+                        self.exclude_lfs = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "PRE_COMMIT_HOOK" => {
+                        self.pre_commit_hook = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "PROTECTED_BRANCHES" => self.protected_branches = parse_list(val),
+                    "PUSH_REMOTE" => self.push_remote = parse_list(val),
+                    "AUTO_SET_UPSTREAM" => {
+                        self.auto_set_upstream = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "WARN_DEFAULT_BRANCH" => {
+                        self.warn_default_branch = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "UPDATE_CHANNEL" if !from_local => self.update_channel = val.trim().to_string(),
+                    "UPDATE_CHECK_HOURS" => {
+                        self.update_check_hours =
+                            parse_usize_or_default(val, default_update_check_hours());
+                    }
+                    "REVIEW_TUI" => {
+                        self.review_tui = val == "1" || val.eq_ignore_ascii_case("true")
+                    }
+                    "SHOW_DIFF" => self.show_diff = val == "1" || val.eq_ignore_ascii_case("true"),
+                    "REQUEST_TIMEOUT_SECS" => {
+                        self.request_timeout_secs =
+                            parse_usize_or_default(val, default_request_timeout_secs());
+                    }
+                    "ASCII_UI" => self.ascii_ui = val == "1" || val.eq_ignore_ascii_case("true"),
+                    "TEMPLATE_COMMANDS" if !from_local => {
+                        self.template_commands = val == "1" || val.eq_ignore_ascii_case("true");
+                    }
+                    "PRESET" => self.preset = val.trim().to_string(),
+                    "ROUTING" => self.routing = val.trim().to_string(),
+                    "FALLBACK_POLICY" => self.fallback_policy = val.trim().to_string(),
+                    "FALLBACK_MAX_ATTEMPTS" => {
+                        self.fallback_max_attempts =
+                            parse_usize_or_default(val, default_fallback_max_attempts());
+                    }
+                    "FALLBACK_COOLDOWN_MINS" => {
+                        self.fallback_cooldown_mins =
+                            parse_usize_or_default(val, default_fallback_cooldown_mins());
+                    }
                     _ => {}
                 }
             }
@@ -327,10 +953,65 @@ impl AppConfig {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create {}", parent.display()))?;
         }
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        let toml::Value::Table(mut table) =
+            toml::Value::try_from(self).context("Failed to serialize config")?
+        else {
+            bail!("Failed to serialize config");
+        };
+        externalize_api_key(&mut table)?;
+        if path.exists() {
+            // Keep the directory profiles; they replace a top-level prompt profile
+            let sections = take_directory_profiles(&mut read_table(&path)?);
+            if !sections.is_empty() {
+                let sections = sections
+                    .into_iter()
+                    .map(|(pattern, section)| (pattern, toml::Value::Table(section)))
+                    .collect();
+                table.insert("profile".into(), toml::Value::Table(sections));
+            }
+        }
+        let content = toml::to_string_pretty(&table).context("Failed to serialize config")?;
         std::fs::write(&path, content)
             .with_context(|| format!("Failed to write {}", path.display()))?;
-        Ok(())
+        crate::credentials::reseal()?;
+        crate::preset::reseal_presets()
+    }
+
+    /// Save to the repository TOML file for `scope` (`.cgen.toml`, team or personal config),
+    /// leaving out global-only settings
+    pub fn save_repo(&self, scope: ConfigScope) -> Result<()> {
+        let root = crate::git::find_repo_root().context("Not in a git repository")?;
+        let path = repo_file_path(scope)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        if scope == ConfigScope::Personal {
+            ignore_personal_config(&path)?;
+        }
+        let toml::Value::Table(mut table) =
+            toml::Value::try_from(self).context("Failed to serialize config")?
+        else {
+            bail!("Failed to serialize config");
+        };
+        table.remove("config_version");
+        for suffix in GLOBAL_ONLY {
+            table.remove(field_name(suffix));
+        }
+        if scope == ConfigScope::Team {
+            for suffix in TEAM_EXCLUDED {
+                table.remove(field_name(suffix));
+            }
+            if !team_may_set("PROVIDER", &self.provider) {
+                table.remove("provider");
+            }
+        } else if let Some(toml::Value::String(key)) = table.get_mut("api_key") {
+            // Keep the key itself out of the repository
+            *key = crate::credentials::store(&local_credential_name(&root), key)?;
+        }
+        let content = toml::to_string_pretty(&table).context("Failed to serialize config")?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))
     }
 
     /// Save to local .env file in the git repo root
@@ -342,7 +1023,10 @@ impl AppConfig {
         lines.push(format!("ACR_PROVIDER={}", self.provider));
         lines.push(format!("ACR_MODEL={}", self.model));
         if !self.api_key.is_empty() {
-            lines.push(format!("ACR_API_KEY={}", self.api_key));
+            // Keep the key itself out of the repository
+            let reference =
+                crate::credentials::store(&local_credential_name(&root), &self.api_key)?;
+            lines.push(format!("ACR_API_KEY={reference}"));
         }
         if !self.api_url.is_empty() {
             lines.push(format!("ACR_API_URL={}", self.api_url));
@@ -390,11 +1074,19 @@ impl AppConfig {
             "ACR_WARN_STAGED_FILES_THRESHOLD={}",
             self.warn_staged_files_threshold
         ));
+        lines.push(format!(
+            "ACR_WARN_STAGED_FILES_MAX_LINES={}",
+            self.warn_staged_files_max_lines
+        ));
+        lines.push(format!(
+            "ACR_WARN_STAGED_FILES_MAX_BYTES={}",
+            self.warn_staged_files_max_bytes
+        ));
         lines.push(format!(
             "ACR_CONFIRM_NEW_VERSION={}",
             if self.confirm_new_version { "1" } else { "0" }
         ));
-        // auto_update is global-only, not written to local .env
+        // auto_update and update_channel are global-only, not written to local .env
         lines.push(format!(
             "ACR_FALLBACK_ENABLED={}",
             if self.fallback_enabled { "1" } else { "0" }
@@ -413,6 +1105,166 @@ impl AppConfig {
                 self.diff_exclude_globs.join(",")
             ));
         }
+        lines.push(format!(
+            "ACR_MESSAGE_STYLE={}",
+            normalize_message_style(&self.message_style)
+        ));
+        lines.push(format!(
+            "ACR_POST_PROCESS={}",
+            if self.post_process { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_SUBJECT_MAX_LENGTH={}",
+            self.subject_max_length
+        ));
+        lines.push(format!(
+            "ACR_LOWERCASE_SUBJECT={}",
+            if self.lowercase_subject { "1" } else { "0" }
+        ));
+        if !self.allowed_types.is_empty() {
+            lines.push(format!(
+                "ACR_ALLOWED_TYPES={}",
+                self.allowed_types.join(",")
+            ));
+        }
+        if !self.allowed_scopes.is_empty() {
+            lines.push(format!(
+                "ACR_ALLOWED_SCOPES={}",
+                self.allowed_scopes.join(",")
+            ));
+        }
+        if !self.scope_map.is_empty() {
+            lines.push(format!("ACR_SCOPE_MAP={}", self.scope_map.join(",")));
+        }
+        lines.push(format!(
+            "ACR_AUTO_SCOPE={}",
+            if self.auto_scope { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_GITMOJI_DETERMINISTIC={}",
+            if self.gitmoji_deterministic { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_SKIP_CI={}",
+            if self.skip_ci { "1" } else { "0" }
+        ));
+        if self.skip_ci_token != default_skip_ci_token() {
+            lines.push(format!("ACR_SKIP_CI_TOKEN={}", self.skip_ci_token));
+        }
+        if !self.type_templates.is_empty() {
+            lines.push(format!(
+                "ACR_TYPE_TEMPLATES={}",
+                format_type_templates(&self.type_templates)
+            ));
+        }
+        if !self.prompt_include.is_empty() {
+            lines.push(format!(
+                "ACR_PROMPT_INCLUDE={}",
+                self.prompt_include.join(",")
+            ));
+        }
+        if !self.prompt_exclude.is_empty() {
+            lines.push(format!(
+                "ACR_PROMPT_EXCLUDE={}",
+                self.prompt_exclude.join(",")
+            ));
+        }
+        if !self.profile.is_empty() {
+            lines.push(format!("ACR_PROFILE={}", self.profile));
+        }
+        lines.push(format!("ACR_MAX_PAYLOAD_BYTES={}", self.max_payload_bytes));
+        lines.push(format!("ACR_CONTEXT_WINDOW={}", self.context_window));
+        if !self.reasoning_effort.is_empty() {
+            lines.push(format!("ACR_REASONING_EFFORT={}", self.reasoning_effort));
+        }
+        if !self.seed.is_empty() {
+            lines.push(format!("ACR_SEED={}", self.seed));
+        }
+        if !self.monthly_budget_usd.is_empty() {
+            lines.push(format!(
+                "ACR_MONTHLY_BUDGET_USD={}",
+                self.monthly_budget_usd
+            ));
+        }
+        if !self.budget_action.is_empty() {
+            lines.push(format!("ACR_BUDGET_ACTION={}", self.budget_action));
+        }
+        lines.push(format!(
+            "ACR_USAGE_METRICS={}",
+            if self.usage_metrics { "1" } else { "0" }
+        ));
+        lines.push(format!("ACR_CACHE_MAX_COMMITS={}", self.cache_max_commits));
+        lines.push(format!("ACR_CACHE_MAX_BYTES={}", self.cache_max_bytes));
+        lines.push(format!(
+            "ACR_REPO_HISTORY={}",
+            if self.repo_history { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_AUTO_STASH={}",
+            if self.auto_stash { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_EXCLUDE_LFS={}",
+            if self.exclude_lfs { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_PRE_COMMIT_HOOK={}",
+            if self.pre_commit_hook { "1" } else { "0" }
+        ));
+        if !self.protected_branches.is_empty() {
+            lines.push(format!(
+                "ACR_PROTECTED_BRANCHES={}",
+                self.protected_branches.join(",")
+            ));
+        }
+        if !self.push_remote.is_empty() {
+            lines.push(format!("ACR_PUSH_REMOTE={}", self.push_remote.join(",")));
+        }
+        lines.push(format!(
+            "ACR_AUTO_SET_UPSTREAM={}",
+            if self.auto_set_upstream { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_WARN_DEFAULT_BRANCH={}",
+            if self.warn_default_branch { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_UPDATE_CHECK_HOURS={}",
+            self.update_check_hours
+        ));
+        lines.push(format!(
+            "ACR_REVIEW_TUI={}",
+            if self.review_tui { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_SHOW_DIFF={}",
+            if self.show_diff { "1" } else { "0" }
+        ));
+        lines.push(format!(
+            "ACR_REQUEST_TIMEOUT_SECS={}",
+            self.request_timeout_secs
+        ));
+        lines.push(format!(
+            "ACR_ASCII_UI={}",
+            if self.ascii_ui { "1" } else { "0" }
+        ));
+        if !self.preset.is_empty() {
+            lines.push(format!("ACR_PRESET={}", self.preset));
+        }
+        if !self.routing.is_empty() {
+            lines.push(format!("ACR_ROUTING={}", self.routing));
+        }
+        if !self.fallback_policy.is_empty() {
+            lines.push(format!("ACR_FALLBACK_POLICY={}", self.fallback_policy));
+        }
+        lines.push(format!(
+            "ACR_FALLBACK_MAX_ATTEMPTS={}",
+            self.fallback_max_attempts
+        ));
+        lines.push(format!(
+            "ACR_FALLBACK_COOLDOWN_MINS={}",
+            self.fallback_cooldown_mins
+        ));
 
         std::fs::write(&env_path, lines.join("\n") + "\n")
             .with_context(|| format!("Failed to write {}", env_path.display()))?;
@@ -522,6 +1374,16 @@ impl AppConfig {
                 "WARN_STAGED_FILES_THRESHOLD",
                 self.warn_staged_files_threshold.to_string(),
             ),
+            (
+                "Staged Lines Warn Threshold",
+                "WARN_STAGED_FILES_MAX_LINES",
+                self.warn_staged_files_max_lines.to_string(),
+            ),
+            (
+                "Staged Bytes Warn Threshold",
+                "WARN_STAGED_FILES_MAX_BYTES",
+                self.warn_staged_files_max_bytes.to_string(),
+            ),
             (
                 "Confirm New Version",
                 "CONFIRM_NEW_VERSION",
@@ -555,18 +1417,379 @@ impl AppConfig {
                 if self.track_generated_commits {
                     "enabled".into()
                 } else {
-                    "disabled".into()
+                    "disabled".into()
+                },
+            ),
+            (
+                "Diff Exclude Globs",
+                "DIFF_EXCLUDE_GLOBS",
+                if self.diff_exclude_globs.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.diff_exclude_globs.join(", ")
+                },
+            ),
+            (
+                "Message Style",
+                "MESSAGE_STYLE",
+                normalize_message_style(&self.message_style),
+            ),
+            (
+                "Post-process Messages",
+                "POST_PROCESS",
+                if self.post_process {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Subject Max Length",
+                "SUBJECT_MAX_LENGTH",
+                if self.subject_max_length == 0 {
+                    "(no limit)".into()
+                } else {
+                    self.subject_max_length.to_string()
+                },
+            ),
+            (
+                "Lowercase Subject",
+                "LOWERCASE_SUBJECT",
+                if self.lowercase_subject {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Allowed Types",
+                "ALLOWED_TYPES",
+                if self.allowed_types.is_empty() {
+                    "(any)".into()
+                } else {
+                    self.allowed_types.join(", ")
+                },
+            ),
+            (
+                "Allowed Scopes",
+                "ALLOWED_SCOPES",
+                if self.allowed_scopes.is_empty() {
+                    "(any)".into()
+                } else {
+                    self.allowed_scopes.join(", ")
+                },
+            ),
+            (
+                "Scope Map",
+                "SCOPE_MAP",
+                if self.scope_map.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.scope_map.join(", ")
+                },
+            ),
+            (
+                "Auto Scope",
+                "AUTO_SCOPE",
+                if self.auto_scope {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Deterministic Gitmoji",
+                "GITMOJI_DETERMINISTIC",
+                if self.gitmoji_deterministic {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Skip CI",
+                "SKIP_CI",
+                if self.skip_ci {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            ("Skip CI Token", "SKIP_CI_TOKEN", self.skip_ci_token.clone()),
+            (
+                "Per-type Templates",
+                "TYPE_TEMPLATES",
+                if self.type_templates.is_empty() {
+                    "(none)".into()
+                } else {
+                    format_type_templates(&self.type_templates)
+                },
+            ),
+            (
+                "Prompt Snippets Include",
+                "PROMPT_INCLUDE",
+                if self.prompt_include.is_empty() {
+                    "(all)".into()
+                } else {
+                    self.prompt_include.join(", ")
+                },
+            ),
+            (
+                "Prompt Snippets Exclude",
+                "PROMPT_EXCLUDE",
+                if self.prompt_exclude.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.prompt_exclude.join(", ")
+                },
+            ),
+            (
+                "Prompt Profile",
+                "PROFILE",
+                if self.profile.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.profile.clone()
+                },
+            ),
+            (
+                "Encrypt Secrets",
+                "ENCRYPT_SECRETS",
+                if self.encrypt_secrets {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Max Payload Bytes",
+                "MAX_PAYLOAD_BYTES",
+                self.max_payload_bytes.to_string(),
+            ),
+            (
+                "Context Window",
+                "CONTEXT_WINDOW",
+                self.context_window.to_string(),
+            ),
+            (
+                "Reasoning Effort",
+                "REASONING_EFFORT",
+                if self.reasoning_effort.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.reasoning_effort.clone()
+                },
+            ),
+            (
+                "Seed",
+                "SEED",
+                if self.seed.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.seed.clone()
+                },
+            ),
+            (
+                "Monthly Budget (USD)",
+                "MONTHLY_BUDGET_USD",
+                if self.monthly_budget_usd.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.monthly_budget_usd.clone()
+                },
+            ),
+            (
+                "Budget Action",
+                "BUDGET_ACTION",
+                if self.budget_action.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.budget_action.clone()
+                },
+            ),
+            (
+                "Usage Metrics",
+                "USAGE_METRICS",
+                if self.usage_metrics {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "History Max Commits",
+                "CACHE_MAX_COMMITS",
+                self.cache_max_commits.to_string(),
+            ),
+            (
+                "History Max Size",
+                "CACHE_MAX_BYTES",
+                self.cache_max_bytes.to_string(),
+            ),
+            (
+                "Repo History File",
+                "REPO_HISTORY",
+                if self.repo_history {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Auto Stash",
+                "AUTO_STASH",
+                if self.auto_stash {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Exclude LFS Files",
+                "EXCLUDE_LFS",
+                if self.exclude_lfs {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Run Pre-commit Hook First",
+                "PRE_COMMIT_HOOK",
+                if self.pre_commit_hook {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Protected Branches",
+                "PROTECTED_BRANCHES",
+                if self.protected_branches.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.protected_branches.join(", ")
+                },
+            ),
+            (
+                "Push Remotes",
+                "PUSH_REMOTE",
+                if self.push_remote.is_empty() {
+                    "(default)".into()
+                } else {
+                    self.push_remote.join(", ")
+                },
+            ),
+            (
+                "Auto Set Upstream",
+                "AUTO_SET_UPSTREAM",
+                if self.auto_set_upstream {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Warn On Default Branch",
+                "WARN_DEFAULT_BRANCH",
+                if self.warn_default_branch {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Update Channel",
+                "UPDATE_CHANNEL",
+                if self.update_channel.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.update_channel.clone()
+                },
+            ),
+            (
+                "Update Check Interval (hours)",
+                "UPDATE_CHECK_HOURS",
+                self.update_check_hours.to_string(),
+            ),
+            (
+                "Full-screen Review",
+                "REVIEW_TUI",
+                if self.review_tui {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Show Diff Before Generating",
+                "SHOW_DIFF",
+                if self.show_diff {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Request Timeout (seconds)",
+                "REQUEST_TIMEOUT_SECS",
+                self.request_timeout_secs.to_string(),
+            ),
+            (
+                "ASCII UI",
+                "ASCII_UI",
+                if self.ascii_ui {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Template Commands",
+                "TEMPLATE_COMMANDS",
+                if self.template_commands {
+                    "enabled".into()
+                } else {
+                    "disabled".into()
+                },
+            ),
+            (
+                "Preset",
+                "PRESET",
+                if self.preset.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.preset.clone()
+                },
+            ),
+            (
+                "Routing",
+                "ROUTING",
+                if self.routing.is_empty() {
+                    "(none)".into()
+                } else {
+                    self.routing.clone()
                 },
             ),
             (
-                "Diff Exclude Globs",
-                "DIFF_EXCLUDE_GLOBS",
-                if self.diff_exclude_globs.is_empty() {
+                "Fallback Policy",
+                "FALLBACK_POLICY",
+                if self.fallback_policy.is_empty() {
                     "(none)".into()
                 } else {
-                    self.diff_exclude_globs.join(", ")
+                    self.fallback_policy.clone()
                 },
             ),
+            (
+                "Fallback Max Attempts",
+                "FALLBACK_MAX_ATTEMPTS",
+                self.fallback_max_attempts.to_string(),
+            ),
+            (
+                "Fallback Cooldown (min)",
+                "FALLBACK_COOLDOWN_MINS",
+                self.fallback_cooldown_mins.to_string(),
+            ),
         ]
     }
 
@@ -584,22 +1807,72 @@ impl AppConfig {
             "LOCALE",
             "LLM_SYSTEM_PROMPT",
             "COMMIT_TEMPLATE",
+            "TYPE_TEMPLATES",
             "FALLBACK_ENABLED",
+            "FALLBACK_POLICY",
+            "FALLBACK_MAX_ATTEMPTS",
+            "FALLBACK_COOLDOWN_MINS",
             "DIFF_EXCLUDE_GLOBS",
+            "EXCLUDE_LFS",
+            "PROMPT_INCLUDE",
+            "PROMPT_EXCLUDE",
+            "PROFILE",
+            "ENCRYPT_SECRETS",
+            "MAX_PAYLOAD_BYTES",
+            "CONTEXT_WINDOW",
+            "REASONING_EFFORT",
+            "SEED",
+            "REQUEST_TIMEOUT_SECS",
+            "TEMPLATE_COMMANDS",
+            "PRESET",
+            "ROUTING",
         ];
         let commit_keys: &[&'static str] = &[
             "ONE_LINER",
             "USE_GITMOJI",
             "GITMOJI_FORMAT",
+            "GITMOJI_DETERMINISTIC",
             "REVIEW_COMMIT",
+            "REVIEW_TUI",
+            "SHOW_DIFF",
+            "ASCII_UI",
             "TRACK_GENERATED_COMMITS",
+            "CACHE_MAX_COMMITS",
+            "CACHE_MAX_BYTES",
+            "REPO_HISTORY",
+            "USAGE_METRICS",
+            "AUTO_STASH",
+            "PRE_COMMIT_HOOK",
+            "MESSAGE_STYLE",
+            "POST_PROCESS",
+            "SUBJECT_MAX_LENGTH",
+            "LOWERCASE_SUBJECT",
+            "ALLOWED_TYPES",
+            "ALLOWED_SCOPES",
+            "SCOPE_MAP",
+            "AUTO_SCOPE",
+            "SKIP_CI",
+            "SKIP_CI_TOKEN",
+        ];
+        let post_commit_keys: &[&'static str] = &[
+            "POST_COMMIT_PUSH",
+            "PUSH_REMOTE",
+            "AUTO_SET_UPSTREAM",
+            "SUPPRESS_TOOL_OUTPUT",
         ];
-        let post_commit_keys: &[&'static str] = &["POST_COMMIT_PUSH", "SUPPRESS_TOOL_OUTPUT"];
         let warnings_keys: &[&'static str] = &[
             "WARN_STAGED_FILES_ENABLED",
             "WARN_STAGED_FILES_THRESHOLD",
+            "WARN_STAGED_FILES_MAX_LINES",
+            "WARN_STAGED_FILES_MAX_BYTES",
             "CONFIRM_NEW_VERSION",
+            "PROTECTED_BRANCHES",
+            "WARN_DEFAULT_BRANCH",
             "AUTO_UPDATE",
+            "UPDATE_CHANNEL",
+            "UPDATE_CHECK_HOURS",
+            "MONTHLY_BUDGET_USD",
+            "BUDGET_ACTION",
         ];
 
         let collect = |keys: &[&'static str]| -> Vec<(&'static str, &'static str, String)> {
@@ -651,6 +1924,7 @@ impl AppConfig {
                 let locale = normalize_locale(value);
                 validate_locale(&locale)?;
                 self.locale = locale;
+                self.locale_configured = true;
             }
             "ONE_LINER" => self.one_liner = value == "1" || value.eq_ignore_ascii_case("true"),
             "COMMIT_TEMPLATE" => self.commit_template = value.into(),
@@ -671,6 +1945,14 @@ impl AppConfig {
                 self.warn_staged_files_threshold =
                     parse_usize_or_default(value, default_warn_staged_files_threshold());
             }
+            "WARN_STAGED_FILES_MAX_LINES" => {
+                self.warn_staged_files_max_lines =
+                    parse_usize_or_default(value, default_warn_staged_files_max_lines());
+            }
+            "WARN_STAGED_FILES_MAX_BYTES" => {
+                self.warn_staged_files_max_bytes =
+                    parse_usize_or_default(value, default_warn_staged_files_max_bytes());
+            }
             "CONFIRM_NEW_VERSION" => {
                 self.confirm_new_version = value == "1" || value.eq_ignore_ascii_case("true");
             }
@@ -690,17 +1972,281 @@ impl AppConfig {
                     .filter(|s| !s.is_empty())
                     .collect();
             }
+            "MESSAGE_STYLE" => self.message_style = normalize_message_style(value),
+            "POST_PROCESS" => {
+                self.post_process = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "SUBJECT_MAX_LENGTH" => {
+                self.subject_max_length =
+                    parse_usize_or_default(value, default_subject_max_length());
+            }
+            "LOWERCASE_SUBJECT" => {
+                self.lowercase_subject = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "ALLOWED_TYPES" => self.allowed_types = parse_list(value),
+            "ALLOWED_SCOPES" => self.allowed_scopes = parse_list(value),
+            "SCOPE_MAP" => self.scope_map = parse_list(value),
+            "AUTO_SCOPE" => {
+                self.auto_scope = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "GITMOJI_DETERMINISTIC" => {
+                self.gitmoji_deterministic = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "SKIP_CI" => {
+                self.skip_ci = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "SKIP_CI_TOKEN" => self.skip_ci_token = value.into(),
+            "TYPE_TEMPLATES" => self.type_templates = parse_type_templates(value),
+            "PROMPT_INCLUDE" => self.prompt_include = parse_list(value),
+            "PROMPT_EXCLUDE" => self.prompt_exclude = parse_list(value),
+            "PROFILE" => self.profile = value.trim().to_string(),
+            "ENCRYPT_SECRETS" => {
+                self.encrypt_secrets = value == "1" || value.eq_ignore_ascii_case("true");
+            }
+            "MAX_PAYLOAD_BYTES" => {
+                self.max_payload_bytes = parse_usize_or_default(value, default_max_payload_bytes());
+            }
+            "CONTEXT_WINDOW" => {
+                self.context_window = parse_usize_or_default(value, default_context_window());
+            }
+            "REASONING_EFFORT" => self.reasoning_effort = value.trim().to_string(),
+            "SEED" => self.seed = value.trim().to_string(),
+            "MONTHLY_BUDGET_USD" => self.monthly_budget_usd = value.trim().to_string(),
+            "BUDGET_ACTION" => self.budget_action = value.trim().to_string(),
+            "USAGE_METRICS" => {
+                self.usage_metrics = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "CACHE_MAX_COMMITS" => {
+                self.cache_max_commits = parse_usize_or_default(value, default_cache_max_commits());
+            }
+            "CACHE_MAX_BYTES" => {
+                self.cache_max_bytes = parse_usize_or_default(value, default_cache_max_bytes());
+            }
+            "REPO_HISTORY" => {
+                self.repo_history = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "AUTO_STASH" => self.auto_stash = value == "1" || value.eq_ignore_ascii_case("true"),
+            "EXCLUDE_LFS" => self.exclude_lfs = value == "1" || value.eq_ignore_ascii_case("true"),
+            "PRE_COMMIT_HOOK" => {
+                self.pre_commit_hook = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "PROTECTED_BRANCHES" => self.protected_branches = parse_list(value),
+            "PUSH_REMOTE" => self.push_remote = parse_list(value),
+            "AUTO_SET_UPSTREAM" => {
+                self.auto_set_upstream = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "WARN_DEFAULT_BRANCH" => {
+                self.warn_default_branch = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "UPDATE_CHANNEL" => self.update_channel = value.trim().to_string(),
+            "UPDATE_CHECK_HOURS" => {
+                self.update_check_hours =
+                    parse_usize_or_default(value, default_update_check_hours());
+            }
+            "REVIEW_TUI" => self.review_tui = value == "1" || value.eq_ignore_ascii_case("true"),
+            "SHOW_DIFF" => self.show_diff = value == "1" || value.eq_ignore_ascii_case("true"),
+            "REQUEST_TIMEOUT_SECS" => {
+                self.request_timeout_secs =
+                    parse_usize_or_default(value, default_request_timeout_secs());
+            }
+            "ASCII_UI" => self.ascii_ui = value == "1" || value.eq_ignore_ascii_case("true"),
+            "TEMPLATE_COMMANDS" => {
+                self.template_commands = value == "1" || value.eq_ignore_ascii_case("true")
+            }
+            "PRESET" => self.preset = value.trim().to_string(),
+            "ROUTING" => self.routing = value.trim().to_string(),
+            "FALLBACK_POLICY" => self.fallback_policy = value.trim().to_string(),
+            "FALLBACK_MAX_ATTEMPTS" => {
+                self.fallback_max_attempts =
+                    parse_usize_or_default(value, default_fallback_max_attempts());
+            }
+            "FALLBACK_COOLDOWN_MINS" => {
+                self.fallback_cooldown_mins =
+                    parse_usize_or_default(value, default_fallback_cooldown_mins());
+            }
             _ => {}
         }
         Ok(())
     }
 
+    /// Current value of a setting in `set_field` form, or `None` when it has no value
+    pub fn setting_value(&self, suffix: &str) -> Option<String> {
+        let toml::Value::Table(all) = toml::Value::try_from(self).ok()? else {
+            return None;
+        };
+        all.get(field_name(suffix)).map(toml_to_field_string)
+    }
+
+    /// Apply the `[profile."<glob>"]` sections matching the current repository (or working
+    /// directory). Longer patterns are applied last, so the most specific section wins.
+    fn apply_directory_profiles(&mut self, sections: &[(String, toml::Table)]) -> Result<()> {
+        let dir = match crate::git::find_repo_root() {
+            Ok(root) => PathBuf::from(root),
+            Err(_) => std::env::current_dir().context("Failed to read current directory")?,
+        };
+        let mut matching: Vec<&(String, toml::Table)> = sections
+            .iter()
+            .filter(|(pattern, _)| directory_pattern_matches(pattern, &dir))
+            .collect();
+        matching.sort_by_key(|(pattern, _)| pattern.len());
+
+        for (pattern, section) in matching {
+            let env_map: HashMap<String, String> = table_settings(section)
+                .into_iter()
+                .filter(|(suffix, _)| suffix != "API_KEY")
+                .map(|(suffix, value)| (format!("ACR_{suffix}"), value))
+                .collect();
+            self.locale_configured |= env_map.contains_key("ACR_LOCALE");
+            let source = format!("[profile.\"{pattern}\"]");
+            log_layer(&source, &env_map);
+            self.apply_layer_preset(&env_map, &source)?;
+            self.apply_env_map(&env_map, true);
+        }
+        Ok(())
+    }
+
     fn ensure_valid_locale(&mut self) -> Result<()> {
         self.locale = normalize_locale(&self.locale);
         validate_locale(&self.locale)
     }
 }
 
+/// Repository config file, same schema as the global `config.toml`
+pub const REPO_CONFIG_FILE: &str = ".cgen.toml";
+
+/// Committed team defaults, applied under every other repository file. Never holds API keys.
+pub const TEAM_CONFIG_FILE: &str = ".cgen/config.toml";
+
+/// Per-user overrides of the team defaults, kept out of git by `.cgen/.gitignore`
+pub const PERSONAL_CONFIG_FILE: &str = ".cgen/config.local.toml";
+
+/// Schema version written by this build; bump it and append to `MIGRATIONS` when a key is
+/// renamed or its value format changes
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a global config table from version `n` to `n + 1`
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[migrate_v0_value_types];
+
+/// Read the global config, upgrading it to `CONFIG_VERSION` first and moving a plain-text API
+/// key into the credentials file. A changed file is written back once; an upgrade first keeps
+/// the original as `config.toml.v<old>.bak`, readable by the owner only since it may hold the key.
+fn load_global_table(path: &PathBuf) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut table: toml::Table = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let version = migrate_table(&mut table);
+    let externalized = externalize_plain_api_key(&mut table);
+    if version < CONFIG_VERSION {
+        let backup = path.with_extension(format!("toml.v{version}.bak"));
+        crate::credentials::write_private(&backup, &content)?;
+    }
+    if version < CONFIG_VERSION || externalized {
+        let migrated = toml::to_string_pretty(&table).context("Failed to serialize config")?;
+        std::fs::write(path, migrated)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(table)
+}
+
+/// Remove the `[profile."<glob>"]` sections from a global config table. A plain
+/// `profile = "name"` (the prompt profile) is a string and stays in place.
+pub fn take_directory_profiles(table: &mut toml::Table) -> Vec<(String, toml::Table)> {
+    if !matches!(table.get("profile"), Some(toml::Value::Table(_))) {
+        return Vec::new();
+    }
+    let Some(toml::Value::Table(sections)) = table.remove("profile") else {
+        return Vec::new();
+    };
+    sections
+        .into_iter()
+        .filter_map(|(pattern, value)| match value {
+            toml::Value::Table(section) => Some((pattern, section)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a directory profile pattern such as `~/work/**` matches `dir`. A leading `~` is the
+/// home directory; `*` does not cross `/`.
+pub fn directory_pattern_matches(pattern: &str, dir: &std::path::Path) -> bool {
+    let expanded = match pattern.strip_prefix('~') {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => format!("{}{rest}", home.display()),
+            None => return false,
+        },
+        None => pattern.to_string(),
+    };
+    let Ok(glob) = glob::Pattern::new(expanded.trim_end_matches('/')) else {
+        return false;
+    };
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    glob.matches_path_with(dir, options)
+}
+
+/// Apply every migration newer than the table's `config_version` and stamp the current
+/// version. Returns the version the table had before. Tables from a newer cgen are left as is.
+pub fn migrate_table(table: &mut toml::Table) -> u32 {
+    let version = table
+        .get("config_version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0);
+    if version >= CONFIG_VERSION {
+        return version;
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(table);
+    }
+    table.insert(
+        "config_version".into(),
+        toml::Value::Integer(CONFIG_VERSION.into()),
+    );
+    version
+}
+
+/// Version 0 files were hand-edited as freely as `.env` files: coerce `"1"`/`"true"` strings to
+/// booleans, numeric strings to integers, comma-separated strings to lists, a boolean
+/// `post_commit_push` to `always`/`never`, and normalize the locale spelling
+fn migrate_v0_value_types(table: &mut toml::Table) {
+    let Ok(toml::Value::Table(defaults)) = toml::Value::try_from(AppConfig::default()) else {
+        return;
+    };
+    for (_, field) in ENV_FIELD_MAP {
+        let Some(value) = table.get_mut(*field) else {
+            continue;
+        };
+        // auto_update has no default value, so it is absent from the defaults table
+        let expected = defaults
+            .get(*field)
+            .map_or("boolean", toml::Value::type_str);
+        let coerced = match (expected, &*value) {
+            ("boolean", toml::Value::String(s)) => Some(toml::Value::Boolean(
+                s == "1" || s.eq_ignore_ascii_case("true"),
+            )),
+            ("integer", toml::Value::String(s)) => s.trim().parse().ok().map(toml::Value::Integer),
+            ("array", toml::Value::String(s)) => Some(toml::Value::Array(
+                parse_list(s).into_iter().map(toml::Value::String).collect(),
+            )),
+            ("string", toml::Value::Boolean(b)) if *field == "post_commit_push" => Some(
+                toml::Value::String(if *b { "always" } else { "never" }.into()),
+            ),
+            _ => None,
+        };
+        if let Some(coerced) = coerced {
+            *value = coerced;
+        }
+    }
+    if let Some(toml::Value::String(locale)) = table.get_mut("locale") {
+        *locale = normalize_locale(locale);
+    }
+}
+
 /// Global config file path
 pub fn global_config_path() -> Option<PathBuf> {
     if let Some(override_dir) = std::env::var_os("ACR_CONFIG_HOME") {
@@ -714,17 +2260,48 @@ pub fn global_config_path() -> Option<PathBuf> {
 
 /// Save only the auto_update preference to global config without overwriting other fields
 pub fn save_auto_update_preference(value: bool) -> Result<()> {
+    save_global_value("auto_update", Some(toml::Value::Boolean(value)))
+}
+
+/// Save only the locale to global config without overwriting other fields
+pub fn save_locale_preference(locale: &str) -> Result<()> {
+    save_global_value("locale", Some(toml::Value::String(locale.to_string())))
+}
+
+/// Set (or remove, with `None`) a single key in the global config without touching the others
+pub fn save_global_value(key: &str, value: Option<toml::Value>) -> Result<()> {
     let path = global_config_path().context("Could not determine global config directory")?;
 
     let mut table: toml::Table = if path.exists() {
         let content = std::fs::read_to_string(&path)
             .with_context(|| format!("Failed to read {}", path.display()))?;
-        content.parse().unwrap_or_default()
+        // Never replace a file that fails to parse with just this one key
+        content
+            .parse()
+            .with_context(|| format!("Failed to parse {}", path.display()))?
     } else {
-        toml::Table::new()
+        toml::Table::from_iter([(
+            "config_version".to_string(),
+            toml::Value::Integer(CONFIG_VERSION.into()),
+        )])
     };
 
-    table.insert("auto_update".to_string(), toml::Value::Boolean(value));
+    if key == "profile" && matches!(table.get(key), Some(toml::Value::Table(_))) {
+        bail!(
+            "{} has directory profiles under `profile`; set the prompt profile inside a \
+             [profile.\"<dir>\"] section instead",
+            path.display()
+        );
+    }
+    match value {
+        Some(value) => {
+            table.insert(key.to_string(), value);
+        }
+        None => {
+            table.remove(key);
+        }
+    }
+    externalize_api_key(&mut table)?;
 
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
@@ -734,15 +2311,370 @@ pub fn save_auto_update_preference(value: bool) -> Result<()> {
     let content = toml::to_string_pretty(&table).context("Failed to serialize config")?;
     std::fs::write(&path, content)
         .with_context(|| format!("Failed to write {}", path.display()))?;
+    if key == "encrypt_secrets" {
+        crate::credentials::reseal()?;
+        crate::preset::reseal_presets()?;
+    }
+    Ok(())
+}
+
+/// Whether the global config asks for API keys to be stored encrypted
+pub fn secrets_encryption_enabled() -> bool {
+    global_config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| content.parse::<toml::Table>().ok())
+        .and_then(|table| table.get("encrypt_secrets")?.as_bool())
+        .unwrap_or(false)
+}
+
+/// Move a plain-text `api_key` left in a global config table (by hand or by an older cgen) into
+/// the credentials file. Returns whether the table changed. Encrypted keys stay where they are
+/// rather than asking for the passphrase while loading, and a failure only leaves the key in place.
+fn externalize_plain_api_key(table: &mut toml::Table) -> bool {
+    let Some(toml::Value::String(key)) = table.get_mut("api_key") else {
+        return false;
+    };
+    if key.is_empty() || crate::credentials::is_reference(key) || crate::secret::is_encrypted(key) {
+        return false;
+    }
+    match crate::credentials::store(crate::credentials::GLOBAL_NAME, key) {
+        Ok(reference) => {
+            *key = reference;
+            true
+        }
+        Err(e) => {
+            crate::ui::log_event!("config: could not move the API key to credentials.toml: {e:#}");
+            false
+        }
+    }
+}
+
+/// Move a plain `api_key` in a global config table into the credentials file
+fn externalize_api_key(table: &mut toml::Table) -> Result<()> {
+    if let Some(toml::Value::String(key)) = table.get_mut("api_key") {
+        *key = crate::credentials::store(crate::credentials::GLOBAL_NAME, key)?;
+    }
     Ok(())
 }
 
-fn mask_key(key: &str) -> String {
-    if key.len() <= 8 {
-        "*".repeat(key.len())
+/// Resolve a setting name as users type it (`model`, `MODEL`, `ACR_MODEL`, `api-key`) to its
+/// env suffix
+pub fn resolve_setting(key: &str) -> Result<&'static str> {
+    let normalized = key.trim().replace('-', "_").to_ascii_uppercase();
+    let suffix = normalized.strip_prefix("ACR_").unwrap_or(&normalized);
+    ENV_FIELD_MAP
+        .iter()
+        .find(|(s, _)| *s == suffix)
+        .map(|(s, _)| *s)
+        .with_context(|| {
+            format!("Unknown setting '{key}'. Run `cgen config list` to see all settings")
+        })
+}
+
+fn field_name(suffix: &str) -> &'static str {
+    ENV_FIELD_MAP
+        .iter()
+        .find(|(s, _)| *s == suffix)
+        .map(|(_, field)| *field)
+        .unwrap_or_default()
+}
+
+/// Render a TOML value in the string form `set_field` and `.env` files use
+pub fn toml_to_field_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Boolean(b) => if *b { "1" } else { "0" }.to_string(),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(toml_to_field_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        toml::Value::Table(table) => format_type_templates(
+            &table
+                .iter()
+                .map(|(k, v)| (k.clone(), toml_to_field_string(v)))
+                .collect(),
+        ),
+        other => other.to_string(),
+    }
+}
+
+/// Where a single setting is read from or written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+    /// The global `config.toml`
+    Global,
+    /// The committed `.cgen/config.toml`
+    Team,
+    /// The repository `.cgen.toml`
+    Repo,
+    /// The uncommitted `.cgen/config.local.toml`
+    Personal,
+    /// The repository `.env`
+    Env,
+}
+
+impl ConfigScope {
+    /// TOML file for this scope, relative to the repository root
+    pub fn repo_file(self) -> Option<&'static str> {
+        match self {
+            ConfigScope::Team => Some(TEAM_CONFIG_FILE),
+            ConfigScope::Repo => Some(REPO_CONFIG_FILE),
+            ConfigScope::Personal => Some(PERSONAL_CONFIG_FILE),
+            ConfigScope::Global | ConfigScope::Env => None,
+        }
+    }
+}
+
+/// Repository TOML files in the order they are applied
+pub const REPO_LAYERS: [ConfigScope; 3] =
+    [ConfigScope::Team, ConfigScope::Repo, ConfigScope::Personal];
+
+/// Settings whose values may contain `$(command)`s (with `ACR_TEMPLATE_COMMANDS`)
+pub const COMMAND_SETTINGS: &[&str] = &[
+    "COMMIT_TEMPLATE",
+    "TYPE_TEMPLATES",
+    "API_URL",
+    "API_HEADERS",
+];
+
+/// Settings only honored in the global config
+/// Settings the committed team config may not set: the API key, and where it is sent
+pub(crate) const TEAM_EXCLUDED: &[&str] = &["API_KEY", "API_URL", "API_HEADERS"];
+
+/// Whether the team config may set `suffix` to `value`. Besides [`TEAM_EXCLUDED`], it may only
+/// pick a known provider, since a custom one is defined by the URL it may not set.
+pub(crate) fn team_may_set(suffix: &str, value: &str) -> bool {
+    !TEAM_EXCLUDED.contains(&suffix)
+        && (suffix != "PROVIDER" || crate::provider::is_builtin_provider(value))
+}
+
+pub(crate) const GLOBAL_ONLY: &[&str] = &[
+    "AUTO_UPDATE",
+    "UPDATE_CHANNEL",
+    "ENCRYPT_SECRETS",
+    "TEMPLATE_COMMANDS",
+];
+
+fn read_table(path: &PathBuf) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn table_settings(table: &toml::Table) -> BTreeMap<String, String> {
+    ENV_FIELD_MAP
+        .iter()
+        .filter_map(|(suffix, field)| {
+            Some((suffix.to_string(), toml_to_field_string(table.get(*field)?)))
+        })
+        .collect()
+}
+
+/// Settings written in one config file, keyed by env suffix
+pub fn settings_in(scope: ConfigScope) -> Result<BTreeMap<String, String>> {
+    match scope {
+        ConfigScope::Global => {
+            let Some(path) = global_config_path().filter(|p| p.exists()) else {
+                return Ok(BTreeMap::new());
+            };
+            let mut table = read_table(&path)?;
+            take_directory_profiles(&mut table);
+            Ok(table_settings(&table))
+        }
+        ConfigScope::Team | ConfigScope::Repo | ConfigScope::Personal => {
+            let path = repo_file_path(scope)?;
+            if !path.exists() {
+                return Ok(BTreeMap::new());
+            }
+            Ok(table_settings(&read_table(&path)?))
+        }
+        ConfigScope::Env => {
+            let path = local_env_path()?;
+            if !path.exists() {
+                return Ok(BTreeMap::new());
+            }
+            let env_map = parse_dotenv(&path)?;
+            Ok(ENV_FIELD_MAP
+                .iter()
+                .filter_map(|(suffix, _)| {
+                    let value = env_map.get(&format!("ACR_{suffix}"))?;
+                    Some((suffix.to_string(), value.clone()))
+                })
+                .collect())
+        }
+    }
+}
+
+/// Credential name for the API key of a repository's `.env` or `.cgen.toml`
+fn local_credential_name(repo_root: &str) -> String {
+    format!("repo:{repo_root}")
+}
+
+fn local_env_path() -> Result<PathBuf> {
+    let root = crate::git::find_repo_root().context("Not in a git repository")?;
+    Ok(PathBuf::from(root).join(".env"))
+}
+
+/// Path of the repository TOML file for `scope`
+pub fn repo_file_path(scope: ConfigScope) -> Result<PathBuf> {
+    let file = scope
+        .repo_file()
+        .context("Scope is not a repository TOML file")?;
+    let root = crate::git::find_repo_root().context("Not in a git repository")?;
+    Ok(PathBuf::from(root).join(file))
+}
+
+/// Validate a value with `set_field` and write only that setting to the chosen file, leaving
+/// every other entry as it is
+pub fn save_setting(suffix: &str, value: &str, scope: ConfigScope) -> Result<()> {
+    crate::validate::validate_value(suffix, value).map_err(|e| anyhow!("{suffix}: {e}"))?;
+    let mut cfg = AppConfig::default();
+    cfg.set_field(suffix, value)?;
+    if scope != ConfigScope::Global && GLOBAL_ONLY.contains(&suffix) {
+        bail!("{suffix} is global-only and cannot be set in a repository config");
+    }
+    if suffix == "API_KEY" && scope == ConfigScope::Team {
+        bail!("API keys cannot be stored in the team config {TEAM_CONFIG_FILE}; use --personal");
+    }
+    if scope == ConfigScope::Team && !team_may_set(suffix, value) {
+        bail!(
+            "{suffix} decides where API keys are sent and cannot be set in the team config \
+             {TEAM_CONFIG_FILE}; use --personal"
+        );
+    }
+    if suffix == "API_KEY" && scope != ConfigScope::Global {
+        let root = crate::git::find_repo_root().context("Not in a git repository")?;
+        cfg.api_key = crate::credentials::store(&local_credential_name(&root), &cfg.api_key)?;
+    }
+    match scope {
+        ConfigScope::Env => {
+            let value = cfg.setting_value(suffix).unwrap_or_default();
+            save_local_value(suffix, Some(&value))
+        }
+        _ => {
+            let field = field_name(suffix);
+            let toml::Value::Table(mut all) =
+                toml::Value::try_from(&cfg).context("Failed to serialize config")?
+            else {
+                bail!("Failed to serialize config");
+            };
+            if scope == ConfigScope::Global {
+                save_global_value(field, all.remove(field))
+            } else {
+                save_repo_value(scope, field, all.remove(field))
+            }
+        }
+    }
+}
+
+/// Remove a setting from the chosen file
+pub fn remove_setting(suffix: &str, scope: ConfigScope) -> Result<()> {
+    match scope {
+        ConfigScope::Global => save_global_value(field_name(suffix), None),
+        ConfigScope::Team | ConfigScope::Repo | ConfigScope::Personal => {
+            save_repo_value(scope, field_name(suffix), None)
+        }
+        ConfigScope::Env => save_local_value(suffix, None),
+    }
+}
+
+/// Set (or remove, with `None`) a single key in a repository TOML file
+fn save_repo_value(scope: ConfigScope, key: &str, value: Option<toml::Value>) -> Result<()> {
+    let path = repo_file_path(scope)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    if scope == ConfigScope::Personal {
+        ignore_personal_config(&path)?;
+    }
+    let mut table = if path.exists() {
+        read_table(&path)?
+    } else {
+        toml::Table::new()
+    };
+    match value {
+        Some(value) => {
+            table.insert(key.to_string(), value);
+        }
+        None => {
+            table.remove(key);
+        }
+    }
+    let content = toml::to_string_pretty(&table).context("Failed to serialize config")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Make sure `.cgen/.gitignore` lists the personal config next to it
+fn ignore_personal_config(path: &std::path::Path) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let name = name.to_string_lossy();
+    let gitignore = dir.join(".gitignore");
+    let content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|l| l.trim() == name) {
+        return Ok(());
+    }
+    let mut out = content;
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("{name}\n"));
+    std::fs::write(&gitignore, out)
+        .with_context(|| format!("Failed to write {}", gitignore.display()))
+}
+
+/// Set (or remove, with `None`) a single `ACR_*` line in the repository `.env`, keeping comments
+/// and unrelated lines
+fn save_local_value(suffix: &str, value: Option<&str>) -> Result<()> {
+    let path = local_env_path()?;
+    let key = format!("ACR_{suffix}");
+    let content = if path.exists() {
+        std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
     } else {
-        format!("{}...{}", &key[..4], &key[key.len() - 4..])
+        String::new()
+    };
+
+    let mut replaced = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        let is_key = line
+            .split_once('=')
+            .is_some_and(|(k, _)| k.trim() == key && !line.trim_start().starts_with('#'));
+        if !is_key {
+            lines.push(line.to_string());
+        } else if let (Some(value), false) = (value, replaced) {
+            lines.push(format!("{key}={value}"));
+            replaced = true;
+        }
+    }
+    if let (Some(value), false) = (value, replaced) {
+        lines.push(format!("{key}={value}"));
+    }
+
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
     }
+    std::fs::write(&path, out).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `key` with all but a few characters at each end hidden; short keys reveal fewer characters
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let shown = match chars.len() {
+        0..=8 => return "*".repeat(chars.len()),
+        9..=15 => 2,
+        _ => 4,
+    };
+    let head: String = chars[..shown].iter().collect();
+    let tail: String = chars[chars.len() - shown..].iter().collect();
+    format!("{head}...{tail}")
 }
 
 fn truncate(s: &str, max: usize) -> String {
@@ -761,6 +2693,46 @@ fn normalize_post_commit_push(value: &str) -> String {
     }
 }
 
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse `type=template` pairs separated by `;` (templates may contain commas)
+fn parse_type_templates(value: &str) -> BTreeMap<String, String> {
+    value
+        .split(';')
+        .filter_map(|entry| {
+            let (commit_type, template) = entry.split_once('=')?;
+            let commit_type = commit_type.trim().to_ascii_lowercase();
+            let template = template.trim();
+            if commit_type.is_empty() || template.is_empty() {
+                return None;
+            }
+            Some((commit_type, template.to_string()))
+        })
+        .collect()
+}
+
+/// Inverse of `parse_type_templates`, with real newlines written as `\n`
+pub fn format_type_templates(templates: &BTreeMap<String, String>) -> String {
+    templates
+        .iter()
+        .map(|(t, template)| format!("{t}={}", template.replace('\n', "\\n")))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn normalize_message_style(value: &str) -> String {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "detailed" => "detailed".into(),
+        _ => "default".into(),
+    }
+}
+
 fn parse_usize_or_default(value: &str, default: usize) -> usize {
     value.trim().parse::<usize>().unwrap_or(default)
 }
@@ -770,16 +2742,93 @@ fn normalize_locale(value: &str) -> String {
     if normalized.is_empty() {
         default_locale()
     } else {
-        normalized.to_ascii_lowercase()
+        normalized.to_ascii_lowercase().replace('_', "-")
+    }
+}
+
+/// Locale from the environment: `LC_ALL`, `LC_MESSAGES` then `LANG` (POSIX precedence), or
+/// the user locale from the registry on Windows. Only supported locales are returned.
+pub fn detect_system_locale() -> Option<String> {
+    let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.trim().is_empty())
+        .and_then(|v| locale_from_system_value(&v));
+    from_env.or_else(windows_user_locale)
+}
+
+/// Convert a value like `de_DE.UTF-8`, `pt_BR` or `zh-TW` to a cgen locale. The region is kept
+/// only where it changes the written language (Portuguese and Chinese); `C`/`POSIX` yield `None`.
+pub fn locale_from_system_value(value: &str) -> Option<String> {
+    let tag = value.split(['.', '@']).next()?.trim();
+    if tag.is_empty() || tag.eq_ignore_ascii_case("C") || tag.eq_ignore_ascii_case("POSIX") {
+        return None;
     }
+    let tag = normalize_locale(tag);
+    let locale = match tag.split_once('-') {
+        Some((language, _)) if !matches!(language, "pt" | "zh") => language.to_string(),
+        _ => tag,
+    };
+    is_supported_locale(&locale).then_some(locale)
+}
+
+#[cfg(windows)]
+fn windows_user_locale() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Control Panel\International",
+            "/v",
+            "LocaleName",
+        ])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout
+        .lines()
+        .find(|l| l.trim_start().starts_with("LocaleName"))?
+        .split_whitespace()
+        .last()?
+        .to_string();
+    locale_from_system_value(&value)
+}
+
+#[cfg(not(windows))]
+fn windows_user_locale() -> Option<String> {
+    None
+}
+
+/// ISO 639-1 languages commit messages can be written in, embedded so no `i18n/` directory
+/// is needed. Regional variants of these (`pt-br`, `zh-tw`, `es-419`) are accepted too.
+pub const SUPPORTED_LOCALES: &[&str] = &[
+    "af", "am", "ar", "az", "be", "bg", "bn", "bs", "ca", "cs", "cy", "da", "de", "el", "en", "eo",
+    "es", "et", "eu", "fa", "fi", "fil", "fr", "ga", "gl", "gu", "he", "hi", "hr", "hu", "hy",
+    "id", "is", "it", "ja", "ka", "kk", "km", "kn", "ko", "ky", "lo", "lt", "lv", "mk", "ml", "mn",
+    "mr", "ms", "mt", "my", "nb", "ne", "nl", "nn", "no", "pa", "pl", "pt", "ro", "ru", "si", "sk",
+    "sl", "sq", "sr", "sv", "sw", "ta", "te", "th", "tl", "tr", "uk", "ur", "uz", "vi", "zh", "zu",
+];
+
+/// Whether a normalized locale is a supported language, optionally with a region subtag
+/// (two letters, or three digits for UN M.49 areas like `es-419`)
+pub fn is_supported_locale(locale: &str) -> bool {
+    let (language, region) = match locale.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (locale, None),
+    };
+    SUPPORTED_LOCALES.contains(&language)
+        && region.is_none_or(|r| {
+            (r.len() == 2 && r.chars().all(|c| c.is_ascii_alphabetic()))
+                || (r.len() == 3 && r.chars().all(|c| c.is_ascii_digit()))
+                || matches!(r, "hans" | "hant")
+        })
 }
 
 fn validate_locale(locale: &str) -> Result<()> {
-    if locale == "en" || locale_has_i18n(locale) {
+    if is_supported_locale(locale) || locale_has_i18n(locale) {
         return Ok(());
     }
     anyhow::bail!(
-        "Unsupported locale '{}'. Only 'en' is available unless matching i18n resources exist. Set locale with `cgen config` or add i18n files first.",
+        "Unsupported locale '{}'. Use an ISO 639-1 language code, optionally with a region (e.g. 'de', 'pt-br', 'zh-tw'), or add matching i18n resources.",
         locale
     );
 }
@@ -790,7 +2839,8 @@ fn locale_has_i18n(locale: &str) -> bool {
         .any(|dir| locale_exists_in_i18n_dir(dir, locale))
 }
 
-fn locale_i18n_dirs() -> Vec<PathBuf> {
+/// `i18n/` directories in the repository root and the working directory
+pub fn locale_i18n_dirs() -> Vec<PathBuf> {
     let mut dirs = Vec::new();
     if let Ok(repo_root) = crate::git::find_repo_root() {
         dirs.push(PathBuf::from(repo_root).join("i18n"));
@@ -840,7 +2890,7 @@ pub fn field_description(suffix: &str) -> &'static str {
         "API_HEADERS" => "Additional HTTP headers for API requests (JSON format)",
         "LOCALE" => "Language locale for commit messages (e.g., en, pt-br)",
         "ONE_LINER" => "Generate single-line commit messages when enabled",
-        "COMMIT_TEMPLATE" => "Template for commit message ($msg is replaced with generated text)",
+        "COMMIT_TEMPLATE" => "Template for commit message ($msg is replaced with generated text; $BRANCH, $DATE and env vars also work)",
         "LLM_SYSTEM_PROMPT" => "System prompt sent to the LLM for context",
         "USE_GITMOJI" => "Prepend gitmoji to commit messages when enabled",
         "GITMOJI_FORMAT" => "Gitmoji style: unicode (🎨) or shortcode (:art:)",
@@ -849,15 +2899,69 @@ pub fn field_description(suffix: &str) -> &'static str {
         "SUPPRESS_TOOL_OUTPUT" => "Hide git command output when enabled",
         "WARN_STAGED_FILES_ENABLED" => "Warn when staged file count exceeds threshold",
         "WARN_STAGED_FILES_THRESHOLD" => "Number of staged files before warning is shown",
+        "WARN_STAGED_FILES_MAX_LINES" => "Changed lines in the diff before warning is shown (0 = off)",
+        "WARN_STAGED_FILES_MAX_BYTES" => "Diff size in bytes before warning is shown (0 = off)",
         "CONFIRM_NEW_VERSION" => "Ask for confirmation before creating version tags",
         "AUTO_UPDATE" => "Automatically update cgen when new versions are available",
         "FALLBACK_ENABLED" => "Try fallback presets if primary LLM call fails",
         "TRACK_GENERATED_COMMITS" => "Track commits generated by cgen for history view",
         "DIFF_EXCLUDE_GLOBS" => "Comma-separated glob patterns for files to exclude from LLM diff analysis (e.g., *.json,*.lock)",
+        "MESSAGE_STYLE" => "Message style: default (follows One-liner) or detailed (subject, bullet body, footers)",
+        "POST_PROCESS" => "Enforce subject rules and wrap the body at 72 columns after generation",
+        "SUBJECT_MAX_LENGTH" => "Maximum subject length; longer subjects are shortened at a word boundary (0 = no limit)",
+        "LOWERCASE_SUBJECT" => "Lowercase the first word of the subject description (acronyms are kept)",
+        "ALLOWED_TYPES" => "Comma-separated commit types the message may use; others are corrected or regenerated (empty = any)",
+        "ALLOWED_SCOPES" => "Comma-separated scopes the message may use; unknown scopes are corrected or dropped (empty = any)",
+        "SCOPE_MAP" => "Comma-separated glob=scope pairs; the scope owning most changed files is forced (e.g., crates/api/**=api)",
+        "AUTO_SCOPE" => "Use the Cargo/npm/pnpm/Nx workspace package owning most changed files as the scope",
+        "GITMOJI_DETERMINISTIC" => "Pick the gitmoji from the commit type (feat → ✨, fix → 🐛, ...) instead of trusting the model",
+        "SKIP_CI" => "Append the CI-skip token to every commit message (or pass --skip-ci per commit)",
+        "SKIP_CI_TOKEN" => "Token appended when skipping CI: [skip ci], [ci skip] or ***NO_CI***",
+        "TYPE_TEMPLATES" => "Per-type templates as type=template pairs separated by ; (e.g. fix=$msg\\n\\nBug: $ticket); other types use Commit Template",
+        "PROMPT_INCLUDE" => "Only use prompt snippets whose file name matches one of these globs (empty = all)",
+        "PROMPT_EXCLUDE" => "Skip prompt snippets whose file name matches one of these globs",
+        "PROFILE" => "Prompt profile from profiles.toml applied on every run (override with --profile)",
+        "ENCRYPT_SECRETS" => "Encrypt stored API keys with a passphrase (global only)",
+        "MAX_PAYLOAD_BYTES" => "Largest request body in bytes; bigger diffs are truncated to fit (0 = no limit)",
+        "CONTEXT_WINDOW" => "Model context window in tokens (0 = look up the model)",
+        "REASONING_EFFORT" => "Reasoning effort for reasoning models: minimal, low, medium or high (empty = provider default)",
+        "SEED" => "Sampling seed for reproducible output on providers that support it (empty = off)",
+        "MONTHLY_BUDGET_USD" => "Monthly LLM spending budget in USD; runs that would exceed it warn or ask first (empty = off)",
+        "BUDGET_ACTION" => "What happens when a run would exceed the monthly budget: warn or confirm",
+        "USAGE_METRICS" => "Record per-run provider, latency, token and outcome metrics locally for cgen stats",
+        "CACHE_MAX_COMMITS" => "Most commits kept in the history cache per repository; older ones are pruned (0 = unlimited)",
+        "CACHE_MAX_BYTES" => "Largest total size of the history cache in bytes; least recently used repositories are pruned first (0 = unlimited)",
+        "REPO_HISTORY" => "Also append each generated commit (hash, subject, model; no secrets) to .cgen/history.toml in the repository",
+        "AUTO_STASH" => "Stash uncommitted changes before alter rewrites a commit, and restore them afterwards",
+        "EXCLUDE_LFS" => "Leave Git LFS pointer files out of the diff sent to the LLM instead of summarizing them",
+        "PUSH_REMOTE" => "Comma-separated remotes to push to, in order (e.g., origin,upstream); empty uses git's default or asks when there are several",
+        "PROTECTED_BRANCHES" => "Comma-separated branch globs (e.g., @default,release/*; @default is the remote's default branch); committing or pushing there asks first and alter refuses without --force",
+        "PRE_COMMIT_HOOK" => "Run the pre-commit hook (or the pre-commit framework) before generating, restaging files it reformats so the message matches the commit",
+        "AUTO_SET_UPSTREAM" => "Push a branch without an upstream with git push -u without asking first",
+        "WARN_DEFAULT_BRANCH" => "Print a note when committing directly to the remote's default branch (from origin/HEAD)",
+        "UPDATE_CHANNEL" => "Releases cgen update follows: stable, beta (adds beta and rc pre-releases) or nightly (all pre-releases)",
+        "UPDATE_CHECK_HOURS" => "Hours between checks for a new cgen release; 0 checks on every run",
+        "REVIEW_TUI" => "Review generated messages on a full-screen screen with the staged diff beside the message",
+        "SHOW_DIFF" => "Page the filtered, colored staged diff before it is sent and ask to continue",
+        "REQUEST_TIMEOUT_SECS" => "Seconds before an LLM request is abandoned; 0 waits indefinitely",
+        "ASCII_UI" => "Draw trees and arrows with plain ASCII for terminals that mangle Unicode",
+        "TEMPLATE_COMMANDS" => "Run $(command) substitutions in commit templates, URLs and headers (global only)",
+        "PRESET" => "Saved LLM preset (name or id) applied on load, before this layer's own provider settings",
+        "ROUTING" => "How each run picks a preset from the routing list in presets.toml: none, round-robin, random or cheapest",
+        "FALLBACK_POLICY" => "What an HTTP error does, as status=fallback, retry or fail rules (e.g. 401=fail,429=retry,5xx=fallback); other statuses fall back",
+        "FALLBACK_MAX_ATTEMPTS" => "How many fallback presets to try after the primary fails (0 = all)",
+        "FALLBACK_COOLDOWN_MINS" => "Skip fallback presets that failed within this many minutes (0 = off)",
         _ => "",
     }
 }
 
+/// Note in the `--log-file` which settings a config layer sets, by key only
+fn log_layer(source: &str, map: &HashMap<String, String>) {
+    let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    crate::ui::log_event!("config: {source} sets {}", keys.join(", "));
+}
+
 fn parse_dotenv(path: &PathBuf) -> Result<HashMap<String, String>> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
@@ -890,10 +2994,17 @@ mod tests {
 
     #[test]
     fn test_mask_key_long() {
-        assert_eq!(mask_key("abcdefghij"), "abcd...ghij");
+        assert_eq!(mask_key("abcdefghij"), "ab...ij");
         assert_eq!(mask_key("sk-1234567890abcdef"), "sk-1...cdef");
     }
 
+    #[test]
+    fn test_mask_key_non_ascii() {
+        assert_eq!(mask_key("aéééé"), "*****");
+        assert_eq!(mask_key("aéééééééé"), "aé...éé");
+        assert_eq!(mask_key("ééééééééééééééééé"), "éééé...éééé");
+    }
+
     #[test]
     fn test_truncate_short() {
         assert_eq!(truncate("hello", 10), "hello");
@@ -918,6 +3029,15 @@ mod tests {
         assert_eq!(normalize_post_commit_push(""), "ask");
     }
 
+    #[test]
+    fn test_normalize_message_style() {
+        assert_eq!(normalize_message_style("detailed"), "detailed");
+        assert_eq!(normalize_message_style("  DETAILED "), "detailed");
+        assert_eq!(normalize_message_style("default"), "default");
+        assert_eq!(normalize_message_style("verbose"), "default");
+        assert_eq!(normalize_message_style(""), "default");
+    }
+
     #[test]
     fn test_parse_usize_or_default() {
         assert_eq!(parse_usize_or_default("10", 5), 10);
@@ -933,6 +3053,108 @@ mod tests {
         assert_eq!(normalize_locale("  pt-BR  "), "pt-br");
         assert_eq!(normalize_locale(""), "en");
         assert_eq!(normalize_locale("   "), "en");
+        assert_eq!(normalize_locale("pt_BR"), "pt-br");
+    }
+
+    #[test]
+    fn test_migrate_table_coerces_v0_values() {
+        let mut table: toml::Table = r#"
+one_liner = "0"
+subject_max_length = "50"
+allowed_types = "feat, fix"
+post_commit_push = true
+locale = "pt_BR"
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(migrate_table(&mut table), 0);
+        assert_eq!(table["one_liner"], toml::Value::Boolean(false));
+        assert_eq!(table["subject_max_length"], toml::Value::Integer(50));
+        assert_eq!(
+            table["allowed_types"],
+            toml::Value::Array(vec!["feat".into(), "fix".into()])
+        );
+        assert_eq!(table["post_commit_push"], toml::Value::from("always"));
+        assert_eq!(table["locale"], toml::Value::from("pt-br"));
+        assert_eq!(
+            table["config_version"],
+            toml::Value::Integer(CONFIG_VERSION.into())
+        );
+        let cfg: AppConfig = toml::Value::Table(table.clone()).try_into().unwrap();
+        assert_eq!(cfg.subject_max_length, 50);
+
+        // Already current: untouched
+        let before = table.clone();
+        assert_eq!(migrate_table(&mut table), CONFIG_VERSION);
+        assert_eq!(table, before);
+    }
+
+    #[test]
+    fn test_directory_profiles_are_split_from_prompt_profile() {
+        let mut table: toml::Table =
+            "model = \"m\"\n[profile.\"~/work/**\"]\npreset = \"Corp\"\none_liner = false\n"
+                .parse()
+                .unwrap();
+        let sections = take_directory_profiles(&mut table);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].0, "~/work/**");
+        assert!(sections[0].1.contains_key("preset"));
+        assert!(!table.contains_key("profile"));
+
+        let mut table: toml::Table = "profile = \"terse\"".parse().unwrap();
+        assert!(take_directory_profiles(&mut table).is_empty());
+        assert!(table.contains_key("profile"));
+    }
+
+    #[test]
+    fn test_directory_pattern_matches() {
+        let dir = std::path::Path::new("/src/work/api");
+        assert!(directory_pattern_matches("/src/work/**", dir));
+        assert!(directory_pattern_matches("/src/work/*", dir));
+        assert!(directory_pattern_matches("/src/work/api/", dir));
+        assert!(!directory_pattern_matches("/src/*", dir));
+        assert!(!directory_pattern_matches("/src/personal/**", dir));
+        if let Some(home) = dirs::home_dir() {
+            assert!(directory_pattern_matches(
+                "~/work/**",
+                &home.join("work/api")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_locale_from_system_value() {
+        assert_eq!(
+            locale_from_system_value("de_DE.UTF-8").as_deref(),
+            Some("de")
+        );
+        assert_eq!(
+            locale_from_system_value("pt_BR.utf8").as_deref(),
+            Some("pt-br")
+        );
+        assert_eq!(locale_from_system_value("zh-TW").as_deref(), Some("zh-tw"));
+        assert_eq!(
+            locale_from_system_value("sr_RS@latin").as_deref(),
+            Some("sr")
+        );
+        assert_eq!(
+            locale_from_system_value("en_US.UTF-8").as_deref(),
+            Some("en")
+        );
+        assert_eq!(locale_from_system_value("C.UTF-8"), None);
+        assert_eq!(locale_from_system_value("POSIX"), None);
+        assert_eq!(locale_from_system_value("xx_YY"), None);
+    }
+
+    #[test]
+    fn test_is_supported_locale() {
+        for locale in ["de", "ja", "pt-br", "zh-tw", "zh-hant", "es-419", "fil"] {
+            assert!(is_supported_locale(locale), "{locale} should be supported");
+        }
+        for locale in ["xx", "pt-brazil", "de-1", "en-", "tlh"] {
+            assert!(!is_supported_locale(locale), "{locale} should be rejected");
+        }
     }
 
     #[test]
@@ -989,7 +3211,7 @@ mod tests {
     #[test]
     fn test_parse_dotenv_skips_empty_lines() {
         let mut file = NamedTempFile::new().unwrap();
-        writeln!(file, "").unwrap();
+        writeln!(file).unwrap();
         writeln!(file, "KEY=value").unwrap();
         writeln!(file, "   ").unwrap();
         let map = parse_dotenv(&file.path().to_path_buf()).unwrap();
@@ -1205,15 +3427,67 @@ mod tests {
         map.insert("ACR_USE_GITMOJI".into(), "true".into());
         map.insert("ACR_GITMOJI_FORMAT".into(), "shortcode".into());
         map.insert("ACR_REVIEW_COMMIT".into(), "false".into());
+        map.insert("ACR_REVIEW_TUI".into(), "true".into());
+        map.insert("ACR_SHOW_DIFF".into(), "true".into());
+        map.insert("ACR_ASCII_UI".into(), "1".into());
         map.insert("ACR_POST_COMMIT_PUSH".into(), "always".into());
         map.insert("ACR_SUPPRESS_TOOL_OUTPUT".into(), "true".into());
         map.insert("ACR_WARN_STAGED_FILES_ENABLED".into(), "false".into());
         map.insert("ACR_WARN_STAGED_FILES_THRESHOLD".into(), "50".into());
+        map.insert("ACR_WARN_STAGED_FILES_MAX_LINES".into(), "300".into());
+        map.insert("ACR_WARN_STAGED_FILES_MAX_BYTES".into(), "0".into());
+        map.insert("ACR_MAX_PAYLOAD_BYTES".into(), "100000".into());
+        map.insert("ACR_CONTEXT_WINDOW".into(), "32768".into());
+        map.insert("ACR_REASONING_EFFORT".into(), "low".into());
+        map.insert("ACR_SEED".into(), "42".into());
+        map.insert("ACR_MONTHLY_BUDGET_USD".into(), "12.5".into());
+        map.insert("ACR_BUDGET_ACTION".into(), "warn".into());
+        map.insert("ACR_UPDATE_CHANNEL".into(), "beta".into());
+        map.insert("ACR_TEMPLATE_COMMANDS".into(), "1".into());
+        map.insert("ACR_PRESET".into(), " Corp ".into());
+        map.insert("ACR_ROUTING".into(), " random ".into());
+        map.insert("ACR_FALLBACK_POLICY".into(), "429=retry".into());
+        map.insert("ACR_FALLBACK_MAX_ATTEMPTS".into(), "2".into());
+        map.insert("ACR_FALLBACK_COOLDOWN_MINS".into(), "15".into());
+        map.insert("ACR_UPDATE_CHECK_HOURS".into(), "6".into());
+        map.insert("ACR_REQUEST_TIMEOUT_SECS".into(), "30".into());
+        map.insert("ACR_USAGE_METRICS".into(), "1".into());
+        map.insert("ACR_CACHE_MAX_COMMITS".into(), "50".into());
+        map.insert("ACR_CACHE_MAX_BYTES".into(), "0".into());
+        map.insert("ACR_REPO_HISTORY".into(), "true".into());
+        map.insert("ACR_AUTO_STASH".into(), "1".into());
+        map.insert("ACR_EXCLUDE_LFS".into(), "true".into());
+        map.insert("ACR_PRE_COMMIT_HOOK".into(), "1".into());
+        map.insert("ACR_PROTECTED_BRANCHES".into(), "main, release/*".into());
+        map.insert("ACR_PUSH_REMOTE".into(), "origin, upstream".into());
+        map.insert("ACR_AUTO_SET_UPSTREAM".into(), "1".into());
+        map.insert("ACR_WARN_DEFAULT_BRANCH".into(), "0".into());
         map.insert("ACR_CONFIRM_NEW_VERSION".into(), "false".into());
         map.insert("ACR_AUTO_UPDATE".into(), "true".into());
         map.insert("ACR_FALLBACK_ENABLED".into(), "false".into());
         map.insert("ACR_TRACK_GENERATED_COMMITS".into(), "false".into());
         map.insert("ACR_DIFF_EXCLUDE_GLOBS".into(), "*.md,*.txt".into());
+        map.insert("ACR_MESSAGE_STYLE".into(), "Detailed".into());
+        map.insert("ACR_POST_PROCESS".into(), "false".into());
+        map.insert("ACR_SUBJECT_MAX_LENGTH".into(), "50".into());
+        map.insert("ACR_LOWERCASE_SUBJECT".into(), "true".into());
+        map.insert("ACR_ALLOWED_TYPES".into(), "feat, fix".into());
+        map.insert("ACR_ALLOWED_SCOPES".into(), "api,,cli".into());
+        map.insert(
+            "ACR_SCOPE_MAP".into(),
+            "crates/api/**=api, docs/**=docs".into(),
+        );
+        map.insert("ACR_AUTO_SCOPE".into(), "0".into());
+        map.insert("ACR_GITMOJI_DETERMINISTIC".into(), "0".into());
+        map.insert("ACR_SKIP_CI".into(), "1".into());
+        map.insert("ACR_SKIP_CI_TOKEN".into(), "***NO_CI***".into());
+        map.insert("ACR_PROMPT_INCLUDE".into(), "team-*, style".into());
+        map.insert("ACR_PROMPT_EXCLUDE".into(), "draft".into());
+        map.insert("ACR_PROFILE".into(), " terse ".into());
+        map.insert(
+            "ACR_TYPE_TEMPLATES".into(),
+            "fix=$msg\\n\\nBug: $ticket; Feat = $msg ;broken".into(),
+        );
 
         cfg.apply_env_map(&map, false);
 
@@ -1228,15 +3502,67 @@ mod tests {
         assert!(cfg.use_gitmoji);
         assert_eq!(cfg.gitmoji_format, "shortcode");
         assert!(!cfg.review_commit);
+        assert!(cfg.review_tui);
+        assert!(cfg.show_diff);
+        assert!(cfg.ascii_ui);
         assert_eq!(cfg.post_commit_push, "always");
         assert!(cfg.suppress_tool_output);
         assert!(!cfg.warn_staged_files_enabled);
         assert_eq!(cfg.warn_staged_files_threshold, 50);
+        assert_eq!(cfg.warn_staged_files_max_lines, 300);
+        assert_eq!(cfg.warn_staged_files_max_bytes, 0);
+        assert_eq!(cfg.max_payload_bytes, 100_000);
+        assert_eq!(cfg.context_window, 32768);
+        assert_eq!(cfg.reasoning_effort, "low");
+        assert_eq!(cfg.seed, "42");
+        assert_eq!(cfg.monthly_budget_usd, "12.5");
+        assert_eq!(cfg.budget_action, "warn");
+        assert_eq!(cfg.update_channel, "beta");
+        assert!(cfg.template_commands);
+        assert_eq!(cfg.preset, "Corp");
+        assert_eq!(cfg.routing, "random");
+        assert_eq!(cfg.fallback_policy, "429=retry");
+        assert_eq!(cfg.fallback_max_attempts, 2);
+        assert_eq!(cfg.fallback_cooldown_mins, 15);
+        assert_eq!(cfg.update_check_hours, 6);
+        assert_eq!(cfg.request_timeout_secs, 30);
+        assert!(cfg.usage_metrics);
+        assert_eq!(cfg.cache_max_commits, 50);
+        assert_eq!(cfg.cache_max_bytes, 0);
+        assert!(cfg.repo_history);
+        assert!(cfg.auto_stash);
+        assert!(cfg.exclude_lfs);
+        assert!(cfg.pre_commit_hook);
+        assert_eq!(cfg.protected_branches, vec!["main", "release/*"]);
+        assert_eq!(cfg.push_remote, vec!["origin", "upstream"]);
+        assert!(cfg.auto_set_upstream);
+        assert!(!cfg.warn_default_branch);
         assert!(!cfg.confirm_new_version);
         assert_eq!(cfg.auto_update, Some(true));
         assert!(!cfg.fallback_enabled);
         assert!(!cfg.track_generated_commits);
         assert_eq!(cfg.diff_exclude_globs, vec!["*.md", "*.txt"]);
+        assert_eq!(cfg.message_style, "detailed");
+        assert!(!cfg.post_process);
+        assert_eq!(cfg.subject_max_length, 50);
+        assert!(cfg.lowercase_subject);
+        assert_eq!(cfg.allowed_types, vec!["feat", "fix"]);
+        assert_eq!(cfg.allowed_scopes, vec!["api", "cli"]);
+        assert_eq!(cfg.scope_map, vec!["crates/api/**=api", "docs/**=docs"]);
+        assert!(!cfg.auto_scope);
+        assert!(!cfg.gitmoji_deterministic);
+        assert!(cfg.skip_ci);
+        assert_eq!(cfg.skip_ci_token, "***NO_CI***");
+        assert_eq!(cfg.prompt_include, vec!["team-*", "style"]);
+        assert_eq!(cfg.prompt_exclude, vec!["draft"]);
+        assert_eq!(cfg.profile, "terse");
+        assert_eq!(
+            cfg.type_templates,
+            BTreeMap::from([
+                ("feat".to_string(), "$msg".to_string()),
+                ("fix".to_string(), "$msg\\n\\nBug: $ticket".to_string()),
+            ])
+        );
     }
 
     #[test]
@@ -1245,9 +3571,12 @@ mod tests {
         let mut map = HashMap::new();
         map.insert("ACR_AUTO_UPDATE".into(), "true".into());
 
+        map.insert("ACR_TEMPLATE_COMMANDS".into(), "1".into());
+
         // from_local = true should skip auto_update
         cfg.apply_env_map(&map, true);
         assert!(cfg.auto_update.is_none());
+        assert!(!cfg.template_commands);
 
         // from_local = false should apply auto_update
         cfg.apply_env_map(&map, false);
@@ -1276,6 +3605,7 @@ mod tests {
     fn test_merge_from_with_all_fields() {
         let mut cfg = AppConfig::default();
         let other = AppConfig {
+            config_version: CONFIG_VERSION,
             provider: "anthropic".into(),
             model: "claude-3".into(),
             api_key: "sk-ant".into(),
@@ -1292,11 +3622,64 @@ mod tests {
             suppress_tool_output: true,
             warn_staged_files_enabled: false,
             warn_staged_files_threshold: 100,
+            warn_staged_files_max_lines: 900,
+            warn_staged_files_max_bytes: 90_000,
             confirm_new_version: false,
             auto_update: Some(true),
             fallback_enabled: false,
             track_generated_commits: false,
             diff_exclude_globs: vec!["*.log".into()],
+            message_style: "detailed".into(),
+            post_process: false,
+            subject_max_length: 50,
+            lowercase_subject: true,
+            commitlint: None,
+            gitmoji_set: None,
+            prompt_snippets: Vec::new(),
+            locale_configured: false,
+            preset_overrides: Default::default(),
+            repo_command_settings: Vec::new(),
+            allowed_types: vec!["feat".into()],
+            allowed_scopes: vec!["api".into()],
+            scope_map: vec!["crates/api/**=api".into()],
+            auto_scope: false,
+            gitmoji_deterministic: false,
+            skip_ci: true,
+            skip_ci_token: "[ci skip]".into(),
+            type_templates: BTreeMap::from([("fix".into(), "$msg\n\nBug: 1".into())]),
+            prompt_include: vec!["team-*".into()],
+            prompt_exclude: vec!["draft".into()],
+            profile: "terse".into(),
+            encrypt_secrets: true,
+            max_payload_bytes: 0,
+            context_window: default_context_window(),
+            reasoning_effort: String::new(),
+            seed: String::new(),
+            monthly_budget_usd: String::new(),
+            budget_action: default_budget_action(),
+            usage_metrics: false,
+            cache_max_commits: default_cache_max_commits(),
+            cache_max_bytes: default_cache_max_bytes(),
+            repo_history: false,
+            auto_stash: false,
+            exclude_lfs: false,
+            pre_commit_hook: false,
+            protected_branches: vec!["main".into()],
+            push_remote: Vec::new(),
+            auto_set_upstream: false,
+            warn_default_branch: true,
+            update_channel: default_update_channel(),
+            update_check_hours: default_update_check_hours(),
+            review_tui: false,
+            show_diff: false,
+            request_timeout_secs: default_request_timeout_secs(),
+            ascii_ui: false,
+            template_commands: false,
+            preset: String::new(),
+            routing: default_routing(),
+            fallback_policy: default_fallback_policy(),
+            fallback_max_attempts: default_fallback_max_attempts(),
+            fallback_cooldown_mins: default_fallback_cooldown_mins(),
         };
 
         cfg.merge_from(&other);
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// When fallback presets last failed, kept in `cache/cooldown.toml` so `ACR_FALLBACK_COOLDOWN_MINS`
+/// can skip them on the next runs
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Cooldowns {
+    /// Unix time of each preset's last failure, by preset id
+    #[serde(default)]
+    pub failed: BTreeMap<String, u64>,
+}
+
+impl Cooldowns {
+    /// Whether preset `id` failed less than `minutes` ago
+    pub fn cooling_down(&self, id: u32, minutes: usize, now: u64) -> bool {
+        self.failed
+            .get(&id.to_string())
+            .is_some_and(|&at| now.saturating_sub(at) < minutes as u64 * 60)
+    }
+
+    /// Remember a failure of preset `id` at `now`, or forget its last one after a success
+    pub fn record(&mut self, id: u32, ok: bool, now: u64) {
+        if ok {
+            self.failed.remove(&id.to_string());
+        } else {
+            self.failed.insert(id.to_string(), now);
+        }
+    }
+}
+
+fn cooldown_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("cooldown.toml"))
+}
+
+pub fn load() -> Result<Cooldowns> {
+    let Some(path) = cooldown_path().filter(|p| p.exists()) else {
+        return Ok(Cooldowns::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save(cooldowns: &Cooldowns) -> Result<()> {
+    let path = cooldown_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string(cooldowns).context("Failed to serialize cooldowns")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Record the outcome of a request to preset `id` in the cooldown file
+pub fn note(id: u32, ok: bool) -> Result<()> {
+    let mut cooldowns = load().unwrap_or_default();
+    cooldowns.record(id, ok, crate::timestamp::now_unix());
+    save(&cooldowns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_cools_down_until_it_expires_or_succeeds() {
+        let mut cooldowns = Cooldowns::default();
+        cooldowns.record(3, false, 1_000);
+        assert!(cooldowns.cooling_down(3, 10, 1_000 + 599));
+        assert!(!cooldowns.cooling_down(3, 10, 1_000 + 600));
+        assert!(!cooldowns.cooling_down(3, 0, 1_000));
+        assert!(!cooldowns.cooling_down(4, 10, 1_000));
+
+        cooldowns.record(3, true, 1_100);
+        assert!(!cooldowns.cooling_down(3, 10, 1_100));
+        assert_eq!(cooldowns, Cooldowns::default());
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Prefix of a value that points into `credentials.toml` instead of holding the key itself
+pub const REFERENCE_PREFIX: &str = "credential:";
+
+/// Credential name used for the global config's `api_key`
+pub const GLOBAL_NAME: &str = "default";
+
+/// Contents of `credentials.toml`: credential name → API key (possibly encrypted)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CredentialsFile {
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+}
+
+/// Path of the credentials file, next to the global config file
+pub fn credentials_path() -> Option<PathBuf> {
+    crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("credentials.toml")))
+}
+
+pub fn load_credentials() -> Result<CredentialsFile> {
+    let Some(path) = credentials_path().filter(|p| p.exists()) else {
+        return Ok(CredentialsFile::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Write the credentials file readable by the owner only, sealing keys when
+/// `encrypt_secrets` is enabled and unsealing them otherwise
+pub fn save_credentials(file: &CredentialsFile) -> Result<()> {
+    let path = credentials_path().context("Could not determine credentials file path")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let encrypt = crate::config::secrets_encryption_enabled();
+    let mut file = file.clone();
+    for key in file.keys.values_mut() {
+        *key = if encrypt {
+            crate::secret::seal(key)?
+        } else {
+            crate::secret::unseal(key)?
+        };
+    }
+    let content = toml::to_string_pretty(&file).context("Failed to serialize credentials")?;
+    write_private(&path, &content)
+}
+
+/// Write `content` to `path` readable by the owner only. It goes to a temp file next to `path`,
+/// which is created with mode 0600, and is renamed into place, so it is never readable by others,
+/// not even briefly.
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    // A bare file name has an empty parent: the current directory
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".cgen-")
+        .tempfile_in(dir)
+        .with_context(|| format!("Failed to create a temp file in {}", dir.display()))?;
+    tmp.write_all(content.as_bytes())
+        .and_then(|_| tmp.as_file().sync_all())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    tmp.persist(path)
+        .map_err(|e| e.error)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(REFERENCE_PREFIX)
+}
+
+/// Move a key into the credentials file under `name` and return the reference to store in its
+/// place. Empty values and existing references are returned unchanged.
+pub fn store(name: &str, key: &str) -> Result<String> {
+    if key.is_empty() || is_reference(key) {
+        return Ok(key.to_string());
+    }
+    let mut file = load_credentials()?;
+    file.keys
+        .insert(name.to_string(), crate::secret::unseal(key)?);
+    save_credentials(&file)?;
+    Ok(format!("{REFERENCE_PREFIX}{name}"))
+}
+
+/// The API key a stored value stands for: references are looked up in the credentials file and
+/// encrypted values decrypted; plain keys pass through
+pub fn resolve(value: &str) -> Result<String> {
+    let Some(name) = value.strip_prefix(REFERENCE_PREFIX) else {
+        return crate::secret::unseal(value);
+    };
+    let file = load_credentials()?;
+    let key = file.keys.get(name).with_context(|| {
+        format!(
+            "Credential '{name}' not found in {}",
+            credentials_path()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "credentials.toml".into())
+        )
+    })?;
+    crate::secret::unseal(key)
+}
+
+/// Drop credentials whose name starts with `prefix` and is not in `keep`
+pub fn prune(prefix: &str, keep: &[String]) -> Result<()> {
+    let mut file = load_credentials()?;
+    let before = file.keys.len();
+    file.keys
+        .retain(|name, _| !name.starts_with(prefix) || keep.contains(name));
+    if file.keys.len() != before {
+        save_credentials(&file)?;
+    }
+    Ok(())
+}
+
+/// Rewrite the credentials file so its keys match the current `encrypt_secrets` setting
+pub fn reseal() -> Result<()> {
+    if !credentials_path().is_some_and(|p| p.exists()) {
+        return Ok(());
+    }
+    save_credentials(&load_credentials()?)
+}
@@ -1,14 +1,99 @@
+use crate::i18n::{t, tf};
 use anyhow::{bail, Context, Result};
+use colored::Colorize;
 use glob::Pattern;
-use std::fs;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::OnceLock;
+
+/// Repository given with `--repo`; git runs there instead of in the working directory
+static REPO_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Run every git command in `dir` (`git -C <dir>`) for the rest of the process
+pub fn set_repo_dir(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        bail!("Repository directory not found: {}", dir.display());
+    }
+    REPO_DIR
+        .set(dir.to_path_buf())
+        .map_err(|_| anyhow::anyhow!("Repository directory is already set"))
+}
+
+/// Open the repository in-process for reads that would otherwise spawn `git`: repository
+/// discovery, HEAD, branch, tag and object lookups. Like the CLI it honors `--repo`, `GIT_DIR`
+/// and `GIT_WORK_TREE`. Diffs, commits, rebases and pushes stay on the `git` CLI so hooks,
+/// signing, credential helpers and diff settings (`diff.external`, textconv, rename detection)
+/// behave exactly as the user configured them.
+fn open_repo() -> Result<gix::Repository> {
+    let cwd = std::env::current_dir().context("Failed to read current directory")?;
+    let dir = match REPO_DIR.get() {
+        Some(dir) => cwd.join(dir),
+        None => cwd,
+    };
+    let repo = gix::ThreadSafeRepository::discover_with_environment_overrides(&dir)
+        .map_err(|_| anyhow::anyhow!("Not in a git repository"))?;
+    Ok(repo.to_thread_local())
+}
+
+/// A `git` command, run in the `--repo` directory when one was given. `GIT_DIR` and
+/// `GIT_WORK_TREE` are inherited from the environment and honored by git as usual.
+pub fn command() -> Command {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = REPO_DIR.get() {
+        cmd.arg("-C").arg(dir);
+    }
+    cmd
+}
+
+/// [`Command::output`] and [`Command::status`] that also write the git command, its exit status
+/// and how long it took to the `--log-file`
+trait Logged {
+    fn logged_output(&mut self) -> std::io::Result<Output>;
+    fn logged_status(&mut self) -> std::io::Result<ExitStatus>;
+}
+
+impl Logged for Command {
+    fn logged_output(&mut self) -> std::io::Result<Output> {
+        let _span = tracing::debug_span!("git").entered();
+        let start = std::time::Instant::now();
+        let output = self.output();
+        log_command(self, output.as_ref().map(|o| o.status), start);
+        output
+    }
+
+    fn logged_status(&mut self) -> std::io::Result<ExitStatus> {
+        let _span = tracing::debug_span!("git").entered();
+        let start = std::time::Instant::now();
+        let status = self.status();
+        log_command(self, status.as_ref().copied(), start);
+        status
+    }
+}
+
+fn log_command(
+    cmd: &Command,
+    status: Result<ExitStatus, &std::io::Error>,
+    start: std::time::Instant,
+) {
+    let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+    let outcome = match status {
+        Ok(status) => status.to_string(),
+        Err(e) => format!("failed to start: {e}"),
+    };
+    crate::ui::log_event!(
+        "git {} ({outcome}, {:.2}s)",
+        args.join(" "),
+        start.elapsed().as_secs_f64()
+    );
+}
 
 /// Get the output of `git diff --staged`
 pub fn get_staged_diff() -> Result<String> {
-    let output = Command::new("git")
+    let output = command()
         .args(["diff", "--staged"])
-        .output()
+        .logged_output()
         .context("Failed to run git diff --staged")?;
 
     if !output.status.success() {
@@ -30,9 +115,9 @@ pub fn get_staged_diff() -> Result<String> {
 
 /// List staged file paths
 pub fn list_staged_files() -> Result<Vec<String>> {
-    let output = Command::new("git")
+    let output = command()
         .args(["diff", "--staged", "--name-only"])
-        .output()
+        .logged_output()
         .context("Failed to run git diff --staged --name-only")?;
 
     if !output.status.success() {
@@ -52,25 +137,98 @@ pub fn list_staged_files() -> Result<Vec<String>> {
 
 /// Find the git repository root directory
 pub fn find_repo_root() -> Result<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
-        .output()
-        .context("Failed to run git rev-parse")?;
+    let repo = open_repo()?;
+    let root = repo.workdir().context("Not in a git repository")?;
+    path_string(root)
+}
 
-    if !output.status.success() {
-        bail!("Not in a git repository");
+/// Root of the main checkout that owns the current repository. Linked worktrees (`git worktree
+/// add`) share it, so history recorded in any of them is kept together. Elsewhere (bare
+/// repositories, submodules) this is the same as `find_repo_root`.
+pub fn repo_identity() -> Result<String> {
+    let repo = open_repo()?;
+    let root = path_string(repo.workdir().context("Not in a git repository")?)?;
+    // A linked worktree's `commondir` file is usually relative, e.g. `../..`
+    let common_dir = gix::path::realpath(repo.common_dir())
+        .context("Failed to resolve the git common directory")?;
+    match common_dir.parent() {
+        Some(main_root) if common_dir.file_name().is_some_and(|n| n == ".git") => {
+            path_string(main_root)
+        }
+        _ => Ok(root),
     }
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// `path` with symlinks resolved, spelled the way `git rev-parse` prints paths (forward
+/// slashes on Windows too), so cache keys stay stable across versions
+fn path_string(path: &Path) -> Result<String> {
+    let path = gix::path::realpath(path)
+        .with_context(|| format!("Failed to resolve {}", path.display()))?;
+    let path = path.to_string_lossy();
+    Ok(if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    })
+}
+
+/// Name of the checked-out branch, or `HEAD` when detached
+pub fn current_branch() -> Result<String> {
+    let repo = open_repo()?;
+    let head = repo.head_name().context("Failed to read HEAD")?;
+    Ok(head.map_or_else(|| "HEAD".to_string(), |name| name.shorten().to_string()))
+}
+
+/// Full hash of the commit HEAD points to
+pub fn head_hash() -> Result<String> {
+    let repo = open_repo()?;
+    let id = repo.head_id().context("Failed to resolve HEAD")?;
+    Ok(id.to_string())
+}
+
+/// Whether `branch` matches one of the glob `patterns` (`main`, `release/*`)
+pub fn branch_matches(branch: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .any(|p| p.matches(branch))
+}
+
+/// Resolve the editor the same way `git commit` does:
+/// `GIT_EDITOR`, then `core.editor`, then `VISUAL`, then `EDITOR`.
+pub fn resolve_editor() -> Option<String> {
+    let from_env = |key: &str| {
+        std::env::var(key)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    if let Some(editor) = from_env("GIT_EDITOR") {
+        return Some(editor);
+    }
+
+    let core_editor = command()
+        .args(["config", "--get", "core.editor"])
+        .logged_output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|v| !v.is_empty());
+    if core_editor.is_some() {
+        return core_editor;
+    }
+
+    from_env("VISUAL").or_else(|| from_env("EDITOR"))
 }
 
 /// Run `git commit -m "<message>" [extra_args...]`
 pub fn run_commit(message: &str, extra_args: &[String], suppress_output: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
+    let mut cmd = command();
     cmd.args(["commit", "-m", message]);
     cmd.args(extra_args);
     configure_stdio(&mut cmd, suppress_output);
-    let status = cmd.status().context("Failed to run git commit")?;
+    let status = cmd.logged_status().context("Failed to run git commit")?;
 
     if !status.success() {
         bail!("git commit exited with status {status}");
@@ -81,11 +239,11 @@ pub fn run_commit(message: &str, extra_args: &[String], suppress_output: bool) -
 
 /// Run `git push`
 pub fn run_push(suppress_output: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
+    let mut cmd = command();
     cmd.arg("push");
     configure_stdio(&mut cmd, suppress_output);
 
-    let status = cmd.status().context("Failed to run git push")?;
+    let status = cmd.logged_status().context("Failed to run git push")?;
     if !status.success() {
         bail!("git push exited with status {status}");
     }
@@ -93,27 +251,279 @@ pub fn run_push(suppress_output: bool) -> Result<()> {
     Ok(())
 }
 
-/// Returns the latest tag according to git version sorting.
-pub fn get_latest_tag() -> Result<Option<String>> {
-    let output = Command::new("git")
-        .args(["tag", "--sort=-version:refname"])
-        .output()
-        .context("Failed to run git tag --sort=-version:refname")?;
+/// Configured remotes, in `git remote` order
+pub fn remotes() -> Result<Vec<String>> {
+    let output = command()
+        .arg("remote")
+        .logged_output()
+        .context("Failed to run git remote")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git remote failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
 
+/// The remote a branch without an upstream should be pushed to: `remote.pushDefault`, then
+/// `origin`, then the only remote
+pub fn default_push_remote() -> Result<Option<String>> {
+    let output = command()
+        .args(["config", "--get", "remote.pushDefault"])
+        .logged_output()
+        .context("Failed to run git config")?;
+    let push_default = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !push_default.is_empty() {
+        return Ok(Some(push_default));
+    }
+    let remotes = remotes()?;
+    if remotes.iter().any(|r| r == "origin") {
+        return Ok(Some("origin".to_string()));
+    }
+    Ok(match remotes.as_slice() {
+        [only] => Some(only.clone()),
+        _ => None,
+    })
+}
+
+/// The remote's default branch, from its `HEAD` recorded at clone time (`origin/HEAD`). Uses
+/// the upstream's remote, or the default push remote. `None` when git never recorded it; run
+/// `git remote set-head origin --auto` to fix that.
+pub fn default_branch() -> Result<Option<String>> {
+    let remote = match upstream()? {
+        Some(upstream) => upstream.remote,
+        None => match default_push_remote()? {
+            Some(remote) => remote,
+            None => return Ok(None),
+        },
+    };
+    let output = command()
+        .args(["symbolic-ref", "--quiet", "--short"])
+        .arg(format!("refs/remotes/{remote}/HEAD"))
+        .logged_output()
+        .context("Failed to run git symbolic-ref")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let target = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(target
+        .strip_prefix(&format!("{remote}/"))
+        .map(str::to_string))
+}
+
+/// Push the current branch to the branch of the same name on `remote`, making it the
+/// upstream when `set_upstream` is set (`git push -u`)
+pub fn push_to_remote(remote: &str, set_upstream: bool, suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.arg("push");
+    if set_upstream {
+        cmd.arg("--set-upstream");
+    }
+    cmd.args([remote, "HEAD"]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .with_context(|| format!("Failed to run git push {remote}"))?;
+    if !status.success() {
+        bail!("git push {remote} exited with status {status}");
+    }
+    Ok(())
+}
+
+/// The branch the current branch tracks, and where its remote-tracking ref pointed when read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    pub remote: String,
+    /// Full ref on the remote, e.g. `refs/heads/main`
+    pub remote_ref: String,
+    /// Commit of the remote-tracking branch (`@{u}`), the lease for a later force-push
+    pub expected: String,
+}
+
+impl Upstream {
+    /// `origin/main` style name for messages
+    pub fn display_name(&self) -> String {
+        let branch = self
+            .remote_ref
+            .strip_prefix("refs/heads/")
+            .unwrap_or(&self.remote_ref);
+        format!("{}/{branch}", self.remote)
+    }
+}
+
+/// The current branch's upstream, or `None` when it has none
+pub fn upstream() -> Result<Option<Upstream>> {
+    if !has_upstream_branch()? {
+        return Ok(None);
+    }
+    let output = command()
+        .args([
+            "for-each-ref",
+            "--format=%(upstream:remotename) %(upstream:remoteref)",
+        ])
+        .arg(format!("refs/heads/{}", current_branch()?))
+        .logged_output()
+        .context("Failed to read the upstream branch")?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git tag --sort=-version:refname failed: {stderr}");
+        bail!("git for-each-ref failed: {stderr}");
     }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some((remote, remote_ref)) = stdout.trim().split_once(' ') else {
+        return Ok(None);
+    };
+    Ok(Some(Upstream {
+        remote: remote.to_string(),
+        remote_ref: remote_ref.to_string(),
+        expected: resolve_commit("@{u}")?,
+    }))
+}
+
+/// `git fetch <remote>`, updating its remote-tracking branches
+pub fn fetch(remote: &str, suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.args(["fetch", "--quiet", remote]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .with_context(|| format!("Failed to run git fetch {remote}"))?;
+    if !status.success() {
+        bail!("git fetch {remote} exited with status {status}");
+    }
+    Ok(())
+}
 
-    let latest = String::from_utf8_lossy(&output.stdout)
+/// Commits HEAD has that its upstream lacks, and the reverse: `(ahead, behind)`
+pub fn ahead_behind() -> Result<(usize, usize)> {
+    let output = command()
+        .args(["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+        .logged_output()
+        .context("Failed to run git rev-list --left-right")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git rev-list --left-right failed: {stderr}");
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut counts = stdout.split_whitespace().map(str::parse::<usize>);
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+        _ => bail!("Unexpected git rev-list output: {stdout}"),
+    }
+}
+
+/// `git pull --rebase` from the upstream
+pub fn pull_rebase(suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.args(["pull", "--rebase"]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .context("Failed to run git pull --rebase")?;
+    if !status.success() {
+        bail!("git pull --rebase exited with status {status}");
+    }
+    Ok(())
+}
+
+/// Where `upstream`'s branch points on the remote right now (`git ls-remote`), or `None` when
+/// it no longer exists there
+pub fn remote_branch_commit(upstream: &Upstream) -> Result<Option<String>> {
+    let output = command()
+        .args(["ls-remote", &upstream.remote, &upstream.remote_ref])
+        .logged_output()
+        .with_context(|| format!("Failed to run git ls-remote {}", upstream.remote))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git ls-remote {} failed: {stderr}", upstream.remote);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
-        .map(str::trim)
-        .find(|line| !line.is_empty())
-        .map(ToString::to_string);
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(_, name)| *name == upstream.remote_ref)
+        .map(|(sha, _)| sha.to_string()))
+}
+
+/// Force-push HEAD to `upstream`, only if the remote branch is still at `upstream.expected`
+pub fn force_push_with_lease(upstream: &Upstream, suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.arg("push")
+        .arg(format!(
+            "--force-with-lease={}:{}",
+            upstream.remote_ref, upstream.expected
+        ))
+        .arg(&upstream.remote)
+        .arg(format!("HEAD:{}", upstream.remote_ref));
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .context("Failed to run git push --force-with-lease")?;
+    if !status.success() {
+        bail!("git push --force-with-lease exited with status {status}");
+    }
+    Ok(())
+}
 
+/// Returns the latest tag according to git version sorting.
+pub fn get_latest_tag() -> Result<Option<String>> {
+    let repo = open_repo()?;
+    let refs = repo.references().context("Failed to read references")?;
+    let mut latest: Option<String> = None;
+    for reference in refs.tags().context("Failed to list tags")? {
+        let reference = reference.map_err(|e| anyhow::anyhow!("Failed to read tag: {e}"))?;
+        let name = reference.name().shorten().to_string();
+        if latest
+            .as_deref()
+            .is_none_or(|best| version_cmp(&name, best).is_gt())
+        {
+            latest = Some(name);
+        }
+    }
     Ok(latest)
 }
 
+/// Order two ref names like `git tag --sort=version:refname`: runs of digits compare as
+/// numbers, everything else byte by byte
+pub fn version_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    fn digits(s: &[u8]) -> usize {
+        s.iter().take_while(|c| c.is_ascii_digit()).count()
+    }
+    fn without_leading_zeros(s: &[u8]) -> &[u8] {
+        &s[s.iter().take_while(|&&c| c == b'0').count()..]
+    }
+    let (mut a_rest, mut b_rest) = (a.as_bytes(), b.as_bytes());
+    loop {
+        match (a_rest.first(), b_rest.first()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return std::cmp::Ordering::Less,
+            (Some(_), None) => return std::cmp::Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (a_len, b_len) = (digits(a_rest), digits(b_rest));
+                let a_num = without_leading_zeros(&a_rest[..a_len]);
+                let b_num = without_leading_zeros(&b_rest[..b_len]);
+                let ordering = a_num.len().cmp(&b_num.len()).then(a_num.cmp(b_num));
+                if ordering.is_ne() {
+                    return ordering;
+                }
+                a_rest = &a_rest[a_len..];
+                b_rest = &b_rest[b_len..];
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
+                a_rest = &a_rest[1..];
+                b_rest = &b_rest[1..];
+            }
+        }
+    }
+}
+
 /// Compute the next minor semver tag from latest tag.
 pub fn compute_next_minor_tag(latest: Option<&str>) -> Result<String> {
     let Some(latest_tag) = latest else {
@@ -126,10 +536,10 @@ pub fn compute_next_minor_tag(latest: Option<&str>) -> Result<String> {
 
 /// Create a git lightweight tag.
 pub fn create_tag(tag_name: &str, suppress_output: bool) -> Result<()> {
-    let mut cmd = Command::new("git");
+    let mut cmd = command();
     cmd.args(["tag", tag_name]);
     configure_stdio(&mut cmd, suppress_output);
-    let status = cmd.status().context("Failed to run git tag")?;
+    let status = cmd.logged_status().context("Failed to run git tag")?;
 
     if !status.success() {
         bail!("git tag exited with status {status}");
@@ -144,9 +554,9 @@ pub fn is_head_pushed() -> Result<bool> {
         return Ok(false);
     }
 
-    let output = Command::new("git")
+    let output = command()
         .args(["branch", "-r", "--contains", "HEAD"])
-        .output()
+        .logged_output()
         .context("Failed to determine whether HEAD is pushed")?;
 
     if !output.status.success() {
@@ -166,9 +576,9 @@ pub fn is_head_pushed() -> Result<bool> {
 pub fn head_is_merge_commit() -> Result<bool> {
     ensure_head_exists()?;
 
-    let output = Command::new("git")
+    let output = command()
         .args(["rev-list", "--parents", "-n", "1", "HEAD"])
-        .output()
+        .logged_output()
         .context("Failed to inspect latest commit parents")?;
 
     if !output.status.success() {
@@ -183,39 +593,313 @@ pub fn head_is_merge_commit() -> Result<bool> {
     Ok(parent_count > 1)
 }
 
+/// How `cgen undo` treats the changes of the undone commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMode {
+    /// Keep them staged (`--soft`)
+    Soft,
+    /// Keep them in the working tree, unstaged (`--mixed`)
+    Mixed,
+    /// Throw them away, with any other uncommitted change (`--hard`)
+    Hard,
+}
+
+impl ResetMode {
+    pub fn flag(self) -> &'static str {
+        match self {
+            ResetMode::Soft => "--soft",
+            ResetMode::Mixed => "--mixed",
+            ResetMode::Hard => "--hard",
+        }
+    }
+}
+
 /// Undo latest commit, keep all changes staged
 pub fn undo_last_commit_soft(suppress_output: bool) -> Result<()> {
+    undo_last_commit(ResetMode::Soft, suppress_output)
+}
+
+/// `git reset <mode> HEAD~1`
+pub fn undo_last_commit(mode: ResetMode, suppress_output: bool) -> Result<()> {
+    undo_last_commits(mode, 1, suppress_output)
+}
+
+/// `git reset <mode> HEAD~<count>`
+pub fn undo_last_commits(mode: ResetMode, count: usize, suppress_output: bool) -> Result<()> {
     ensure_head_exists()?;
 
-    let mut cmd = Command::new("git");
-    cmd.args(["reset", "--soft", "HEAD~1"]);
+    let target = format!("HEAD~{count}");
+    let mut cmd = command();
+    cmd.args(["reset", mode.flag(), &target]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .with_context(|| format!("Failed to run git reset {} {target}", mode.flag()))?;
+    if !status.success() {
+        bail!(
+            "git reset {} {target} exited with status {status}",
+            mode.flag()
+        );
+    }
+    Ok(())
+}
+
+/// Tree object of `commit`
+pub fn commit_tree(commit: &str) -> Result<String> {
+    let spec = format!("{commit}^{{tree}}");
+    let output = command()
+        .args(["rev-parse", &spec])
+        .logged_output()
+        .with_context(|| format!("Failed to run git rev-parse {spec}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git rev-parse {spec} failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Tree object of the current index (`git write-tree`)
+pub fn index_tree() -> Result<String> {
+    let output = command()
+        .arg("write-tree")
+        .logged_output()
+        .context("Failed to run git write-tree")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git write-tree failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether `commit` still exists in the object database
+pub fn commit_exists(commit: &str) -> bool {
+    let Ok(id) = gix::ObjectId::from_hex(commit.as_bytes()) else {
+        // Abbreviated hashes and ref names need git's revision parsing
+        return command()
+            .args(["cat-file", "-e", &format!("{commit}^{{commit}}")])
+            .stderr(Stdio::null())
+            .logged_status()
+            .is_ok_and(|s| s.success());
+    };
+    open_repo()
+        .ok()
+        .and_then(|repo| repo.find_header(id).ok())
+        .is_some_and(|header| header.kind() == gix::object::Kind::Commit)
+}
+
+/// Move HEAD to `commit`, keeping the index and working tree (`git reset --soft`)
+pub fn reset_soft_to(commit: &str, suppress_output: bool) -> Result<()> {
+    reset_to(commit, ResetMode::Soft, suppress_output)
+}
+
+/// `git reset <mode> <commit>`
+pub fn reset_to(commit: &str, mode: ResetMode, suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.args(["reset", mode.flag(), commit]);
     configure_stdio(&mut cmd, suppress_output);
 
     let status = cmd
-        .status()
-        .context("Failed to run git reset --soft HEAD~1")?;
+        .logged_status()
+        .with_context(|| format!("Failed to run git reset {} {commit}", mode.flag()))?;
     if !status.success() {
-        bail!("git reset --soft HEAD~1 exited with status {status}");
+        bail!(
+            "git reset {} {commit} exited with status {status}",
+            mode.flag()
+        );
+    }
+    Ok(())
+}
+
+/// Whether the index or working tree has changes, untracked files included
+pub fn has_uncommitted_changes() -> Result<bool> {
+    let output = command()
+        .args(["status", "--porcelain"])
+        .logged_output()
+        .context("Failed to run git status")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git status failed: {stderr}");
+    }
+    Ok(!output.stdout.is_empty())
+}
+
+/// Stash staged, unstaged and untracked changes under `message`
+pub fn stash_push(message: &str, suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.args(["stash", "push", "--include-untracked", "-m", message]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd
+        .logged_status()
+        .context("Failed to run git stash push")?;
+    if !status.success() {
+        bail!("git stash push exited with status {status}");
+    }
+    Ok(())
+}
+
+/// Restore the latest stash, staged changes included. On conflict git keeps the stash.
+pub fn stash_pop(suppress_output: bool) -> Result<()> {
+    let mut cmd = command();
+    cmd.args(["stash", "pop", "--index"]);
+    configure_stdio(&mut cmd, suppress_output);
+
+    let status = cmd.logged_status().context("Failed to run git stash pop")?;
+    if !status.success() {
+        bail!("git stash pop exited with status {status}");
+    }
+    Ok(())
+}
+
+/// A multi-step git operation that has stopped part way, waiting for the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InProgressOperation {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+}
+
+impl InProgressOperation {
+    /// The git subcommand that continues or aborts it
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Rebase => "rebase",
+            Self::Merge => "merge",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+        }
+    }
+}
+
+/// Absolute paths of files inside the git directory (`git rev-parse --git-path`)
+fn git_paths(names: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut cmd = command();
+    cmd.args(["rev-parse", "--path-format=absolute"]);
+    for name in names {
+        cmd.args(["--git-path", name]);
+    }
+    let output = cmd
+        .logged_output()
+        .context("Failed to run git rev-parse --git-path")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git rev-parse --git-path failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// The rebase, merge, cherry-pick or revert the repository is in the middle of, if any
+pub fn in_progress_operation() -> Result<Option<InProgressOperation>> {
+    use InProgressOperation::*;
+    let markers = [
+        ("rebase-merge", Rebase),
+        ("rebase-apply", Rebase),
+        ("MERGE_HEAD", Merge),
+        ("CHERRY_PICK_HEAD", CherryPick),
+        ("REVERT_HEAD", Revert),
+    ];
+    let names: Vec<&str> = markers.iter().map(|(name, _)| *name).collect();
+    let paths = git_paths(&names)?;
+    Ok(markers
+        .iter()
+        .zip(paths)
+        .find(|(_, path)| path.exists())
+        .map(|((_, op), _)| *op))
+}
+
+/// Result of running the pre-commit checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreCommitOutcome {
+    /// No hook is installed and the project does not use the `pre-commit` framework
+    Missing,
+    Passed,
+    Failed,
+}
+
+/// Run the repository's pre-commit hook (honoring `core.hooksPath`), or `pre-commit run` when
+/// the project has a `.pre-commit-config.yaml` but the framework's hook is not installed
+pub fn run_pre_commit_hook(suppress_output: bool) -> Result<PreCommitOutcome> {
+    let hook = git_paths(&["hooks/pre-commit"])?
+        .pop()
+        .context("Failed to locate the pre-commit hook")?;
+
+    let mut cmd = if hook.is_file() {
+        let mut cmd = command();
+        cmd.args(["hook", "run", "pre-commit"]);
+        cmd
+    } else {
+        let root = find_repo_root()?;
+        if !Path::new(&root).join(".pre-commit-config.yaml").is_file() {
+            return Ok(PreCommitOutcome::Missing);
+        }
+        let mut cmd = Command::new("pre-commit");
+        cmd.arg("run").current_dir(root);
+        cmd
+    };
+    configure_stdio(&mut cmd, suppress_output);
+    match cmd.logged_status() {
+        Ok(status) if status.success() => Ok(PreCommitOutcome::Passed),
+        Ok(_) => Ok(PreCommitOutcome::Failed),
+        // The framework is configured but not installed on this machine
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PreCommitOutcome::Missing),
+        Err(e) => Err(e).context("Failed to run the pre-commit hook"),
+    }
+}
+
+/// Tracked files with changes that are not staged
+pub fn unstaged_files() -> Result<Vec<String>> {
+    let output = command()
+        .args(["diff", "--name-only"])
+        .logged_output()
+        .context("Failed to run git diff --name-only")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git diff --name-only failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// `git add` the given paths, relative to the repository root
+pub fn stage_files(files: &[String]) -> Result<()> {
+    let root = find_repo_root()?;
+    let output = command()
+        .arg("-C")
+        .arg(&root)
+        .args(["add", "--"])
+        .args(files)
+        .logged_output()
+        .context("Failed to run git add")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git add failed: {stderr}");
     }
     Ok(())
 }
 
 pub fn has_upstream_branch() -> Result<bool> {
-    let status = Command::new("git")
+    let status = command()
         .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .status()
+        .logged_status()
         .context("Failed to detect upstream branch")?;
     Ok(status.success())
 }
 
 pub fn ensure_head_exists() -> Result<()> {
-    let status = Command::new("git")
+    let status = command()
         .args(["rev-parse", "--verify", "HEAD"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .status()
+        .logged_status()
         .context("Failed to run git rev-parse --verify HEAD")?;
 
     if !status.success() {
@@ -231,11 +915,11 @@ fn configure_stdio(cmd: &mut Command, suppress_output: bool) {
 }
 
 pub fn ensure_commit_exists(commit: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = command()
         .args(["rev-parse", "--verify", &format!("{commit}^{{commit}}")])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .status()
+        .logged_status()
         .with_context(|| format!("Failed to verify commit reference {commit}"))?;
 
     if !status.success() {
@@ -246,9 +930,9 @@ pub fn ensure_commit_exists(commit: &str) -> Result<()> {
 
 pub fn get_commit_diff(commit: &str) -> Result<String> {
     ensure_commit_exists(commit)?;
-    let output = Command::new("git")
+    let output = command()
         .args(["show", "--format=", "--no-color", commit])
-        .output()
+        .logged_output()
         .with_context(|| format!("Failed to run git show for {commit}"))?;
 
     if !output.status.success() {
@@ -263,13 +947,117 @@ pub fn get_commit_diff(commit: &str) -> Result<String> {
     Ok(diff)
 }
 
+/// `git show --stat` summary of a commit in `repo`: one line per file plus the totals
+pub fn commit_stat(repo: &str, commit: &str) -> Result<String> {
+    let output = command()
+        .args([
+            "-C",
+            repo,
+            "show",
+            "--stat",
+            "--format=",
+            "--no-color",
+            commit,
+        ])
+        .logged_output()
+        .with_context(|| format!("Failed to run git show --stat for {commit}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git show --stat failed for {commit}: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Local branches of `repo` that contain `commit`
+pub fn branches_containing(repo: &str, commit: &str) -> Result<Vec<String>> {
+    let output = command()
+        .args([
+            "-C",
+            repo,
+            "branch",
+            "--format=%(refname:short)",
+            "--contains",
+            commit,
+        ])
+        .logged_output()
+        .with_context(|| format!("Failed to run git branch --contains for {commit}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git branch --contains failed for {commit}: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// List `(hash, full message)` pairs, newest first. A plain ref selects that single commit;
+/// a `a..b` or `a...b` range selects every commit in it.
+pub fn get_commit_messages(revision: &str) -> Result<Vec<(String, String)>> {
+    let mut args = vec!["log", "--no-color", "--format=%H%x1f%B%x1e"];
+    if !revision.contains("..") {
+        ensure_commit_exists(revision)?;
+        args.push("-1");
+    }
+    args.push(revision);
+
+    let output = command()
+        .args(&args)
+        .logged_output()
+        .with_context(|| format!("Failed to run git log for {revision}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git log failed for {revision}: {stderr}");
+    }
+
+    Ok(parse_log_records(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The last `count` commits on HEAD's first-parent line, newest first, as `(hash, message)`.
+/// Fewer are returned when the history is shorter.
+pub fn last_commits(count: usize) -> Result<Vec<(String, String)>> {
+    ensure_head_exists()?;
+    let output = command()
+        .args([
+            "log",
+            "--no-color",
+            "--first-parent",
+            "--format=%H%x1f%B%x1e",
+            "-n",
+            &count.to_string(),
+            "HEAD",
+        ])
+        .logged_output()
+        .context("Failed to run git log")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git log failed: {stderr}");
+    }
+    Ok(parse_log_records(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Split `git log --format=%H%x1f%B%x1e` output into `(hash, message)` pairs
+fn parse_log_records(stdout: &str) -> Vec<(String, String)> {
+    stdout
+        .split('\x1e')
+        .filter_map(|record| {
+            let (hash, message) = record.trim_start_matches('\n').split_once('\x1f')?;
+            Some((hash.to_string(), message.trim_end().to_string()))
+        })
+        .collect()
+}
+
 pub fn get_range_diff(older: &str, newer: &str) -> Result<String> {
     ensure_commit_exists(older)?;
     ensure_commit_exists(newer)?;
 
-    let output = Command::new("git")
+    let output = command()
         .args(["diff", "--no-color", older, newer])
-        .output()
+        .logged_output()
         .with_context(|| format!("Failed to run git diff {older} {newer}"))?;
 
     if !output.status.success() {
@@ -291,9 +1079,9 @@ pub fn is_head_commit(commit: &str) -> Result<bool> {
 
 pub fn commit_is_merge(commit: &str) -> Result<bool> {
     ensure_commit_exists(commit)?;
-    let output = Command::new("git")
+    let output = command()
         .args(["rev-list", "--parents", "-n", "1", commit])
-        .output()
+        .logged_output()
         .with_context(|| format!("Failed to inspect parents for {commit}"))?;
 
     if !output.status.success() {
@@ -314,9 +1102,9 @@ pub fn commit_is_pushed(commit: &str) -> Result<bool> {
         return Ok(false);
     }
 
-    let output = Command::new("git")
+    let output = command()
         .args(["branch", "-r", "--contains", commit])
-        .output()
+        .logged_output()
         .with_context(|| format!("Failed to determine whether {commit} is pushed"))?;
 
     if !output.status.success() {
@@ -331,18 +1119,25 @@ pub fn commit_is_pushed(commit: &str) -> Result<bool> {
         .any(|line| !line.is_empty() && !line.contains("->")))
 }
 
-pub fn rewrite_commit_message(target: &str, message: &str, suppress_output: bool) -> Result<()> {
+/// Give `target` the new `message` and return the rewritten commit's hash
+pub fn rewrite_commit_message(
+    target: &str,
+    message: &str,
+    suppress_output: bool,
+) -> Result<String> {
     ensure_commit_exists(target)?;
 
     if is_head_commit(target)? {
-        let mut cmd = Command::new("git");
+        let mut cmd = command();
         cmd.args(["commit", "--amend", "-m", message]);
         configure_stdio(&mut cmd, suppress_output);
-        let status = cmd.status().context("Failed to run git commit --amend")?;
+        let status = cmd
+            .logged_status()
+            .context("Failed to run git commit --amend")?;
         if !status.success() {
             bail!("git commit --amend exited with status {status}");
         }
-        return Ok(());
+        return resolve_commit("HEAD");
     }
 
     if commit_is_merge(target)? {
@@ -350,13 +1145,13 @@ pub fn rewrite_commit_message(target: &str, message: &str, suppress_output: bool
     }
 
     ensure_ancestor_of_head(target)?;
-    reword_non_head_commit(target, message, suppress_output)
+    reword_non_head_commit(target, message)
 }
 
 fn resolve_commit(commit: &str) -> Result<String> {
-    let output = Command::new("git")
+    let output = command()
         .args(["rev-parse", "--verify", &format!("{commit}^{{commit}}")])
-        .output()
+        .logged_output()
         .with_context(|| format!("Failed to resolve commit {commit}"))?;
 
     if !output.status.success() {
@@ -368,11 +1163,11 @@ fn resolve_commit(commit: &str) -> Result<String> {
 }
 
 pub fn ensure_ancestor_of_head(commit: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = command()
         .args(["merge-base", "--is-ancestor", commit, "HEAD"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .status()
+        .logged_status()
         .with_context(|| format!("Failed to check whether {commit} is an ancestor of HEAD"))?;
 
     if !status.success() {
@@ -381,85 +1176,211 @@ pub fn ensure_ancestor_of_head(commit: &str) -> Result<()> {
     Ok(())
 }
 
-fn reword_non_head_commit(target: &str, message: &str, suppress_output: bool) -> Result<()> {
-    let parent = format!("{target}^");
-    let temp = std::env::temp_dir();
-    let sequence_editor = temp.join(format!("cgen-seq-editor-{}.sh", std::process::id()));
-    let message_editor = temp.join(format!("cgen-msg-editor-{}.sh", std::process::id()));
-
-    write_sequence_editor_script(&sequence_editor)?;
-    write_message_editor_script(&message_editor)?;
-
-    let mut cmd = Command::new("git");
-    cmd.args(["rebase", "-i", &parent]);
-    cmd.env("GIT_SEQUENCE_EDITOR", script_command(&sequence_editor));
-    cmd.env("GIT_EDITOR", script_command(&message_editor));
-    cmd.env("CGEN_NEW_MESSAGE", message);
-    configure_stdio(&mut cmd, suppress_output);
-
-    let status = cmd.status().context("Failed to run git rebase -i")?;
-
-    let _ = fs::remove_file(&sequence_editor);
-    let _ = fs::remove_file(&message_editor);
+/// Reword a commit below HEAD without a rebase: the target and every commit descending from
+/// it are recreated with `git commit-tree`, keeping their trees, authors and messages, and the
+/// branch is moved to the new HEAD. Since no tree changes there are no conflicts, no editor
+/// scripts (which need a POSIX shell) and the working tree is never touched. Merges after the
+/// target are kept as merges. Returns the reworded commit's new hash.
+fn reword_non_head_commit(target: &str, message: &str) -> Result<String> {
+    let target = resolve_commit(target)?;
+    let head = resolve_commit("HEAD")?;
+
+    let output = command()
+        .args(["rev-list", "--reverse", "--topo-order", "--ancestry-path"])
+        .arg(format!("{target}..{head}"))
+        .logged_output()
+        .context("Failed to run git rev-list --ancestry-path")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git rev-list --ancestry-path failed: {stderr}");
+    }
+    let descendants: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
 
-    if !status.success() {
-        bail!(
-            "Rewriting commit message failed during rebase. Resolve conflicts and run `git rebase --abort` if needed."
+    // Read every commit before creating any, so nothing is half-rewritten
+    let objects = std::iter::once(&target)
+        .chain(&descendants)
+        .map(|commit| read_commit_object(commit))
+        .collect::<Result<Vec<_>>>()?;
+    let sign = signs_commits();
+    let signed = objects.iter().filter(|o| o.signed).count();
+    if signed > 0 && !sign {
+        eprintln!(
+            "{} {}",
+            t("warning").yellow().bold(),
+            tf("reword_drops_signatures", &[("count", &signed.to_string())])
         );
     }
-    Ok(())
-}
-
-fn write_sequence_editor_script(path: &Path) -> Result<()> {
-    let script = r#"#!/bin/sh
-set -e
-todo="$1"
-tmp="${todo}.cgen"
-first=1
-
-while IFS= read -r line; do
-  if [ "$first" -eq 1 ] && printf '%s\n' "$line" | grep -q '^pick '; then
-    printf '%s\n' "$line" | sed 's/^pick /reword /' >> "$tmp"
-    first=0
-  else
-    printf '%s\n' "$line" >> "$tmp"
-  fi
-done < "$todo"
 
-mv "$tmp" "$todo"
-"#;
-    fs::write(path, script).with_context(|| format!("Failed to write {:?}", path))?;
-    make_executable(path)?;
-    Ok(())
+    let mut objects = objects.into_iter();
+    let mut rewritten: HashMap<String, String> = HashMap::new();
+    let target_object = objects
+        .next()
+        .context("Failed to read the reworded commit")?;
+    let new_target = recreate_commit(target_object, Some(message), &rewritten, sign)?;
+    rewritten.insert(target, new_target.clone());
+    for (commit, object) in descendants.into_iter().zip(objects) {
+        let new_commit = recreate_commit(object, None, &rewritten, sign)?;
+        rewritten.insert(commit, new_commit);
+    }
+    let new_head = rewritten
+        .get(&head)
+        .context("HEAD does not descend from the reworded commit")?;
+
+    // Fails, leaving everything as it was, if HEAD moved in the meantime
+    let output = command()
+        .args([
+            "update-ref",
+            "-m",
+            "cgen alter: reword",
+            "HEAD",
+            new_head,
+            &head,
+        ])
+        .logged_output()
+        .context("Failed to run git update-ref")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git update-ref HEAD failed: {stderr}");
+    }
+    Ok(new_target)
 }
 
-fn write_message_editor_script(path: &Path) -> Result<()> {
-    let script = r#"#!/bin/sh
-set -e
-msg_file="$1"
-printf '%s\n' "$CGEN_NEW_MESSAGE" > "$msg_file"
-"#;
-    fs::write(path, script).with_context(|| format!("Failed to write {:?}", path))?;
-    make_executable(path)?;
-    Ok(())
+/// Fields of a commit object needed to recreate it
+struct CommitObject {
+    hash: String,
+    tree: String,
+    parents: Vec<String>,
+    /// `Name <email> <unix time> <offset>`
+    author: String,
+    /// Value of the `encoding` header, for messages not written in UTF-8
+    encoding: Option<String>,
+    /// Has a `gpgsig` header, which `commit-tree` cannot carry over
+    signed: bool,
+    message: String,
 }
 
-fn make_executable(path: &Path) -> Result<()> {
-    #[cfg(not(unix))]
-    let _ = path;
+fn parse_commit_object(hash: &str, raw: &str) -> Option<CommitObject> {
+    let (headers, message) = raw.split_once("\n\n").unwrap_or((raw, ""));
+    let mut tree = None;
+    let mut parents = Vec::new();
+    let mut author = None;
+    let mut encoding = None;
+    let mut signed = false;
+    for line in headers.lines() {
+        if let Some(value) = line.strip_prefix("tree ") {
+            tree = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("parent ") {
+            parents.push(value.to_string());
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("encoding ") {
+            encoding = Some(value.to_string());
+        } else if line.starts_with("gpgsig ") || line.starts_with("gpgsig-sha256 ") {
+            signed = true;
+        }
+    }
+    Some(CommitObject {
+        hash: hash.to_string(),
+        tree: tree?,
+        parents,
+        author: author?,
+        encoding,
+        signed,
+        message: message.to_string(),
+    })
+}
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(path)?.permissions();
-        perms.set_mode(0o700);
-        fs::set_permissions(path, perms)?;
+/// Read and parse `commit`. Commits whose message is not UTF-8 are refused: `commit-tree`
+/// would write them back re-encoded or corrupted.
+fn read_commit_object(commit: &str) -> Result<CommitObject> {
+    let output = command()
+        .args(["cat-file", "commit", commit])
+        .logged_output()
+        .with_context(|| format!("Failed to read commit {commit}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git cat-file commit {commit} failed: {stderr}");
     }
-    Ok(())
+    let unsupported = || {
+        format!(
+            "Commit {commit} is not encoded in UTF-8 and cannot be reworded without corrupting \
+             it; use `git rebase -i` instead"
+        )
+    };
+    let raw = String::from_utf8(output.stdout).with_context(unsupported)?;
+    let object =
+        parse_commit_object(commit, &raw).with_context(|| format!("Malformed commit {commit}"))?;
+    if object.encoding.is_some() {
+        bail!(unsupported());
+    }
+    Ok(object)
 }
 
-fn script_command(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
+/// Whether the user has git sign new commits (`commit.gpgSign`). `commit-tree` ignores the
+/// setting, so rewritten commits are signed with `-S` explicitly.
+fn signs_commits() -> bool {
+    command()
+        .args(["config", "--bool", "commit.gpgsign"])
+        .logged_output()
+        .is_ok_and(|o| o.status.success() && String::from_utf8_lossy(&o.stdout).trim() == "true")
+}
+
+/// `git commit-tree` a copy of `object` with its parents replaced through `rewritten`, and
+/// `message` instead of its own when given, signed when `sign` is set. Returns the new hash.
+fn recreate_commit(
+    object: CommitObject,
+    message: Option<&str>,
+    rewritten: &HashMap<String, String>,
+    sign: bool,
+) -> Result<String> {
+    let commit = &object.hash;
+    // "Name <email> 1700000000 +0100"
+    let (name, rest) = object
+        .author
+        .split_once(" <")
+        .with_context(|| format!("Malformed author in {commit}"))?;
+    let (email, date) = rest
+        .split_once("> ")
+        .with_context(|| format!("Malformed author in {commit}"))?;
+
+    let mut cmd = command();
+    cmd.args(["commit-tree", &object.tree]);
+    if sign {
+        cmd.arg("-S");
+    }
+    for parent in &object.parents {
+        cmd.arg("-p").arg(rewritten.get(parent).unwrap_or(parent));
+    }
+    cmd.env("GIT_AUTHOR_NAME", name)
+        .env("GIT_AUTHOR_EMAIL", email)
+        .env("GIT_AUTHOR_DATE", date)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let start = std::time::Instant::now();
+    let mut child = cmd.spawn().context("Failed to run git commit-tree")?;
+    let message = match message {
+        Some(message) => format!("{}\n", message.trim_end()),
+        None => object.message,
+    };
+    child
+        .stdin
+        .take()
+        .context("Failed to open git commit-tree stdin")?
+        .write_all(message.as_bytes())
+        .context("Failed to write the commit message")?;
+    let output = child
+        .wait_with_output()
+        .context("Failed to run git commit-tree")?;
+    log_command(&cmd, Ok(output.status), start);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git commit-tree for {commit} failed: {stderr}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 fn parse_semver_tag(tag: &str) -> Result<(u64, u64, u64)> {
@@ -526,11 +1447,300 @@ pub fn filter_diff_by_globs(diff: &str, exclude_patterns: &[String]) -> String {
     result
 }
 
-/// Get staged diff with files filtered by glob patterns.
-/// Excluded files are still committed, just not sent to the LLM for analysis.
-pub fn get_staged_diff_filtered(exclude_patterns: &[String]) -> Result<String> {
-    let diff = get_staged_diff()?;
-    Ok(filter_diff_by_globs(&diff, exclude_patterns))
+/// Number of added and removed lines in a unified diff (file headers excluded)
+pub fn count_changed_lines(diff: &str) -> usize {
+    diff.lines()
+        .filter(|line| {
+            (line.starts_with('+') && !line.starts_with("+++"))
+                || (line.starts_with('-') && !line.starts_with("---"))
+        })
+        .count()
+}
+
+/// Shrink a unified diff to at most `max_bytes`. Every file header is kept so the model still
+/// sees which files changed; hunk lines are kept in order until the budget runs out and the rest
+/// of each file is replaced by an omission marker. Returns `None` when the headers alone do not
+/// fit.
+pub fn truncate_diff(diff: &str, max_bytes: usize) -> Option<String> {
+    if diff.len() <= max_bytes {
+        return Some(diff.to_string());
+    }
+
+    // (header lines, hunk lines) per file
+    let mut files: Vec<(Vec<&str>, Vec<&str>)> = Vec::new();
+    for line in diff.lines() {
+        match files.last_mut() {
+            Some((_, hunks)) if !line.starts_with("diff --git ") && !hunks.is_empty() => {
+                hunks.push(line)
+            }
+            Some((header, hunks)) if !line.starts_with("diff --git ") => {
+                if line.starts_with("@@") {
+                    hunks.push(line);
+                } else {
+                    header.push(line);
+                }
+            }
+            _ => files.push((vec![line], Vec::new())),
+        }
+    }
+
+    let marker = |omitted: usize| {
+        format!("[... {omitted} diff lines omitted to fit the request size limit]\n")
+    };
+    let skeleton: usize = files
+        .iter()
+        .map(|(header, hunks)| {
+            header.iter().map(|l| l.len() + 1).sum::<usize>() + marker(hunks.len()).len()
+        })
+        .sum();
+    if skeleton > max_bytes {
+        return None;
+    }
+
+    let mut budget = max_bytes - skeleton;
+    let mut out = String::new();
+    for (header, hunks) in &files {
+        for line in header {
+            out.push_str(line);
+            out.push('\n');
+        }
+        let mut kept = 0;
+        for line in hunks {
+            if line.len() + 1 > budget {
+                break;
+            }
+            budget -= line.len() + 1;
+            out.push_str(line);
+            out.push('\n');
+            kept += 1;
+        }
+        if kept < hunks.len() {
+            out.push_str(&marker(hunks.len() - kept));
+        } else {
+            // The marker was reserved but is not needed
+            budget += marker(hunks.len()).len();
+        }
+    }
+    Some(out)
+}
+
+/// Get staged diff with files filtered by glob patterns, LFS pointers and submodule bumps
+/// summarized. Excluded files are still committed, just not sent to the LLM for analysis.
+pub fn get_staged_diff_filtered(exclude_patterns: &[String], exclude_lfs: bool) -> Result<String> {
+    let diff = filter_diff_by_globs(&get_staged_diff()?, exclude_patterns);
+    Ok(with_submodule_summaries(&summarize_lfs_pointers(
+        &diff,
+        exclude_lfs,
+    )))
+}
+
+/// Whether a diff line (without its `+`/`-`/space prefix) belongs to a Git LFS pointer file
+fn is_lfs_pointer_line(line: &str) -> bool {
+    line.starts_with("version https://git-lfs.github.com/spec/")
+        || line.starts_with("oid sha256:")
+        || line.starts_with("size ")
+        || line.starts_with("ext-")
+        || line.is_empty()
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// One-line note for a file section whose hunks only touch an LFS pointer, or `None` for any
+/// other file
+fn lfs_pointer_note(section: &[&str]) -> Option<String> {
+    let path = section
+        .first()?
+        .strip_prefix("diff --git a/")?
+        .split(" b/")
+        .next()?;
+    let hunk_start = section.iter().position(|l| l.starts_with("@@"))?;
+    let mut is_pointer = false;
+    let (mut old_size, mut new_size) = (None, None);
+    for line in &section[hunk_start..] {
+        if line.starts_with("@@") || line.starts_with('\\') {
+            continue;
+        }
+        let content = line.strip_prefix(['+', '-', ' '])?;
+        if !is_lfs_pointer_line(content) {
+            return None;
+        }
+        is_pointer |= content.starts_with("version https://git-lfs");
+        let size = content
+            .strip_prefix("size ")
+            .and_then(|n| n.trim().parse::<u64>().ok());
+        match &line[..1] {
+            "-" => old_size = size.or(old_size),
+            "+" => new_size = size.or(new_size),
+            _ => {
+                old_size = size.or(old_size);
+                new_size = size.or(new_size);
+            }
+        }
+    }
+    if !is_pointer {
+        return None;
+    }
+    let added = section.iter().any(|l| l.starts_with("new file mode"));
+    let removed = section.iter().any(|l| l.starts_with("deleted file mode"));
+    let (action, size) = match (added, removed) {
+        (true, _) => ("added", new_size),
+        (_, true) => ("removed", old_size),
+        _ => ("updated", new_size),
+    };
+    Some(match size {
+        Some(size) => format!("LFS asset {action}: {path} ({})", format_size(size)),
+        None => format!("LFS asset {action}: {path}"),
+    })
+}
+
+/// Replace Git LFS pointer diffs with a one-line note each, or drop them when `exclude` is set.
+/// Pointer diffs only show oid changes, which models tend to describe verbatim.
+pub fn summarize_lfs_pointers(diff: &str, exclude: bool) -> String {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut starts: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.starts_with("diff --git "))
+        .map(|(i, _)| i)
+        .collect();
+    let Some(&first) = starts.first() else {
+        return diff.to_string();
+    };
+    starts.push(lines.len());
+
+    let mut out = String::new();
+    for line in &lines[..first] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for bounds in starts.windows(2) {
+        let section = &lines[bounds[0]..bounds[1]];
+        match lfs_pointer_note(section) {
+            Some(_) if exclude => {}
+            Some(note) => {
+                out.push_str(&note);
+                out.push('\n');
+            }
+            None => {
+                for line in section {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// A submodule whose recorded commit moved, from a `Subproject commit` diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmoduleBump {
+    pub path: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Submodule commits listed per bump before the rest is summarized as a count
+const MAX_SUBMODULE_LOG_LINES: usize = 30;
+
+/// Submodule pointer changes in a unified diff. Added and removed submodules have no range
+/// to describe and are left out.
+pub fn parse_submodule_bumps(diff: &str) -> Vec<SubmoduleBump> {
+    let mut bumps = Vec::new();
+    let mut path = "";
+    let mut old: Option<&str> = None;
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git a/") {
+            path = rest.split(" b/").next().unwrap_or("");
+            old = None;
+        } else if let Some(sha) = line.strip_prefix("-Subproject commit ") {
+            old = Some(sha.trim());
+        } else if let Some(sha) = line.strip_prefix("+Subproject commit ") {
+            if let Some(old) = old.take() {
+                bumps.push(SubmoduleBump {
+                    path: path.to_string(),
+                    // A dirty submodule is recorded as `<sha>-dirty`
+                    old: old.trim_end_matches("-dirty").to_string(),
+                    new: sha.trim().trim_end_matches("-dirty").to_string(),
+                });
+            }
+        }
+    }
+    bumps
+}
+
+/// `git log --oneline old..new` inside a submodule checkout
+pub fn submodule_log(repo_root: &str, bump: &SubmoduleBump) -> Result<Vec<String>> {
+    let dir = Path::new(repo_root).join(&bump.path);
+    let range = format!("{}..{}", bump.old, bump.new);
+    let output = command()
+        .arg("-C")
+        .arg(&dir)
+        .args(["log", "--oneline", "--no-decorate", &range])
+        .logged_output()
+        .with_context(|| format!("Failed to run git log in {}", dir.display()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git log {range} failed in {}: {stderr}", bump.path);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Append what changed in each bumped submodule to `diff`, so the model sees more than two
+/// opaque hashes. Submodules that are not checked out, or lack the commits, are noted as such.
+pub fn with_submodule_summaries(diff: &str) -> String {
+    let bumps = parse_submodule_bumps(diff);
+    if bumps.is_empty() {
+        return diff.to_string();
+    }
+    let root = find_repo_root().unwrap_or_else(|_| ".".to_string());
+    let mut out = diff.to_string();
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("\nSubmodule changes:\n");
+    for bump in &bumps {
+        let short = |sha: &str| sha.chars().take(7).collect::<String>();
+        out.push_str(&format!(
+            "\nSubmodule {} {}..{}:\n",
+            bump.path,
+            short(&bump.old),
+            short(&bump.new)
+        ));
+        match submodule_log(&root, bump) {
+            Ok(lines) if lines.is_empty() => {
+                out.push_str("  (no new commits; the submodule was moved back)\n")
+            }
+            Ok(lines) => {
+                for line in lines.iter().take(MAX_SUBMODULE_LOG_LINES) {
+                    out.push_str(&format!("  {line}\n"));
+                }
+                if lines.len() > MAX_SUBMODULE_LOG_LINES {
+                    out.push_str(&format!(
+                        "  ... and {} more commits\n",
+                        lines.len() - MAX_SUBMODULE_LOG_LINES
+                    ));
+                }
+            }
+            Err(_) => out.push_str("  (commits not available locally)\n"),
+        }
+    }
+    out
 }
 
 #[cfg(test)]
@@ -603,22 +1813,6 @@ mod tests {
         assert_eq!((major, minor, patch), (1, 2, 3));
     }
 
-    #[test]
-    fn test_script_command_unix_path() {
-        let path = std::path::Path::new("/tmp/script.sh");
-        let result = script_command(path);
-        assert_eq!(result, "/tmp/script.sh");
-    }
-
-    #[test]
-    fn test_script_command_windows_path() {
-        let path = std::path::Path::new("C:\\Users\\test\\script.sh");
-        let result = script_command(path);
-        // Backslashes should be converted to forward slashes
-        assert!(result.contains('/'));
-        assert!(!result.contains('\\'));
-    }
-
     #[test]
     fn test_configure_stdio_suppress() {
         let mut cmd = Command::new("echo");
@@ -681,41 +1875,6 @@ mod tests {
         assert_eq!(result, "0.1.0");
     }
 
-    #[test]
-    fn test_make_executable_windows() {
-        // On Windows, make_executable is a no-op
-        let temp_dir = std::env::temp_dir();
-        let test_file = temp_dir.join("test_make_executable.sh");
-        std::fs::write(&test_file, "#!/bin/sh\necho test").unwrap();
-        let result = make_executable(&test_file);
-        assert!(result.is_ok());
-        let _ = std::fs::remove_file(&test_file);
-    }
-
-    #[test]
-    fn test_write_sequence_editor_script() {
-        let temp_dir = std::env::temp_dir();
-        let script_path = temp_dir.join("test_seq_editor.sh");
-        let result = write_sequence_editor_script(&script_path);
-        assert!(result.is_ok());
-        let content = std::fs::read_to_string(&script_path).unwrap();
-        assert!(content.contains("#!/bin/sh"));
-        assert!(content.contains("reword"));
-        let _ = std::fs::remove_file(&script_path);
-    }
-
-    #[test]
-    fn test_write_message_editor_script() {
-        let temp_dir = std::env::temp_dir();
-        let script_path = temp_dir.join("test_msg_editor.sh");
-        let result = write_message_editor_script(&script_path);
-        assert!(result.is_ok());
-        let content = std::fs::read_to_string(&script_path).unwrap();
-        assert!(content.contains("#!/bin/sh"));
-        assert!(content.contains("CGEN_NEW_MESSAGE"));
-        let _ = std::fs::remove_file(&script_path);
-    }
-
     #[test]
     fn test_filter_diff_single_file_excluded() {
         let diff = "diff --git a/config.json b/config.json\n+{}\n";
@@ -770,20 +1929,6 @@ diff --git a/c.rs b/c.rs
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_script_command_empty_path() {
-        let path = std::path::Path::new("");
-        let result = script_command(path);
-        assert_eq!(result, "");
-    }
-
-    #[test]
-    fn test_script_command_relative_path() {
-        let path = std::path::Path::new("scripts/test.sh");
-        let result = script_command(path);
-        assert_eq!(result, "scripts/test.sh");
-    }
-
     #[test]
     fn test_filter_diff_mixed_content_types() {
         let diff = r#"diff --git a/readme.md b/readme.md
@@ -832,4 +1977,72 @@ diff --git a/src/app.ts b/src/app.ts
         let result = compute_next_minor_tag(Some("not-semver"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_submodule_bumps_skips_added_submodules() {
+        let diff = "\
+diff --git a/libs/core b/libs/core
+index 1111111..2222222 160000
+--- a/libs/core
++++ b/libs/core
+@@ -1 +1 @@
+-Subproject commit 1111111111111111111111111111111111111111
++Subproject commit 2222222222222222222222222222222222222222-dirty
+diff --git a/libs/new b/libs/new
+new file mode 160000
+--- /dev/null
++++ b/libs/new
+@@ -0,0 +1 @@
++Subproject commit 3333333333333333333333333333333333333333
+";
+        let bumps = parse_submodule_bumps(diff);
+        assert_eq!(
+            bumps,
+            vec![SubmoduleBump {
+                path: "libs/core".into(),
+                old: "1".repeat(40),
+                new: "2".repeat(40),
+            }]
+        );
+    }
+
+    const LFS_DIFF: &str = "\
+diff --git a/assets/logo.psd b/assets/logo.psd
+index 1111111..2222222 100644
+--- a/assets/logo.psd
++++ b/assets/logo.psd
+@@ -1,3 +1,3 @@
+ version https://git-lfs.github.com/spec/v1
+-oid sha256:aaaa
+-size 1000
++oid sha256:bbbb
++size 2621440
+diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-fn main() {}
++fn main() { run() }
+";
+
+    #[test]
+    fn test_summarize_lfs_pointers_replaces_pointer_diffs() {
+        let summary = summarize_lfs_pointers(LFS_DIFF, false);
+        assert!(summary.starts_with("LFS asset updated: assets/logo.psd (2.5 MB)\n"));
+        assert!(!summary.contains("oid sha256"));
+        assert!(summary.contains("+fn main() { run() }"));
+
+        let excluded = summarize_lfs_pointers(LFS_DIFF, true);
+        assert!(!excluded.contains("logo.psd"));
+        assert!(excluded.starts_with("diff --git a/src/main.rs"));
+    }
+
+    #[test]
+    fn test_branch_matches_globs() {
+        let patterns = vec!["main".to_string(), "release/*".to_string()];
+        assert!(branch_matches("main", &patterns));
+        assert!(branch_matches("release/1.2", &patterns));
+        assert!(!branch_matches("maintenance", &patterns));
+        assert!(!branch_matches("feature/release", &patterns));
+    }
 }
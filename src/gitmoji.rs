@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::AppConfig;
+
+/// Default conventional type → (unicode, shortcode) gitmoji mapping
+const TYPE_GITMOJI: &[(&str, &str, &str)] = &[
+    ("feat", "\u{2728}", ":sparkles:"),
+    ("fix", "\u{1f41b}", ":bug:"),
+    ("docs", "\u{1f4dd}", ":memo:"),
+    ("style", "\u{1f3a8}", ":art:"),
+    ("refactor", "\u{267b}\u{fe0f}", ":recycle:"),
+    ("perf", "\u{26a1}\u{fe0f}", ":zap:"),
+    ("test", "\u{2705}", ":white_check_mark:"),
+    ("build", "\u{1f4e6}\u{fe0f}", ":package:"),
+    ("ci", "\u{1f477}", ":construction_worker:"),
+    ("chore", "\u{1f527}", ":wrench:"),
+    ("revert", "\u{23ea}\u{fe0f}", ":rewind:"),
+    ("security", "\u{1f512}\u{fe0f}", ":lock:"),
+    ("deps", "\u{2b06}\u{fe0f}", ":arrow_up:"),
+    ("release", "\u{1f516}", ":bookmark:"),
+    ("init", "\u{1f389}", ":tada:"),
+];
+
+/// A single emoji in both notations
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitmojiEntry {
+    pub unicode: String,
+    pub shortcode: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl GitmojiEntry {
+    fn render(&self, format: &str) -> &str {
+        if format == "shortcode" {
+            &self.shortcode
+        } else {
+            &self.unicode
+        }
+    }
+}
+
+/// User-defined gitmoji set from `gitmoji.toml` in the cgen config directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct GitmojiSet {
+    /// Type → emoji overrides and additions to the built-in mapping
+    #[serde(default)]
+    pub types: BTreeMap<String, GitmojiEntry>,
+    /// Extra emojis the model may use for changes no type mapping covers
+    #[serde(default)]
+    pub catalog: Vec<GitmojiEntry>,
+}
+
+/// Path of the custom gitmoji set, next to the global config file
+pub fn custom_set_path() -> Option<PathBuf> {
+    crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("gitmoji.toml")))
+}
+
+/// Load the custom gitmoji set, if the file exists
+pub fn load_custom_set() -> Result<Option<GitmojiSet>> {
+    let Some(path) = custom_set_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let set: GitmojiSet =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(set))
+}
+
+/// Gitmoji for a conventional type in the given format, checking the custom set first
+pub fn emoji_for_type_in(
+    set: Option<&GitmojiSet>,
+    commit_type: &str,
+    format: &str,
+) -> Option<String> {
+    let lower = commit_type.to_lowercase();
+    if let Some(entry) = set.and_then(|s| s.types.get(&lower)) {
+        return Some(entry.render(format).to_string());
+    }
+    TYPE_GITMOJI
+        .iter()
+        .find(|(t, _, _)| *t == lower)
+        .map(|(_, unicode, shortcode)| {
+            if format == "shortcode" {
+                shortcode.to_string()
+            } else {
+                unicode.to_string()
+            }
+        })
+}
+
+/// Gitmoji for a conventional type from the built-in mapping
+pub fn emoji_for_type(commit_type: &str, format: &str) -> Option<String> {
+    emoji_for_type_in(None, commit_type, format)
+}
+
+/// Prompt text describing the custom set, so the model's examples match the mapper
+pub fn custom_set_instructions(set: &GitmojiSet, format: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    if !set.types.is_empty() {
+        let mapping = set
+            .types
+            .iter()
+            .map(|(t, e)| format!("{t} \u{2192} {}", e.render(format)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        parts.push(format!(
+            "Use this project's gitmoji for these types: {mapping}."
+        ));
+    }
+    if !set.catalog.is_empty() {
+        let catalog = set
+            .catalog
+            .iter()
+            .map(|e| {
+                if e.description.is_empty() {
+                    e.render(format).to_string()
+                } else {
+                    format!("{} ({})", e.render(format), e.description)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        parts.push(format!("Additional gitmoji available: {catalog}."));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Replace whatever emoji the model chose with the one mapped from the commit type, so the
+/// same type always gets the same gitmoji. Unknown types keep the model's choice.
+pub fn apply(cfg: &AppConfig, message: &str) -> String {
+    if !cfg.use_gitmoji || !cfg.gitmoji_deterministic {
+        return message.to_string();
+    }
+    let (header, rest) = match message.split_once('\n') {
+        Some((header, rest)) => (header, Some(rest)),
+        None => (message, None),
+    };
+    let Some(parsed) = crate::lint::parse_header(header) else {
+        return message.to_string();
+    };
+    let Some(emoji) = emoji_for_type_in(
+        cfg.gitmoji_set.as_ref(),
+        &parsed.commit_type,
+        &cfg.gitmoji_format,
+    ) else {
+        return message.to_string();
+    };
+
+    let without_prefix = &header[parsed.prefix.len()..];
+    let rebuilt = format!("{emoji} {without_prefix}");
+    match rest {
+        Some(rest) => format!("{rebuilt}\n{rest}"),
+        None => rebuilt,
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// English catalog, used for any key a translation does not provide
+const EN_CATALOG: &str = include_str!("../i18n/en.toml");
+
+/// Translations shipped with the binary
+const EMBEDDED_CATALOGS: &[(&str, &str)] = &[("pt-br", include_str!("../i18n/pt-br.toml"))];
+
+static EN: OnceLock<HashMap<String, String>> = OnceLock::new();
+static CATALOG: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// Parse a flat `key = "text"` TOML catalog, ignoring anything that is not a string
+pub fn parse_catalog(content: &str) -> HashMap<String, String> {
+    content
+        .parse::<toml::Table>()
+        .map(|table| {
+            table
+                .into_iter()
+                .filter_map(|(k, v)| v.as_str().map(|s| (k, s.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Build the catalog for a locale: English, then the base language (`pt` for `pt-br`), then
+/// the full locale. At each step the embedded catalog is overlaid by `<dir>/<locale>.toml`
+/// files from `dirs`, so users can add or patch translations without a rebuild.
+pub fn catalog_for(locale: &str, dirs: &[PathBuf]) -> HashMap<String, String> {
+    let mut catalog = parse_catalog(EN_CATALOG);
+    let locale = locale.trim().to_ascii_lowercase().replace('_', "-");
+
+    let mut candidates = Vec::new();
+    if let Some((base, _)) = locale.split_once('-') {
+        candidates.push(base.to_string());
+    }
+    candidates.push(locale);
+
+    for candidate in candidates.iter().filter(|c| c.as_str() != "en") {
+        if let Some((_, content)) = EMBEDDED_CATALOGS.iter().find(|(l, _)| l == candidate) {
+            catalog.extend(parse_catalog(content));
+        }
+        for dir in dirs {
+            if let Some(content) = read_catalog_file(dir, candidate) {
+                catalog.extend(parse_catalog(&content));
+            }
+        }
+    }
+    catalog
+}
+
+fn read_catalog_file(dir: &Path, locale: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(format!("{locale}.toml"))).ok()
+}
+
+/// Directories searched for user catalogs: the repository/working-directory `i18n/` folders
+/// used for locale validation, then `i18n/` in the cgen config directory
+fn catalog_dirs() -> Vec<PathBuf> {
+    let mut dirs = crate::config::locale_i18n_dirs();
+    if let Some(dir) =
+        crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("i18n")))
+    {
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// Select the CLI language; later calls replace the catalog
+pub fn init(locale: &str) {
+    let catalog = catalog_for(locale, &catalog_dirs());
+    if let Ok(mut current) = CATALOG.write() {
+        *current = Some(catalog);
+    }
+}
+
+/// Translated text for `key`, falling back to English and then to the key itself
+pub fn t(key: &str) -> String {
+    let selected = CATALOG
+        .read()
+        .ok()
+        .and_then(|catalog| catalog.as_ref()?.get(key).cloned());
+    selected
+        .or_else(|| {
+            EN.get_or_init(|| parse_catalog(EN_CATALOG))
+                .get(key)
+                .cloned()
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Translated text with `{name}` placeholders filled from `args`
+pub fn tf(key: &str, args: &[(&str, &str)]) -> String {
+    fill(&t(key), args)
+}
+
+/// Replace `{name}` placeholders in a message
+pub fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |acc, (name, value)| {
+            acc.replace(&format!("{{{name}}}"), value)
+        })
+}
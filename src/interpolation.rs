@@ -1,25 +1,242 @@
+use colored::Colorize;
+use std::collections::HashMap;
+use std::process::Stdio;
+
 use crate::config::AppConfig;
-use regex_lite::Regex;
+use crate::i18n::{t, tf};
+
+/// Interpolate `$VARIABLE_NAME` patterns in a string using the [built-in variables](builtin),
+/// the `ACR_` config values and environment variables ([`variables`]). See [`expand`] for the
+/// supported syntax; `$(command)` runs only if [`AppConfig::commands_allowed`] for `setting`,
+/// the setting `template` is the value of.
+pub fn interpolate(template: &str, cfg: &AppConfig, setting: &str) -> String {
+    let vars = variables(cfg);
+    let lookup = |name: &str| builtin(name).or_else(|| vars.get(name).cloned());
+    let options = Options {
+        keep_unknown: false,
+        commands: cfg.commands_allowed(setting),
+    };
+    expand_inner(template, &lookup, options)
+}
+
+/// Fill a commit template, the value of `setting`: `$msg` is the generated message, then the
+/// same variables and `$(command)`s as [`interpolate`]. Unknown placeholders such as `$ticket`
+/// are left for the user to fill in.
+pub fn interpolate_template(
+    template: &str,
+    message: &str,
+    cfg: &AppConfig,
+    setting: &str,
+) -> String {
+    let vars = variables(cfg);
+    let lookup = |name: &str| match name {
+        "msg" => Some(message.to_string()),
+        _ => builtin(name).or_else(|| vars.get(name).cloned()),
+    };
+    let options = Options {
+        keep_unknown: true,
+        commands: cfg.commands_allowed(setting),
+    };
+    expand_inner(template, &lookup, options)
+}
 
-/// Interpolate `$VARIABLE_NAME` patterns in a string using environment variables.
-/// Before interpolation, ACR_ config values are temporarily set as env vars.
-pub fn interpolate(template: &str, cfg: &AppConfig) -> String {
-    // Temporarily set ACR_ env vars from config so $ACR_MODEL etc. resolve
-    let env_pairs = [
+/// A snapshot of the environment with `ACR_PROVIDER`, `ACR_MODEL`, `ACR_API_KEY` and
+/// `ACR_LOCALE` set from `cfg`, so `$ACR_MODEL` etc. follow the resolved config. The process
+/// environment itself, which git hooks and other child processes inherit, is left alone.
+pub fn variables(cfg: &AppConfig) -> HashMap<String, String> {
+    let mut vars: HashMap<String, String> = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    let config_values = [
         ("ACR_PROVIDER", &cfg.provider),
         ("ACR_MODEL", &cfg.model),
         ("ACR_API_KEY", &cfg.api_key),
         ("ACR_LOCALE", &cfg.locale),
     ];
-    for (key, val) in &env_pairs {
-        std::env::set_var(key, val);
+    for (key, value) in config_values {
+        vars.insert(key.to_string(), value.clone());
+    }
+    vars
+}
+
+/// Variables cgen provides itself: `REPO_NAME` (the repository's directory name), `BRANCH`,
+/// `DATE` (`YYYY-MM-DD`, UTC) and `CGEN_VERSION`. Only the one asked for is looked up.
+pub fn builtin(name: &str) -> Option<String> {
+    match name {
+        "REPO_NAME" => crate::git::find_repo_root().ok().and_then(|root| {
+            std::path::Path::new(&root)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        }),
+        "BRANCH" => crate::git::current_branch().ok(),
+        "DATE" => Some(crate::timestamp::date(crate::timestamp::now_unix())),
+        "CGEN_VERSION" => Some(env!("CARGO_PKG_VERSION").to_string()),
+        _ => None,
     }
+}
+
+/// Expand `$NAME`, `${NAME}` and `${NAME:-default}` with `lookup`. Like the shell, the default
+/// is used when the variable is unset or empty and may itself contain variables; other unknown
+/// variables become empty. `\$` is a literal dollar sign, and a `$` that does not start a
+/// variable (`$5`, a lone `$`, an unclosed `${`) is kept as is.
+pub fn expand(template: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    expand_inner(template, lookup, Options::default())
+}
+
+/// [`expand`], keeping unknown variables without a default as written
+pub fn expand_known(template: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let options = Options {
+        keep_unknown: true,
+        ..Options::default()
+    };
+    expand_inner(template, lookup, options)
+}
 
-    let re = Regex::new(r"\$([A-Za-z_][A-Za-z0-9_]*)").unwrap();
-    let result = re.replace_all(template, |caps: &regex_lite::Captures| {
-        let var_name = &caps[1];
-        std::env::var(var_name).unwrap_or_default()
-    });
+#[derive(Debug, Clone, Copy, Default)]
+struct Options {
+    /// Leave unknown variables without a default as written instead of emptying them
+    keep_unknown: bool,
+    /// Replace `$(command)` with its output; otherwise it is kept as written
+    commands: bool,
+}
 
-    result.into_owned()
+fn expand_inner(
+    template: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    options: Options,
+) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['$', '\\']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix("\\$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix('\\') {
+            out.push('\\');
+            rest = after;
+        } else if let Some((command, after)) = command_at(&tail[1..]).filter(|_| options.commands) {
+            out.push_str(&run_command(command));
+            rest = after;
+        } else if let Some((value, after)) = expand_variable(&tail[1..], lookup, options) {
+            out.push_str(&value);
+            rest = after;
+        } else {
+            out.push('$');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Expand the variable right after a `$`, returning its value and the remaining input.
+/// `None` leaves the `$` and what follows as written.
+fn expand_variable<'a>(
+    input: &'a str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    options: Options,
+) -> Option<(String, &'a str)> {
+    let resolve = |name: &str| match lookup(name) {
+        Some(value) => Some(value),
+        None if options.keep_unknown => None,
+        None => Some(String::new()),
+    };
+    let Some(braced) = input.strip_prefix('{') else {
+        let len = name_len(input);
+        if len == 0 {
+            return None;
+        }
+        return resolve(&input[..len]).map(|value| (value, &input[len..]));
+    };
+    let len = name_len(braced);
+    if len == 0 {
+        return None;
+    }
+    let (name, after_name) = braced.split_at(len);
+    if let Some(after) = after_name.strip_prefix('}') {
+        return resolve(name).map(|value| (value, after));
+    }
+    let default_and_rest = after_name.strip_prefix(":-")?;
+    let end = closing_brace(default_and_rest)?;
+    let value = lookup(name)
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| expand_inner(&default_and_rest[..end], lookup, options));
+    Some((value, &default_and_rest[end + 1..]))
+}
+
+/// Length of the variable name at the start of `input`: a letter or `_`, then alphanumerics
+fn name_len(input: &str) -> usize {
+    let mut chars = input.char_indices();
+    match chars.next() {
+        Some((_, c)) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return 0,
+    }
+    chars
+        .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_'))
+        .map_or(input.len(), |(i, _)| i)
+}
+
+/// Index of the `}` closing a `${...:-` default, skipping nested `${...}`
+fn closing_brace(input: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The command of a `$(...)` right after the `$`, with the input after its closing `)`.
+/// Nested parentheses are matched.
+fn command_at(input: &str) -> Option<(&str, &str)> {
+    let body = input.strip_prefix('(')?;
+    let mut depth = 0usize;
+    for (i, c) in body.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some((&body[..i], &body[i + 1..])),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Output of `command` run through the shell in the repository root, without trailing
+/// newlines like in the shell. A failing command is reported and gives an empty value.
+fn run_command(command: &str) -> String {
+    let mut cmd = crate::ui::shell_command(command);
+    if let Ok(root) = crate::git::find_repo_root() {
+        cmd.current_dir(root);
+    }
+    let error = match cmd.stdin(Stdio::null()).output() {
+        Ok(output) if output.status.success() => {
+            return String::from_utf8_lossy(&output.stdout)
+                .trim_end_matches(['\n', '\r'])
+                .to_string();
+        }
+        Ok(output) => match String::from_utf8_lossy(&output.stderr).trim() {
+            "" => output.status.to_string(),
+            stderr => stderr.to_string(),
+        },
+        Err(e) => e.to_string(),
+    };
+    eprintln!(
+        "{} {}",
+        t("warning").yellow().bold(),
+        tf(
+            "template_command_failed",
+            &[("command", command.trim()), ("error", &error)]
+        )
+    );
+    String::new()
 }
@@ -1,10 +1,30 @@
+pub mod bundle;
 pub mod cache;
 pub mod cli;
+pub mod commitlint;
 pub mod config;
+pub mod cooldown;
+pub mod credentials;
 pub mod git;
+pub mod gitmoji;
+pub mod i18n;
 pub mod interpolation;
+pub mod lint;
+pub mod postprocess;
 pub mod preset;
+pub mod profile;
 pub mod prompt;
 pub mod provider;
+pub mod registry;
+pub mod routing;
+pub mod scope;
+pub mod secret;
+pub mod server;
+pub mod snippet;
+pub mod timestamp;
+pub mod tui;
 pub mod ui;
+pub mod undo;
 pub mod update;
+pub mod usage;
+pub mod validate;
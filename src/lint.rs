@@ -0,0 +1,218 @@
+use crate::config::AppConfig;
+use crate::postprocess::{
+    effective_allowed_scopes, effective_allowed_types, effective_lowercase_subject,
+    effective_subject_max_length, BODY_WRAP_WIDTH,
+};
+use regex_lite::Regex;
+
+/// A single rule violation found in a commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Rules a commit message is checked against
+#[derive(Debug, Clone)]
+pub struct LintRules {
+    /// Maximum header length in characters (0 = no limit)
+    pub header_max_length: usize,
+    /// Maximum body line length in characters (0 = no limit)
+    pub body_max_line_length: usize,
+    /// Require the first word of the description to be lowercase
+    pub lowercase_subject: bool,
+    /// Allowed commit types; empty means any
+    pub allowed_types: Vec<String>,
+    /// Allowed scopes; empty means any
+    pub allowed_scopes: Vec<String>,
+}
+
+impl LintRules {
+    /// Rules from config, tightened by the repository's commitlint config when present
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            header_max_length: effective_subject_max_length(cfg),
+            body_max_line_length: cfg
+                .commitlint
+                .as_ref()
+                .and_then(|r| r.body_max_line_length)
+                .unwrap_or(BODY_WRAP_WIDTH),
+            lowercase_subject: effective_lowercase_subject(cfg),
+            allowed_types: effective_allowed_types(cfg),
+            allowed_scopes: effective_allowed_scopes(cfg),
+        }
+    }
+}
+
+/// Parsed conventional commit header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Gitmoji prefix including its trailing whitespace, or empty
+    pub prefix: String,
+    pub commit_type: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+/// Parse a `[gitmoji ]type[(scope)][!]: description` header
+pub fn parse_header(header: &str) -> Option<Header> {
+    let re = Regex::new(
+        r"^((?::[a-z0-9_+-]+:|[^\sA-Za-z0-9]+)\s+)?([A-Za-z]+)(?:\(([^()]*)\))?(!)?: (.*)$",
+    )
+    .unwrap();
+    let caps = re.captures(header.trim_end())?;
+    Some(Header {
+        prefix: caps
+            .get(1)
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default(),
+        commit_type: caps[2].to_string(),
+        scope: caps.get(3).map(|m| m.as_str().to_string()),
+        breaking: caps.get(4).is_some(),
+        description: caps[5].to_string(),
+    })
+}
+
+/// Messages git generates itself and that are not expected to follow the spec
+pub fn is_ignored(message: &str) -> bool {
+    let first = message.lines().next().unwrap_or("");
+    ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|p| first.starts_with(p))
+}
+
+/// Check a commit message against the Conventional Commits spec and the given rules
+pub fn lint_message(message: &str, rules: &LintRules) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let message = message.trim_end();
+    if is_ignored(message) {
+        return violations;
+    }
+
+    let mut lines = message.lines();
+    let header = lines.next().unwrap_or("").trim_end();
+    if header.trim().is_empty() {
+        violations.push(Violation {
+            rule: "header-empty",
+            message: "commit message is empty".into(),
+        });
+        return violations;
+    }
+
+    match parse_header(header) {
+        None => violations.push(Violation {
+            rule: "header-format",
+            message: "header must look like `type(scope): description`".into(),
+        }),
+        Some(parsed) => {
+            if parsed.commit_type != parsed.commit_type.to_lowercase() {
+                violations.push(Violation {
+                    rule: "type-case",
+                    message: format!("type `{}` must be lowercase", parsed.commit_type),
+                });
+            }
+            if !rules.allowed_types.is_empty() && !rules.allowed_types.contains(&parsed.commit_type)
+            {
+                violations.push(Violation {
+                    rule: "type-enum",
+                    message: format!(
+                        "type `{}` is not one of: {}",
+                        parsed.commit_type,
+                        rules.allowed_types.join(", ")
+                    ),
+                });
+            }
+            if let Some(scope) = parsed.scope.as_deref().filter(|s| !s.trim().is_empty()) {
+                let unknown: Vec<&str> = scope
+                    .split([',', '/'])
+                    .map(str::trim)
+                    .filter(|s| !rules.allowed_scopes.iter().any(|a| a == s))
+                    .collect();
+                if !rules.allowed_scopes.is_empty() && !unknown.is_empty() {
+                    violations.push(Violation {
+                        rule: "scope-enum",
+                        message: format!(
+                            "scope `{}` is not one of: {}",
+                            unknown.join(", "),
+                            rules.allowed_scopes.join(", ")
+                        ),
+                    });
+                }
+            }
+            if parsed.scope.as_deref().is_some_and(|s| s.trim().is_empty()) {
+                violations.push(Violation {
+                    rule: "scope-empty",
+                    message: "scope parentheses must not be empty".into(),
+                });
+            }
+            let description = parsed.description.trim();
+            if description.is_empty() {
+                violations.push(Violation {
+                    rule: "subject-empty",
+                    message: "description must not be empty".into(),
+                });
+            } else {
+                if description.ends_with('.') && !description.ends_with("..") {
+                    violations.push(Violation {
+                        rule: "subject-full-stop",
+                        message: "description must not end with a period".into(),
+                    });
+                }
+                if rules.lowercase_subject && starts_with_capitalized_word(description) {
+                    violations.push(Violation {
+                        rule: "subject-case",
+                        message: "description must start with a lowercase word".into(),
+                    });
+                }
+            }
+        }
+    }
+
+    let header_len = header.chars().count();
+    if rules.header_max_length > 0 && header_len > rules.header_max_length {
+        violations.push(Violation {
+            rule: "header-max-length",
+            message: format!(
+                "header is {header_len} characters, maximum is {}",
+                rules.header_max_length
+            ),
+        });
+    }
+
+    let body: Vec<&str> = lines.collect();
+    if body.first().is_some_and(|l| !l.trim().is_empty()) {
+        violations.push(Violation {
+            rule: "body-leading-blank",
+            message: "body must begin one blank line after the header".into(),
+        });
+    }
+
+    if rules.body_max_line_length > 0 {
+        for (i, line) in body.iter().enumerate() {
+            let len = line.chars().count();
+            // Long unbreakable tokens such as URLs cannot be wrapped
+            if len > rules.body_max_line_length && line.trim().contains(char::is_whitespace) {
+                violations.push(Violation {
+                    rule: "body-max-line-length",
+                    message: format!(
+                        "body line {} is {len} characters, maximum is {}",
+                        i + 2,
+                        rules.body_max_line_length
+                    ),
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+fn starts_with_capitalized_word(text: &str) -> bool {
+    let word = text.split_whitespace().next().unwrap_or("");
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.is_uppercase() && !chars.any(|c| c.is_uppercase()),
+        None => false,
+    }
+}
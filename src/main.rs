@@ -1,79 +1,252 @@
 use anyhow::{Context, Result};
-use auto_commit_rs::{cache, cli, config, git, preset, prompt, provider, ui, update};
+use auto_commit_rs::i18n::{self, t, tf};
+use auto_commit_rs::{
+    bundle, cache, cli, config, credentials, git, lint, preset, profile, prompt, provider,
+    registry, routing, scope, server, snippet, tui, ui, undo, update, usage, validate,
+};
 use colored::Colorize;
-use inquire::Select;
-use std::time::Instant;
+use inquire::{MultiSelect, Select, Text};
+use std::io::{IsTerminal, Read};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 fn main() {
     if let Err(e) = run() {
-        eprintln!("{} {:#}", "error:".red().bold(), e);
+        ui::log(&format!("error: {e:#}"));
+        eprintln!("{} {:#}", t("error").red().bold(), e);
         std::process::exit(1);
     }
 }
 
 fn run() -> Result<()> {
+    init_tracing();
     let cli = cli::parse();
+    if let Some(dir) = &cli.repo {
+        git::set_repo_dir(dir)?;
+    }
+    if cli.no_pager {
+        ui::disable_pager();
+    }
+    if cli.quiet {
+        ui::set_quiet();
+    }
+    ui::set_verbosity(cli.verbose);
+    if let Some(path) = &cli.log_file {
+        ui::set_log_file(path)?;
+    }
+    match (cli.no_color, cli.color) {
+        (true, _) | (false, cli::ColorChoice::Never) => ui::set_colors(false),
+        (false, cli::ColorChoice::Always) => ui::set_colors(true),
+        // `colored` already checks NO_COLOR and stdout; prompts draw on stderr and need telling
+        (false, cli::ColorChoice::Auto) => {
+            if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                ui::set_colors(false);
+            }
+        }
+    }
     let cfg = match &cli.command {
         Some(
-            cli::Command::Config
-            | cli::Command::Update
-            | cli::Command::History
-            | cli::Command::Preset
-            | cli::Command::Fallback,
+            cli::Command::Config { .. }
+            | cli::Command::Update { .. }
+            | cli::Command::History { .. }
+            | cli::Command::Stats { .. }
+            | cli::Command::Preset { .. }
+            | cli::Command::Fallback
+            | cli::Command::Profile,
         ) => None,
         _ => Some(config::AppConfig::load()?),
     };
+    let mut cfg = match cfg {
+        Some(mut c) => {
+            c.skip_ci |= cli.skip_ci;
+            c.review_tui |= cli.tui;
+            c.show_diff |= cli.show_diff;
+            if let Some(seed) = cli.seed {
+                c.seed = seed.to_string();
+            }
+            let profile = cli.profile.clone().unwrap_or_else(|| c.profile.clone());
+            if !profile.is_empty() {
+                profile::apply_named(&mut c, &profile)?;
+            }
+            for path in &cli.prompt_file {
+                c.prompt_snippets.push(snippet::load_file(path)?);
+            }
+            // The shared history file is bookkeeping, not a change to describe
+            if c.repo_history {
+                c.diff_exclude_globs
+                    .push(cache::REPO_HISTORY_FILE.to_string());
+            }
+            for pattern in &cli.exclude {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid --exclude pattern `{pattern}`"))?;
+                c.diff_exclude_globs.push(pattern.clone());
+            }
+            Some(c)
+        }
+        None => None,
+    };
+    // Lint runs in CI and hooks, serve under an editor: neither may stop for questions
+    let non_interactive = matches!(
+        cli.command,
+        Some(cli::Command::Lint { .. } | cli::Command::Serve { .. })
+    );
+    // Commands that don't load the config up front still follow its locale and UI settings
+    let settings = match cfg {
+        Some(_) => None,
+        None => config::AppConfig::load().ok(),
+    };
+    let locale = match &mut cfg {
+        Some(c) => {
+            // On first run, offer the system locale instead of silently using English
+            if !c.locale_configured && !non_interactive && std::io::stdin().is_terminal() {
+                if let Some(detected) = prompt_detected_locale() {
+                    c.locale = detected;
+                }
+            }
+            c.locale.clone()
+        }
+        None => settings
+            .as_ref()
+            .map(|c| c.locale.clone())
+            .unwrap_or_default(),
+    };
+    i18n::init(&locale);
+    if cfg
+        .as_ref()
+        .or(settings.as_ref())
+        .is_some_and(|c| c.ascii_ui)
+    {
+        ui::set_ascii();
+    }
 
-    // On first run, ask about auto-update preference
+    // On first run, ask about auto-update preference (lint and serve stay non-interactive)
     if let Some(ref c) = cfg {
-        if c.auto_update.is_none() {
+        if c.auto_update.is_none() && !non_interactive {
             prompt_auto_update();
         }
     }
 
-    // Check for updates (except for config/update/history commands)
-    let update_warning = match &cli.command {
+    // Check for updates (except for config/update/history commands) in the background
+    let pending_update_check = match &cli.command {
         Some(
-            cli::Command::Config
-            | cli::Command::Update
-            | cli::Command::History
-            | cli::Command::Preset
-            | cli::Command::Fallback,
+            cli::Command::Config { .. }
+            | cli::Command::Update { .. }
+            | cli::Command::History { .. }
+            | cli::Command::Cache { .. }
+            | cli::Command::Stats { .. }
+            | cli::Command::Preset { .. }
+            | cli::Command::Fallback
+            | cli::Command::Profile
+            | cli::Command::Lint { .. }
+            | cli::Command::Serve { .. },
         ) => None,
-        _ => check_for_updates(cfg.as_ref()),
+        _ => {
+            registry::refresh_if_stale();
+            Some(spawn_update_check(cfg.as_ref()))
+        }
     };
 
     match &cli.command {
-        Some(cli::Command::Config) => {
+        Some(cli::Command::Config { action: None }) => {
             run_config_command()?;
         }
-        Some(cli::Command::Update) => {
-            run_update_command()?;
+        Some(cli::Command::Config {
+            action: Some(action),
+        }) => {
+            run_config_action(action)?;
+        }
+        Some(cli::Command::Update { skip, snooze }) => {
+            run_update_command(*skip, *snooze)?;
+        }
+        Some(cli::Command::History { grep, branch }) => {
+            let branch = match branch.as_deref() {
+                Some("") => Some(git::current_branch().context("Failed to get current branch")?),
+                other => other.map(str::to_string),
+            };
+            let filter = cache::HistoryFilter {
+                text: grep.clone(),
+                branch,
+            };
+            run_history(&cli, &filter)?;
+        }
+        Some(cli::Command::Cache { action }) => {
+            run_cache(cfg.as_ref().expect("config should be loaded"), action)?;
         }
-        Some(cli::Command::History) => {
-            cache::interactive_history()?;
+        Some(cli::Command::Stats { clear }) => {
+            run_stats(*clear)?;
         }
-        Some(cli::Command::Preset) => {
+        Some(cli::Command::Preset { action: None }) => {
             preset::interactive_presets()?;
         }
+        Some(cli::Command::Preset {
+            action: Some(action),
+        }) => {
+            run_preset_action(action)?;
+        }
         Some(cli::Command::Fallback) => {
             preset::interactive_fallback_order()?;
         }
-        Some(cli::Command::Undo) => {
-            run_undo(cfg.as_ref().expect("config should be loaded"))?;
+        Some(cli::Command::Profile) => {
+            profile::interactive_profiles()?;
+        }
+        Some(cli::Command::Undo {
+            count,
+            mixed,
+            discard,
+        }) => {
+            let mode = if *discard {
+                git::ResetMode::Hard
+            } else if *mixed {
+                git::ResetMode::Mixed
+            } else {
+                git::ResetMode::Soft
+            };
+            run_undo(
+                cfg.as_ref().expect("config should be loaded"),
+                mode,
+                *count as usize,
+            )?;
         }
-        Some(cli::Command::Alter { commits }) => {
+        Some(cli::Command::Redo) => {
+            run_redo(cfg.as_ref().expect("config should be loaded"))?;
+        }
+        Some(cli::Command::Alter { commits, force }) => {
             run_alter(
                 cfg.as_ref().expect("config should be loaded"),
                 &cli,
                 commits,
+                *force,
             )?;
         }
-        Some(cli::Command::Prompt) => {
+        Some(cli::Command::Lint { range, stdin, fix }) => {
+            run_lint(
+                cfg.as_ref().expect("config should be loaded"),
+                &cli,
+                range.as_deref(),
+                *stdin,
+                *fix,
+            )?;
+        }
+        Some(cli::Command::Serve { .. }) => {
+            server::serve_stdio(cfg.as_ref().expect("config should be loaded"))?;
+        }
+        Some(cli::Command::Prompt { diff }) => {
             let c = cfg.as_ref().expect("config should be loaded");
-            let system_prompt = prompt::build_system_prompt(c);
-            println!("\n{}", "LLM system prompt:".cyan().bold());
-            println!("{system_prompt}");
+            let mut out = format!("\n{}\n", t("system_prompt").cyan().bold());
+            for block in prompt::build_prompt_blocks(c) {
+                out.push_str(&format!(
+                    "\n{}\n",
+                    format!("{rule} {} {rule}", block.source, rule = ui::glyphs().rule).dimmed()
+                ));
+                out.push_str(&format!("{}\n", block.text));
+            }
+            if *diff {
+                out.push_str(&request_preview(c)?);
+            }
+            ui::page(&out)?;
+        }
+        None if cli.dry_run_request => {
+            print_dry_run_request(cfg.as_ref().expect("config should be loaded"))?;
         }
         None => {
             run_standard_commit(cfg.as_ref().expect("config should be loaded"), &cli)?;
@@ -81,7 +254,9 @@ fn run() -> Result<()> {
     }
 
     // Show update warning at the end so it doesn't get buried
-    if let Some(latest) = update_warning {
+    let update_warning =
+        pending_update_check.and_then(|pending| check_for_updates(cfg.as_ref(), pending));
+    if let Some(latest) = update_warning.filter(|_| !cli.quiet) {
         update::print_update_warning(&latest);
     }
 
@@ -89,59 +264,101 @@ fn run() -> Result<()> {
 }
 
 fn run_standard_commit(cfg: &config::AppConfig, cli: &cli::Cli) -> Result<()> {
-    ensure_api_key(cfg)?;
+    ensure_api_key_unless_routed(cfg)?;
 
     let staged_files = git::list_staged_files().context("Failed to list staged files")?;
+    if !cli.dry_run {
+        ensure_no_operation_in_progress()?;
+        if !confirm_protected_commit(cfg) {
+            println!("{}", t("commit_cancelled").dimmed());
+            return Ok(());
+        }
+        note_default_branch_commit(cfg);
+    }
     print_staged_files(&staged_files);
 
     if cfg.warn_staged_files_enabled && staged_files.len() > cfg.warn_staged_files_threshold {
-        let prompt = format!(
-            "You have {} staged files (threshold {}). Continue with commit generation?",
-            staged_files.len(),
-            cfg.warn_staged_files_threshold
+        let prompt = tf(
+            "staged_threshold_confirm",
+            &[
+                ("count", &staged_files.len().to_string()),
+                ("threshold", &cfg.warn_staged_files_threshold.to_string()),
+            ],
         );
         let should_continue = ui::confirm(&prompt, false);
         if !should_continue {
-            println!("{}", "Commit cancelled.".dimmed());
+            println!("{}", t("commit_cancelled").dimmed());
+            return Ok(());
+        }
+    }
+
+    if cfg.pre_commit_hook && !skips_hooks(&cli.extra_args) {
+        run_pre_commit_checks(cfg, &staged_files)?;
+    }
+
+    let diff = if cli.allow_empty && staged_files.is_empty() {
+        let Some(reason) = cli.context.as_deref().filter(|r| !r.trim().is_empty()) else {
+            anyhow::bail!(t("allow_empty_needs_context"));
+        };
+        prompt::empty_commit_input(reason)
+    } else {
+        git::get_staged_diff_filtered(&cfg.diff_exclude_globs, cfg.exclude_lfs)
+            .context("Failed to get staged diff")?
+    };
+    let routed = route_preset(cfg, &diff)?;
+    let cfg = routed.as_ref().unwrap_or(cfg);
+    if cfg.warn_staged_files_enabled && !confirm_large_diff(cfg, &diff) {
+        println!("{}", t("commit_cancelled").dimmed());
+        return Ok(());
+    }
+    if cfg.show_diff {
+        ui::page(&ui::colorize_diff(&diff))?;
+        if !ui::confirm(&t("diff_preview_confirm"), true) {
+            println!("{}", t("commit_cancelled").dimmed());
             return Ok(());
         }
     }
 
     let gen_start = Instant::now();
-    let diff = git::get_staged_diff_filtered(&cfg.diff_exclude_globs)
-        .context("Failed to get staged diff")?;
-    let Some((final_msg, time_to_ready)) =
-        generate_final_message(cfg, &diff, cli.verbose, gen_start)?
+    let Some(Generated {
+        message: final_msg,
+        time_to_ready,
+        fallback,
+    }) = generate_final_message(cfg, &diff, cli.verbose > 0, gen_start)?
     else {
         return Ok(());
     };
-    if cli.verbose {
+    if cli.verbose > 0 {
         if let Some(elapsed) = time_to_ready {
             println!(
                 "  {} {}",
-                "Generated in".dimmed(),
+                t("generated_in").dimmed(),
                 format!("{:.2}s", elapsed.as_secs_f64()).dimmed()
             );
         }
     }
 
     if cli.dry_run {
-        println!(
-            "\n{}",
-            "Dry run enabled. Commit not created.".yellow().bold()
-        );
+        if !ui::quiet() {
+            println!("\n{}", t("dry_run_commit").yellow().bold());
+        }
         return Ok(());
     }
 
-    git::run_commit(&final_msg, &cli.extra_args, cfg.suppress_tool_output)
+    let mut commit_args = cli.extra_args.clone();
+    if cli.allow_empty {
+        commit_args.push("--allow-empty".into());
+    }
+    // git's own summary line is success chatter too; its errors still show
+    if ui::quiet() {
+        commit_args.push("--quiet".into());
+    }
+    git::run_commit(&final_msg, &commit_args, cfg.suppress_tool_output)
         .context("git commit failed")?;
 
-    if cfg.track_generated_commits {
-        if let Ok(repo_root) = git::find_repo_root() {
-            if let Ok(hash) = cache::get_head_hash() {
-                let preview: String = final_msg.chars().take(80).collect();
-                let _ = cache::record_commit(&repo_root, &hash, &preview);
-            }
+    if cfg.track_generated_commits || cfg.repo_history {
+        if let Ok(hash) = cache::get_head_hash() {
+            track_commit(cfg, &hash, &final_msg, fallback);
         }
     }
 
@@ -149,12 +366,130 @@ fn run_standard_commit(cfg: &config::AppConfig, cli: &cli::Cli) -> Result<()> {
         create_semver_tag(cfg)?;
     }
 
-    handle_post_commit_push(cfg, "Commit created. Push now?")?;
+    handle_post_commit_push(cfg, &t("push_after_commit"))?;
+    Ok(())
+}
+
+/// Whether the arguments forwarded to `git commit` disable its hooks
+fn skips_hooks(extra_args: &[String]) -> bool {
+    extra_args.iter().any(|a| a == "--no-verify" || a == "-n")
+}
+
+/// Run the pre-commit hook before generating, so the message describes what will actually be
+/// committed and `git commit` does not fail after the message was approved. Staged files the
+/// hook rewrites (formatters) are restaged and the hook runs once more.
+fn run_pre_commit_checks(cfg: &config::AppConfig, staged_files: &[String]) -> Result<()> {
+    // Partially staged files are left alone: the hook's edits can't be told apart from yours
+    let dirty_before = git::unstaged_files()?;
+    if !ui::quiet() {
+        println!("{}", t("pre_commit_running").dimmed());
+    }
+    let outcome = git::run_pre_commit_hook(cfg.suppress_tool_output)?;
+    if outcome == git::PreCommitOutcome::Missing {
+        return Ok(());
+    }
+
+    let modified: Vec<String> = git::unstaged_files()?
+        .into_iter()
+        .filter(|f| staged_files.contains(f) && !dirty_before.contains(f))
+        .collect();
+    if modified.is_empty() {
+        if outcome == git::PreCommitOutcome::Failed {
+            anyhow::bail!(t("pre_commit_failed"));
+        }
+        return Ok(());
+    }
+    git::stage_files(&modified).context("Failed to restage files modified by the hook")?;
+    println!(
+        "{}",
+        tf("pre_commit_restaged", &[("files", &modified.join(", "))]).dimmed()
+    );
+    if outcome == git::PreCommitOutcome::Failed
+        && git::run_pre_commit_hook(cfg.suppress_tool_output)? == git::PreCommitOutcome::Failed
+    {
+        anyhow::bail!(t("pre_commit_failed"));
+    }
     Ok(())
 }
 
-fn run_alter(cfg: &config::AppConfig, cli: &cli::Cli, commits: &[String]) -> Result<()> {
-    ensure_api_key(cfg)?;
+/// Browse the history cache, then commit the staged changes with a message picked for reuse,
+/// after a chance to edit it
+fn run_history(cli: &cli::Cli, filter: &cache::HistoryFilter) -> Result<()> {
+    let Some(message) = cache::interactive_history(filter)? else {
+        return Ok(());
+    };
+    let cfg = config::AppConfig::load()?;
+    ensure_no_operation_in_progress()?;
+    if !confirm_protected_commit(&cfg) {
+        println!("{}", t("commit_cancelled").dimmed());
+        return Ok(());
+    }
+    if git::list_staged_files()
+        .context("Failed to list staged files")?
+        .is_empty()
+    {
+        anyhow::bail!(t("reuse_nothing_staged"));
+    }
+    let message = match ui::edit_message(&message)? {
+        Some(message) if !message.trim().is_empty() => message,
+        _ => {
+            println!("{}", t("commit_cancelled").dimmed());
+            return Ok(());
+        }
+    };
+    git::run_commit(&message, &cli.extra_args, cfg.suppress_tool_output)
+        .context("git commit failed")?;
+    handle_post_commit_push(&cfg, &t("push_after_commit"))
+}
+
+/// Record commit `hash` in the history cache and/or the repository's history file, with the
+/// provider and model that wrote it. Tracking is best-effort and never fails the commit.
+fn track_commit(cfg: &config::AppConfig, hash: &str, message: &str, fallback: Option<String>) {
+    let Ok(repo_root) = git::find_repo_root() else {
+        return;
+    };
+    let mut commit = cache::CachedCommit::new(hash, message);
+    commit.branch = git::current_branch().unwrap_or_default();
+    commit.provider = cfg.provider.clone();
+    commit.model = cfg.model.clone();
+    if let Some(name) = &fallback {
+        let preset = preset::load_presets()
+            .ok()
+            .and_then(|file| file.presets.into_iter().find(|p| &p.name == name));
+        if let Some(preset) = preset {
+            commit.provider = preset.fields.provider;
+            commit.model = preset.fields.model;
+        }
+    }
+    commit.fallback = fallback;
+    // The entry describes a commit that already exists, so it is staged to land in the next one
+    if cfg.repo_history {
+        let recorded = cache::append_repo_history(&repo_root, &commit)
+            .and_then(|()| git::stage_files(&[cache::REPO_HISTORY_FILE.to_string()]));
+        if let Err(e) = recorded {
+            eprintln!("{} {:#}", t("warning").yellow().bold(), e);
+        }
+    }
+    // Worktrees of one repository share its history
+    let identity = git::repo_identity().unwrap_or(repo_root);
+    if cfg.track_generated_commits && cache::record_commit(&identity, commit).is_ok() {
+        let _ = cache::prune(&cache::CacheLimits::from_config(cfg), false);
+    }
+}
+
+fn run_alter(
+    cfg: &config::AppConfig,
+    cli: &cli::Cli,
+    commits: &[String],
+    force: bool,
+) -> Result<()> {
+    ensure_api_key_unless_routed(cfg)?;
+    if !cli.dry_run {
+        ensure_no_operation_in_progress()?;
+        if let Some(branch) = protected_branch(cfg).filter(|_| !force) {
+            anyhow::bail!(tf("protected_alter_refused", &[("branch", &branch)]));
+        }
+    }
 
     let (target, diff) = match commits {
         [single] => (
@@ -165,174 +500,424 @@ fn run_alter(cfg: &config::AppConfig, cli: &cli::Cli, commits: &[String]) -> Res
             newer.to_string(),
             git::get_range_diff(older, newer).context("Failed to get range diff")?,
         ),
-        _ => anyhow::bail!("Expected one or two commit hashes."),
+        _ => anyhow::bail!(t("expected_hashes")),
     };
+    let diff = git::with_submodule_summaries(&git::summarize_lfs_pointers(&diff, cfg.exclude_lfs));
+    let routed = route_preset(cfg, &diff)?;
+    let cfg = routed.as_ref().unwrap_or(cfg);
 
-    let target_is_head = git::is_head_commit(&target)?;
     let target_is_pushed = git::commit_is_pushed(&target)?;
     if target_is_pushed {
-        let proceed = ui::confirm(
-            "Target commit appears to be pushed already. Rewriting history may require a force push. Continue?",
-            false,
-        );
+        let proceed = ui::confirm(&t("alter_pushed_confirm"), false);
         if !proceed {
-            println!("{}", "Alter cancelled.".dimmed());
+            println!("{}", t("alter_cancelled").dimmed());
             return Ok(());
         }
     }
 
     let gen_start = Instant::now();
-    let Some((final_msg, time_to_ready)) =
-        generate_final_message(cfg, &diff, cli.verbose, gen_start)?
+    let Some(Generated {
+        message: final_msg,
+        time_to_ready,
+        fallback,
+    }) = generate_final_message(cfg, &diff, cli.verbose > 0, gen_start)?
     else {
         return Ok(());
     };
-    if cli.verbose {
+    if cli.verbose > 0 {
         if let Some(elapsed) = time_to_ready {
             println!(
                 "  {} {}",
-                "Generated in".dimmed(),
+                t("generated_in").dimmed(),
                 format!("{:.2}s", elapsed.as_secs_f64()).dimmed()
             );
         }
     }
 
     if cli.dry_run {
-        println!(
-            "\n{}",
-            "Dry run enabled. Commit message was generated but history was not rewritten."
-                .yellow()
-                .bold()
-        );
+        println!("\n{}", t("dry_run_alter").yellow().bold());
         return Ok(());
     }
 
-    git::rewrite_commit_message(&target, &final_msg, cfg.suppress_tool_output)
-        .context("Failed to rewrite commit message")?;
+    // Read before the rewrite: the remote-tracking commit is the lease for the force-push
+    let upstream = if target_is_pushed {
+        git::upstream()?
+    } else {
+        None
+    };
+    let rewritten = with_auto_stash(cfg, || {
+        git::rewrite_commit_message(&target, &final_msg, cfg.suppress_tool_output)
+            .context("Failed to rewrite commit message")
+    })?;
+
+    if cfg.track_generated_commits || cfg.repo_history {
+        track_commit(cfg, &rewritten, &final_msg, fallback);
+    }
+
+    if target_is_pushed {
+        force_push_after_rewrite(cfg, upstream)?;
+    } else {
+        handle_post_commit_push(cfg, &t("push_after_alter"))?;
+    }
 
-    if cfg.track_generated_commits {
-        if let Ok(repo_root) = git::find_repo_root() {
-            if let Ok(hash) = cache::get_head_hash() {
-                let preview: String = final_msg.chars().take(80).collect();
-                let _ = cache::record_commit(&repo_root, &hash, &preview);
-            }
+    Ok(())
+}
+
+fn run_lint(
+    cfg: &config::AppConfig,
+    cli: &cli::Cli,
+    range: Option<&str>,
+    stdin: bool,
+    fix: bool,
+) -> Result<()> {
+    let rules = lint::LintRules::from_config(cfg);
+    let entries = if stdin {
+        let mut raw = String::new();
+        std::io::stdin()
+            .read_to_string(&mut raw)
+            .context("Failed to read commit message from stdin")?;
+        // Drop git's comment lines so a commit-msg hook can pipe the message file directly
+        let message = raw
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        vec![("stdin".to_string(), message)]
+    } else {
+        git::get_commit_messages(range.unwrap_or("HEAD"))?
+    };
+
+    let mut failing = Vec::new();
+    for (id, message) in &entries {
+        let label: String = id.chars().take(7).collect();
+        let header = message.lines().next().unwrap_or("");
+        let violations = lint::lint_message(message, &rules);
+        if violations.is_empty() {
+            println!("  {} {} {}", "ok".green().bold(), label.dimmed(), header);
+            continue;
         }
+        println!("  {} {} {}", "fail".red().bold(), label.yellow(), header);
+        for v in &violations {
+            println!("      {} {}", format!("[{}]", v.rule).dimmed(), v.message);
+        }
+        failing.push(id.clone());
     }
 
-    if target_is_pushed {
-        let should_push = ui::confirm(
-            "History was rewritten on a pushed commit. Attempt `git push` now?",
-            false,
+    if failing.is_empty() {
+        println!(
+            "\n{} {}",
+            t("lint_all_good").green().bold(),
+            tf("lint_checked", &[("count", &entries.len().to_string())])
         );
-        if should_push {
-            if !target_is_head {
-                println!(
-                    "{}",
-                    "Note: a non-HEAD rewrite may require `git push --force-with-lease`."
-                        .yellow()
-                        .bold()
-                );
-            }
-            git::run_push(cfg.suppress_tool_output).context("git push failed")?;
-        } else {
+        return Ok(());
+    }
+
+    if fix {
+        // Newest first: rewriting a commit never changes the hashes of its ancestors
+        for hash in &failing {
             println!(
-                "{}",
-                "Skipped push after history rewrite. Push manually when ready.".dimmed()
+                "\n{} {}",
+                t("lint_regenerating").cyan().bold(),
+                &hash[..hash.len().min(7)]
             );
+            run_alter(cfg, cli, std::slice::from_ref(hash), false)?;
         }
-    } else {
-        handle_post_commit_push(cfg, "Commit message altered. Push now?")?;
+        return Ok(());
     }
 
+    anyhow::bail!(tf(
+        "lint_failed",
+        &[
+            ("failed", &failing.len().to_string()),
+            ("total", &entries.len().to_string()),
+        ],
+    ));
+}
+
+/// Refuse to move HEAD while a rebase, merge, cherry-pick or revert is stopped part way,
+/// instead of failing with git's errors half way through
+fn ensure_no_operation_in_progress() -> Result<()> {
+    if let Some(op) = git::in_progress_operation()? {
+        let command = op.command();
+        anyhow::bail!(tf(
+            "operation_in_progress",
+            &[
+                ("operation", command),
+                ("continue", &format!("git {command} --continue")),
+                ("abort", &format!("git {command} --abort")),
+            ],
+        ));
+    }
+    Ok(())
+}
+
+/// The config with the preset `ACR_ROUTING` picks for this run and `diff`, if it picks one.
+/// Its API key is checked here, since it may differ from the configured one.
+fn route_preset(cfg: &config::AppConfig, diff: &str) -> Result<Option<config::AppConfig>> {
+    let Some((routed, name)) = routing::route(cfg, diff)? else {
+        ensure_api_key(cfg)?;
+        return Ok(None);
+    };
+    ensure_api_key(&routed)?;
+    ui::note(&tf(
+        "routed_preset",
+        &[("name", &name), ("policy", cfg.routing.trim())],
+    ));
+    Ok(Some(routed))
+}
+
+/// Fail early without an API key, unless `ACR_ROUTING` will pick the run's preset later
+fn ensure_api_key_unless_routed(cfg: &config::AppConfig) -> Result<()> {
+    if routing::Policy::parse(&cfg.routing) == routing::Policy::None {
+        ensure_api_key(cfg)?;
+    }
     Ok(())
 }
 
 fn ensure_api_key(cfg: &config::AppConfig) -> Result<()> {
     if cfg.api_key.is_empty() {
-        anyhow::bail!(
-            "No API key configured. Run {} or set {}",
-            "cgen config".yellow(),
-            "ACR_API_KEY".yellow()
-        );
+        anyhow::bail!(tf(
+            "no_api_key",
+            &[
+                ("command", &"cgen config".yellow().to_string()),
+                ("variable", &"ACR_API_KEY".yellow().to_string()),
+            ],
+        ));
     }
     Ok(())
 }
 
+/// A reviewed message, ready to commit
+struct Generated {
+    message: String,
+    time_to_ready: Option<std::time::Duration>,
+    /// Fallback preset that wrote the message, if the primary provider failed
+    fallback: Option<String>,
+}
+
+/// Print `tracing` spans and events to stderr when `RUST_LOG` is set, e.g.
+/// `RUST_LOG=auto_commit_rs::provider=debug`
+fn init_tracing() {
+    if std::env::var_os("RUST_LOG").is_none() {
+        return;
+    }
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr)
+        .try_init();
+}
+
+/// What `cgen prompt --diff` adds after the system prompt: the filtered staged diff and the
+/// request body the configured provider would get for it, with its size
+fn request_preview(cfg: &config::AppConfig) -> Result<String> {
+    let diff = git::get_staged_diff_filtered(&cfg.diff_exclude_globs, cfg.exclude_lfs)
+        .context("Failed to get staged diff")?;
+    let system_prompt = prompt::system_prompt_for(cfg, scope::detect_scope(cfg, &diff).as_deref());
+    let body = provider::prepare_request(cfg, &system_prompt, &diff)?.body;
+    let json = serde_json::to_string_pretty(&body).context("Failed to serialize request body")?;
+
+    let mut out = format!("\n{}\n", t("staged_diff").cyan().bold());
+    if diff.trim().is_empty() {
+        out.push_str(&format!("{}\n", t("nothing_staged").dimmed()));
+    } else {
+        out.push_str(&ui::colorize_diff(&diff));
+    }
+    let target = format!("{} {} {}", cfg.provider, ui::glyphs().dot, cfg.model);
+    out.push_str(&format!(
+        "\n{}\n{json}\n",
+        tf("request_body", &[("target", &target)]).cyan().bold()
+    ));
+    let size = tf(
+        "request_size",
+        &[
+            ("bytes", &json.len().to_string()),
+            ("tokens", &provider::estimated_tokens(&body).to_string()),
+        ],
+    );
+    out.push_str(&format!("\n{}\n", size.dimmed()));
+    Ok(out)
+}
+
+/// `--dry-run-request`: print the request the configured provider would get for the staged
+/// diff, with secrets masked, instead of sending it
+fn print_dry_run_request(cfg: &config::AppConfig) -> Result<()> {
+    let diff = git::get_staged_diff_filtered(&cfg.diff_exclude_globs, cfg.exclude_lfs)
+        .context("Failed to get staged diff")?;
+    let system_prompt = prompt::system_prompt_for(cfg, scope::detect_scope(cfg, &diff).as_deref());
+    let request = provider::prepare_request(cfg, &system_prompt, &diff)?;
+    ui::page(&request.redacted(&cfg.api_key))
+}
+
 fn generate_final_message(
     cfg: &config::AppConfig,
     diff: &str,
     verbose: bool,
     gen_start: Instant,
-) -> Result<Option<(String, Option<std::time::Duration>)>> {
-    let system_prompt = prompt::build_system_prompt(cfg);
+) -> Result<Option<Generated>> {
+    let detected_scope = scope::detect_scope(cfg, diff);
+    let system_prompt = prompt::system_prompt_for(cfg, detected_scope.as_deref());
+    let finalize = |raw: &str| prompt::finalize_commit_message(cfg, raw, detected_scope.as_deref());
     if verbose {
-        println!("\n{}", "LLM system prompt:".cyan().bold());
+        println!("\n{}", t("system_prompt").cyan().bold());
         println!("{system_prompt}\n");
     }
-    let (raw_message, fallback_name) = provider::call_llm_with_fallback(cfg, &system_prompt, diff)
-        .context("LLM API call failed")?;
-    let mut message = prompt::clean_commit_message(&raw_message);
+    let Some((raw_message, fallback_name)) = call_llm_within_budget(cfg, &system_prompt, diff)?
+    else {
+        println!("{}", t("commit_cancelled").dimmed());
+        return Ok(None);
+    };
+    let mut message =
+        retry_disallowed_type(cfg, &system_prompt, diff, finalize(&raw_message), &finalize)?;
 
     if let Some(ref name) = fallback_name {
-        println!(
-            "  {} Used fallback preset: {}",
-            "note:".yellow().bold(),
-            name
-        );
+        ui::note(&tf("used_fallback", &[("name", name)]));
     }
+    let mut fallback_used = fallback_name;
+    // Metrics are best-effort and must never stop a commit
+    let record_run = |outcome, fallback: &Option<String>| {
+        let _ = usage::record_run(cfg, outcome, fallback.clone());
+    };
 
     let mut time_to_ready: Option<std::time::Duration> = None;
 
     let final_msg = if cfg.review_commit {
+        let mut rejected: Vec<String> = Vec::new();
         loop {
-            let candidate = cfg
-                .commit_template
-                .replace("$msg", message.trim())
-                .trim()
-                .to_string();
+            let candidate = prompt::apply_commit_template(cfg, &message);
 
             if time_to_ready.is_none() {
                 time_to_ready = Some(gen_start.elapsed());
             }
-            println!("\n{}", "Commit message:".green().bold());
+            println!("\n{}", t("commit_message").green().bold());
             println!("  {}\n", candidate);
 
-            match review_message()? {
-                ReviewAction::Accept => break candidate,
+            let action = if cfg.review_tui && std::io::stdout().is_terminal() {
+                review_on_screen(diff, &candidate)?
+            } else {
+                review_message()?
+            };
+            match action {
+                ReviewAction::Accept => {
+                    record_run(usage::Outcome::Accepted, &fallback_used);
+                    break candidate;
+                }
                 ReviewAction::Regenerate => {
-                    let (new_raw, fb) = provider::call_llm_with_fallback(cfg, &system_prompt, diff)
-                        .context("LLM API call failed")?;
-                    message = prompt::clean_commit_message(&new_raw);
+                    rejected.push(message.clone());
+                    let regen_prompt = prompt::build_regenerate_prompt(&system_prompt, &rejected);
+                    let Some((new_raw, fb)) = call_llm_within_budget(cfg, &regen_prompt, diff)?
+                    else {
+                        continue;
+                    };
+                    message = retry_disallowed_type(
+                        cfg,
+                        &system_prompt,
+                        diff,
+                        finalize(&new_raw),
+                        &finalize,
+                    )?;
+                    if let Some(ref name) = fb {
+                        ui::note(&tf("used_fallback", &[("name", name)]));
+                    }
+                    fallback_used = fb.or(fallback_used);
+                }
+                ReviewAction::Refine => {
+                    let refine_help = t("refine_help");
+                    let Ok(instruction) = Text::new(&t("refine_instruction"))
+                        .with_help_message(&refine_help)
+                        .prompt()
+                    else {
+                        continue;
+                    };
+                    if instruction.trim().is_empty() {
+                        continue;
+                    }
+                    let refine_prompt =
+                        prompt::build_refine_prompt(&system_prompt, &message, &instruction);
+                    let Some((new_raw, fb)) = call_llm_within_budget(cfg, &refine_prompt, diff)?
+                    else {
+                        continue;
+                    };
+                    message = retry_disallowed_type(
+                        cfg,
+                        &system_prompt,
+                        diff,
+                        finalize(&new_raw),
+                        &finalize,
+                    )?;
                     if let Some(ref name) = fb {
-                        println!(
-                            "  {} Used fallback preset: {}",
-                            "note:".yellow().bold(),
-                            name
-                        );
+                        ui::note(&tf("used_fallback", &[("name", name)]));
                     }
+                    fallback_used = fb.or(fallback_used);
                 }
                 ReviewAction::Edit => {
-                    let edited = edit::edit(&candidate)?;
+                    let Some(edited) = ui::edit_message(&candidate)? else {
+                        continue;
+                    };
+                    if edited.trim().is_empty() {
+                        // An emptied buffer aborts, as in git's own editor flow
+                        record_run(usage::Outcome::Cancelled, &fallback_used);
+                        println!("{}", t("commit_cancelled").dimmed());
+                        return Ok(None);
+                    }
+                    record_run(usage::Outcome::Edited, &fallback_used);
                     break edited.trim().to_string();
                 }
                 ReviewAction::Cancel => {
-                    println!("{}", "Commit cancelled.".dimmed());
+                    record_run(usage::Outcome::Cancelled, &fallback_used);
+                    println!("{}", t("commit_cancelled").dimmed());
                     return Ok(None);
                 }
             }
         }
     } else {
-        let final_msg = cfg
-            .commit_template
-            .replace("$msg", message.trim())
-            .trim()
-            .to_string();
+        let final_msg = prompt::apply_commit_template(cfg, &message);
+        record_run(usage::Outcome::Accepted, &fallback_used);
         time_to_ready = Some(gen_start.elapsed());
-        println!("\n{} {}", "Commit message:".green().bold(), final_msg);
+        if ui::quiet() {
+            println!("{final_msg}");
+        } else {
+            println!("\n{} {}", t("commit_message").green().bold(), final_msg);
+        }
         final_msg
     };
 
-    Ok(Some((final_msg, time_to_ready)))
+    Ok(Some(Generated {
+        message: final_msg,
+        time_to_ready,
+        fallback: fallback_used,
+    }))
+}
+
+/// Regenerate once when the message still uses a type outside the allowed list after
+/// post-processing could not map it to an allowed one
+fn retry_disallowed_type(
+    cfg: &config::AppConfig,
+    system_prompt: &str,
+    diff: &str,
+    message: String,
+    finalize: &dyn Fn(&str) -> String,
+) -> Result<String> {
+    let rules = lint::LintRules::from_config(cfg);
+    let Some(violation) = lint::lint_message(&message, &rules)
+        .into_iter()
+        .find(|v| v.rule == "type-enum")
+    else {
+        return Ok(message);
+    };
+
+    ui::note(&tf(
+        "regenerating_disallowed",
+        &[("reason", &violation.message)],
+    ));
+    let retry_prompt =
+        prompt::build_regenerate_prompt(system_prompt, std::slice::from_ref(&message));
+    // Over budget and declined: keep the message for the user to judge in review
+    let Some((raw, _)) = call_llm_within_budget(cfg, &retry_prompt, diff)? else {
+        return Ok(message);
+    };
+    Ok(finalize(&raw))
 }
 
 fn create_semver_tag(cfg: &config::AppConfig) -> Result<()> {
@@ -341,8 +926,8 @@ fn create_semver_tag(cfg: &config::AppConfig) -> Result<()> {
 
     let should_create = if cfg.confirm_new_version {
         let prompt = match latest.as_deref() {
-            Some(tag) => format!("Create new tag {next_tag} (latest: {tag})?"),
-            None => format!("Create initial tag {next_tag}?"),
+            Some(tag) => tf("tag_create_new", &[("tag", &next_tag), ("latest", tag)]),
+            None => tf("tag_create_initial", &[("tag", &next_tag)]),
         };
         ui::confirm(&prompt, true)
     } else {
@@ -350,100 +935,359 @@ fn create_semver_tag(cfg: &config::AppConfig) -> Result<()> {
     };
 
     if !should_create {
-        println!("{}", "Tag creation skipped.".dimmed());
+        println!("{}", t("tag_skipped").dimmed());
         return Ok(());
     }
 
     git::create_tag(&next_tag, cfg.suppress_tool_output).context("Failed to create git tag")?;
-    println!("{} {}", "Created tag:".green().bold(), next_tag);
+    if !ui::quiet() {
+        println!("{} {}", t("tag_created").green().bold(), next_tag);
+    }
     Ok(())
 }
 
 enum ReviewAction {
     Accept,
     Regenerate,
+    Refine,
     Edit,
     Cancel,
 }
 
 fn review_message() -> Result<ReviewAction> {
-    let choices = vec!["Accept", "Regenerate", "Edit", "Cancel"];
+    let actions = [
+        (t("review_accept"), ReviewAction::Accept),
+        (t("review_regenerate"), ReviewAction::Regenerate),
+        (t("review_refine"), ReviewAction::Refine),
+        (t("review_edit"), ReviewAction::Edit),
+        (t("review_cancel"), ReviewAction::Cancel),
+    ];
+    let choices: Vec<&str> = actions.iter().map(|(label, _)| label.as_str()).collect();
 
-    let answer = Select::new("", choices).without_help_message().prompt();
+    let answer = Select::new("", choices)
+        .without_help_message()
+        .prompt()
+        .map(str::to_string);
 
-    match answer {
-        Ok("Accept") => Ok(ReviewAction::Accept),
-        Ok("Regenerate") => Ok(ReviewAction::Regenerate),
-        Ok("Edit") => Ok(ReviewAction::Edit),
-        _ => Ok(ReviewAction::Cancel),
-    }
+    Ok(match answer {
+        Ok(choice) => actions
+            .into_iter()
+            .find(|(label, _)| *label == choice)
+            .map_or(ReviewAction::Cancel, |(_, action)| action),
+        Err(_) => ReviewAction::Cancel,
+    })
+}
+
+/// `review_message` on the full-screen diff view
+fn review_on_screen(diff: &str, candidate: &str) -> Result<ReviewAction> {
+    Ok(match tui::review(diff, candidate)? {
+        tui::ReviewChoice::Accept => ReviewAction::Accept,
+        tui::ReviewChoice::Regenerate => ReviewAction::Regenerate,
+        tui::ReviewChoice::Refine => ReviewAction::Refine,
+        tui::ReviewChoice::Edit => ReviewAction::Edit,
+        tui::ReviewChoice::Cancel => ReviewAction::Cancel,
+    })
 }
 
 fn print_staged_files(staged_files: &[String]) {
+    if ui::quiet() {
+        return;
+    }
     println!(
         "\n{} {}",
-        "Staged files:".green().bold(),
+        t("staged_files").green().bold(),
         staged_files.len()
     );
     if staged_files.is_empty() {
-        println!("  {}", "(none)".dimmed());
+        println!("  {}", t("none").dimmed());
         return;
     }
 
     let last = staged_files.len() - 1;
+    let g = ui::glyphs();
     for (i, file) in staged_files.iter().enumerate() {
-        let connector = if i == last {
-            "\u{2514}\u{2500}\u{2500}"
-        } else {
-            "\u{251C}\u{2500}\u{2500}"
-        };
+        let connector = if i == last { g.last } else { g.branch };
         println!("  {} {}", connector, file);
     }
 }
 
 fn handle_post_commit_push(cfg: &config::AppConfig, ask_prompt: &str) -> Result<()> {
-    match cfg.post_commit_push.as_str() {
-        "never" => {}
-        "always" => {
-            git::run_push(cfg.suppress_tool_output).context("git push failed")?;
+    let should_push = match cfg.post_commit_push.as_str() {
+        "never" => false,
+        "always" => true,
+        _ => ui::confirm(ask_prompt, true),
+    };
+    if !should_push {
+        return Ok(());
+    }
+    // Even `always` asks again before pushing to a protected branch
+    if let Some(branch) = protected_branch(cfg) {
+        if !ui::confirm(&tf("protected_push_confirm", &[("branch", &branch)]), false) {
+            println!("{}", t("push_skipped").dimmed());
+            return Ok(());
         }
-        _ => {
-            let should_push = ui::confirm(ask_prompt, true);
-            if should_push {
-                git::run_push(cfg.suppress_tool_output).context("git push failed")?;
+    }
+    if !catch_up_with_upstream(cfg)? {
+        println!("{}", t("push_skipped").dimmed());
+        return Ok(());
+    }
+    let remotes = if !cfg.push_remote.is_empty() {
+        cfg.push_remote.clone()
+    } else if cfg.post_commit_push == "always" {
+        Vec::new()
+    } else {
+        match pick_push_remotes()? {
+            Some(remotes) => remotes,
+            None => {
+                println!("{}", t("push_skipped").dimmed());
+                return Ok(());
+            }
+        }
+    };
+    // A new branch: a plain `git push` would fail, so the first push sets the upstream
+    let needs_upstream = !git::has_upstream_branch()?;
+    if remotes.is_empty() {
+        let remote = match git::default_push_remote()? {
+            Some(remote) if needs_upstream => remote,
+            _ => return git::run_push(cfg.suppress_tool_output).context("git push failed"),
+        };
+        if !cfg.auto_set_upstream {
+            let branch = git::current_branch()?;
+            let prompt = tf(
+                "set_upstream_confirm",
+                &[("branch", &branch), ("remote", &remote)],
+            );
+            if !ui::confirm(&prompt, true) {
+                println!("{}", t("push_skipped").dimmed());
+                return Ok(());
             }
         }
+        return git::push_to_remote(&remote, true, cfg.suppress_tool_output)
+            .with_context(|| format!("git push -u {remote} failed"));
+    }
+    for (i, remote) in remotes.iter().enumerate() {
+        if !ui::quiet() {
+            println!("{}", tf("pushing_to", &[("remote", remote)]).dimmed());
+        }
+        git::push_to_remote(remote, needs_upstream && i == 0, cfg.suppress_tool_output)
+            .with_context(|| format!("git push {remote} failed"))?;
     }
     Ok(())
 }
 
-fn prompt_auto_update() {
+/// Fetch the upstream and, when the branch is behind it, offer `git pull --rebase` so the push
+/// is not rejected. False when the push should be skipped.
+fn catch_up_with_upstream(cfg: &config::AppConfig) -> Result<bool> {
+    let Some(upstream) = git::upstream()? else {
+        return Ok(true);
+    };
+    // Offline or unreachable: let the push report it
+    if git::fetch(&upstream.remote, true).is_err() {
+        return Ok(true);
+    }
+    let (ahead, behind) = git::ahead_behind()?;
+    if behind == 0 {
+        return Ok(true);
+    }
+    let name = upstream.display_name();
     println!(
-        "  {}",
-        "You can change this later with `cgen config`".dimmed()
+        "{} {}",
+        t("warning").yellow().bold(),
+        tf(
+            "branch_behind",
+            &[
+                ("upstream", &name),
+                ("behind", &behind.to_string()),
+                ("ahead", &ahead.to_string()),
+            ],
+        )
     );
-    let yes = ui::confirm("Would you like to enable automatic updates for cgen?", true);
+    if !ui::confirm(&t("pull_rebase_confirm"), true) {
+        return Ok(false);
+    }
+    git::pull_rebase(cfg.suppress_tool_output).with_context(|| t("pull_rebase_failed"))?;
+    Ok(true)
+}
+
+/// With several remotes, ask which to push to, the upstream's (or default) remote preselected. Returns an
+/// empty list when there is only one remote (git's default push), `None` when cancelled.
+fn pick_push_remotes() -> Result<Option<Vec<String>>> {
+    let remotes = git::remotes()?;
+    if remotes.len() < 2 {
+        return Ok(Some(Vec::new()));
+    }
+    let upstream_remote = match git::upstream().ok().flatten() {
+        Some(upstream) => Some(upstream.remote),
+        None => git::default_push_remote().ok().flatten(),
+    };
+    let defaults: Vec<usize> = remotes
+        .iter()
+        .position(|r| Some(r) == upstream_remote.as_ref())
+        .into_iter()
+        .collect();
+    Ok(MultiSelect::new(&t("push_remote_picker"), remotes)
+        .with_scorer(&ui::fuzzy_score)
+        .with_default(&defaults)
+        .prompt()
+        .ok()
+        .filter(|picked| !picked.is_empty()))
+}
+
+/// Offer `git push --force-with-lease` after rewriting pushed history. A plain push would be
+/// rejected; the lease, and a check of the remote first, make sure nobody else's commits are
+/// overwritten.
+fn force_push_after_rewrite(
+    cfg: &config::AppConfig,
+    upstream: Option<git::Upstream>,
+) -> Result<()> {
+    let Some(upstream) = upstream else {
+        println!("{}", t("push_skipped_after_rewrite").dimmed());
+        return Ok(());
+    };
+    let name = upstream.display_name();
+    if !ui::confirm(&tf("force_push_confirm", &[("upstream", &name)]), false) {
+        println!("{}", t("push_skipped_after_rewrite").dimmed());
+        return Ok(());
+    }
+    let remote = git::remote_branch_commit(&upstream).context("Failed to check the remote")?;
+    if remote.as_deref() != Some(upstream.expected.as_str()) {
+        eprintln!(
+            "{} {}",
+            t("warning").yellow().bold(),
+            tf("force_push_remote_moved", &[("upstream", &name)])
+        );
+        return Ok(());
+    }
+    git::force_push_with_lease(&upstream, cfg.suppress_tool_output)
+        .context("git push --force-with-lease failed")
+}
+
+/// The current branch, when it matches `ACR_PROTECTED_BRANCHES`. `@default` there stands for
+/// the remote's default branch.
+fn protected_branch(cfg: &config::AppConfig) -> Option<String> {
+    if cfg.protected_branches.is_empty() {
+        return None;
+    }
+    let mut patterns = cfg.protected_branches.clone();
+    if let Some(pattern) = patterns.iter_mut().find(|p| *p == "@default") {
+        match git::default_branch().ok().flatten() {
+            Some(default) => *pattern = default,
+            None => pattern.clear(),
+        }
+    }
+    git::current_branch()
+        .ok()
+        .filter(|branch| git::branch_matches(branch, &patterns))
+}
+
+/// Note a commit made straight to the remote's default branch, unless it is protected (and
+/// so was already confirmed)
+fn note_default_branch_commit(cfg: &config::AppConfig) {
+    if !cfg.warn_default_branch || protected_branch(cfg).is_some() {
+        return;
+    }
+    let (Ok(branch), Ok(Some(default))) = (git::current_branch(), git::default_branch()) else {
+        return;
+    };
+    if branch == default {
+        ui::note(&tf("default_branch_commit", &[("branch", &branch)]));
+    }
+}
+
+/// Ask before committing to a protected branch. True when the branch is not protected.
+fn confirm_protected_commit(cfg: &config::AppConfig) -> bool {
+    match protected_branch(cfg) {
+        Some(branch) => ui::confirm(
+            &tf("protected_commit_confirm", &[("branch", &branch)]),
+            false,
+        ),
+        None => true,
+    }
+}
+
+/// Ask whether to use the locale detected from the system and remember the answer, so the
+/// question is asked once. Returns the detected locale when accepted.
+fn prompt_detected_locale() -> Option<String> {
+    let detected = config::detect_system_locale().filter(|l| l != "en")?;
+    i18n::init(&detected);
+    // Cancelling leaves the question for next time
+    let yes = match ui::ask(
+        &tf("locale_detected_question", &[("locale", &detected)]),
+        true,
+    ) {
+        ui::Answer::Yes => true,
+        ui::Answer::No => false,
+        ui::Answer::Cancelled => return None,
+    };
+    let chosen = if yes { detected } else { "en".to_string() };
+    if let Err(e) = config::save_locale_preference(&chosen) {
+        eprintln!(
+            "{} {}",
+            t("warning").yellow().bold(),
+            tf("locale_save_failed", &[("error", &e.to_string())])
+        );
+    }
+    yes.then_some(chosen)
+}
+
+fn prompt_auto_update() {
+    println!("  {}", t("auto_update_hint").dimmed());
+    let yes = match ui::ask(&t("auto_update_question"), true) {
+        ui::Answer::Yes => true,
+        ui::Answer::No => false,
+        // Cancelling leaves the question for next time
+        ui::Answer::Cancelled => return,
+    };
     if let Err(e) = config::save_auto_update_preference(yes) {
         eprintln!(
-            "{} Failed to save auto-update preference: {}",
-            "warning:".yellow().bold(),
-            e
+            "{} {}",
+            t("warning").yellow().bold(),
+            tf("auto_update_save_failed", &[("error", &e.to_string())])
         );
     } else {
-        let status = if yes { "enabled" } else { "disabled" };
-        println!("{} Auto-updates {}.\n", "done!".green().bold(), status);
+        let status = if yes {
+            t("auto_update_enabled")
+        } else {
+            t("auto_update_disabled")
+        };
+        println!("{} {}\n", t("done").green().bold(), status);
     }
 }
 
-/// Check for updates and either auto-update or return the latest version for a warning.
+/// How long a finished command waits for the background update check before dropping it
+const UPDATE_CHECK_GRACE: Duration = Duration::from_millis(300);
+
+/// Run the update check on its own thread so a slow or missing network never holds up the
+/// command. Failed checks send nothing.
+fn spawn_update_check(cfg: Option<&config::AppConfig>) -> mpsc::Receiver<update::VersionCheck> {
+    let channel = update::UpdateChannel::parse(cfg.map_or("", |c| c.update_channel.as_str()));
+    let max_age = cfg.map_or(24, |c| c.update_check_hours);
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Ok(check) = update::check_version_cached(channel, max_age) {
+            let _ = tx.send(check);
+        }
+    });
+    rx
+}
+
+/// Once the command is done, either auto-update or return the latest version for a warning.
 /// Returns Some(latest_version) if a warning should be shown, None otherwise.
-fn check_for_updates(cfg: Option<&config::AppConfig>) -> Option<String> {
-    let version_check = match update::check_version() {
-        Ok(v) => v,
-        Err(_) => return None, // silently ignore network errors
-    };
+fn check_for_updates(
+    cfg: Option<&config::AppConfig>,
+    pending: mpsc::Receiver<update::VersionCheck>,
+) -> Option<String> {
+    let channel = update::UpdateChannel::parse(cfg.map_or("", |c| c.update_channel.as_str()));
+    // Offline or slow: give up, the thread dies with the process and the next run tries again
+    let version_check = pending.recv_timeout(UPDATE_CHECK_GRACE).ok()?;
 
-    if !version_check.update_available {
+    if !version_check.update_available
+        || update::warning_suppressed(
+            &update::load_reminders(),
+            &version_check.latest,
+            auto_commit_rs::timestamp::now_unix(),
+        )
+    {
         return None;
     }
 
@@ -451,18 +1295,24 @@ fn check_for_updates(cfg: Option<&config::AppConfig>) -> Option<String> {
 
     if auto_update {
         println!(
-            "{} {} → {}",
-            "Auto-updating cgen...".cyan().bold(),
+            "{} {} {} {}",
+            t("auto_updating").cyan().bold(),
             version_check.current.dimmed(),
+            ui::glyphs().arrow,
             version_check.latest.green(),
         );
-        if let Err(e) = update::run_update() {
-            eprintln!("{} Auto-update failed: {}", "warning:".yellow().bold(), e);
+        if let Err(e) = update::run_update(channel) {
+            eprintln!(
+                "{} {}",
+                t("warning").yellow().bold(),
+                tf("auto_update_failed", &[("error", &e.to_string())])
+            );
             return Some(version_check.latest);
         }
         println!(
-            "{} Restart cgen to use the new version.\n",
-            "note:".yellow().bold()
+            "{} {}\n",
+            t("note").yellow().bold(),
+            t("restart_after_update")
         );
         return None;
     }
@@ -470,88 +1320,687 @@ fn check_for_updates(cfg: Option<&config::AppConfig>) -> Option<String> {
     Some(version_check.latest)
 }
 
+/// Ask before sending a diff with more changed lines or bytes than the configured thresholds
+/// (0 disables a threshold). Returns whether to continue.
+fn confirm_large_diff(cfg: &config::AppConfig, diff: &str) -> bool {
+    let lines = git::count_changed_lines(diff);
+    if cfg.warn_staged_files_max_lines > 0 && lines > cfg.warn_staged_files_max_lines {
+        let prompt = tf(
+            "staged_lines_confirm",
+            &[
+                ("count", &lines.to_string()),
+                ("threshold", &cfg.warn_staged_files_max_lines.to_string()),
+            ],
+        );
+        return ui::confirm(&prompt, false);
+    }
+    if cfg.warn_staged_files_max_bytes > 0 && diff.len() > cfg.warn_staged_files_max_bytes {
+        let prompt = tf(
+            "staged_bytes_confirm",
+            &[
+                ("size", &diff.len().to_string()),
+                ("threshold", &cfg.warn_staged_files_max_bytes.to_string()),
+            ],
+        );
+        return ui::confirm(&prompt, false);
+    }
+    true
+}
+
+/// Prune the history cache to the configured limits, or delete it
+fn run_cache(cfg: &config::AppConfig, action: &cli::CacheAction) -> Result<()> {
+    let message = match action {
+        cli::CacheAction::Prune => {
+            let summary = cache::prune(&cache::CacheLimits::from_config(cfg), true)?;
+            tf(
+                "cache_pruned",
+                &[
+                    ("repos", &summary.repos_removed.to_string()),
+                    ("commits", &summary.commits_removed.to_string()),
+                    ("kib", &summary.bytes_freed.div_ceil(1024).to_string()),
+                ],
+            )
+        }
+        cli::CacheAction::Clear => {
+            let repos = cache::clear()?;
+            tf("cache_cleared", &[("repos", &repos.to_string())])
+        }
+    };
+    println!("{} {}", t("done").green().bold(), message);
+    Ok(())
+}
+
+/// The presets called (or numbered) `names`, or all of them when none are given
+fn named_presets<'a>(
+    file: &'a preset::PresetsFile,
+    names: &[String],
+) -> Result<Vec<&'a preset::Preset>> {
+    if names.is_empty() {
+        return Ok(file.presets.iter().collect());
+    }
+    names
+        .iter()
+        .map(|name| {
+            preset::find_preset(file, name)
+                .with_context(|| tf("preset_not_found", &[("name", name)]))
+        })
+        .collect()
+}
+
+/// Export presets to stdout or a file, import them from a file or URL, or test them
+fn run_preset_action(action: &cli::PresetAction) -> Result<()> {
+    let mut file = preset::load_presets()?;
+    match action {
+        cli::PresetAction::Export { presets, out, keys } => {
+            let ids: Vec<u32> = named_presets(&file, presets)?
+                .iter()
+                .map(|p| p.id)
+                .collect();
+            let content = preset::export_presets(&file, &ids, *keys)?;
+            match out {
+                Some(path) => {
+                    if *keys {
+                        // Plain keys must not be readable by other users
+                        credentials::write_private(path, &content)?;
+                    } else {
+                        std::fs::write(path, content)
+                            .with_context(|| format!("Failed to write {}", path.display()))?;
+                    }
+                    println!(
+                        "{} {}",
+                        t("done").green().bold(),
+                        tf(
+                            "presets_exported",
+                            &[
+                                ("count", &ids.len().to_string()),
+                                ("file", &path.display().to_string()),
+                            ],
+                        )
+                    );
+                }
+                None => print!("{content}"),
+            }
+        }
+        cli::PresetAction::Test { presets } => {
+            let cfg = config::AppConfig::load()?;
+            let presets = named_presets(&file, presets)?;
+            let results = preset::test_presets(&cfg, &presets);
+            let failed = results.values().filter(|c| c.error.is_some()).count();
+            let total = results.len().to_string();
+            if failed > 0 {
+                anyhow::bail!(tf(
+                    "preset_tests_failed",
+                    &[("count", &failed.to_string()), ("total", &total)]
+                ));
+            }
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf("preset_tests_passed", &[("total", &total)])
+            );
+        }
+        cli::PresetAction::Import { source } => {
+            let data = preset::read_import_source(source)?;
+            let count = preset::import_presets(&mut file, &data)?;
+            if count > 0 {
+                preset::save_presets(&file)?;
+            }
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf("presets_imported", &[("count", &count.to_string())])
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print the per-provider/model table from the local usage metrics, or delete them
+fn run_stats(clear: bool) -> Result<()> {
+    if clear {
+        usage::clear_metrics()?;
+        println!("{} {}", t("done").green().bold(), t("stats_cleared"));
+        return Ok(());
+    }
+    let file = usage::load_metrics()?;
+    if file.runs.is_empty() {
+        println!("{}", t("stats_empty").dimmed());
+        return Ok(());
+    }
+    let fallback_runs = file.runs.iter().filter(|r| r.fallback.is_some()).count();
+    println!(
+        "{}",
+        tf(
+            "stats_header",
+            &[
+                ("runs", &file.runs.len().to_string()),
+                ("fallbacks", &fallback_runs.to_string()),
+            ],
+        )
+        .cyan()
+        .bold()
+    );
+    println!(
+        "\n  {:<36} {:>8} {:>7} {:>9} {:>11} {:>8} {:>6} {:>6}",
+        "provider/model", "requests", "failed", "latency", "tokens", "accepted", "edited", "regen"
+    );
+    for stats in usage::summarize(&file.runs) {
+        let ok = (stats.requests - stats.failures).max(1);
+        println!(
+            "  {:<36} {:>8} {:>7} {:>9} {:>11} {:>8} {:>6} {:>6}",
+            format!("{}/{}", stats.provider, stats.model),
+            stats.requests,
+            stats.failures,
+            format!("{}ms", stats.avg_latency_ms()),
+            format!("{}/{}", stats.input_tokens / ok, stats.output_tokens / ok),
+            stats.accepted,
+            stats.edited,
+            stats.regenerated,
+        );
+    }
+    println!("\n  {}", t("stats_legend").dimmed());
+    Ok(())
+}
+
+/// Warn, or ask when `ACR_BUDGET_ACTION=confirm`, before a request that would exceed the
+/// monthly budget. Returns whether to continue.
+fn confirm_budget(cfg: &config::AppConfig, diff: &str) -> Result<bool> {
+    let Some(warning) = usage::check_request_budget(cfg, diff)? else {
+        return Ok(true);
+    };
+    if cfg.budget_action.eq_ignore_ascii_case("warn") {
+        eprintln!("{} {}", "warning:".yellow().bold(), warning.message());
+        return Ok(true);
+    }
+    Ok(ui::confirm(
+        &format!("{} {}", warning.message(), t("continue_anyway")),
+        false,
+    ))
+}
+
+/// [`provider::call_llm_with_fallback`] once [`confirm_budget`] allows it, so every request of a
+/// run (first try, regenerate, refine and the disallowed-type retry) respects the monthly
+/// budget. `None` means the user stopped at the budget prompt.
+fn call_llm_within_budget(
+    cfg: &config::AppConfig,
+    system_prompt: &str,
+    diff: &str,
+) -> Result<Option<(String, Option<String>)>> {
+    if !confirm_budget(cfg, diff)? {
+        return Ok(None);
+    }
+    provider::call_llm_with_fallback(cfg, system_prompt, diff)
+        .context("LLM API call failed")
+        .map(Some)
+}
+
+fn run_config_action(action: &cli::ConfigAction) -> Result<()> {
+    match action {
+        cli::ConfigAction::Get { key, scope, reveal } => {
+            let suffix = config::resolve_setting(key)?;
+            let value = match scope.scope() {
+                Some(scope) => config::settings_in(scope)?.remove(suffix),
+                None => config::AppConfig::load()?.setting_value(suffix),
+            };
+            match value {
+                Some(value) if suffix == "API_KEY" && !reveal => {
+                    println!("{}", config::mask_key(&value))
+                }
+                Some(value) => println!("{value}"),
+                None => anyhow::bail!("{suffix} is not set"),
+            }
+        }
+        cli::ConfigAction::Set { key, value, scope } => {
+            let suffix = config::resolve_setting(key)?;
+            let scope = scope.scope().unwrap_or(config::ConfigScope::Global);
+            config::save_setting(suffix, value, scope)?;
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf(
+                    "config_value_set",
+                    &[("key", suffix), ("file", &scope_label(scope))]
+                )
+            );
+        }
+        cli::ConfigAction::Unset { key, scope } => {
+            let suffix = config::resolve_setting(key)?;
+            let scope = scope.scope().unwrap_or(config::ConfigScope::Global);
+            config::remove_setting(suffix, scope)?;
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf(
+                    "config_value_unset",
+                    &[("key", suffix), ("file", &scope_label(scope))]
+                )
+            );
+        }
+        cli::ConfigAction::Validate => {
+            let issues = validate::validate_all();
+            for issue in &issues {
+                let label = if issue.warning {
+                    t("warning").yellow().bold()
+                } else {
+                    t("error").red().bold()
+                };
+                let location = if issue.key.is_empty() {
+                    issue.source.clone()
+                } else {
+                    format!("{}: {}", issue.source, issue.key)
+                };
+                println!("{label} {}: {}", location.cyan(), issue.message);
+            }
+            let errors = issues.iter().filter(|i| !i.warning).count();
+            if errors > 0 {
+                anyhow::bail!(tf("config_invalid", &[("count", &errors.to_string())]));
+            }
+            println!("{} {}", t("done").green().bold(), t("config_valid"));
+        }
+        cli::ConfigAction::Export {
+            output,
+            presets,
+            keys,
+        } => {
+            let cfg = config::AppConfig::load()?;
+            let presets = if *presets {
+                Some(preset::load_presets()?.presets)
+            } else {
+                None
+            };
+            let content = bundle::to_toml(&bundle::export(&cfg, presets.as_deref(), *keys)?)?;
+            match output {
+                // Plain keys must not be readable by other users
+                Some(path) if *keys => credentials::write_private(path, &content)?,
+                Some(path) => std::fs::write(path, &content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{content}"),
+            }
+            if let Some(path) = output {
+                println!(
+                    "{} {}",
+                    t("done").green().bold(),
+                    tf("config_exported", &[("file", &path.display().to_string())])
+                );
+            }
+        }
+        cli::ConfigAction::Import {
+            path,
+            keep,
+            overwrite,
+        } => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let bundle = bundle::parse(&content)?;
+            let interactive = std::io::stdin().is_terminal();
+            let summary = bundle::import(&bundle, |conflict| {
+                if *keep || *overwrite {
+                    return Ok(*overwrite);
+                }
+                if !interactive {
+                    anyhow::bail!(tf("config_import_conflict", &[("key", &conflict.label)]));
+                }
+                Ok(ui::confirm(
+                    &tf(
+                        "config_import_replace",
+                        &[
+                            ("key", &conflict.label),
+                            ("current", &conflict.current),
+                            ("imported", &conflict.imported),
+                        ],
+                    ),
+                    false,
+                ))
+            })?;
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf(
+                    "config_imported",
+                    &[
+                        ("settings", &summary.settings.to_string()),
+                        ("presets", &summary.presets.to_string()),
+                        ("kept", &summary.kept.to_string()),
+                    ]
+                )
+            );
+        }
+        cli::ConfigAction::List { scope } => {
+            let values = match scope.scope() {
+                Some(scope) => config::settings_in(scope)?,
+                None => {
+                    let cfg = config::AppConfig::load()?;
+                    config::ENV_FIELD_MAP
+                        .iter()
+                        .filter_map(|(suffix, _)| {
+                            Some((suffix.to_string(), cfg.setting_value(suffix)?))
+                        })
+                        .collect()
+                }
+            };
+            for (suffix, value) in values {
+                let value = if suffix == "API_KEY" && !value.is_empty() {
+                    "********".to_string()
+                } else {
+                    value
+                };
+                println!("ACR_{suffix}={value}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scope_label(scope: config::ConfigScope) -> String {
+    match scope {
+        config::ConfigScope::Global => config::global_config_path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        config::ConfigScope::Env => ".env".to_string(),
+        scope => scope.repo_file().unwrap_or_default().to_string(),
+    }
+}
+
 fn run_config_command() -> Result<()> {
     match git::find_repo_root() {
         Ok(_) => {
-            let choices = vec!["Local (.env in repo)", "Global (TOML config)"];
-            let answer = Select::new("Configure global or local settings?", choices).prompt();
+            let choices = vec![
+                t("config_scope_repo"),
+                t("config_scope_local"),
+                t("config_scope_global"),
+            ];
+            let answer = Select::new(&t("config_scope_question"), choices).prompt();
             match answer {
                 Ok(choice) => {
-                    let global = choice.contains("Global");
-                    cli::interactive_config(global)?;
+                    let target = if choice == t("config_scope_repo") {
+                        config::ConfigScope::Repo
+                    } else if choice == t("config_scope_local") {
+                        config::ConfigScope::Env
+                    } else {
+                        config::ConfigScope::Global
+                    };
+                    cli::interactive_config(target)?;
                 }
                 Err(_) => {
-                    println!("{}", "Cancelled.".dimmed());
+                    println!("{}", t("cancelled").dimmed());
                 }
             }
         }
         Err(_) => {
-            cli::interactive_config(true)?;
+            cli::interactive_config(config::ConfigScope::Global)?;
         }
     }
     Ok(())
 }
 
-fn run_update_command() -> Result<()> {
-    println!("{}", "Checking for updates...".cyan().bold());
+fn run_update_command(skip: bool, snooze: Option<u64>) -> Result<()> {
+    if let Some(days) = snooze {
+        update::snooze(days)?;
+        println!(
+            "{} {}",
+            t("done").green().bold(),
+            tf("update_snoozed", &[("days", &days.to_string())])
+        );
+        return Ok(());
+    }
+
+    println!("{}", t("checking_updates").cyan().bold());
+
+    match registry::refresh() {
+        Ok(registry) => println!(
+            "{}",
+            tf(
+                "registry_refreshed",
+                &[("count", &registry.providers.len().to_string())]
+            )
+            .dimmed()
+        ),
+        Err(e) => eprintln!(
+            "{} {}",
+            "warning:".yellow().bold(),
+            tf("registry_refresh_failed", &[("error", &e.to_string())])
+        ),
+    }
 
-    match update::check_version() {
+    // The update command runs without loading the config, but the channel is global-only
+    let channel = config::AppConfig::load()
+        .map(|c| update::UpdateChannel::parse(&c.update_channel))
+        .unwrap_or_default();
+    match update::check_version(channel) {
+        Ok(v) if v.update_available && skip => {
+            update::skip_version(&v.latest)?;
+            println!(
+                "{} {}",
+                t("done").green().bold(),
+                tf("update_skipped", &[("version", &v.latest)])
+            );
+        }
         Ok(v) if v.update_available => {
             println!(
-                "{} {} → {}",
-                "New version available!".green().bold(),
+                "{} {} {} {}",
+                t("new_version").green().bold(),
                 v.current.dimmed(),
+                ui::glyphs().arrow,
                 v.latest.green(),
             );
-            update::run_update()?;
+            update::run_update(channel)?;
         }
         Ok(v) => {
             println!(
-                "{} You are already on the latest version ({}).",
-                "Up to date!".green().bold(),
-                v.current,
+                "{} {}",
+                t("up_to_date").green().bold(),
+                tf("already_latest", &[("version", &v.current)]),
             );
         }
         Err(e) => {
-            anyhow::bail!("Failed to check for updates: {}", e);
+            anyhow::bail!(tf("update_check_failed", &[("error", &e.to_string())]));
         }
     }
     Ok(())
 }
 
-fn run_undo(cfg: &config::AppConfig) -> Result<()> {
+fn run_undo(cfg: &config::AppConfig, mode: git::ResetMode, count: usize) -> Result<()> {
     git::ensure_head_exists()?;
+    ensure_no_operation_in_progress()?;
 
-    if git::head_is_merge_commit()? {
-        let proceed_merge = ui::confirm(
-            "Latest commit is a merge commit. Undo it with git reset --soft HEAD~1?",
-            false,
-        );
-        if !proceed_merge {
-            println!("{}", "Undo cancelled.".dimmed());
-            return Ok(());
-        }
+    let commits = git::last_commits(count)?;
+    if commits.len() < count {
+        anyhow::bail!(tf(
+            "undo_not_enough",
+            &[
+                ("count", &count.to_string()),
+                ("available", &commits.len().to_string()),
+            ],
+        ));
     }
-
-    if !git::has_upstream_branch()? {
+    let single = count == 1;
+    let has_upstream = git::has_upstream_branch()?;
+    let mut any_merge = false;
+    let mut any_pushed = false;
+    if !single {
         println!(
             "{}",
-            "No upstream branch detected. Assuming latest commit is not pushed."
-                .yellow()
-                .bold()
-        );
-    } else if git::is_head_pushed()? {
-        let proceed_pushed = ui::confirm(
-            "Latest commit appears to be pushed already. Undo locally anyway?",
-            false,
+            tf("undo_list_header", &[("count", &count.to_string())])
         );
-        if !proceed_pushed {
-            println!("{}", "Undo cancelled.".dimmed());
+    }
+    for (hash, message) in &commits {
+        let merge = git::commit_is_merge(hash)?;
+        let pushed = has_upstream && git::commit_is_pushed(hash)?;
+        any_merge |= merge;
+        any_pushed |= pushed;
+        if !single {
+            let mut line = format!(
+                "  {} {}",
+                &hash[..7.min(hash.len())],
+                message.lines().next().unwrap_or("")
+            );
+            if merge {
+                line = format!("{line} {}", t("undo_tag_merge").yellow());
+            }
+            if pushed {
+                line = format!("{line} {}", t("undo_tag_pushed").yellow());
+            }
+            println!("{line}");
+        }
+    }
+
+    if any_merge {
+        let prompt = if single {
+            t("undo_merge_confirm")
+        } else {
+            t("undo_merges_confirm")
+        };
+        if !ui::confirm(&prompt, false) {
+            println!("{}", t("undo_cancelled").dimmed());
             return Ok(());
         }
     }
 
-    git::undo_last_commit_soft(cfg.suppress_tool_output).context("Failed to undo latest commit")?;
-    println!("{}", "Latest commit undone (soft reset).".green().bold());
+    if !has_upstream {
+        println!("{}", t("undo_no_upstream").yellow().bold());
+    }
+    let confirmed = if any_pushed {
+        let prompt = if single {
+            t("undo_pushed_confirm")
+        } else {
+            t("undo_some_pushed_confirm")
+        };
+        ui::confirm(&prompt, false)
+    } else {
+        single
+            || ui::confirm(
+                &tf("undo_count_confirm", &[("count", &count.to_string())]),
+                true,
+            )
+    };
+    if !confirmed {
+        println!("{}", t("undo_cancelled").dimmed());
+        return Ok(());
+    }
+
+    if mode == git::ResetMode::Hard && !ui::confirm(&t("undo_discard_confirm"), false) {
+        println!("{}", t("undo_cancelled").dimmed());
+        return Ok(());
+    }
+
+    let journal_entry = undo::redoable(mode).then(|| undo_entry(&commits)).flatten();
+    // No auto-stash: a discard was confirmed to throw the other uncommitted changes away too
+    git::undo_last_commits(mode, count, cfg.suppress_tool_output)
+        .context("Failed to undo latest commit")?;
+    // The journal only enables `cgen redo`; failing to write it never fails the undo
+    if let (Some(mut entry), Ok(target)) = (journal_entry, cache::get_head_hash()) {
+        entry.mixed = mode == git::ResetMode::Mixed;
+        // A mixed undo left the index at the new HEAD; redo needs it left that way
+        let tree = if entry.mixed {
+            git::index_tree().ok()
+        } else {
+            Some(entry.tree.clone())
+        };
+        if let Some(tree) = tree {
+            entry.tree = tree;
+            entry.target = target;
+            let _ = undo::record(entry);
+        }
+    }
+    let done = match mode {
+        git::ResetMode::Soft => "undo_done",
+        git::ResetMode::Mixed => "undo_done_mixed",
+        git::ResetMode::Hard => "undo_done_discard",
+    };
+    let done = if single {
+        t(done)
+    } else {
+        tf(&format!("{done}_count"), &[("count", &count.to_string())])
+    };
+    println!("{}", done.green().bold());
+    Ok(())
+}
+
+/// With `ACR_AUTO_STASH`, stash uncommitted changes around `op` and restore them once it
+/// succeeds. After a failure the stash is left alone, since the repo may be mid-rebase.
+fn with_auto_stash<T>(cfg: &config::AppConfig, op: impl FnOnce() -> Result<T>) -> Result<T> {
+    if !cfg.auto_stash || !git::has_uncommitted_changes()? {
+        return op();
+    }
+    git::stash_push("cgen auto-stash", cfg.suppress_tool_output)
+        .context("Failed to stash uncommitted changes")?;
+    println!("{}", t("auto_stash_saved").dimmed());
+
+    let result = op();
+    if result.is_err() {
+        eprintln!("{} {}", t("warning").yellow().bold(), t("auto_stash_kept"));
+        return result;
+    }
+    match git::stash_pop(cfg.suppress_tool_output) {
+        Ok(()) => println!("{}", t("auto_stash_restored").dimmed()),
+        Err(_) => eprintln!(
+            "{} {}",
+            t("warning").yellow().bold(),
+            t("auto_stash_conflict")
+        ),
+    }
+    result
+}
+
+/// Journal entry for undoing `commits` (newest first), filled in before the reset. Redoing
+/// restores the newest one; the messages are kept, oldest first, in case git drops it.
+fn undo_entry(commits: &[(String, String)]) -> Option<undo::UndoEntry> {
+    let (head, _) = commits.first()?;
+    let message = commits
+        .iter()
+        .rev()
+        .map(|(_, m)| m.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    Some(undo::UndoEntry {
+        repo_path: git::find_repo_root().ok()?,
+        tree: git::commit_tree(head).ok()?,
+        undone: head.clone(),
+        target: String::new(),
+        mixed: false,
+        message,
+        timestamp: auto_commit_rs::timestamp::iso8601(auto_commit_rs::timestamp::now_unix()),
+    })
+}
+
+/// Reverse the last `cgen undo` in this repo: move HEAD back to the undone commit, or commit
+/// its message again if git no longer has it. Requires HEAD and the index to be unchanged.
+fn run_redo(cfg: &config::AppConfig) -> Result<()> {
+    let repo_root = git::find_repo_root()?;
+    ensure_no_operation_in_progress()?;
+    let Some(entry) = undo::last_for(&repo_root)? else {
+        anyhow::bail!(t("redo_nothing"));
+    };
+    let head = cache::get_head_hash().unwrap_or_default();
+    match undo::check_redo(&entry, &head, &git::index_tree()?) {
+        Some(undo::RedoBlocker::HeadMoved) => anyhow::bail!(t("redo_head_moved")),
+        Some(undo::RedoBlocker::IndexChanged) if entry.mixed => {
+            anyhow::bail!(t("redo_index_changed_mixed"))
+        }
+        Some(undo::RedoBlocker::IndexChanged) => anyhow::bail!(t("redo_index_changed")),
+        None => {}
+    }
+
+    let subject = entry.message.lines().next().unwrap_or("");
+    if !ui::confirm(&tf("redo_confirm", &[("subject", subject)]), true) {
+        println!("{}", t("redo_cancelled").dimmed());
+        return Ok(());
+    }
+    if git::commit_exists(&entry.undone) {
+        git::reset_to(
+            &entry.undone,
+            undo::redo_mode(&entry),
+            cfg.suppress_tool_output,
+        )
+        .context("Failed to restore the undone commit")?;
+    } else if entry.mixed {
+        // Its changes are unstaged, so there is nothing to commit the message with
+        anyhow::bail!(t("redo_commit_gone"));
+    } else {
+        git::run_commit(&entry.message, &[], cfg.suppress_tool_output)
+            .context("git commit failed")?;
+    }
+    undo::remove_last_for(&repo_root)?;
+    println!("{}", t("redo_done").green().bold());
     Ok(())
 }
@@ -0,0 +1,348 @@
+use crate::config::AppConfig;
+use regex_lite::Regex;
+
+/// Column at which commit message bodies are hard-wrapped
+pub const BODY_WRAP_WIDTH: usize = 72;
+
+/// Apply the configured formatting rules to a generated commit message:
+/// subject length/period/case rules, a single blank line between subject, body and footers,
+/// and a body hard-wrapped at 72 columns.
+pub fn apply(cfg: &AppConfig, message: &str) -> String {
+    if !cfg.post_process {
+        return message.to_string();
+    }
+
+    let message = message.trim();
+    let (subject, body) = match message.split_once('\n') {
+        Some((subject, body)) => (subject, body),
+        None => (message, ""),
+    };
+
+    let subject = format_subject(
+        subject,
+        effective_subject_max_length(cfg),
+        effective_lowercase_subject(cfg),
+    );
+    let body = wrap_body(body, BODY_WRAP_WIDTH);
+    if body.is_empty() {
+        subject
+    } else {
+        format!("{subject}\n\n{body}")
+    }
+}
+
+/// Subject length limit after applying the repository's commitlint rules (0 = no limit)
+pub fn effective_subject_max_length(cfg: &AppConfig) -> usize {
+    let commitlint = cfg.commitlint.as_ref().and_then(|r| r.header_max_length);
+    match (cfg.subject_max_length, commitlint) {
+        (0, Some(n)) => n,
+        (own, Some(n)) => own.min(n),
+        (own, None) => own,
+    }
+}
+
+/// Whether the subject description must start lowercase, from config or commitlint
+pub fn effective_lowercase_subject(cfg: &AppConfig) -> bool {
+    cfg.lowercase_subject || cfg.commitlint.as_ref().is_some_and(|r| r.lowercase_subject)
+}
+
+/// Allowed commit types: the configured list, else the commitlint `type-enum`
+pub fn effective_allowed_types(cfg: &AppConfig) -> Vec<String> {
+    if !cfg.allowed_types.is_empty() {
+        return cfg.allowed_types.clone();
+    }
+    cfg.commitlint
+        .as_ref()
+        .map(|r| r.type_enum.clone())
+        .unwrap_or_default()
+}
+
+/// Allowed scopes: the configured list, else the commitlint `scope-enum`
+pub fn effective_allowed_scopes(cfg: &AppConfig) -> Vec<String> {
+    if !cfg.allowed_scopes.is_empty() {
+        return cfg.allowed_scopes.clone();
+    }
+    cfg.commitlint
+        .as_ref()
+        .map(|r| r.scope_enum.clone())
+        .unwrap_or_default()
+}
+
+/// Rewrite the header so its type and scopes come from the allowed lists. Unknown values are
+/// mapped to the closest allowed one (aliases, case, typos); unknown scopes with no close
+/// match are dropped. A type with no close match is left for the caller to reject.
+pub fn enforce_allowed(cfg: &AppConfig, message: &str) -> String {
+    let types = effective_allowed_types(cfg);
+    let scopes = effective_allowed_scopes(cfg);
+    if types.is_empty() && scopes.is_empty() {
+        return message.to_string();
+    }
+
+    let (header, rest) = match message.split_once('\n') {
+        Some((header, rest)) => (header, Some(rest)),
+        None => (message, None),
+    };
+    let Some(parsed) = crate::lint::parse_header(header) else {
+        return message.to_string();
+    };
+
+    let commit_type = if types.is_empty() {
+        parsed.commit_type.clone()
+    } else {
+        closest_allowed(&parsed.commit_type, &types).unwrap_or(parsed.commit_type.clone())
+    };
+
+    let scope = match parsed.scope.as_deref() {
+        Some(scope) if !scopes.is_empty() => {
+            let kept: Vec<String> = scope
+                .split(',')
+                .filter_map(|s| closest_allowed(s.trim(), &scopes))
+                .fold(Vec::new(), |mut acc, s| {
+                    if !acc.contains(&s) {
+                        acc.push(s);
+                    }
+                    acc
+                });
+            (!kept.is_empty()).then(|| kept.join(","))
+        }
+        other => other.map(str::to_string),
+    };
+
+    let mut rebuilt = format!("{}{}", parsed.prefix, commit_type);
+    if let Some(scope) = scope {
+        rebuilt.push_str(&format!("({scope})"));
+    }
+    if parsed.breaking {
+        rebuilt.push('!');
+    }
+    rebuilt.push_str(": ");
+    rebuilt.push_str(&parsed.description);
+    match rest {
+        Some(rest) => format!("{rebuilt}\n{rest}"),
+        None => rebuilt,
+    }
+}
+
+/// Find the allowed value `value` most plausibly meant: exact or case-insensitive match,
+/// a common alias, a prefix of at least three characters, or a typo within two edits
+pub(crate) fn closest_allowed(value: &str, allowed: &[String]) -> Option<String> {
+    let lower = value.to_lowercase();
+    if lower.is_empty() {
+        return None;
+    }
+    if let Some(hit) = allowed.iter().find(|a| a.to_lowercase() == lower) {
+        return Some(hit.clone());
+    }
+
+    let alias = match lower.as_str() {
+        "feature" | "features" | "add" | "new" => "feat",
+        "bug" | "bugfix" | "hotfix" | "fixes" | "patch" => "fix",
+        "doc" | "documentation" | "readme" => "docs",
+        "tests" | "testing" | "spec" => "test",
+        "refactoring" | "cleanup" => "refactor",
+        "performance" | "optimize" | "optimization" => "perf",
+        "styles" | "format" | "formatting" | "lint" => "style",
+        "deps" | "dependencies" | "release" | "misc" => "chore",
+        "pipeline" | "workflow" => "ci",
+        _ => "",
+    };
+    if let Some(hit) = allowed
+        .iter()
+        .find(|a| !alias.is_empty() && a.as_str() == alias)
+    {
+        return Some(hit.clone());
+    }
+
+    if lower.len() >= 3 {
+        if let Some(hit) = allowed.iter().find(|a| {
+            let a = a.to_lowercase();
+            a.starts_with(&lower) || (a.len() >= 3 && lower.starts_with(&a))
+        }) {
+            return Some(hit.clone());
+        }
+    }
+
+    allowed
+        .iter()
+        .map(|a| (edit_distance(&lower, &a.to_lowercase()), a))
+        .filter(|(d, a)| *d <= 2 && *d < a.len())
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, a)| a.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Strip a trailing period, optionally lowercase the first word of the description and
+/// shorten the subject to `max_len` characters at a word boundary (`0` disables the limit)
+pub fn format_subject(subject: &str, max_len: usize, lowercase: bool) -> String {
+    let mut subject = subject.trim().to_string();
+
+    if subject.ends_with('.') && !subject.ends_with("..") {
+        subject.pop();
+    }
+
+    if lowercase {
+        let start = description_start(&subject);
+        subject = format!(
+            "{}{}",
+            &subject[..start],
+            lowercase_first_word(&subject[start..])
+        );
+    }
+
+    if max_len > 0 && subject.chars().count() > max_len {
+        subject = shorten_at_word_boundary(&subject, max_len);
+    }
+
+    subject
+}
+
+/// Byte offset where the description starts, skipping an optional gitmoji and the
+/// conventional `type(scope)!: ` header
+fn description_start(subject: &str) -> usize {
+    let re = Regex::new(r"^(?:\S+\s+)?[A-Za-z]+(?:\([^)]*\))?!?:\s+").unwrap();
+    re.find(subject).map(|m| m.end()).unwrap_or(0)
+}
+
+/// Lowercase the first letter of the first word unless the word looks like an acronym
+/// or identifier (e.g. `API`, `README`, `HashMap`)
+fn lowercase_first_word(text: &str) -> String {
+    let word = text.split_whitespace().next().unwrap_or("");
+    let mut chars = word.chars();
+    let Some(first) = chars.next() else {
+        return text.to_string();
+    };
+    if !first.is_uppercase() || chars.any(|c| c.is_uppercase()) {
+        return text.to_string();
+    }
+    let mut out = first.to_lowercase().collect::<String>();
+    out.push_str(&text[first.len_utf8()..]);
+    out
+}
+
+fn shorten_at_word_boundary(text: &str, max_len: usize) -> String {
+    let cut: String = text.chars().take(max_len + 1).collect();
+    let shortened = match cut.rfind(' ') {
+        Some(idx) if idx > 0 => &cut[..idx],
+        _ => {
+            &cut[..cut
+                .char_indices()
+                .nth(max_len)
+                .map(|(i, _)| i)
+                .unwrap_or(cut.len())]
+        }
+    };
+    shortened
+        .trim_end_matches(|c: char| c.is_whitespace() || ",;:-".contains(c))
+        .to_string()
+}
+
+/// Reflow body paragraphs and list items to `width` columns. Code blocks, indented lines
+/// and trailer-style footers (`Token: value`, `BREAKING CHANGE: ...`) are kept verbatim.
+/// Runs of blank lines collapse into one.
+pub fn wrap_body(body: &str, width: usize) -> String {
+    let list_re = Regex::new(r"^(\s*)([-*+]|\d+[.)])\s+(.*)$").unwrap();
+    let footer_re =
+        Regex::new(r"^(?:BREAKING CHANGE|BREAKING-CHANGE|[A-Za-z][A-Za-z0-9-]*)(?:: | #)\S")
+            .unwrap();
+
+    let mut out: Vec<String> = Vec::new();
+    // (first line prefix, continuation prefix, accumulated text)
+    let mut pending: Option<(String, String, String)> = None;
+    let mut in_fence = false;
+
+    let flush = |pending: &mut Option<(String, String, String)>, out: &mut Vec<String>| {
+        if let Some((first, cont, text)) = pending.take() {
+            out.extend(wrap_words(&text, width, &first, &cont));
+        }
+    };
+
+    for line in body.lines() {
+        let line = line.trim_end();
+        if in_fence {
+            out.push(line.to_string());
+            if line.trim_start().starts_with("```") {
+                in_fence = false;
+            }
+            continue;
+        }
+        if line.trim_start().starts_with("```") {
+            flush(&mut pending, &mut out);
+            in_fence = true;
+            out.push(line.to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            flush(&mut pending, &mut out);
+            if out.last().is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+        if let Some(caps) = list_re.captures(line) {
+            flush(&mut pending, &mut out);
+            let indent = &caps[1];
+            let marker = &caps[2];
+            pending = Some((
+                format!("{indent}{marker} "),
+                format!("{indent}{}", " ".repeat(marker.chars().count() + 1)),
+                caps[3].to_string(),
+            ));
+            continue;
+        }
+        if footer_re.is_match(line) || line.starts_with("    ") || line.starts_with('\t') {
+            flush(&mut pending, &mut out);
+            out.push(line.to_string());
+            continue;
+        }
+        match pending.as_mut() {
+            Some((_, _, text)) => {
+                text.push(' ');
+                text.push_str(line.trim());
+            }
+            None => pending = Some((String::new(), String::new(), line.trim().to_string())),
+        }
+    }
+    flush(&mut pending, &mut out);
+
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// Greedy word wrap. Words longer than the width are kept whole on their own line.
+pub fn wrap_words(text: &str, width: usize, first_prefix: &str, cont_prefix: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = first_prefix.to_string();
+    let mut has_word = false;
+    for word in text.split_whitespace() {
+        let len = current.chars().count();
+        if has_word && len + 1 + word.chars().count() > width {
+            lines.push(current);
+            current = cont_prefix.to_string();
+            has_word = false;
+        }
+        if has_word {
+            current.push(' ');
+        }
+        current.push_str(word);
+        has_word = true;
+    }
+    if has_word {
+        lines.push(current);
+    }
+    lines
+}
@@ -1,8 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use inquire::{Select, Text};
+use inquire::{MultiSelect, Select, Text};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::config::AppConfig;
 use crate::ui;
@@ -14,12 +16,59 @@ pub struct LlmPresetFields {
     pub api_key: String,
     pub api_url: String,
     pub api_headers: String,
+    #[serde(flatten)]
+    pub overrides: PresetOverrides,
+}
+
+/// Request settings a preset can override; unset ones keep the provider defaults and
+/// `ACR_REQUEST_TIMEOUT_SECS`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PresetOverrides {
+    /// Sampling temperature (cgen sends 0 otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Output token limit, instead of the one derived from the model's limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+    /// Request timeout in seconds (0 = none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<usize>,
+    /// Dot path to the message in the response JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_path: Option<String>,
+}
+
+impl PresetOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Short description of the set overrides, e.g. `temperature 0.2, timeout 300s`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(t) = self.temperature {
+            parts.push(format!("temperature {t}"));
+        }
+        if let Some(n) = self.max_tokens {
+            parts.push(format!("max_tokens {n}"));
+        }
+        if let Some(secs) = self.timeout_secs {
+            parts.push(format!("timeout {secs}s"));
+        }
+        if let Some(path) = &self.response_path {
+            parts.push(format!("response_path {path}"));
+        }
+        parts.join(", ")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Preset {
     pub id: u32,
     pub name: String,
+    /// Labels such as `work` or `cheap` for filtering the pickers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     #[serde(flatten)]
     pub fields: LlmPresetFields,
 }
@@ -32,6 +81,13 @@ pub struct FallbackConfig {
     pub order: Vec<u32>,
 }
 
+/// Presets `ACR_ROUTING` picks from. An id listed more than once gets picked more often.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub presets: Vec<u32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PresetsFile {
     #[serde(default)]
@@ -40,8 +96,13 @@ pub struct PresetsFile {
     pub presets: Vec<Preset>,
     #[serde(default)]
     pub fallback: FallbackConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
 }
 
+/// Credential name prefix for preset API keys, followed by the preset id
+const PRESET_CREDENTIAL_PREFIX: &str = "preset-";
+
 fn presets_file_path() -> Option<PathBuf> {
     crate::config::global_config_path().map(|p| {
         p.parent()
@@ -60,8 +121,14 @@ pub fn load_presets() -> Result<PresetsFile> {
     }
     let content = std::fs::read_to_string(&path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
-    let file: PresetsFile =
+    let mut file: PresetsFile =
         toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    for preset in &mut file.presets {
+        preset.fields.api_key =
+            crate::credentials::resolve(&preset.fields.api_key).with_context(|| {
+                format!("Failed to resolve the API key of preset '{}'", preset.name)
+            })?;
+    }
     Ok(file)
 }
 
@@ -71,7 +138,23 @@ pub fn save_presets(file: &PresetsFile) -> Result<()> {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create {}", parent.display()))?;
     }
-    let content = toml::to_string_pretty(file).context("Failed to serialize presets")?;
+    let mut file = file.clone();
+    for preset in &mut file.presets {
+        let name = format!("{PRESET_CREDENTIAL_PREFIX}{}", preset.id);
+        preset.fields.api_key = crate::credentials::store(&name, &preset.fields.api_key)?;
+    }
+    let in_use: Vec<String> = file
+        .presets
+        .iter()
+        .filter_map(|p| {
+            p.fields
+                .api_key
+                .strip_prefix(crate::credentials::REFERENCE_PREFIX)
+        })
+        .map(str::to_string)
+        .collect();
+    crate::credentials::prune(PRESET_CREDENTIAL_PREFIX, &in_use)?;
+    let content = toml::to_string_pretty(&file).context("Failed to serialize presets")?;
     // Write to temp file then rename for atomicity
     let tmp_path = path.with_extension("toml.tmp");
     std::fs::write(&tmp_path, &content)
@@ -81,6 +164,14 @@ pub fn save_presets(file: &PresetsFile) -> Result<()> {
     Ok(())
 }
 
+/// Rewrite presets.toml so keys still stored inline move to the credentials file
+pub fn reseal_presets() -> Result<()> {
+    if !presets_file_path().is_some_and(|p| p.exists()) {
+        return Ok(());
+    }
+    save_presets(&load_presets()?)
+}
+
 pub fn fields_from_config(cfg: &AppConfig) -> LlmPresetFields {
     LlmPresetFields {
         provider: cfg.provider.clone(),
@@ -88,6 +179,7 @@ pub fn fields_from_config(cfg: &AppConfig) -> LlmPresetFields {
         api_key: cfg.api_key.clone(),
         api_url: cfg.api_url.clone(),
         api_headers: cfg.api_headers.clone(),
+        overrides: cfg.preset_overrides.clone(),
     }
 }
 
@@ -97,6 +189,15 @@ pub fn apply_preset_to_config(cfg: &mut AppConfig, preset: &Preset) {
     cfg.api_key = preset.fields.api_key.clone();
     cfg.api_url = preset.fields.api_url.clone();
     cfg.api_headers = preset.fields.api_headers.clone();
+    cfg.preset_overrides = preset.fields.overrides.clone();
+}
+
+/// The preset called `key`, or else the one whose id is `key`
+pub fn find_preset<'a>(file: &'a PresetsFile, key: &str) -> Option<&'a Preset> {
+    file.presets.iter().find(|p| p.name == key).or_else(|| {
+        let id: u32 = key.parse().ok()?;
+        file.presets.iter().find(|p| p.id == id)
+    })
 }
 
 /// Dedup key: (provider, model, api_key, api_url) — headers excluded
@@ -121,13 +222,19 @@ pub fn create_preset(file: &mut PresetsFile, name: Option<String>, fields: LlmPr
     let id = file.next_id;
     file.next_id += 1;
     let name = name.unwrap_or_else(|| format!("{}/{}", fields.provider, fields.model));
-    file.presets.push(Preset { id, name, fields });
+    file.presets.push(Preset {
+        id,
+        name,
+        tags: Vec::new(),
+        fields,
+    });
     id
 }
 
 pub fn delete_preset(file: &mut PresetsFile, id: u32) {
     file.presets.retain(|p| p.id != id);
     file.fallback.order.retain(|&fid| fid != id);
+    file.routing.presets.retain(|&rid| rid != id);
 }
 
 pub fn rename_preset(file: &mut PresetsFile, id: u32, new_name: String) {
@@ -136,6 +243,53 @@ pub fn rename_preset(file: &mut PresetsFile, id: u32, new_name: String) {
     }
 }
 
+/// Replace the tags of preset `id`
+pub fn set_tags(file: &mut PresetsFile, id: u32, tags: Vec<String>) {
+    if let Some(p) = file.presets.iter_mut().find(|p| p.id == id) {
+        p.tags = tags;
+    }
+}
+
+/// Make `ids` the routing list, keeping the order and repeats of entries that stay
+pub fn set_routing_presets(file: &mut PresetsFile, ids: &[u32]) {
+    file.routing.presets.retain(|id| ids.contains(id));
+    for id in ids {
+        if !file.routing.presets.contains(id) {
+            file.routing.presets.push(*id);
+        }
+    }
+}
+
+/// Comma-separated tags, trimmed and lowercased, without empty or repeated ones
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(|t| t.trim().to_lowercase()) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Every tag used by a preset, sorted
+pub fn all_tags(file: &PresetsFile) -> Vec<String> {
+    let mut tags: Vec<String> = file.presets.iter().flat_map(|p| p.tags.clone()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Presets carrying `tag`, or all of them when `tag` is `None`
+pub fn with_tag<'a>(
+    presets: impl IntoIterator<Item = &'a Preset>,
+    tag: Option<&str>,
+) -> Vec<&'a Preset> {
+    presets
+        .into_iter()
+        .filter(|p| tag.is_none_or(|tag| p.tags.iter().any(|t| t == tag)))
+        .collect()
+}
+
 pub fn duplicate_preset(file: &mut PresetsFile, id: u32) -> Result<u32> {
     let preset = file
         .presets
@@ -145,6 +299,7 @@ pub fn duplicate_preset(file: &mut PresetsFile, id: u32) -> Result<u32> {
         .clone();
     let new_name = format!("{} (copy)", preset.name);
     let new_id = create_preset(file, Some(new_name), preset.fields);
+    set_tags(file, new_id, preset.tags);
     Ok(new_id)
 }
 
@@ -154,6 +309,7 @@ pub fn export_presets(file: &PresetsFile, ids: &[u32], include_keys: bool) -> Re
         next_id: 0,
         presets: Vec::new(),
         fallback: FallbackConfig::default(),
+        routing: RoutingConfig::default(),
     };
     for &id in ids {
         if let Some(p) = file.presets.iter().find(|p| p.id == id) {
@@ -177,25 +333,169 @@ pub fn import_presets(file: &mut PresetsFile, data: &str) -> Result<usize> {
         if find_duplicate(file, &p.fields).is_some() {
             continue;
         }
-        create_preset(file, Some(p.name), p.fields);
+        let id = create_preset(file, Some(p.name), p.fields);
+        set_tags(file, id, p.tags);
         count += 1;
     }
     Ok(count)
 }
 
+/// Read presets to import from a file or an `https://` URL
+pub fn read_import_source(source: &str) -> Result<String> {
+    let lower = source.to_ascii_lowercase();
+    if lower.starts_with("http://") {
+        bail!("Refusing to download presets over plain http; use an https URL");
+    }
+    if lower.starts_with("https://") {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .build();
+        return agent
+            .get(source)
+            .set("User-Agent", "cgen")
+            .call()
+            .with_context(|| format!("Failed to download {source}"))?
+            .into_string()
+            .with_context(|| format!("Failed to read the response from {source}"));
+    }
+    std::fs::read_to_string(source).with_context(|| format!("Failed to read {source}"))
+}
+
+/// Read pasted TOML from stdin until an empty line
+fn read_pasted_toml() -> String {
+    println!("Paste TOML data (end with an empty line):");
+    let mut data = String::new();
+    loop {
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).is_err() {
+            break;
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        data.push_str(&line);
+    }
+    data
+}
+
 fn preset_display(p: &Preset) -> String {
     let key_status = if p.fields.api_key.is_empty() {
         "no key"
     } else {
         "key set"
     };
-    format!(
-        "{} ({}/{}, {})",
-        p.name, p.fields.provider, p.fields.model, key_status
-    )
+    let mut details = format!("{}/{}, {key_status}", p.fields.provider, p.fields.model);
+    if !p.fields.overrides.is_empty() {
+        details.push_str(&format!(", {}", p.fields.overrides.summary()));
+    }
+    let tags = if p.tags.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", p.tags.join(", "))
+    };
+    format!("{} ({details}){tags}", p.name)
+}
+
+/// One-line health status: `OK (812 ms)` or the first line of the error
+pub fn health_status(check: &crate::provider::HealthCheck) -> String {
+    const MAX_ERROR_CHARS: usize = 100;
+    match &check.error {
+        None => format!("{} ({} ms)", "OK".green().bold(), check.latency.as_millis()),
+        Some(error) => {
+            let line = error.lines().next().unwrap_or_default();
+            let mut line: String = line.chars().take(MAX_ERROR_CHARS).collect();
+            if error.chars().count() > line.chars().count() {
+                line.push_str("...");
+            }
+            format!("{} {line}", "failed:".red().bold())
+        }
+    }
+}
+
+/// Test each preset in turn, printing its status as it finishes
+pub fn test_presets(
+    cfg: &AppConfig,
+    presets: &[&Preset],
+) -> HashMap<u32, crate::provider::HealthCheck> {
+    let mut results = HashMap::new();
+    for preset in presets {
+        let check = crate::provider::check_preset(cfg, preset);
+        println!(
+            "  [{}] {}  {}",
+            preset.id,
+            preset.name,
+            health_status(&check)
+        );
+        results.insert(preset.id, check);
+    }
+    results
+}
+
+/// Ask which tag to narrow a picker to, when any preset has tags. `None` when cancelled;
+/// `Some(None)` shows every preset.
+fn prompt_tag_filter(file: &PresetsFile) -> Option<Option<String>> {
+    let tags = all_tags(file);
+    if tags.is_empty() {
+        return Some(None);
+    }
+    let all = "(all)".to_string();
+    let mut choices = vec![all.clone()];
+    choices.extend(tags);
+    let choice = Select::new("Filter by tag:", choices)
+        .with_scorer(&ui::fuzzy_score)
+        .prompt()
+        .ok()?;
+    Some((choice != all).then_some(choice))
+}
+
+/// Pick one of `presets` after an optional tag filter, returning its id
+fn pick_preset(file: &PresetsFile, presets: &[&Preset], prompt: &str) -> Option<u32> {
+    let tag = prompt_tag_filter(file)?;
+    let shown = with_tag(presets.iter().copied(), tag.as_deref());
+    if shown.is_empty() {
+        println!("  {}", "No presets with that tag.".dimmed());
+        return None;
+    }
+    let options: Vec<String> = shown.iter().map(|p| preset_display(p)).collect();
+    let choice = Select::new(prompt, options.clone())
+        .with_scorer(&ui::fuzzy_score)
+        .prompt()
+        .ok()?;
+    let idx = options.iter().position(|o| o == &choice).unwrap();
+    Some(shown[idx].id)
+}
+
+/// Ask for each override, keeping the current value as the default; blank clears it
+fn prompt_overrides(current: &PresetOverrides) -> Option<PresetOverrides> {
+    fn ask<T: std::str::FromStr + ToString>(label: &str, current: Option<T>) -> Option<Option<T>> {
+        let default = current.map(|v| v.to_string()).unwrap_or_default();
+        loop {
+            let input = Text::new(label)
+                .with_default(&default)
+                .with_help_message("blank for default")
+                .prompt()
+                .ok()?;
+            let input = input.trim();
+            if input.is_empty() {
+                return Some(None);
+            }
+            match input.parse() {
+                Ok(value) => return Some(Some(value)),
+                Err(_) => println!("  {} Not a valid value: {input}", "error:".red().bold()),
+            }
+        }
+    }
+    Some(PresetOverrides {
+        temperature: ask("Temperature:", current.temperature)?,
+        max_tokens: ask("Max tokens:", current.max_tokens)?,
+        timeout_secs: ask("Timeout in seconds (0 = none):", current.timeout_secs)?,
+        response_path: ask("Response path:", current.response_path.clone())?,
+    })
 }
 
 pub fn interactive_presets() -> Result<()> {
+    // Health check results, shown next to each preset until the menu is left
+    let mut health = HashMap::new();
     loop {
         let mut file = load_presets()?;
 
@@ -204,13 +504,28 @@ pub fn interactive_presets() -> Result<()> {
         } else {
             println!("\n{}", "Presets:".cyan().bold());
             for p in &file.presets {
-                println!("  [{}] {}", p.id, preset_display(p));
+                match health.get(&p.id) {
+                    Some(check) => println!(
+                        "  [{}] {}  {}",
+                        p.id,
+                        preset_display(p),
+                        health_status(check)
+                    ),
+                    None => println!("  [{}] {}", p.id, preset_display(p)),
+                }
+            }
+            let routing = crate::routing::candidates(&file);
+            if !routing.is_empty() {
+                let names: Vec<&str> = routing.iter().map(|p| p.name.as_str()).collect();
+                println!("  {}", format!("Routing: {}", names.join(", ")).dimmed());
             }
         }
 
         let mut choices = vec!["Create new preset"];
         if !file.presets.is_empty() {
             choices.push("Manage existing preset...");
+            choices.push("Test presets");
+            choices.push("Routing presets...");
             choices.push("Export presets");
         }
         choices.push("Import presets");
@@ -232,7 +547,7 @@ pub fn interactive_presets() -> Result<()> {
                     .with_default(if default_model.is_empty() {
                         ""
                     } else {
-                        default_model
+                        &default_model
                     })
                     .prompt()
                     .unwrap_or_default();
@@ -247,6 +562,9 @@ pub fn interactive_presets() -> Result<()> {
                     .prompt()
                     .unwrap_or_default();
                 let name = if name.is_empty() { None } else { Some(name) };
+                let tags = Text::new("Tags (comma-separated, e.g. work,cheap):")
+                    .prompt()
+                    .unwrap_or_default();
 
                 let fields = LlmPresetFields {
                     provider,
@@ -254,6 +572,7 @@ pub fn interactive_presets() -> Result<()> {
                     api_key,
                     api_url,
                     api_headers,
+                    overrides: PresetOverrides::default(),
                 };
                 if let Some(dup_id) = find_duplicate(&file, &fields) {
                     println!(
@@ -264,18 +583,29 @@ pub fn interactive_presets() -> Result<()> {
                     continue;
                 }
                 let id = create_preset(&mut file, name, fields);
+                set_tags(&mut file, id, parse_tags(&tags));
                 save_presets(&file)?;
                 println!("  {} Created preset [{}]", "done!".green().bold(), id);
             }
             "Manage existing preset..." => {
-                let options: Vec<String> = file.presets.iter().map(preset_display).collect();
-                let Ok(choice) = Select::new("Select preset:", options.clone()).prompt() else {
+                let presets: Vec<&Preset> = file.presets.iter().collect();
+                let Some(selected_id) = pick_preset(&file, &presets, "Select preset:") else {
                     continue;
                 };
-                let idx = options.iter().position(|o| o == &choice).unwrap();
-                let selected_id = file.presets[idx].id;
-
-                let manage_choices = vec!["Rename", "Duplicate", "Delete", "Back"];
+                let idx = file
+                    .presets
+                    .iter()
+                    .position(|p| p.id == selected_id)
+                    .unwrap();
+
+                let manage_choices = vec![
+                    "Rename",
+                    "Edit tags",
+                    "Generation overrides...",
+                    "Duplicate",
+                    "Delete",
+                    "Back",
+                ];
                 let Ok(manage_action) = Select::new("Action:", manage_choices).prompt() else {
                     continue;
                 };
@@ -288,6 +618,24 @@ pub fn interactive_presets() -> Result<()> {
                             println!("  {}", "Renamed.".green().bold());
                         }
                     }
+                    "Edit tags" => {
+                        if let Ok(input) = Text::new("Tags (comma-separated):")
+                            .with_default(&file.presets[idx].tags.join(","))
+                            .prompt()
+                        {
+                            set_tags(&mut file, selected_id, parse_tags(&input));
+                            save_presets(&file)?;
+                            println!("  {}", "Saved.".green().bold());
+                        }
+                    }
+                    "Generation overrides..." => {
+                        let preset = &mut file.presets[idx];
+                        if let Some(overrides) = prompt_overrides(&preset.fields.overrides) {
+                            preset.fields.overrides = overrides;
+                            save_presets(&file)?;
+                            println!("  {}", "Saved.".green().bold());
+                        }
+                    }
                     "Duplicate" => {
                         let new_id = duplicate_preset(&mut file, selected_id)?;
                         save_presets(&file)?;
@@ -304,36 +652,82 @@ pub fn interactive_presets() -> Result<()> {
                     _ => {}
                 }
             }
+            "Test presets" => {
+                // A broken repository config should not keep presets from being tested
+                let cfg = AppConfig::load().unwrap_or_default();
+                let presets: Vec<&Preset> = match prompt_tag_filter(&file) {
+                    Some(tag) => with_tag(&file.presets, tag.as_deref()),
+                    None => continue,
+                };
+                health.extend(test_presets(&cfg, &presets));
+            }
+            "Routing presets..." => {
+                let options: Vec<String> = file.presets.iter().map(preset_display).collect();
+                let selected: Vec<usize> = file
+                    .presets
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| file.routing.presets.contains(&p.id))
+                    .map(|(i, _)| i)
+                    .collect();
+                let Ok(chosen) =
+                    MultiSelect::new("Presets ACR_ROUTING picks from:", options.clone())
+                        .with_default(&selected)
+                        .with_scorer(&ui::fuzzy_score)
+                        .with_help_message("Set ACR_ROUTING to round-robin or random to use them")
+                        .prompt()
+                else {
+                    continue;
+                };
+                let ids: Vec<u32> = chosen
+                    .iter()
+                    .filter_map(|c| options.iter().position(|o| o == c))
+                    .map(|i| file.presets[i].id)
+                    .collect();
+                set_routing_presets(&mut file, &ids);
+                save_presets(&file)?;
+                println!("  {}", "Saved.".green().bold());
+            }
             "Export presets" => {
                 let include_keys = ui::confirm("Include API keys in export?", false);
                 let ids: Vec<u32> = file.presets.iter().map(|p| p.id).collect();
-                match export_presets(&file, &ids, include_keys) {
-                    Ok(data) => {
+                let Ok(path) = Text::new("Write to file (blank to print):").prompt() else {
+                    continue;
+                };
+                let path = path.trim();
+                let written = export_presets(&file, &ids, include_keys).and_then(|data| {
+                    if path.is_empty() {
                         println!("\n{}", "Exported TOML:".cyan().bold());
-                        println!("{data}");
+                        return ui::page(&format!("{data}\n"));
+                    }
+                    if include_keys {
+                        // Plain keys must not be readable by other users
+                        crate::credentials::write_private(Path::new(path), &data)?;
+                    } else {
+                        std::fs::write(path, data)
+                            .with_context(|| format!("Failed to write {path}"))?;
                     }
-                    Err(e) => println!("  {} {}", "error:".red().bold(), e),
+                    println!("  {} Exported to {path}", "done!".green().bold());
+                    Ok(())
+                });
+                if let Err(e) = written {
+                    println!("  {} {:#}", "error:".red().bold(), e);
                 }
             }
             "Import presets" => {
-                println!("Paste TOML data (end with an empty line):");
-                let mut data = String::new();
-                loop {
-                    let mut line = String::new();
-                    if std::io::stdin().read_line(&mut line).is_err() {
-                        break;
-                    }
-                    if line.trim().is_empty() {
-                        break;
-                    }
-                    data.push_str(&line);
-                }
-                match import_presets(&mut file, &data) {
+                let Ok(source) = Text::new("File or https URL (blank to paste):").prompt() else {
+                    continue;
+                };
+                let data = match source.trim() {
+                    "" => Ok(read_pasted_toml()),
+                    source => read_import_source(source),
+                };
+                match data.and_then(|data| import_presets(&mut file, &data)) {
                     Ok(count) => {
                         save_presets(&file)?;
                         println!("  {} Imported {} preset(s)", "done!".green().bold(), count);
                     }
-                    Err(e) => println!("  {} {}", "error:".red().bold(), e),
+                    Err(e) => println!("  {} {:#}", "error:".red().bold(), e),
                 }
             }
             _ => break,
@@ -386,10 +780,7 @@ pub fn interactive_fallback_order() -> Result<()> {
                     println!("  {}", "No presets available to add.".dimmed());
                     continue;
                 }
-                let options: Vec<String> = available.iter().map(|p| preset_display(p)).collect();
-                if let Ok(choice) = Select::new("Select preset to add:", options.clone()).prompt() {
-                    let idx = options.iter().position(|o| o == &choice).unwrap();
-                    let id = available[idx].id;
+                if let Some(id) = pick_preset(&file, &available, "Select preset to add:") {
                     file.fallback.order.push(id);
                     save_presets(&file)?;
                     println!("  {}", "Added.".green().bold());
@@ -413,7 +804,9 @@ pub fn interactive_fallback_order() -> Result<()> {
                         format!("[{}] {}", id, name)
                     })
                     .collect();
-                if let Ok(choice) = Select::new("Select entry to remove:", options.clone()).prompt()
+                if let Ok(choice) = Select::new("Select entry to remove:", options.clone())
+                    .with_scorer(&ui::fuzzy_score)
+                    .prompt()
                 {
                     let idx = options.iter().position(|o| o == &choice).unwrap();
                     file.fallback.order.remove(idx);
@@ -440,7 +833,10 @@ pub fn interactive_fallback_order() -> Result<()> {
                         format!("{}. [{}] {}", i + 1, id, name)
                     })
                     .collect();
-                if let Ok(choice) = Select::new("Move up:", options.clone()).prompt() {
+                if let Ok(choice) = Select::new("Move up:", options.clone())
+                    .with_scorer(&ui::fuzzy_score)
+                    .prompt()
+                {
                     let idx = options.iter().position(|o| o == &choice).unwrap();
                     if idx > 0 {
                         file.fallback.order.swap(idx, idx - 1);
@@ -468,7 +864,10 @@ pub fn interactive_fallback_order() -> Result<()> {
                         format!("{}. [{}] {}", i + 1, id, name)
                     })
                     .collect();
-                if let Ok(choice) = Select::new("Move down:", options.clone()).prompt() {
+                if let Ok(choice) = Select::new("Move down:", options.clone())
+                    .with_scorer(&ui::fuzzy_score)
+                    .prompt()
+                {
                     let idx = options.iter().position(|o| o == &choice).unwrap();
                     if idx < file.fallback.order.len() - 1 {
                         file.fallback.order.swap(idx, idx + 1);
@@ -498,11 +897,10 @@ pub fn select_and_load_preset(cfg: &mut AppConfig) -> Result<Option<(u32, LlmPre
         println!("  {}", "No presets found.".dimmed());
         return Ok(None);
     }
-    let options: Vec<String> = file.presets.iter().map(preset_display).collect();
-    match Select::new("Select preset to load:", options.clone()).prompt() {
-        Ok(choice) => {
-            let idx = options.iter().position(|o| o == &choice).unwrap();
-            let preset = &file.presets[idx];
+    let presets: Vec<&Preset> = file.presets.iter().collect();
+    match pick_preset(&file, &presets, "Select preset to load:") {
+        Some(id) => {
+            let preset = file.presets.iter().find(|p| p.id == id).unwrap();
             let snapshot = preset.fields.clone();
             apply_preset_to_config(cfg, preset);
             println!(
@@ -512,7 +910,7 @@ pub fn select_and_load_preset(cfg: &mut AppConfig) -> Result<Option<(u32, LlmPre
             );
             Ok(Some((preset.id, snapshot)))
         }
-        Err(_) => Ok(None),
+        None => Ok(None),
     }
 }
 
@@ -573,6 +971,7 @@ mod tests {
             api_key: "test-key".into(),
             api_url: String::new(),
             api_headers: String::new(),
+            overrides: PresetOverrides::default(),
         }
     }
 
@@ -705,12 +1104,14 @@ mod tests {
         let preset = Preset {
             id: 0,
             name: "test".into(),
+            tags: Vec::new(),
             fields: LlmPresetFields {
                 provider: "openai".into(),
                 model: "gpt-4o".into(),
                 api_key: "sk-test".into(),
                 api_url: String::new(),
                 api_headers: String::new(),
+                overrides: PresetOverrides::default(),
             },
         };
         apply_preset_to_config(&mut cfg, &preset);
@@ -719,6 +1120,94 @@ mod tests {
         assert_eq!(cfg.api_key, "sk-test");
     }
 
+    #[test]
+    fn test_apply_preset_overrides_replaces_previous_ones() {
+        let mut cfg = AppConfig::default();
+        let mut slow = Preset {
+            id: 0,
+            name: "slow".into(),
+            tags: Vec::new(),
+            fields: sample_fields(),
+        };
+        slow.fields.overrides.timeout_secs = Some(600);
+        apply_preset_to_config(&mut cfg, &slow);
+        assert_eq!(cfg.preset_overrides.timeout_secs, Some(600));
+        assert_eq!(fields_from_config(&cfg), slow.fields);
+
+        let plain = Preset {
+            id: 1,
+            name: "plain".into(),
+            tags: Vec::new(),
+            fields: sample_fields(),
+        };
+        apply_preset_to_config(&mut cfg, &plain);
+        assert!(cfg.preset_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_tags_parse_and_filter() {
+        assert_eq!(parse_tags(" Work, cheap,,work "), vec!["work", "cheap"]);
+
+        let mut file = PresetsFile::default();
+        let work = create_preset(&mut file, Some("Corp".into()), sample_fields());
+        set_tags(&mut file, work, parse_tags("work,cheap"));
+        let local = create_preset(&mut file, Some("Ollama".into()), sample_fields());
+        set_tags(&mut file, local, parse_tags("local,cheap"));
+        create_preset(&mut file, Some("Untagged".into()), sample_fields());
+
+        assert_eq!(all_tags(&file), vec!["cheap", "local", "work"]);
+        let names = |tag| -> Vec<&str> {
+            with_tag(&file.presets, tag)
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect()
+        };
+        assert_eq!(names(Some("cheap")), vec!["Corp", "Ollama"]);
+        assert_eq!(names(Some("local")), vec!["Ollama"]);
+        assert_eq!(names(None).len(), 3);
+        assert!(preset_display(&file.presets[0]).ends_with(" [work, cheap]"));
+
+        let copy = duplicate_preset(&mut file, work).unwrap();
+        assert_eq!(
+            file.presets.iter().find(|p| p.id == copy).unwrap().tags,
+            ["work", "cheap"]
+        );
+    }
+
+    #[test]
+    fn test_health_status_is_one_line() {
+        use crate::provider::HealthCheck;
+        use std::time::Duration;
+
+        let ok = HealthCheck {
+            latency: Duration::from_millis(812),
+            error: None,
+        };
+        assert!(health_status(&ok).ends_with("(812 ms)"));
+
+        let failed = HealthCheck {
+            latency: Duration::from_secs(1),
+            error: Some(format!("API returned HTTP 401: {}\nmore", "x".repeat(200))),
+        };
+        let status = health_status(&failed);
+        assert!(status.contains("HTTP 401"));
+        assert!(status.ends_with("..."));
+        assert!(!status.contains('\n'));
+    }
+
+    #[test]
+    fn test_preset_display_shows_overrides() {
+        let mut preset = Preset {
+            id: 1,
+            name: "Local".into(),
+            tags: Vec::new(),
+            fields: sample_fields(),
+        };
+        preset.fields.overrides.temperature = Some(0.2);
+        preset.fields.overrides.timeout_secs = Some(300);
+        assert!(preset_display(&preset).ends_with(", temperature 0.2, timeout 300s)"));
+    }
+
     #[test]
     fn test_preset_is_modified() {
         let cfg = AppConfig::default();
@@ -749,12 +1238,14 @@ mod tests {
         let preset = Preset {
             id: 1,
             name: "My Preset".into(),
+            tags: Vec::new(),
             fields: LlmPresetFields {
                 provider: "groq".into(),
                 model: "llama".into(),
                 api_key: "sk-test".into(),
                 api_url: String::new(),
                 api_headers: String::new(),
+                overrides: PresetOverrides::default(),
             },
         };
         let display = preset_display(&preset);
@@ -769,12 +1260,14 @@ mod tests {
         let preset = Preset {
             id: 1,
             name: "Empty Key".into(),
+            tags: Vec::new(),
             fields: LlmPresetFields {
                 provider: "openai".into(),
                 model: "gpt-4".into(),
                 api_key: String::new(),
                 api_url: String::new(),
                 api_headers: String::new(),
+                overrides: PresetOverrides::default(),
             },
         };
         let display = preset_display(&preset);
@@ -789,6 +1282,7 @@ mod tests {
             api_key: "key123".into(),
             api_url: "https://api.example.com".into(),
             api_headers: "X-Custom: value".into(),
+            overrides: PresetOverrides::default(),
         };
         let key = dedup_key(&fields);
         assert_eq!(key.0, "groq");
@@ -832,6 +1326,7 @@ mod tests {
             api_key: "sk-ant".into(),
             api_url: "https://api.anthropic.com".into(),
             api_headers: "x-api-key: test".into(),
+            overrides: PresetOverrides::default(),
         };
         let id = create_preset(&mut file, Some("Original".into()), fields);
         let dup_id = duplicate_preset(&mut file, id).unwrap();
@@ -885,6 +1380,22 @@ mod tests {
         assert_eq!(file.presets.len(), 2);
     }
 
+    #[test]
+    fn test_routing_list_keeps_order_and_repeats() {
+        let mut file = PresetsFile::default();
+        for _ in 0..3 {
+            create_preset(&mut file, None, sample_fields());
+        }
+        file.routing.presets = vec![1, 0, 1];
+        set_routing_presets(&mut file, &[0, 1, 2]);
+        assert_eq!(file.routing.presets, vec![1, 0, 1, 2]);
+        set_routing_presets(&mut file, &[2, 1]);
+        assert_eq!(file.routing.presets, vec![1, 1, 2]);
+
+        delete_preset(&mut file, 1);
+        assert_eq!(file.routing.presets, vec![2]);
+    }
+
     #[test]
     fn test_delete_preset_nonexistent() {
         let mut file = PresetsFile::default();
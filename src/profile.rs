@@ -0,0 +1,199 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use inquire::{Select, Text};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::{toml_to_field_string, AppConfig, ENV_FIELD_MAP};
+use crate::ui;
+
+/// Settings a profile may override: prompt and message-format fields only, never credentials
+pub const PROFILE_FIELDS: &[&str] = &[
+    "LLM_SYSTEM_PROMPT",
+    "LOCALE",
+    "ONE_LINER",
+    "MESSAGE_STYLE",
+    "USE_GITMOJI",
+    "GITMOJI_FORMAT",
+    "GITMOJI_DETERMINISTIC",
+    "COMMIT_TEMPLATE",
+    "TYPE_TEMPLATES",
+    "POST_PROCESS",
+    "SUBJECT_MAX_LENGTH",
+    "LOWERCASE_SUBJECT",
+    "ALLOWED_TYPES",
+    "ALLOWED_SCOPES",
+    "SKIP_CI",
+    "SKIP_CI_TOKEN",
+];
+
+/// Contents of `profiles.toml`: profile name → field overrides, keyed like the global config
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProfilesFile {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, toml::Table>,
+}
+
+fn profiles_file_path() -> Option<PathBuf> {
+    crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("profiles.toml")))
+}
+
+pub fn load_profiles() -> Result<ProfilesFile> {
+    let Some(path) = profiles_file_path() else {
+        return Ok(ProfilesFile::default());
+    };
+    if !path.exists() {
+        return Ok(ProfilesFile::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_profiles(file: &ProfilesFile) -> Result<()> {
+    let path = profiles_file_path().context("Could not determine profiles file path")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string_pretty(file).context("Failed to serialize profiles")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Apply a profile's overrides on top of the config, validating each value like `cgen config`
+pub fn apply_profile(cfg: &mut AppConfig, settings: &toml::Table) -> Result<()> {
+    for (key, value) in settings {
+        let suffix = ENV_FIELD_MAP
+            .iter()
+            .find(|(_, field)| field == key)
+            .map(|(suffix, _)| *suffix)
+            .filter(|suffix| PROFILE_FIELDS.contains(suffix))
+            .with_context(|| format!("`{key}` cannot be set by a prompt profile"))?;
+        cfg.set_field(suffix, &toml_to_field_string(value))
+            .with_context(|| format!("Invalid value for `{key}` in profile"))?;
+    }
+    Ok(())
+}
+
+/// Look up a profile by name and apply it
+pub fn apply_named(cfg: &mut AppConfig, name: &str) -> Result<()> {
+    let file = load_profiles()?;
+    let Some(settings) = file.profiles.get(name) else {
+        let available = file.profiles.keys().cloned().collect::<Vec<_>>();
+        if available.is_empty() {
+            bail!("Unknown profile '{name}': no profiles defined. Create one with `cgen profile`");
+        }
+        bail!(
+            "Unknown profile '{name}'. Available: {}",
+            available.join(", ")
+        );
+    };
+    apply_profile(cfg, settings)
+}
+
+/// Snapshot the profile-able settings of the current config
+pub fn profile_from_config(cfg: &AppConfig) -> Result<toml::Table> {
+    let toml::Value::Table(all) =
+        toml::Value::try_from(cfg).context("Failed to serialize config")?
+    else {
+        bail!("Failed to serialize config");
+    };
+    Ok(ENV_FIELD_MAP
+        .iter()
+        .filter(|(suffix, _)| PROFILE_FIELDS.contains(suffix))
+        .filter_map(|(_, field)| Some((field.to_string(), all.get(*field)?.clone())))
+        .collect())
+}
+
+fn profile_display(name: &str, settings: &toml::Table, active: &str) -> String {
+    let keys = settings.keys().cloned().collect::<Vec<_>>().join(", ");
+    let marker = if name == active { " (default)" } else { "" };
+    format!("{name}{marker}: {keys}")
+}
+
+pub fn interactive_profiles() -> Result<()> {
+    loop {
+        let mut file = load_profiles()?;
+        let cfg = AppConfig::load()?;
+
+        if file.profiles.is_empty() {
+            println!("\n{}", "No prompt profiles found.".dimmed());
+        } else {
+            println!("\n{}", "Prompt profiles:".cyan().bold());
+            for (name, settings) in &file.profiles {
+                println!("  {}", profile_display(name, settings, &cfg.profile));
+            }
+        }
+
+        let mut choices = vec!["Create from current settings"];
+        if !file.profiles.is_empty() {
+            choices.push("Set default profile");
+            choices.push("Delete profile");
+        }
+        if !cfg.profile.is_empty() {
+            choices.push("Clear default profile");
+        }
+        choices.push("Back");
+
+        let Ok(action) = Select::new("Prompt profiles:", choices).prompt() else {
+            break;
+        };
+        let names: Vec<String> = file.profiles.keys().cloned().collect();
+
+        match action {
+            "Create from current settings" => {
+                let Ok(name) = Text::new("Profile name:").prompt() else {
+                    continue;
+                };
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    continue;
+                }
+                if file.profiles.contains_key(&name)
+                    && !ui::confirm(&format!("Overwrite profile '{name}'?"), false)
+                {
+                    continue;
+                }
+                file.profiles
+                    .insert(name.clone(), profile_from_config(&cfg)?);
+                save_profiles(&file)?;
+                println!(
+                    "  {} Saved profile '{}'. Edit profiles.toml to keep only the settings it should override.",
+                    "done!".green().bold(),
+                    name
+                );
+            }
+            "Set default profile" => {
+                if let Ok(name) = Select::new("Default profile:", names)
+                    .with_scorer(&ui::fuzzy_score)
+                    .prompt()
+                {
+                    crate::config::save_global_value("profile", Some(toml::Value::String(name)))?;
+                    println!("  {}", "Default profile set.".green().bold());
+                }
+            }
+            "Delete profile" => {
+                if let Ok(name) = Select::new("Delete profile:", names)
+                    .with_scorer(&ui::fuzzy_score)
+                    .prompt()
+                {
+                    if ui::confirm(&format!("Delete profile '{name}'?"), false) {
+                        file.profiles.remove(&name);
+                        save_profiles(&file)?;
+                        if cfg.profile == name {
+                            crate::config::save_global_value("profile", None)?;
+                        }
+                        println!("  {}", "Deleted.".green().bold());
+                    }
+                }
+            }
+            "Clear default profile" => {
+                crate::config::save_global_value("profile", None)?;
+                println!("  {}", "Default profile cleared.".green().bold());
+            }
+            _ => break,
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::config::AppConfig;
+use crate::postprocess::{self, wrap_words, BODY_WRAP_WIDTH};
 
 const CONVENTIONAL_COMMIT_SPEC: &str = "\
 Write all commit messages strictly following the Conventional Commits specification.
@@ -30,15 +31,56 @@ prepend a relevant emoji in :shortcode: format, then a space, then the conventio
 Examples: :zap: feat(api): improve response time, :bug: fix(auth): correct login redirect, \
 :sparkles: feat: add new feature, :recycle: refactor(parser): simplify logic, :memo: docs: update README, :art: style(ui): improve layout";
 
+const DETAILED_STYLE_SPEC: &str = "\
+Write a detailed commit message made of a subject, a body and optional footers. \
+Answer using exactly these three labelled sections, each label on its own line:
+SUBJECT:
+<a single line: type[optional scope]: description>
+BODY:
+- <one bullet per notable change, explaining what changed and why>
+FOOTERS:
+<zero or more footer lines such as `BREAKING CHANGE: <description>` or `Refs: #123`, or leave empty>
+Do NOT wrap lines yourself and do NOT add any text outside these sections.";
+
+/// A section of the system prompt and where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptBlock {
+    pub source: String,
+    pub text: String,
+}
+
 /// Build the full system prompt from config flags
 pub fn build_system_prompt(cfg: &AppConfig) -> String {
-    let mut parts = Vec::new();
+    build_prompt_blocks(cfg)
+        .into_iter()
+        .map(|b| b.text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Build the system prompt as blocks, in order, each labelled with its origin
+pub fn build_prompt_blocks(cfg: &AppConfig) -> Vec<PromptBlock> {
+    let mut blocks = Vec::new();
+    let mut push = |source: &str, text: String| {
+        blocks.push(PromptBlock {
+            source: source.to_string(),
+            text,
+        });
+    };
 
     // Base prompt (user-overridable)
-    parts.push(cfg.llm_system_prompt.clone());
+    let base_source = if cfg.llm_system_prompt == AppConfig::default().llm_system_prompt {
+        "built-in base prompt"
+    } else {
+        "ACR_LLM_SYSTEM_PROMPT"
+    };
+    push(base_source, cfg.llm_system_prompt.clone());
 
     // Conventional commits
-    parts.push(CONVENTIONAL_COMMIT_SPEC.to_string());
+    push(
+        "built-in conventional commits",
+        CONVENTIONAL_COMMIT_SPEC.to_string(),
+    );
 
     // Gitmoji
     if cfg.use_gitmoji {
@@ -46,29 +88,269 @@ pub fn build_system_prompt(cfg: &AppConfig) -> String {
             "shortcode" => GITMOJI_SHORTCODE_SPEC,
             _ => GITMOJI_UNICODE_SPEC,
         };
-        parts.push(spec.to_string());
+        push("built-in gitmoji", spec.to_string());
+        if let Some(text) = cfg
+            .gitmoji_set
+            .as_ref()
+            .and_then(|set| crate::gitmoji::custom_set_instructions(set, &cfg.gitmoji_format))
+        {
+            push("gitmoji.toml", text);
+        }
+    }
+
+    // Allowed types/scopes and repository commitlint rules
+    if let Some(text) = commit_rules_instructions(cfg) {
+        push("allowed types/scopes and commitlint", text);
     }
 
-    // One-liner
-    if cfg.one_liner {
-        parts.push("Craft a concise, single sentence, commit message that encapsulates all changes made, with an emphasis on the primary updates. If the modifications share a common theme or scope, mention it succinctly; otherwise, leave the scope out to maintain focus. The goal is to provide a clear and unified overview of the changes in one single message. Output ONLY a single-line commit message in the format: type[optional scope]: description. Do NOT include a body or footer. The entire commit message must fit on one line.".to_string());
+    // Message style: detailed overrides the one-liner instruction
+    let detailed = cfg.message_style == "detailed";
+    if detailed {
+        push("ACR_MESSAGE_STYLE", DETAILED_STYLE_SPEC.to_string());
+    } else if cfg.one_liner {
+        push("ACR_ONE_LINER", "Craft a concise, single sentence, commit message that encapsulates all changes made, with an emphasis on the primary updates. If the modifications share a common theme or scope, mention it succinctly; otherwise, leave the scope out to maintain focus. The goal is to provide a clear and unified overview of the changes in one single message. Output ONLY a single-line commit message in the format: type[optional scope]: description. Do NOT include a body or footer. The entire commit message must fit on one line.".to_string());
     }
 
     // Locale
     if cfg.locale != "en" {
-        parts.push(format!(
-            "Write the commit message in the '{}' locale.",
-            cfg.locale
-        ));
+        push(
+            "ACR_LOCALE",
+            format!("Write the commit message in the '{}' locale.", cfg.locale),
+        );
+    }
+
+    // Prompt snippets: global, then repository, then --prompt-file
+    for snippet in &cfg.prompt_snippets {
+        push(&snippet.source, snippet.text.clone());
     }
 
     // Universal closing instructions
-    parts.push(
+    let closing = if detailed {
+        "Use present tense. Be concise. Output only the SUBJECT, BODY and FOOTERS sections, nothing else."
+    } else {
         "Use present tense. Be concise. Output only the raw commit message, nothing else."
-            .to_string(),
-    );
+    };
+    push("built-in closing", closing.to_string());
+
+    blocks
+}
+
+/// Describe the allowed types/scopes and the repository's commitlint rules so generated
+/// messages pass its CI checks
+fn commit_rules_instructions(cfg: &AppConfig) -> Option<String> {
+    let types = postprocess::effective_allowed_types(cfg);
+    let scopes = postprocess::effective_allowed_scopes(cfg);
+    let commitlint = cfg.commitlint.clone().unwrap_or_default();
+
+    let mut lines = Vec::new();
+    if !types.is_empty() {
+        lines.push(format!("- The type MUST be one of: {}.", types.join(", ")));
+    }
+    if !scopes.is_empty() {
+        lines.push(format!(
+            "- The scope, if present, MUST be one of: {}. Never invent other scopes.",
+            scopes.join(", ")
+        ));
+    }
+    if let Some(max) = commitlint.header_max_length {
+        lines.push(format!(
+            "- The first line MUST be at most {max} characters long."
+        ));
+    }
+    if commitlint.lowercase_subject {
+        lines.push("- The description MUST start with a lowercase letter.".to_string());
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "This repository enforces the following commit rules:\n{}",
+        lines.join("\n")
+    ))
+}
+
+/// Stand-in for the diff of an intentionally empty commit
+pub fn empty_commit_input(reason: &str) -> String {
+    format!(
+        "This commit is intentionally empty: it changes no files.\n\
+         Reason given by the author: {}",
+        reason.trim()
+    )
+}
+
+/// Extend the system prompt with the scope derived from the changed paths
+pub fn build_scope_prompt(system_prompt: &str, scope: &str) -> String {
+    format!(
+        "{system_prompt}\n\n\
+The changed files belong to the `{scope}` scope. You MUST use exactly `{scope}` as the commit scope."
+    )
+}
+
+/// The system prompt sent with a diff, naming its scope when one was detected
+pub fn system_prompt_for(cfg: &AppConfig, detected_scope: Option<&str>) -> String {
+    match detected_scope {
+        Some(s) => build_scope_prompt(&build_system_prompt(cfg), s),
+        None => build_system_prompt(cfg),
+    }
+}
 
-    parts.join("\n\n")
+/// Extend the system prompt with the messages the user already rejected so regeneration
+/// produces a different candidate instead of repeating itself
+pub fn build_regenerate_prompt(system_prompt: &str, rejected: &[String]) -> String {
+    if rejected.is_empty() {
+        return system_prompt.to_string();
+    }
+    let listed = rejected
+        .iter()
+        .map(|m| format!("- {}", m.trim().replace('\n', "\n  ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "{system_prompt}\n\n\
+The user rejected the following commit message(s) for this diff:\n{listed}\n\n\
+Produce a different, better commit message. Do not repeat any of the rejected messages."
+    )
+}
+
+/// Extend the system prompt with a user instruction for refining a previously generated message
+pub fn build_refine_prompt(system_prompt: &str, previous: &str, instruction: &str) -> String {
+    format!(
+        "{system_prompt}\n\n\
+The previous commit message you generated for this diff was:\n{}\n\n\
+Revise it according to the following instruction, keeping every other rule above: {}",
+        previous.trim(),
+        instruction.trim()
+    )
+}
+
+/// Clean the raw LLM response, assemble the labelled sections in detailed mode, put in the
+/// detected `scope`, correct disallowed types/scopes, map the gitmoji, then apply the
+/// post-processing rules
+pub fn finalize_commit_message(cfg: &AppConfig, raw: &str, scope: Option<&str>) -> String {
+    let cleaned = clean_commit_message(raw);
+    let message = if cfg.message_style == "detailed" {
+        assemble_detailed_message(&cleaned)
+    } else {
+        cleaned
+    };
+    let message = crate::scope::apply_scope(&message, scope);
+    let message = postprocess::enforce_allowed(cfg, &message);
+    let message = crate::gitmoji::apply(cfg, &message);
+    postprocess::apply(cfg, &message)
+}
+
+/// Template for a message and the setting it comes from: the per-type template matching its
+/// header, else `commit_template`
+pub fn template_for_message<'a>(cfg: &'a AppConfig, message: &str) -> (&'static str, &'a str) {
+    let header = message.trim().lines().next().unwrap_or("");
+    crate::lint::parse_header(header)
+        .and_then(|h| cfg.type_templates.get(&h.commit_type.to_ascii_lowercase()))
+        .map(|template| ("TYPE_TEMPLATES", template.as_str()))
+        .unwrap_or(("COMMIT_TEMPLATE", &cfg.commit_template))
+}
+
+/// Apply the commit template to a generated message and append the CI-skip token when
+/// enabled. Literal `\n` sequences in the template become newlines, and variables such as
+/// `$BRANCH` are filled in. The token goes on its own paragraph so it never counts against the
+/// subject length.
+pub fn apply_commit_template(cfg: &AppConfig, message: &str) -> String {
+    let (setting, template) = template_for_message(cfg, message);
+    let template = template.replace("\\n", "\n");
+    let message =
+        crate::interpolation::interpolate_template(&template, message.trim(), cfg, setting)
+            .trim()
+            .to_string();
+    let token = cfg.skip_ci_token.trim();
+    if !cfg.skip_ci || token.is_empty() || message.contains(token) {
+        return message;
+    }
+    format!("{message}\n\n{token}")
+}
+
+/// Assemble a `SUBJECT:` / `BODY:` / `FOOTERS:` response into a commit message.
+///
+/// Body bullets are wrapped at 72 columns with continuation lines indented under the
+/// bullet text. Responses without a `SUBJECT:` section are returned unchanged.
+pub fn assemble_detailed_message(raw: &str) -> String {
+    #[derive(PartialEq)]
+    enum Section {
+        None,
+        Subject,
+        Body,
+        Footers,
+    }
+
+    let mut section = Section::None;
+    let mut subject = String::new();
+    let mut bullets: Vec<String> = Vec::new();
+    let mut footers: Vec<String> = Vec::new();
+    let mut saw_subject = false;
+
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        let (next, rest) = if let Some(rest) = strip_label(trimmed, "SUBJECT:") {
+            saw_subject = true;
+            (Section::Subject, rest)
+        } else if let Some(rest) = strip_label(trimmed, "BODY:") {
+            (Section::Body, rest)
+        } else if let Some(rest) = strip_label(trimmed, "FOOTERS:") {
+            (Section::Footers, rest)
+        } else {
+            (Section::None, trimmed)
+        };
+        if next != Section::None {
+            section = next;
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        match section {
+            Section::Subject if subject.is_empty() => subject = rest.to_string(),
+            Section::Subject | Section::Body => {
+                if let Some(item) = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* ")) {
+                    bullets.push(item.trim().to_string());
+                } else if let Some(last) = bullets.last_mut() {
+                    last.push(' ');
+                    last.push_str(rest);
+                } else {
+                    bullets.push(rest.to_string());
+                }
+            }
+            Section::Footers => {
+                if !rest.eq_ignore_ascii_case("none") {
+                    footers.push(rest.to_string());
+                }
+            }
+            Section::None => {}
+        }
+    }
+
+    if !saw_subject || subject.is_empty() {
+        return raw.to_string();
+    }
+
+    let mut blocks = vec![subject];
+    if !bullets.is_empty() {
+        let body: Vec<String> = bullets
+            .iter()
+            .flat_map(|b| wrap_words(b, BODY_WRAP_WIDTH, "- ", "  "))
+            .collect();
+        blocks.push(body.join("\n"));
+    }
+    if !footers.is_empty() {
+        blocks.push(footers.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+fn strip_label<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+    let head = line.get(..label.len())?;
+    if head.eq_ignore_ascii_case(label) {
+        Some(line[label.len()..].trim())
+    } else {
+        None
+    }
 }
 
 /// Strip common LLM artifacts from the raw response so only the commit message remains.
@@ -1,11 +1,13 @@
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use serde_json::Value;
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 
 use crate::config::AppConfig;
 use crate::interpolation::interpolate;
+use crate::registry::{ModelEntry, RegistryEntry};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum RequestFormat {
@@ -15,108 +17,571 @@ enum RequestFormat {
     LmStudio,
 }
 
+impl RequestFormat {
+    /// Parse the `format` of a registry entry
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "gemini" => Some(Self::Gemini),
+            "openai" => Some(Self::OpenAiCompat),
+            "anthropic" => Some(Self::Anthropic),
+            "lm_studio" => Some(Self::LmStudio),
+            _ => None,
+        }
+    }
+
+    fn default_response_path(self) -> &'static str {
+        match self {
+            Self::Gemini => "candidates.0.content.parts.0.text",
+            Self::OpenAiCompat => "choices.0.message.content",
+            Self::Anthropic => "content.0.text",
+            Self::LmStudio => "output",
+        }
+    }
+}
+
 struct ProviderDef {
-    api_url: &'static str,
-    api_headers: &'static str,
-    default_model: &'static str,
+    api_url: String,
+    api_headers: String,
+    default_model: String,
     format: RequestFormat,
-    response_path: &'static str,
+    response_path: String,
 }
 
-/// Built-in provider definitions
+/// Provider definition: the built-in table, updated by the cached provider registry
 fn get_provider(name: &str) -> Option<ProviderDef> {
+    apply_registry(
+        builtin_provider(name),
+        crate::registry::load().providers.get(name),
+    )
+}
+
+/// Overlay a registry entry onto a built-in definition. Entries for providers that are not
+/// built in need an `api_url`; unknown formats are ignored.
+fn apply_registry(
+    builtin: Option<ProviderDef>,
+    entry: Option<&RegistryEntry>,
+) -> Option<ProviderDef> {
+    let Some(entry) = entry else {
+        return builtin;
+    };
+    let format = entry.format.as_deref().and_then(RequestFormat::parse);
+    let mut def = match builtin {
+        Some(def) => def,
+        None => {
+            let format = format.unwrap_or(RequestFormat::OpenAiCompat);
+            ProviderDef {
+                api_url: entry.api_url.clone().filter(|url| !url.is_empty())?,
+                api_headers: String::new(),
+                default_model: String::new(),
+                format,
+                response_path: format.default_response_path().to_string(),
+            }
+        }
+    };
+    if let Some(format) = format {
+        if format != def.format {
+            def.format = format;
+            def.response_path = format.default_response_path().to_string();
+        }
+    }
+    if let Some(url) = &entry.api_url {
+        def.api_url = url.clone();
+    }
+    if let Some(headers) = &entry.api_headers {
+        def.api_headers = headers.clone();
+    }
+    if let Some(model) = &entry.default_model {
+        def.default_model = model.clone();
+    }
+    if let Some(path) = &entry.response_path {
+        def.response_path = path.clone();
+    }
+    Some(def)
+}
+
+/// Definitions embedded in the binary, used when the registry has no entry for a provider
+fn builtin_provider(name: &str) -> Option<ProviderDef> {
     match name {
         "gemini" => Some(ProviderDef {
-            api_url: "https://generativelanguage.googleapis.com/v1beta/models/$ACR_MODEL:generateContent?key=$ACR_API_KEY",
-            api_headers: "",
-            default_model: "gemini-2.0-flash",
+            api_url: "https://generativelanguage.googleapis.com/v1beta/models/$ACR_MODEL:generateContent?key=$ACR_API_KEY".into(),
+            api_headers: "".into(),
+            default_model: "gemini-2.0-flash".into(),
             format: RequestFormat::Gemini,
-            response_path: "candidates.0.content.parts.0.text",
+            response_path: "candidates.0.content.parts.0.text".into(),
         }),
         "openai" => Some(ProviderDef {
-            api_url: "https://api.openai.com/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "gpt-4o-mini",
+            api_url: "https://api.openai.com/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "gpt-4o-mini".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "anthropic" => Some(ProviderDef {
-            api_url: "https://api.anthropic.com/v1/messages",
-            api_headers: "x-api-key: $ACR_API_KEY, anthropic-version: 2023-06-01",
-            default_model: "claude-sonnet-4-20250514",
+            api_url: "https://api.anthropic.com/v1/messages".into(),
+            api_headers: "x-api-key: $ACR_API_KEY, anthropic-version: 2023-06-01".into(),
+            default_model: "claude-sonnet-4-20250514".into(),
             format: RequestFormat::Anthropic,
-            response_path: "content.0.text",
+            response_path: "content.0.text".into(),
         }),
         "groq" => Some(ProviderDef {
-            api_url: "https://api.groq.com/openai/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "llama-3.3-70b-versatile",
+            api_url: "https://api.groq.com/openai/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "llama-3.3-70b-versatile".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "grok" => Some(ProviderDef {
-            api_url: "https://api.x.ai/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "grok-3",
+            api_url: "https://api.x.ai/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "grok-3".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "deepseek" => Some(ProviderDef {
-            api_url: "https://api.deepseek.com/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "deepseek-chat",
+            api_url: "https://api.deepseek.com/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "deepseek-chat".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "openrouter" => Some(ProviderDef {
-            api_url: "https://openrouter.ai/api/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "openai/gpt-4o-mini",
+            api_url: "https://openrouter.ai/api/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "openai/gpt-4o-mini".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "mistral" => Some(ProviderDef {
-            api_url: "https://api.mistral.ai/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "mistral-small-latest",
+            api_url: "https://api.mistral.ai/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "mistral-small-latest".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "together" => Some(ProviderDef {
-            api_url: "https://api.together.xyz/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "meta-llama/Llama-3.3-70B-Instruct-Turbo",
+            api_url: "https://api.together.xyz/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "meta-llama/Llama-3.3-70B-Instruct-Turbo".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "fireworks" => Some(ProviderDef {
-            api_url: "https://api.fireworks.ai/inference/v1/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "accounts/fireworks/models/llama-v3p3-70b-instruct",
+            api_url: "https://api.fireworks.ai/inference/v1/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "accounts/fireworks/models/llama-v3p3-70b-instruct".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "perplexity" => Some(ProviderDef {
-            api_url: "https://api.perplexity.ai/chat/completions",
-            api_headers: "Authorization: Bearer $ACR_API_KEY",
-            default_model: "sonar",
+            api_url: "https://api.perplexity.ai/chat/completions".into(),
+            api_headers: "Authorization: Bearer $ACR_API_KEY".into(),
+            default_model: "sonar".into(),
             format: RequestFormat::OpenAiCompat,
-            response_path: "choices.0.message.content",
+            response_path: "choices.0.message.content".into(),
         }),
         "lm_studio" => Some(ProviderDef {
-            api_url: "http://localhost:1234/api/v1/chat",
-            api_headers: "Content-Type: application/json",
-            default_model: "qwen/qwen3.5-35b-a3b",
+            api_url: "http://localhost:1234/api/v1/chat".into(),
+            api_headers: "Content-Type: application/json".into(),
+            default_model: "qwen/qwen3.5-35b-a3b".into(),
             format: RequestFormat::LmStudio,
-            response_path: "output",
+            response_path: "output".into(),
         }),
         _ => None,
     }
 }
 
-/// Get the default model for a built-in provider, or empty string for unknown providers.
-pub fn default_model_for(provider: &str) -> &'static str {
-    get_provider(provider).map_or("", |p| p.default_model)
+/// Names of the built-in providers, in the order the config editor lists them
+const BUILTIN_PROVIDERS: &[&str] = &[
+    "gemini",
+    "openai",
+    "anthropic",
+    "groq",
+    "grok",
+    "deepseek",
+    "openrouter",
+    "mistral",
+    "together",
+    "fireworks",
+    "perplexity",
+    "lm_studio",
+];
+
+/// Built-in providers followed by any extra providers from the registry
+pub fn provider_names() -> Vec<String> {
+    let registry = crate::registry::load();
+    let mut names: Vec<String> = BUILTIN_PROVIDERS.iter().map(|n| n.to_string()).collect();
+    for (name, entry) in &registry.providers {
+        if !names.contains(name) && apply_registry(None, Some(entry)).is_some() {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// Whether `name` is a built-in or registry provider (anything else needs ACR_API_URL)
+pub fn is_builtin_provider(name: &str) -> bool {
+    get_provider(name).is_some()
+}
+
+/// Get the default model for a known provider, or empty string for unknown providers.
+pub fn default_model_for(provider: &str) -> String {
+    get_provider(provider).map_or_else(String::new, |p| p.default_model)
+}
+
+/// `max_tokens` sent when the model's limits are unknown
+const DEFAULT_MAX_TOKENS: usize = 512;
+
+/// Upper bound for `max_tokens`; commit messages never need more, and some gateways reserve
+/// credit for the full amount
+const MAX_TOKENS_CAP: usize = 4096;
+
+/// Rough bytes per token for sizing the prompt. Code tokenizes denser than prose, so this errs
+/// on the side of truncating early.
+const BYTES_PER_TOKEN: usize = 3;
+
+/// Output tokens assumed for a commit message when estimating the cost of a run
+const ESTIMATED_OUTPUT_TOKENS: u64 = 200;
+
+/// Tokens reserved for message framing that the byte estimate does not cover
+const PROMPT_OVERHEAD_TOKENS: usize = 64;
+
+/// Upper bound for `max_tokens` on reasoning models, whose hidden reasoning counts toward it
+const REASONING_MAX_TOKENS_CAP: usize = 25_000;
+
+/// How a reasoning model deviates from the regular chat request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reasoning {
+    /// OpenAI o-series and GPT-5: no `temperature`, `developer` instead of `system` role,
+    /// `max_completion_tokens` instead of `max_tokens`, and `reasoning_effort`
+    OpenAi,
+    /// Other reasoning models (DeepSeek R1, QwQ): no `temperature`, plus `reasoning_effort`
+    Generic,
+}
+
+impl Reasoning {
+    /// Parse the `reasoning` of a registry model entry; `none` yields `Ok(None)`
+    fn parse(name: &str) -> Result<Option<Self>, ()> {
+        match name {
+            "openai" => Ok(Some(Self::OpenAi)),
+            "generic" => Ok(Some(Self::Generic)),
+            "none" => Ok(None),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Reasoning models by name prefix, matched like `MODEL_LIMITS`
+const REASONING_MODELS: &[(&str, Reasoning)] = &[
+    ("o1", Reasoning::OpenAi),
+    ("o3", Reasoning::OpenAi),
+    ("o4", Reasoning::OpenAi),
+    ("gpt-5", Reasoning::OpenAi),
+    ("deepseek-reasoner", Reasoning::Generic),
+    ("deepseek-r1", Reasoning::Generic),
+    ("qwq", Reasoning::Generic),
+];
+
+/// Known prices in USD per million input and output tokens, matched like `MODEL_LIMITS`.
+/// Local models are free and left out.
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-5", 1.25, 10.0),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5-nano", 0.05, 0.4),
+    ("o1", 15.0, 60.0),
+    ("o3", 2.0, 8.0),
+    ("o3-mini", 1.1, 4.4),
+    ("o4-mini", 1.1, 4.4),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-haiku-4", 1.0, 5.0),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("llama-3.3-70b", 0.59, 0.79),
+    ("llama-v3p3-70b", 0.9, 0.9),
+    ("grok-3", 3.0, 15.0),
+    ("grok-4", 3.0, 15.0),
+    ("deepseek-chat", 0.27, 1.1),
+    ("deepseek-reasoner", 0.55, 2.19),
+    ("mistral-small", 0.1, 0.3),
+    ("mistral-large", 2.0, 6.0),
+    ("sonar", 1.0, 1.0),
+];
+
+/// Tokens consumed by one request
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrices {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrices {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Token limits of a model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelLimits {
+    pub context_window: usize,
+    pub max_output_tokens: usize,
+}
+
+/// Known models: name prefix, context window, maximum output tokens. Prefixes are matched
+/// case-insensitively against the model name without any `vendor/` path.
+const MODEL_LIMITS: &[(&str, usize, usize)] = &[
+    ("gpt-4o", 128_000, 16_384),
+    ("gpt-4.1", 1_047_576, 32_768),
+    ("gpt-5", 400_000, 128_000),
+    ("o1", 200_000, 100_000),
+    ("o3", 200_000, 100_000),
+    ("o4-mini", 200_000, 100_000),
+    ("claude-3-5", 200_000, 8_192),
+    ("claude-3-7-sonnet", 200_000, 64_000),
+    ("claude-sonnet-4", 200_000, 64_000),
+    ("claude-opus-4", 200_000, 32_000),
+    ("claude-haiku-4", 200_000, 64_000),
+    ("gemini-1.5", 1_048_576, 8_192),
+    ("gemini-2.0", 1_048_576, 8_192),
+    ("gemini-2.5", 1_048_576, 65_536),
+    ("llama-3.3-70b", 131_072, 32_768),
+    ("llama-v3p3-70b", 131_072, 16_384),
+    ("grok-3", 131_072, 16_384),
+    ("grok-4", 256_000, 16_384),
+    ("deepseek-chat", 128_000, 8_192),
+    ("deepseek-reasoner", 128_000, 32_768),
+    ("mistral-small", 128_000, 8_192),
+    ("mistral-large", 128_000, 8_192),
+    ("sonar", 127_072, 8_192),
+    ("qwen3", 32_768, 8_192),
+];
+
+/// Model name as matched against the model tables: lowercase, without any `vendor/` path
+fn model_key(model: &str) -> String {
+    model.rsplit('/').next().unwrap_or(model).to_lowercase()
+}
+
+/// The value whose prefix is the longest match for `name`, with that prefix's length
+fn longest_prefix<'a, T>(
+    name: &str,
+    entries: impl IntoIterator<Item = (&'a str, T)>,
+) -> Option<(usize, T)> {
+    entries
+        .into_iter()
+        .filter(|(prefix, _)| name.starts_with(&prefix.to_lowercase()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(prefix, value)| (prefix.len(), value))
+}
+
+/// Token limits for `model` from the built-in table, updated by the registry's `[models]`.
+/// The longest matching prefix wins.
+pub fn model_limits(model: &str) -> Option<ModelLimits> {
+    limits_from(model, &crate::registry::load().models)
+}
+
+fn limits_from(model: &str, registry: &BTreeMap<String, ModelEntry>) -> Option<ModelLimits> {
+    let name = model_key(model);
+    let builtin = longest_prefix(
+        &name,
+        MODEL_LIMITS
+            .iter()
+            .map(|&(prefix, context_window, max_output_tokens)| {
+                (
+                    prefix,
+                    ModelLimits {
+                        context_window,
+                        max_output_tokens,
+                    },
+                )
+            }),
+    );
+    let listed = longest_prefix(
+        &name,
+        registry
+            .iter()
+            .filter(|(_, e)| e.context_window.is_some() || e.max_output_tokens.is_some())
+            .map(|(prefix, entry)| (prefix.as_str(), entry)),
+    );
+
+    match (builtin, listed) {
+        (Some((len, mut limits)), Some((listed_len, entry))) if listed_len >= len => {
+            limits.context_window = entry.context_window.unwrap_or(limits.context_window);
+            limits.max_output_tokens = entry.max_output_tokens.unwrap_or(limits.max_output_tokens);
+            Some(limits)
+        }
+        (Some((_, limits)), _) => Some(limits),
+        (None, Some((_, entry))) => Some(ModelLimits {
+            context_window: entry.context_window?,
+            max_output_tokens: entry.max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Whether `model` is a reasoning model, per the built-in table updated by the registry's
+/// `[models]`. The longest matching prefix wins.
+pub fn model_reasoning(model: &str) -> Option<Reasoning> {
+    reasoning_from(model, &crate::registry::load().models)
+}
+
+fn reasoning_from(model: &str, registry: &BTreeMap<String, ModelEntry>) -> Option<Reasoning> {
+    let name = model_key(model);
+    let builtin = longest_prefix(&name, REASONING_MODELS.iter().copied());
+    let listed = longest_prefix(
+        &name,
+        registry.iter().filter_map(|(prefix, entry)| {
+            let reasoning = Reasoning::parse(entry.reasoning.as_deref()?).ok()?;
+            Some((prefix.as_str(), reasoning))
+        }),
+    );
+
+    match (builtin, listed) {
+        (Some((len, _)), Some((listed_len, reasoning))) if listed_len >= len => reasoning,
+        (Some((_, reasoning)), _) => Some(reasoning),
+        (None, Some((_, reasoning))) => reasoning,
+        (None, None) => None,
+    }
+}
+
+/// Prices for `model` in USD per million tokens, from the built-in table updated by the
+/// registry's `[models]`. The longest matching prefix wins.
+pub fn model_prices(model: &str) -> Option<ModelPrices> {
+    prices_from(model, &crate::registry::load().models)
+}
+
+fn prices_from(model: &str, registry: &BTreeMap<String, ModelEntry>) -> Option<ModelPrices> {
+    let name = model_key(model);
+    let builtin = longest_prefix(
+        &name,
+        MODEL_PRICES.iter().map(|&(prefix, input, output)| {
+            (
+                prefix,
+                ModelPrices {
+                    input_per_mtok: input,
+                    output_per_mtok: output,
+                },
+            )
+        }),
+    );
+    let listed = longest_prefix(
+        &name,
+        registry.iter().filter_map(|(prefix, entry)| {
+            let prices = ModelPrices {
+                input_per_mtok: entry.input_cost_per_mtok?,
+                output_per_mtok: entry.output_cost_per_mtok?,
+            };
+            Some((prefix.as_str(), prices))
+        }),
+    );
+
+    match (builtin, listed) {
+        (Some((len, _)), Some((listed_len, prices))) if listed_len >= len => Some(prices),
+        (builtin, listed) => builtin.or(listed).map(|(_, prices)| prices),
+    }
+}
+
+/// Estimated cost in USD of a request to `model`, if its prices are known
+pub fn cost_usd(model: &str, usage: &TokenUsage) -> Option<f64> {
+    model_prices(model).map(|p| p.cost(usage))
+}
+
+/// Limits for the configured model, with `ACR_CONTEXT_WINDOW` taking precedence
+fn configured_limits(cfg: &AppConfig) -> Option<ModelLimits> {
+    let known = model_limits(&cfg.model);
+    if cfg.context_window == 0 {
+        return known;
+    }
+    Some(ModelLimits {
+        context_window: cfg.context_window,
+        max_output_tokens: known.map_or(DEFAULT_MAX_TOKENS, |l| l.max_output_tokens),
+    })
+}
+
+/// The largest `max_tokens` that fits the model's output limit and leaves most of the context
+/// window for the prompt. Reasoning models get room for their hidden reasoning.
+fn max_tokens_for(limits: Option<ModelLimits>, reasoning: Option<Reasoning>) -> usize {
+    let cap = if reasoning.is_some() {
+        REASONING_MAX_TOKENS_CAP
+    } else {
+        MAX_TOKENS_CAP
+    };
+    limits.map_or(DEFAULT_MAX_TOKENS, |l| {
+        l.max_output_tokens
+            .min(cap)
+            .min(l.context_window / 4)
+            .max(1)
+    })
+}
+
+/// `max_tokens` for a request: the applied preset's override, or [`max_tokens_for`]
+fn request_max_tokens(
+    cfg: &AppConfig,
+    limits: Option<ModelLimits>,
+    reasoning: Option<Reasoning>,
+) -> usize {
+    cfg.preset_overrides
+        .max_tokens
+        .unwrap_or_else(|| max_tokens_for(limits, reasoning))
+}
+
+/// Context window of `cfg`'s model in tokens, when known or set with `ACR_CONTEXT_WINDOW`
+pub fn context_window(cfg: &AppConfig) -> Option<usize> {
+    configured_limits(cfg).map(|l| l.context_window)
+}
+
+/// Whether the prompt for `diff` fits `cfg`'s model next to its output without truncating
+/// the diff. Models with an unknown context window are assumed to fit.
+pub fn fits_context(cfg: &AppConfig, diff: &str) -> bool {
+    let Some(limits) = configured_limits(cfg) else {
+        return true;
+    };
+    let max_tokens = request_max_tokens(cfg, Some(limits), model_reasoning(&cfg.model));
+    estimate_usage(cfg, diff).input_tokens as usize + max_tokens <= limits.context_window
+}
+
+/// Adjust a request body for a reasoning model, which rejects sampling parameters and, for
+/// OpenAI models, the `system` role and `max_tokens`
+fn apply_reasoning(body: &mut Value, format: RequestFormat, reasoning: Reasoning, effort: &str) {
+    if format != RequestFormat::OpenAiCompat {
+        return;
+    }
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    obj.remove("temperature");
+    if reasoning == Reasoning::OpenAi {
+        if let Some(max_tokens) = obj.remove("max_tokens") {
+            obj.insert("max_completion_tokens".into(), max_tokens);
+        }
+        if let Some(Value::Array(messages)) = obj.get_mut("messages") {
+            for message in messages {
+                if message["role"] == "system" {
+                    message["role"] = "developer".into();
+                }
+            }
+        }
+    }
+    if !effort.is_empty() {
+        obj.insert("reasoning_effort".into(), effort.to_lowercase().into());
+    }
 }
 
 pub enum LlmCallError {
@@ -137,46 +602,305 @@ impl std::fmt::Display for LlmCallError {
     }
 }
 
+/// What an HTTP error from a provider leads to, per `ACR_FALLBACK_POLICY`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Try the next preset in the fallback order
+    Fallback,
+    /// Send the request again, then fall back once the retries are used up
+    Retry,
+    /// Stop with the error, e.g. for a bad API key another provider would only hide
+    Fail,
+}
+
+/// How many times [`ErrorAction::Retry`] resends a request
+pub const MAX_RETRIES: usize = 2;
+
+/// Parse `ACR_FALLBACK_POLICY`: comma-separated `status=action` rules, where the status is a
+/// code (`429`), a class (`5xx`) or `*`, and the action is `fallback`, `retry` or `fail`
+pub fn parse_fallback_policy(value: &str) -> Result<Vec<(String, ErrorAction)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| {
+            let (status, action) = rule
+                .split_once('=')
+                .ok_or_else(|| format!("'{rule}' is not a status=action rule"))?;
+            let status = status.trim().to_ascii_lowercase();
+            let valid_status = status == "*"
+                || (status.len() == 3
+                    && status.as_bytes()[0].is_ascii_digit()
+                    && (status[1..] == *"xx" || status.bytes().all(|b| b.is_ascii_digit())));
+            if !valid_status {
+                return Err(format!(
+                    "'{status}' is not a status code, class like 5xx or *"
+                ));
+            }
+            let action = match action.trim().to_ascii_lowercase().as_str() {
+                "fallback" => ErrorAction::Fallback,
+                "retry" => ErrorAction::Retry,
+                "fail" => ErrorAction::Fail,
+                other => return Err(format!("'{other}' is not fallback, retry or fail")),
+            };
+            Ok((status, action))
+        })
+        .collect()
+}
+
+/// What HTTP status `code` leads to under `policy`: the first matching rule wins, and a status
+/// no rule matches falls back. Malformed rules are ignored; `cgen config check` reports them.
+pub fn error_action(policy: &str, code: u16) -> ErrorAction {
+    let code = code.to_string();
+    policy
+        .split(',')
+        .filter_map(|rule| parse_fallback_policy(rule).ok()?.pop())
+        .find(|(status, _)| {
+            status == "*"
+                || *status == code
+                || (status.ends_with("xx") && code.starts_with(&status[..1]))
+        })
+        .map_or(ErrorAction::Fallback, |(_, action)| action)
+}
+
+/// Where a request stands in the fallback chain, for the spinner
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Attempt<'a> {
+    number: usize,
+    total: usize,
+    /// The fallback preset being tried; `None` for the configured provider
+    preset: Option<&'a str>,
+}
+
+const PRIMARY_ATTEMPT: Attempt<'static> = Attempt {
+    number: 1,
+    total: 1,
+    preset: None,
+};
+
+/// What the spinner says while `cfg`'s provider is called
+fn spinner_message(cfg: &AppConfig, attempt: Attempt) -> String {
+    let target = format!("{} {} {}", cfg.provider, crate::ui::glyphs().dot, cfg.model);
+    match attempt.preset {
+        None => format!("Generating commit message with {target}..."),
+        Some(name) => format!(
+            "Attempt {}/{}: preset {name} ({target})...",
+            attempt.number, attempt.total
+        ),
+    }
+}
+
+/// `ACR_REQUEST_TIMEOUT_SECS`, unless the applied preset sets its own timeout
+fn request_timeout_secs(cfg: &AppConfig) -> usize {
+    cfg.preset_overrides
+        .timeout_secs
+        .unwrap_or(cfg.request_timeout_secs)
+}
+
+/// Spinner template showing the elapsed time against the request timeout, when there is one
+fn spinner_template(timeout_secs: usize) -> String {
+    if timeout_secs == 0 {
+        "{spinner:.cyan} {msg} {elapsed}".into()
+    } else {
+        format!("{{spinner:.cyan}} {{msg}} {{elapsed}}/{timeout_secs}s")
+    }
+}
+
 fn call_llm_inner(
     cfg: &AppConfig,
     system_prompt: &str,
     diff: &str,
+    attempt: Attempt,
 ) -> Result<String, LlmCallError> {
-    let (url, headers_raw, format, response_path) =
-        resolve_provider(cfg).map_err(LlmCallError::Other)?;
+    let start = Instant::now();
+    let result = send_request(cfg, system_prompt, diff, &spinner_message(cfg, attempt));
+    let usage = result.as_ref().ok().map(|(_, usage)| *usage);
+    crate::usage::note_request(cfg, start.elapsed(), usage);
+    if let Some(usage) = usage {
+        // Usage tracking is best-effort and must never fail a generated message
+        let _ = crate::usage::record(cfg, &usage);
+    }
+    result.map(|(message, _)| message)
+}
 
-    let url = interpolate(&url, cfg);
-    let headers_raw = interpolate(&headers_raw, cfg);
+/// [`call_llm_inner`], resending the request up to [`MAX_RETRIES`] times while it fails with a
+/// status `ACR_FALLBACK_POLICY` retries
+fn call_with_retries(
+    cfg: &AppConfig,
+    system_prompt: &str,
+    diff: &str,
+    attempt: Attempt,
+) -> Result<String, LlmCallError> {
+    let mut retries = 0;
+    loop {
+        match call_llm_inner(cfg, system_prompt, diff, attempt) {
+            Err(LlmCallError::HttpError { code, .. })
+                if retries < MAX_RETRIES
+                    && error_action(&cfg.fallback_policy, code) == ErrorAction::Retry =>
+            {
+                retries += 1;
+                eprintln!(
+                    "{} HTTP {code}, retrying ({retries}/{MAX_RETRIES})...",
+                    "retry:".yellow().bold()
+                );
+                std::thread::sleep(Duration::from_millis(500 << (retries - 1)));
+            }
+            result => return result,
+        }
+    }
+}
 
-    let body = build_request_body(format, &cfg.model, system_prompt, diff);
-    let headers = parse_headers(&headers_raw);
+/// A provider request as it is sent: interpolated URL and headers, and the sized body
+pub struct PreparedRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+    format: RequestFormat,
+    response_path: String,
+}
+
+impl PreparedRequest {
+    /// The request as text, for `--dry-run-request`: the URL, the headers and the body. The
+    /// API key is masked wherever it appears, as are credential headers.
+    pub fn redacted(&self, api_key: &str) -> String {
+        let mask = |text: &str| redact(text, api_key);
+        let mut out = format!("POST {}\n", mask(&self.url));
+        for (name, value) in &self.headers {
+            out.push_str(&format!("{name}: {}\n", mask(&redact_header(name, value))));
+        }
+        let body = serde_json::to_string_pretty(&self.body).unwrap_or_default();
+        out.push_str(&format!("\n{body}\n"));
+        out
+    }
+}
+
+/// `text` with every occurrence of `api_key` masked
+fn redact(text: &str, api_key: &str) -> String {
+    match api_key {
+        "" => text.to_string(),
+        key => text.replace(key, &crate::config::mask_key(key)),
+    }
+}
+
+/// A header value with its credential masked when the header name suggests one
+/// (`Authorization`, `x-api-key`, ...), keeping a scheme like `Bearer`
+fn redact_header(name: &str, value: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    if !["auth", "key", "token", "secret"]
+        .iter()
+        .any(|word| name.contains(word))
+    {
+        return value.to_string();
+    }
+    match value.split_once(' ') {
+        Some((scheme, secret)) if !scheme.is_empty() && !secret.contains(' ') => {
+            format!("{scheme} {}", crate::config::mask_key(secret))
+        }
+        _ => crate::config::mask_key(value),
+    }
+}
+
+/// Resolve `cfg`'s provider and build the request [`send_request`] would send
+pub fn prepare_request(
+    cfg: &AppConfig,
+    system_prompt: &str,
+    diff: &str,
+) -> Result<PreparedRequest> {
+    let (url, headers_raw, format, mut response_path) = resolve_provider(cfg)?;
+    if let Some(path) = &cfg.preset_overrides.response_path {
+        response_path = path.clone();
+    }
+    let mut headers = parse_headers(&interpolate(&headers_raw, cfg, "API_HEADERS"));
+    headers.push(("Content-Type".into(), "application/json".into()));
+    Ok(PreparedRequest {
+        url: interpolate(&url, cfg, "API_URL"),
+        headers,
+        body: build_capped_body(cfg, format, system_prompt, diff)?,
+        format,
+        response_path,
+    })
+}
+
+/// Send one request and return the message with the tokens it used
+#[tracing::instrument(
+    level = "debug",
+    name = "provider_call",
+    skip_all,
+    fields(provider = %cfg.provider, model = %cfg.model)
+)]
+fn send_request(
+    cfg: &AppConfig,
+    system_prompt: &str,
+    diff: &str,
+    spinner_msg: &str,
+) -> Result<(String, TokenUsage), LlmCallError> {
+    let PreparedRequest {
+        url,
+        headers,
+        body,
+        format,
+        response_path,
+    } = prepare_request(cfg, system_prompt, diff).map_err(LlmCallError::Other)?;
+    let timeout_secs = request_timeout_secs(cfg);
+    crate::ui::debug_event!(
+        "POST {} ({} {} {}): {} bytes, ~{} input tokens, timeout {}",
+        redact(&url, &cfg.api_key),
+        cfg.provider,
+        crate::ui::glyphs().dot,
+        cfg.model,
+        body_size(&body),
+        body_size(&body) / BYTES_PER_TOKEN,
+        match timeout_secs {
+            0 => "none".to_string(),
+            secs => format!("{secs}s"),
+        }
+    );
 
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(
         ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg} {elapsed}")
-            .unwrap(),
+            .template(&spinner_template(timeout_secs))
+            .unwrap()
+            .tick_chars(crate::ui::glyphs().spinner),
     );
-    spinner.set_message("Generating commit message...");
+    spinner.set_message(spinner_msg.to_string());
+    if crate::ui::quiet() {
+        spinner.set_draw_target(ProgressDrawTarget::hidden());
+    }
     spinner.enable_steady_tick(Duration::from_millis(80));
 
-    let mut req = ureq::post(&url);
+    let mut req = match timeout_secs {
+        0 => ureq::post(&url),
+        secs => ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(secs as u64))
+            .build()
+            .post(&url),
+    };
     for (key, val) in &headers {
         req = req.set(key, val);
     }
-    req = req.set("Content-Type", "application/json");
 
+    let start = Instant::now();
     let response = req.send_json(&body);
+    let elapsed = format!("{:.2}s", start.elapsed().as_secs_f64());
 
     spinner.finish_and_clear();
 
     let response = match response {
-        Ok(resp) => resp,
+        Ok(resp) => {
+            crate::ui::debug_event!("HTTP {} in {elapsed}", resp.status());
+            resp
+        }
         Err(ureq::Error::Status(code, resp)) => {
+            crate::ui::debug_event!("HTTP {code} in {elapsed}");
             let body = resp.into_string().unwrap_or_default();
             return Err(LlmCallError::HttpError { code, body });
         }
         Err(ureq::Error::Transport(t)) => {
+            crate::ui::debug_event!(
+                "network error after {elapsed}: {}",
+                redact(&t.to_string(), &cfg.api_key)
+            );
             return Err(LlmCallError::TransportError(t.to_string()));
         }
     };
@@ -194,22 +918,48 @@ fn call_llm_inner(
         ))
     })?;
 
-    Ok(message)
+    let reported = extract_usage(&json, format);
+    let usage = reported.unwrap_or(TokenUsage {
+        input_tokens: (body_size(&body) / BYTES_PER_TOKEN) as u64,
+        output_tokens: (message.len() / BYTES_PER_TOKEN) as u64,
+    });
+    crate::ui::debug_event!(
+        "{} input, {} output tokens{}",
+        usage.input_tokens,
+        usage.output_tokens,
+        if reported.is_some() {
+            ""
+        } else {
+            " (estimated)"
+        }
+    );
+    Ok((message, usage))
 }
 
 /// Call LLM with fallback support. Returns (message, fallback_preset_name_if_used).
+#[tracing::instrument(level = "debug", name = "llm_call", skip_all)]
 pub fn call_llm_with_fallback(
     cfg: &AppConfig,
     system_prompt: &str,
     diff: &str,
 ) -> Result<(String, Option<String>)> {
-    match call_llm_inner(cfg, system_prompt, diff) {
+    match call_with_retries(cfg, system_prompt, diff, PRIMARY_ATTEMPT) {
         Ok(msg) => Ok((msg, None)),
         Err(LlmCallError::TransportError(msg)) => {
             anyhow::bail!("Network error: {msg}");
         }
         Err(LlmCallError::HttpError { code, body }) => {
-            if !cfg.fallback_enabled {
+            let action = error_action(&cfg.fallback_policy, code);
+            crate::ui::debug_event!(
+                "HTTP {code}: {action:?} per ACR_FALLBACK_POLICY \"{}\"{}",
+                cfg.fallback_policy,
+                if cfg.fallback_enabled {
+                    ""
+                } else {
+                    ", fallback disabled"
+                }
+            );
+            if !cfg.fallback_enabled || action == ErrorAction::Fail {
                 anyhow::bail!("API returned HTTP {code}: {body}");
             }
 
@@ -219,27 +969,62 @@ pub fn call_llm_with_fallback(
             };
 
             if presets_file.fallback.order.is_empty() {
+                crate::ui::debug_event!("no fallback order configured");
                 anyhow::bail!("API returned HTTP {code}: {body}");
             }
 
             let current_fields = crate::preset::fields_from_config(cfg);
             let mut errors = vec![format!("Primary (HTTP {code})")];
 
-            for &preset_id in &presets_file.fallback.order {
-                let preset = match presets_file.presets.iter().find(|p| p.id == preset_id) {
-                    Some(p) => p,
-                    None => continue,
-                };
-
+            let candidates: Vec<_> = presets_file
+                .fallback
+                .order
+                .iter()
+                .filter_map(|&preset_id| presets_file.presets.iter().find(|p| p.id == preset_id))
                 // Skip if this preset matches current config (dedup key comparison)
-                if preset.fields.provider == current_fields.provider
-                    && preset.fields.model == current_fields.model
-                    && preset.fields.api_key == current_fields.api_key
-                    && preset.fields.api_url == current_fields.api_url
-                {
-                    continue;
+                .filter(|preset| {
+                    !(preset.fields.provider == current_fields.provider
+                        && preset.fields.model == current_fields.model
+                        && preset.fields.api_key == current_fields.api_key
+                        && preset.fields.api_url == current_fields.api_url)
+                })
+                .collect();
+
+            // Presets that failed recently are skipped rather than waited on again
+            let cooldown_mins = cfg.fallback_cooldown_mins;
+            let cooldowns = match cooldown_mins {
+                0 => crate::cooldown::Cooldowns::default(),
+                _ => crate::cooldown::load().unwrap_or_default(),
+            };
+            let now = crate::timestamp::now_unix();
+            let (cooling, mut candidates): (Vec<_>, Vec<_>) = candidates
+                .into_iter()
+                .partition(|p| cooldowns.cooling_down(p.id, cooldown_mins, now));
+            for preset in cooling {
+                eprintln!(
+                    "{} Skipping {}, it failed within the last {cooldown_mins} min",
+                    "fallback:".yellow().bold(),
+                    preset.name
+                );
+                errors.push(format!("{} (cooling down)", preset.name));
+            }
+            if cfg.fallback_max_attempts > 0 && candidates.len() > cfg.fallback_max_attempts {
+                crate::ui::debug_event!(
+                    "trying {} of {} fallback presets (ACR_FALLBACK_MAX_ATTEMPTS)",
+                    cfg.fallback_max_attempts,
+                    candidates.len()
+                );
+                candidates.truncate(cfg.fallback_max_attempts);
+            }
+            let note = |preset: &crate::preset::Preset, ok: bool| {
+                if cooldown_mins > 0 {
+                    // Best-effort: a cache that cannot be written only loses the cooldown
+                    let _ = crate::cooldown::note(preset.id, ok);
                 }
+            };
 
+            for (i, preset) in candidates.iter().enumerate() {
+                let _span = tracing::debug_span!("fallback", preset = %preset.name).entered();
                 eprintln!(
                     "{} Primary failed (HTTP {}), trying: {}...",
                     "fallback:".yellow().bold(),
@@ -250,8 +1035,26 @@ pub fn call_llm_with_fallback(
                 let mut temp_cfg = cfg.clone();
                 crate::preset::apply_preset_to_config(&mut temp_cfg, preset);
 
-                match call_llm_inner(&temp_cfg, system_prompt, diff) {
+                let attempt = Attempt {
+                    number: i + 2,
+                    total: candidates.len() + 1,
+                    preset: Some(&preset.name),
+                };
+                let result = call_with_retries(&temp_cfg, system_prompt, diff, attempt);
+                note(preset, result.is_ok());
+                if let Err(e) = &result {
+                    crate::ui::debug_event!("fallback preset {} failed: {e}", preset.name);
+                }
+                match result {
                     Ok(msg) => return Ok((msg, Some(preset.name.clone()))),
+                    Err(LlmCallError::HttpError { code: fc, body })
+                        if error_action(&cfg.fallback_policy, fc) == ErrorAction::Fail =>
+                    {
+                        anyhow::bail!(
+                            "Fallback preset '{}' returned HTTP {fc}: {body}",
+                            preset.name
+                        );
+                    }
                     Err(LlmCallError::HttpError { code: fc, .. }) => {
                         errors.push(format!("{} (HTTP {fc})", preset.name));
                         continue;
@@ -274,6 +1077,37 @@ pub fn call_llm_with_fallback(
     }
 }
 
+/// Outcome of a health check request
+#[derive(Debug)]
+pub struct HealthCheck {
+    pub latency: Duration,
+    /// `None` when the provider answered with a message
+    pub error: Option<String>,
+}
+
+/// Send a minimal request with `preset` applied on top of `cfg` and time it. Token usage
+/// counts toward the monthly budget, but the request is not part of the usage metrics.
+pub fn check_preset(cfg: &AppConfig, preset: &crate::preset::Preset) -> HealthCheck {
+    let mut cfg = cfg.clone();
+    crate::preset::apply_preset_to_config(&mut cfg, preset);
+    let target = format!("{} {} {}", cfg.provider, crate::ui::glyphs().dot, cfg.model);
+    let start = Instant::now();
+    let result = send_request(
+        &cfg,
+        "You are a health check. Reply with the single word OK.",
+        "ping",
+        &format!("Testing preset {} ({target})...", preset.name),
+    );
+    let latency = start.elapsed();
+    if let Ok((_, usage)) = &result {
+        let _ = crate::usage::record(&cfg, usage);
+    }
+    HealthCheck {
+        latency,
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
 /// Call the LLM API and return the generated commit message
 pub fn call_llm(cfg: &AppConfig, system_prompt: &str, diff: &str) -> Result<String> {
     let (msg, _) = call_llm_with_fallback(cfg, system_prompt, diff)?;
@@ -283,16 +1117,16 @@ pub fn call_llm(cfg: &AppConfig, system_prompt: &str, diff: &str) -> Result<Stri
 fn resolve_provider(cfg: &AppConfig) -> Result<(String, String, RequestFormat, String)> {
     if let Some(def) = get_provider(&cfg.provider) {
         let url = if cfg.api_url.is_empty() {
-            def.api_url.to_string()
+            def.api_url
         } else {
             cfg.api_url.clone()
         };
         let headers = if cfg.api_headers.is_empty() {
-            def.api_headers.to_string()
+            def.api_headers
         } else {
             cfg.api_headers.clone()
         };
-        Ok((url, headers, def.format, def.response_path.to_string()))
+        Ok((url, headers, def.format, def.response_path))
     } else {
         // Custom provider: require API URL, default to OpenAI-compatible format
         if cfg.api_url.is_empty() {
@@ -314,6 +1148,7 @@ fn resolve_provider(cfg: &AppConfig) -> Result<(String, String, RequestFormat, S
 fn build_request_body(
     format: RequestFormat,
     model: &str,
+    max_tokens: usize,
     system_prompt: &str,
     diff: &str,
 ) -> Value {
@@ -328,7 +1163,8 @@ fn build_request_body(
                     "parts": [{ "text": diff }]
                 }],
                 "generationConfig": {
-                    "temperature": 0
+                    "temperature": 0,
+                    "maxOutputTokens": max_tokens
                 }
             })
         }
@@ -339,7 +1175,7 @@ fn build_request_body(
                     { "role": "system", "content": system_prompt },
                     { "role": "user", "content": diff }
                 ],
-                "max_tokens": 512,
+                "max_tokens": max_tokens,
                 "temperature": 0
             })
         }
@@ -350,7 +1186,7 @@ fn build_request_body(
                 "messages": [
                     { "role": "user", "content": diff }
                 ],
-                "max_tokens": 512
+                "max_tokens": max_tokens
             })
         }
         RequestFormat::LmStudio => {
@@ -362,6 +1198,164 @@ fn build_request_body(
     }
 }
 
+fn body_size(body: &Value) -> usize {
+    serde_json::to_vec(body).map(|b| b.len()).unwrap_or(0)
+}
+
+/// Set the sampling temperature where the request format has one (all but LM Studio)
+fn apply_temperature(body: &mut Value, format: RequestFormat, temperature: f64) {
+    match format {
+        RequestFormat::OpenAiCompat | RequestFormat::Anthropic => {
+            body["temperature"] = temperature.into()
+        }
+        RequestFormat::Gemini => body["generationConfig"]["temperature"] = temperature.into(),
+        RequestFormat::LmStudio => {}
+    }
+}
+
+/// Add a sampling seed where the request format has one (OpenAI-compatible and Gemini)
+fn apply_seed(body: &mut Value, format: RequestFormat, seed: u64) {
+    match format {
+        RequestFormat::OpenAiCompat => body["seed"] = seed.into(),
+        RequestFormat::Gemini => body["generationConfig"]["seed"] = seed.into(),
+        RequestFormat::Anthropic | RequestFormat::LmStudio => {}
+    }
+}
+
+/// Truncate the diff when the prompt would not fit the model's context window next to
+/// `max_tokens` of output. Returns `None` when it already fits.
+fn fit_to_context(
+    cfg: &AppConfig,
+    limits: ModelLimits,
+    max_tokens: usize,
+    system_prompt: &str,
+    diff: &str,
+) -> Result<Option<String>> {
+    let budget_tokens = limits
+        .context_window
+        .saturating_sub(max_tokens + PROMPT_OVERHEAD_TOKENS)
+        .saturating_sub(system_prompt.len() / BYTES_PER_TOKEN);
+    let budget = budget_tokens * BYTES_PER_TOKEN;
+    if diff.len() <= budget {
+        return Ok(None);
+    }
+    let Some(truncated) = crate::git::truncate_diff(diff, budget) else {
+        bail!(
+            "The diff (~{} tokens) does not fit the {}-token context window of '{}' even when \
+             reduced to file headers. Exclude files with --exclude or ACR_DIFF_EXCLUDE_GLOBS, or \
+             use a model with a larger context window.",
+            diff.len() / BYTES_PER_TOKEN,
+            limits.context_window,
+            cfg.model
+        );
+    };
+    eprintln!(
+        "{} The diff (~{} tokens) exceeds the {}-token context window of '{}'; it was truncated to fit",
+        "note:".yellow().bold(),
+        diff.len() / BYTES_PER_TOKEN,
+        limits.context_window,
+        cfg.model
+    );
+    Ok(Some(truncated))
+}
+
+/// Rough input tokens of a request body
+pub fn estimated_tokens(body: &Value) -> usize {
+    body_size(body) / BYTES_PER_TOKEN
+}
+
+/// Build the request body sized for the model: the diff is truncated to the model's context
+/// window and, when the body would exceed `ACR_MAX_PAYLOAD_BYTES`, to the payload limit instead
+/// of letting the provider reject it
+fn build_capped_body(
+    cfg: &AppConfig,
+    format: RequestFormat,
+    system_prompt: &str,
+    diff: &str,
+) -> Result<Value> {
+    let limits = configured_limits(cfg);
+    let reasoning = model_reasoning(&cfg.model);
+    let overrides = &cfg.preset_overrides;
+    let max_tokens = request_max_tokens(cfg, limits, reasoning);
+    let build = |diff: &str| {
+        let mut body = build_request_body(format, &cfg.model, max_tokens, system_prompt, diff);
+        if let Some(temperature) = overrides.temperature {
+            apply_temperature(&mut body, format, temperature);
+        }
+        if let Some(reasoning) = reasoning {
+            apply_reasoning(&mut body, format, reasoning, &cfg.reasoning_effort);
+        }
+        if let Ok(seed) = cfg.seed.parse::<u64>() {
+            apply_seed(&mut body, format, seed);
+        }
+        body
+    };
+    let fitted = match limits {
+        Some(limits) => fit_to_context(cfg, limits, max_tokens, system_prompt, diff)?,
+        None => None,
+    };
+    let diff = fitted.as_deref().unwrap_or(diff);
+
+    let body = build(diff);
+    let limit = cfg.max_payload_bytes;
+    let size = body_size(&body);
+    if limit == 0 || size <= limit {
+        return Ok(body);
+    }
+
+    // JSON escaping makes the diff larger in the body than on its own, so shrink until it fits
+    let mut target = diff.len().saturating_sub(size - limit);
+    for _ in 0..4 {
+        let Some(truncated) = crate::git::truncate_diff(diff, target) else {
+            break;
+        };
+        let body = build(&truncated);
+        let truncated_size = body_size(&body);
+        if truncated_size <= limit {
+            eprintln!(
+                "{} Request is {size} bytes (ACR_MAX_PAYLOAD_BYTES={limit}); the diff was truncated to fit",
+                "note:".yellow().bold()
+            );
+            return Ok(body);
+        }
+        target = target.saturating_sub(truncated_size - limit);
+    }
+    bail!(
+        "Request is {size} bytes, over ACR_MAX_PAYLOAD_BYTES={limit} even with the diff reduced to \
+         file headers. Exclude files with --exclude or ACR_DIFF_EXCLUDE_GLOBS, shorten the system \
+         prompt, or raise the limit."
+    )
+}
+
+/// Token counts reported in a provider response
+fn extract_usage(json: &Value, format: RequestFormat) -> Option<TokenUsage> {
+    let (usage, input, output) = match format {
+        RequestFormat::OpenAiCompat => (&json["usage"], "prompt_tokens", "completion_tokens"),
+        RequestFormat::Anthropic => (&json["usage"], "input_tokens", "output_tokens"),
+        RequestFormat::Gemini => (
+            &json["usageMetadata"],
+            "promptTokenCount",
+            "candidatesTokenCount",
+        ),
+        RequestFormat::LmStudio => (&json["stats"], "input_tokens", "total_output_tokens"),
+    };
+    Some(TokenUsage {
+        input_tokens: usage[input].as_u64()?,
+        output_tokens: usage[output].as_u64()?
+            // Gemini reports reasoning separately, but bills it as output
+            + usage["thoughtsTokenCount"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Rough token usage of generating a message for `diff`, before anything is sent
+pub fn estimate_usage(cfg: &AppConfig, diff: &str) -> TokenUsage {
+    TokenUsage {
+        input_tokens: ((cfg.llm_system_prompt.len() + diff.len()) / BYTES_PER_TOKEN
+            + PROMPT_OVERHEAD_TOKENS) as u64,
+        output_tokens: ESTIMATED_OUTPUT_TOKENS,
+    }
+}
+
 /// Parse "Key: Value, Key2: Value2" header string into pairs
 fn parse_headers(raw: &str) -> Vec<(String, String)> {
     if raw.trim().is_empty() {
@@ -423,6 +1417,37 @@ fn extract_message(value: &Value, format: RequestFormat, response_path: &str) ->
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_prepared_request_redacts_secrets() {
+        let cfg = AppConfig {
+            provider: "custom".into(),
+            model: "chat".into(),
+            api_key: "sk-1234567890abcdef".into(),
+            api_url: "https://llm.example/v1?key=$ACR_API_KEY".into(),
+            api_headers:
+                "Authorization: Bearer $ACR_API_KEY, X-Team: core, X-Token: t0ps3cr3tvalue, X-Key: aéééé"
+                    .into(),
+            ..Default::default()
+        };
+        let request = prepare_request(&cfg, "system", "diff").unwrap();
+        assert_eq!(
+            request.url,
+            "https://llm.example/v1?key=sk-1234567890abcdef"
+        );
+        assert!(request
+            .headers
+            .contains(&("Content-Type".into(), "application/json".into())));
+
+        let text = request.redacted(&cfg.api_key);
+        assert!(!text.contains("sk-1234567890abcdef"));
+        assert!(text.contains("POST https://llm.example/v1?key=sk-1...cdef\n"));
+        assert!(text.contains("Authorization: Bearer sk-1...cdef\n"));
+        assert!(text.contains("X-Team: core\n"));
+        assert!(text.contains("X-Token: t0...ue\n"));
+        assert!(text.contains("X-Key: *****\n"));
+        assert!(text.contains(r#""model": "chat""#));
+    }
+
     #[test]
     fn test_parse_headers_empty() {
         assert!(parse_headers("").is_empty());
@@ -493,6 +1518,268 @@ mod tests {
         assert_eq!(result, "generated");
     }
 
+    #[test]
+    fn test_build_capped_body_truncates_diff_to_limit() {
+        let diff: String = (0..200)
+            .map(|i| format!("+line \"{i}\" with quotes\n"))
+            .collect();
+        let diff =
+            format!("diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -0,0 +1,200 @@\n{diff}");
+        let mut cfg = AppConfig::default();
+        let full = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", &diff).unwrap();
+        assert_eq!(full["messages"][1]["content"], diff.as_str());
+
+        cfg.max_payload_bytes = 2000;
+        let body = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", &diff).unwrap();
+        assert!(body_size(&body) <= 2000);
+        let content = body["messages"][1]["content"].as_str().unwrap();
+        assert!(content.starts_with("diff --git a/a.rs b/a.rs"));
+        assert!(content.contains("diff lines omitted"));
+
+        cfg.max_payload_bytes = 50;
+        assert!(build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", &diff).is_err());
+    }
+
+    #[test]
+    fn test_limits_from_matches_longest_prefix_and_registry() {
+        let limits = limits_from("openai/GPT-4o-mini", &Default::default()).unwrap();
+        assert_eq!(limits.context_window, 128_000);
+        assert!(limits_from("my-local-model", &Default::default()).is_none());
+
+        let mut registry = BTreeMap::new();
+        registry.insert(
+            "gpt-4o-mini".to_string(),
+            ModelEntry {
+                context_window: Some(64_000),
+                ..Default::default()
+            },
+        );
+        registry.insert(
+            "my-local".to_string(),
+            ModelEntry {
+                context_window: Some(8_192),
+                ..Default::default()
+            },
+        );
+        let limits = limits_from("gpt-4o-mini", &registry).unwrap();
+        assert_eq!(limits.context_window, 64_000);
+        assert_eq!(limits.max_output_tokens, 16_384);
+        assert_eq!(
+            limits_from("gpt-4o", &registry).unwrap().context_window,
+            128_000
+        );
+        assert_eq!(
+            limits_from("my-local-model", &registry)
+                .unwrap()
+                .max_output_tokens,
+            DEFAULT_MAX_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_reasoning_from_table_and_registry() {
+        let none = Default::default();
+        assert_eq!(reasoning_from("o3-mini", &none), Some(Reasoning::OpenAi));
+        assert_eq!(
+            reasoning_from("deepseek/deepseek-r1", &none),
+            Some(Reasoning::Generic)
+        );
+        assert_eq!(reasoning_from("gpt-4o", &none), None);
+
+        let mut registry = BTreeMap::new();
+        registry.insert(
+            "gpt-5-chat".to_string(),
+            ModelEntry {
+                reasoning: Some("none".into()),
+                ..Default::default()
+            },
+        );
+        registry.insert(
+            "magistral".to_string(),
+            ModelEntry {
+                reasoning: Some("generic".into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(reasoning_from("gpt-5-chat-latest", &registry), None);
+        assert_eq!(
+            reasoning_from("gpt-5-mini", &registry),
+            Some(Reasoning::OpenAi)
+        );
+        assert_eq!(
+            reasoning_from("magistral-medium", &registry),
+            Some(Reasoning::Generic)
+        );
+    }
+
+    #[test]
+    fn test_apply_reasoning_adjusts_openai_body() {
+        let mut body = build_request_body(RequestFormat::OpenAiCompat, "o3", 512, "sys", "diff");
+        apply_reasoning(
+            &mut body,
+            RequestFormat::OpenAiCompat,
+            Reasoning::OpenAi,
+            "High",
+        );
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(body["max_completion_tokens"], 512);
+        assert_eq!(body["messages"][0]["role"], "developer");
+        assert_eq!(body["reasoning_effort"], "high");
+
+        let mut body = build_request_body(
+            RequestFormat::OpenAiCompat,
+            "deepseek-r1",
+            512,
+            "sys",
+            "diff",
+        );
+        apply_reasoning(
+            &mut body,
+            RequestFormat::OpenAiCompat,
+            Reasoning::Generic,
+            "",
+        );
+        assert!(body.get("temperature").is_none());
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert!(body.get("reasoning_effort").is_none());
+    }
+
+    #[test]
+    fn test_build_capped_body_sends_seed() {
+        let mut cfg = AppConfig {
+            seed: "42".into(),
+            ..AppConfig::default()
+        };
+        let body = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", "diff").unwrap();
+        assert_eq!(body["seed"], 42);
+        let body = build_capped_body(&cfg, RequestFormat::Gemini, "sys", "diff").unwrap();
+        assert_eq!(body["generationConfig"]["seed"], 42);
+        let body = build_capped_body(&cfg, RequestFormat::Anthropic, "sys", "diff").unwrap();
+        assert!(body.get("seed").is_none());
+
+        cfg.seed = String::new();
+        let body = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", "diff").unwrap();
+        assert!(body.get("seed").is_none());
+    }
+
+    #[test]
+    fn test_build_capped_body_applies_preset_overrides() {
+        let mut cfg = AppConfig::default();
+        cfg.preset_overrides.temperature = Some(0.7);
+        cfg.preset_overrides.max_tokens = Some(4_096);
+        let body = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", "diff").unwrap();
+        assert_eq!(body["temperature"], 0.7);
+        assert_eq!(body["max_tokens"], 4_096);
+        let body = build_capped_body(&cfg, RequestFormat::Gemini, "sys", "diff").unwrap();
+        assert_eq!(body["generationConfig"]["temperature"], 0.7);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 4_096);
+        let body = build_capped_body(&cfg, RequestFormat::Anthropic, "sys", "diff").unwrap();
+        assert_eq!(body["temperature"], 0.7);
+
+        // Reasoning models still reject a temperature
+        cfg.model = "o3-mini".into();
+        let body = build_capped_body(&cfg, RequestFormat::OpenAiCompat, "sys", "diff").unwrap();
+        assert!(body.get("temperature").is_none());
+
+        assert_eq!(request_timeout_secs(&cfg), 120);
+        cfg.preset_overrides.timeout_secs = Some(600);
+        assert_eq!(request_timeout_secs(&cfg), 600);
+    }
+
+    #[test]
+    fn test_extract_usage_per_format() {
+        let openai = serde_json::json!({"usage": {"prompt_tokens": 120, "completion_tokens": 30}});
+        assert_eq!(
+            extract_usage(&openai, RequestFormat::OpenAiCompat),
+            Some(TokenUsage {
+                input_tokens: 120,
+                output_tokens: 30
+            })
+        );
+        let gemini = serde_json::json!({"usageMetadata": {
+            "promptTokenCount": 100, "candidatesTokenCount": 20, "thoughtsTokenCount": 50
+        }});
+        assert_eq!(
+            extract_usage(&gemini, RequestFormat::Gemini).map(|u| u.output_tokens),
+            Some(70)
+        );
+        assert!(extract_usage(&openai, RequestFormat::Anthropic).is_none());
+    }
+
+    #[test]
+    fn test_prices_from_matches_longest_prefix() {
+        let none = BTreeMap::new();
+        let mini = prices_from("openai/gpt-4o-mini", &none).unwrap();
+        assert_eq!(mini.input_per_mtok, 0.15);
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+        };
+        assert!((mini.cost(&usage) - 0.45).abs() < 1e-9);
+        assert!(prices_from("qwen/qwen3.5-35b-a3b", &none).is_none());
+
+        let mut registry = BTreeMap::new();
+        registry.insert(
+            "gpt-4o-mini".to_string(),
+            ModelEntry {
+                input_cost_per_mtok: Some(0.2),
+                output_cost_per_mtok: Some(0.8),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            prices_from("gpt-4o-mini-2024", &registry)
+                .unwrap()
+                .input_per_mtok,
+            0.2
+        );
+        assert_eq!(
+            prices_from("gpt-4o", &registry).unwrap().input_per_mtok,
+            2.5
+        );
+    }
+
+    #[test]
+    fn test_max_tokens_for_respects_model_and_cap() {
+        assert_eq!(max_tokens_for(None, None), DEFAULT_MAX_TOKENS);
+        let large = ModelLimits {
+            context_window: 200_000,
+            max_output_tokens: 64_000,
+        };
+        assert_eq!(max_tokens_for(Some(large), None), MAX_TOKENS_CAP);
+        assert_eq!(
+            max_tokens_for(Some(large), Some(Reasoning::OpenAi)),
+            REASONING_MAX_TOKENS_CAP
+        );
+        let small = ModelLimits {
+            context_window: 4_096,
+            max_output_tokens: 4_096,
+        };
+        assert_eq!(max_tokens_for(Some(small), None), 1_024);
+    }
+
+    #[test]
+    fn test_build_capped_body_fits_context_window() {
+        let diff: String = (0..2000).map(|i| format!("+line {i}\n")).collect();
+        let diff =
+            format!("diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -0,0 +1,2000 @@\n{diff}");
+        let mut cfg = AppConfig {
+            context_window: 2_048,
+            ..AppConfig::default()
+        };
+        let body = build_capped_body(&cfg, RequestFormat::Anthropic, "sys", &diff).unwrap();
+        assert_eq!(body["max_tokens"], 512);
+        let content = body["messages"][0]["content"].as_str().unwrap();
+        assert!(content.len() <= (2_048 - 512 - PROMPT_OVERHEAD_TOKENS) * BYTES_PER_TOKEN);
+        assert!(content.contains("diff lines omitted"));
+
+        cfg.context_window = 0;
+        let body = build_capped_body(&cfg, RequestFormat::Anthropic, "sys", &diff).unwrap();
+        assert_eq!(body["messages"][0]["content"], diff.as_str());
+    }
+
     #[test]
     fn test_extract_by_path_gemini_format() {
         let json = serde_json::json!({
@@ -540,6 +1827,7 @@ mod tests {
         let body = build_request_body(
             RequestFormat::OpenAiCompat,
             "gpt-4o",
+            512,
             "system prompt",
             "user diff",
         );
@@ -557,6 +1845,7 @@ mod tests {
         let body = build_request_body(
             RequestFormat::Gemini,
             "gemini-pro",
+            512,
             "system prompt",
             "user diff",
         );
@@ -574,6 +1863,7 @@ mod tests {
         let body = build_request_body(
             RequestFormat::Anthropic,
             "claude-3-opus",
+            512,
             "system prompt",
             "user diff",
         );
@@ -589,6 +1879,7 @@ mod tests {
         let body = build_request_body(
             RequestFormat::LmStudio,
             "qwen/qwen3.5-35b-a3b",
+            512,
             "system prompt",
             "user diff",
         );
@@ -599,29 +1890,29 @@ mod tests {
 
     #[test]
     fn test_get_provider_known() {
-        assert!(get_provider("gemini").is_some());
-        assert!(get_provider("openai").is_some());
-        assert!(get_provider("anthropic").is_some());
-        assert!(get_provider("groq").is_some());
-        assert!(get_provider("grok").is_some());
-        assert!(get_provider("deepseek").is_some());
-        assert!(get_provider("openrouter").is_some());
-        assert!(get_provider("mistral").is_some());
-        assert!(get_provider("together").is_some());
-        assert!(get_provider("fireworks").is_some());
-        assert!(get_provider("perplexity").is_some());
-        assert!(get_provider("lm_studio").is_some());
+        assert!(builtin_provider("gemini").is_some());
+        assert!(builtin_provider("openai").is_some());
+        assert!(builtin_provider("anthropic").is_some());
+        assert!(builtin_provider("groq").is_some());
+        assert!(builtin_provider("grok").is_some());
+        assert!(builtin_provider("deepseek").is_some());
+        assert!(builtin_provider("openrouter").is_some());
+        assert!(builtin_provider("mistral").is_some());
+        assert!(builtin_provider("together").is_some());
+        assert!(builtin_provider("fireworks").is_some());
+        assert!(builtin_provider("perplexity").is_some());
+        assert!(builtin_provider("lm_studio").is_some());
     }
 
     #[test]
     fn test_get_provider_unknown() {
-        assert!(get_provider("unknown").is_none());
-        assert!(get_provider("custom").is_none());
+        assert!(builtin_provider("unknown").is_none());
+        assert!(builtin_provider("custom").is_none());
     }
 
     #[test]
     fn test_get_provider_gemini_format() {
-        let provider = get_provider("gemini").unwrap();
+        let provider = builtin_provider("gemini").unwrap();
         assert_eq!(provider.format, RequestFormat::Gemini);
         assert!(provider
             .api_url
@@ -631,7 +1922,7 @@ mod tests {
 
     #[test]
     fn test_get_provider_anthropic_format() {
-        let provider = get_provider("anthropic").unwrap();
+        let provider = builtin_provider("anthropic").unwrap();
         assert_eq!(provider.format, RequestFormat::Anthropic);
         assert!(provider.api_url.contains("anthropic.com"));
         assert!(provider.api_headers.contains("anthropic-version"));
@@ -650,7 +1941,7 @@ mod tests {
             "fireworks",
             "perplexity",
         ] {
-            let provider = get_provider(name).unwrap();
+            let provider = builtin_provider(name).unwrap();
             assert_eq!(
                 provider.format,
                 RequestFormat::OpenAiCompat,
@@ -661,13 +1952,49 @@ mod tests {
 
     #[test]
     fn test_get_provider_lm_studio_format() {
-        let provider = get_provider("lm_studio").unwrap();
+        let provider = builtin_provider("lm_studio").unwrap();
         assert_eq!(provider.format, RequestFormat::LmStudio);
         assert_eq!(provider.api_url, "http://localhost:1234/api/v1/chat");
         assert_eq!(provider.api_headers, "Content-Type: application/json");
         assert_eq!(provider.default_model, "qwen/qwen3.5-35b-a3b");
     }
 
+    #[test]
+    fn test_apply_registry_overrides_and_adds_providers() {
+        let entry = RegistryEntry {
+            default_model: Some("gpt-5-mini".into()),
+            ..Default::default()
+        };
+        let openai = apply_registry(builtin_provider("openai"), Some(&entry)).unwrap();
+        assert_eq!(openai.default_model, "gpt-5-mini");
+        assert_eq!(openai.api_url, "https://api.openai.com/v1/chat/completions");
+
+        let entry = RegistryEntry {
+            format: Some("anthropic".into()),
+            ..Default::default()
+        };
+        let switched = apply_registry(builtin_provider("groq"), Some(&entry)).unwrap();
+        assert_eq!(switched.format, RequestFormat::Anthropic);
+        assert_eq!(switched.response_path, "content.0.text");
+
+        let entry = RegistryEntry {
+            api_url: Some("https://api.example.com/v1/chat/completions".into()),
+            default_model: Some("example-1".into()),
+            ..Default::default()
+        };
+        let added = apply_registry(None, Some(&entry)).unwrap();
+        assert_eq!(added.format, RequestFormat::OpenAiCompat);
+        assert_eq!(added.response_path, "choices.0.message.content");
+        assert_eq!(added.default_model, "example-1");
+
+        let no_url = RegistryEntry {
+            default_model: Some("example-1".into()),
+            ..Default::default()
+        };
+        assert!(apply_registry(None, Some(&no_url)).is_none());
+        assert!(apply_registry(None, None).is_none());
+    }
+
     #[test]
     fn test_default_model_for_known() {
         assert_eq!(default_model_for("groq"), "llama-3.3-70b-versatile");
@@ -736,6 +2063,23 @@ mod tests {
         assert_eq!(path, "choices.0.message.content");
     }
 
+    #[test]
+    fn test_error_action_first_matching_rule_wins() {
+        let policy = "401=fail, 429=retry, 5xx=Retry, 503=fail";
+        assert_eq!(error_action(policy, 401), ErrorAction::Fail);
+        assert_eq!(error_action(policy, 429), ErrorAction::Retry);
+        assert_eq!(error_action(policy, 503), ErrorAction::Retry);
+        assert_eq!(error_action(policy, 404), ErrorAction::Fallback);
+        assert_eq!(error_action("*=fail", 500), ErrorAction::Fail);
+        assert_eq!(error_action("", 401), ErrorAction::Fallback);
+
+        assert!(parse_fallback_policy(policy).is_ok());
+        assert!(parse_fallback_policy("429").is_err());
+        assert!(parse_fallback_policy("42=retry").is_err());
+        assert!(parse_fallback_policy("5x=retry").is_err());
+        assert!(parse_fallback_policy("429=skip").is_err());
+    }
+
     #[test]
     fn test_llm_call_error_display_http() {
         let err = LlmCallError::HttpError {
@@ -794,4 +2138,47 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("type 'message'"));
     }
+
+    #[test]
+    fn test_check_preset_reports_errors() {
+        let preset = crate::preset::Preset {
+            id: 4,
+            name: "Offline".into(),
+            tags: Vec::new(),
+            fields: crate::preset::LlmPresetFields {
+                provider: "custom".into(),
+                model: "m".into(),
+                api_key: String::new(),
+                api_url: "http://127.0.0.1:9/v1/chat/completions".into(),
+                api_headers: String::new(),
+                overrides: Default::default(),
+            },
+        };
+        let check = check_preset(&AppConfig::default(), &preset);
+        assert!(check.error.unwrap().starts_with("Network error"));
+    }
+
+    #[test]
+    fn test_spinner_shows_target_attempt_and_timeout() {
+        let cfg = AppConfig {
+            provider: "openai".into(),
+            model: "gpt-4o".into(),
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            spinner_message(&cfg, PRIMARY_ATTEMPT),
+            "Generating commit message with openai · gpt-4o..."
+        );
+        let attempt = Attempt {
+            number: 2,
+            total: 3,
+            preset: Some("Backup"),
+        };
+        assert_eq!(
+            spinner_message(&cfg, attempt),
+            "Attempt 2/3: preset Backup (openai · gpt-4o)..."
+        );
+        assert!(spinner_template(120).ends_with("{elapsed}/120s"));
+        assert!(spinner_template(0).ends_with("{elapsed}"));
+    }
 }
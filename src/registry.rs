@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Provider registry published with the project, so default models and endpoints can change
+/// without a new release
+const REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/gtkacz/smart-commit-rs/main/providers.toml";
+
+/// Refetch the cached registry once it is older than this
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Contents of `providers.toml`: provider name → overrides of the built-in definition, and
+/// model name prefix → token limits
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Registry {
+    #[serde(default)]
+    pub providers: BTreeMap<String, RegistryEntry>,
+    #[serde(default)]
+    pub models: BTreeMap<String, ModelEntry>,
+}
+
+/// Fields a registry entry may set. Unset fields keep the built-in value; providers not built
+/// in need `api_url` and default to the OpenAI-compatible format.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RegistryEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_headers: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// `openai`, `anthropic`, `gemini` or `lm_studio`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_path: Option<String>,
+}
+
+/// Token limits of the models whose name starts with the entry's key
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ModelEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<usize>,
+    /// `openai` (developer role, `max_completion_tokens`), `generic` (no sampling parameters)
+    /// or `none`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// USD per million input tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cost_per_mtok: Option<f64>,
+    /// USD per million output tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_cost_per_mtok: Option<f64>,
+}
+
+/// Cached copy of the registry, next to the global config file
+pub fn registry_path() -> Option<PathBuf> {
+    crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("providers.toml")))
+}
+
+pub fn parse(content: &str) -> Result<Registry> {
+    toml::from_str(content).context("Failed to parse provider registry")
+}
+
+/// The cached registry; a missing or unreadable cache means only the built-in table applies
+pub fn load() -> Registry {
+    registry_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|content| parse(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Download the published registry and replace the cached copy
+pub fn refresh() -> Result<Registry> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(5))
+        .build();
+    let content = agent
+        .get(REGISTRY_URL)
+        .set("User-Agent", "cgen")
+        .call()
+        .context("Failed to download provider registry")?
+        .into_string()
+        .context("Failed to read provider registry")?;
+    let registry = parse(&content)?;
+
+    let path = registry_path().context("Could not determine provider registry path")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(registry)
+}
+
+/// Refresh the cache when it is missing or stale. Failures are ignored (the built-in table
+/// still applies), but the cache is marked as checked so an offline machine does not retry on
+/// every run.
+pub fn refresh_if_stale() {
+    let Some(path) = registry_path() else {
+        return;
+    };
+    let age = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if age.is_some_and(|age| age < MAX_AGE) {
+        return;
+    }
+    if refresh().is_ok() {
+        return;
+    }
+    if path.exists() {
+        let _ = std::fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+    } else if path.parent().is_some_and(|p| p.exists()) {
+        let _ = std::fs::write(&path, "");
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+
+use crate::config::AppConfig;
+use crate::preset::{self, Preset};
+
+/// Values of `ACR_ROUTING`
+pub const POLICIES: &[&str] = &["none", "round-robin", "random", "cheapest"];
+
+/// How a run picks its preset from the routing list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Use the configured provider
+    None,
+    /// Take the presets in turn, one per run
+    RoundRobin,
+    /// Pick a preset at random; listing one several times makes it more likely
+    Random,
+    /// Pick the cheapest preset whose context window fits the diff
+    Cheapest,
+}
+
+impl Policy {
+    pub fn parse(value: &str) -> Policy {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "round-robin" => Policy::RoundRobin,
+            "random" => Policy::Random,
+            "cheapest" => Policy::Cheapest,
+            _ => Policy::None,
+        }
+    }
+}
+
+/// Where the rotation stands, kept in `cache/routing.toml`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RoutingState {
+    /// Position in the routing list of the next round-robin run
+    #[serde(default)]
+    pub next: usize,
+}
+
+fn state_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("routing.toml"))
+}
+
+pub fn load_state() -> Result<RoutingState> {
+    let Some(path) = state_path().filter(|p| p.exists()) else {
+        return Ok(RoutingState::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_state(state: &RoutingState) -> Result<()> {
+    let path = state_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string(state).context("Failed to serialize routing state")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The routing list with ids that no longer exist left out
+pub fn candidates(file: &preset::PresetsFile) -> Vec<&Preset> {
+    file.routing
+        .presets
+        .iter()
+        .filter_map(|id| file.presets.iter().find(|p| p.id == *id))
+        .collect()
+}
+
+/// Index of the preset this run uses among `len` candidates, advancing `state` for round-robin.
+/// [`Policy::Cheapest`] depends on the diff and is picked by [`cheapest`] instead.
+pub fn pick(policy: Policy, len: usize, state: &mut RoutingState) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    match policy {
+        Policy::None | Policy::Cheapest => None,
+        Policy::RoundRobin => {
+            let index = state.next % len;
+            state.next = (index + 1) % len;
+            Some(index)
+        }
+        Policy::Random => {
+            let random = RandomState::new().build_hasher().finish();
+            Some((random % len as u64) as usize)
+        }
+    }
+}
+
+/// Index of the config with the lowest estimated cost for `diff` among those whose context
+/// window fits it, in list order on ties. Models without a known price come after priced
+/// ones. When none fits, the one with the largest context window is used.
+pub fn cheapest(configs: &[AppConfig], diff: &str) -> Option<usize> {
+    let cost = |cfg: &AppConfig| {
+        crate::provider::cost_usd(&cfg.model, &crate::provider::estimate_usage(cfg, diff))
+    };
+    let fitting = (0..configs.len()).filter(|&i| crate::provider::fits_context(&configs[i], diff));
+    fitting
+        .map(|i| (i, cost(&configs[i])))
+        .min_by(|(_, a), (_, b)| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        })
+        .map(|(i, _)| i)
+        .or_else(|| {
+            (0..configs.len()).min_by_key(|&i| {
+                std::cmp::Reverse(crate::provider::context_window(&configs[i]).unwrap_or(0))
+            })
+        })
+}
+
+/// `cfg` with the preset `ACR_ROUTING` picks for this run and `diff` applied, and the preset's
+/// name. `None` when routing is off or the routing list is empty.
+pub fn route(cfg: &AppConfig, diff: &str) -> Result<Option<(AppConfig, String)>> {
+    let policy = Policy::parse(&cfg.routing);
+    if policy == Policy::None {
+        return Ok(None);
+    }
+    let file = preset::load_presets()?;
+    let mut candidates = candidates(&file);
+    let with_preset = |p: &Preset| {
+        let mut routed = cfg.clone();
+        preset::apply_preset_to_config(&mut routed, p);
+        routed
+    };
+    if policy == Policy::Cheapest {
+        let mut seen = Vec::new();
+        candidates.retain(|p| {
+            let first = !seen.contains(&p.id);
+            seen.push(p.id);
+            first
+        });
+        let configs: Vec<AppConfig> = candidates.iter().map(|p| with_preset(p)).collect();
+        return Ok(
+            cheapest(&configs, diff).map(|i| (configs[i].clone(), candidates[i].name.clone()))
+        );
+    }
+    // A missing or broken state file only restarts the rotation
+    let mut state = load_state().unwrap_or_default();
+    let Some(index) = pick(policy, candidates.len(), &mut state) else {
+        return Ok(None);
+    };
+    if policy == Policy::RoundRobin {
+        let _ = save_state(&state);
+    }
+    let chosen = candidates[index];
+    Ok(Some((with_preset(chosen), chosen.name.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin_cycles_and_wraps() {
+        let mut state = RoutingState::default();
+        let picks: Vec<usize> = (0..5)
+            .map(|_| pick(Policy::RoundRobin, 3, &mut state).unwrap())
+            .collect();
+        assert_eq!(picks, vec![0, 1, 2, 0, 1]);
+
+        // A list that shrank since the last run starts over instead of going out of range
+        let mut state = RoutingState { next: 7 };
+        assert_eq!(pick(Policy::RoundRobin, 2, &mut state), Some(1));
+        assert_eq!(state.next, 0);
+    }
+
+    #[test]
+    fn test_random_stays_in_range_and_off_picks_nothing() {
+        let mut state = RoutingState::default();
+        for _ in 0..20 {
+            assert!(pick(Policy::Random, 3, &mut state).unwrap() < 3);
+        }
+        assert_eq!(state, RoutingState::default());
+        assert_eq!(pick(Policy::None, 3, &mut state), None);
+        assert_eq!(pick(Policy::RoundRobin, 0, &mut state), None);
+    }
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!(Policy::parse(" Round-Robin "), Policy::RoundRobin);
+        assert_eq!(Policy::parse("random"), Policy::Random);
+        assert_eq!(Policy::parse("cheapest"), Policy::Cheapest);
+        assert_eq!(Policy::parse(""), Policy::None);
+        assert_eq!(POLICIES.len(), 4);
+    }
+
+    #[test]
+    fn test_cheapest_fitting_model_wins_and_large_diffs_escalate() {
+        let model = |model: &str, context_window: usize| AppConfig {
+            model: model.into(),
+            context_window,
+            ..AppConfig::default()
+        };
+        let configs = vec![
+            model("gpt-4o", 16_384),
+            model("gpt-4o-mini", 16_384),
+            model("gpt-4.1", 1_000_000),
+            model("my-local-model", 0),
+        ];
+        assert_eq!(cheapest(&configs, "small diff"), Some(1));
+
+        // Too big for the 16k models: the cheaper of the rest that fit
+        let large = "x".repeat(100_000);
+        assert_eq!(cheapest(&configs, &large), Some(2));
+
+        // Nothing fits: the largest context window
+        let configs = vec![model("gpt-4o-mini", 8_000), model("gpt-4o", 12_000)];
+        assert_eq!(cheapest(&configs, &large), Some(1));
+        assert_eq!(cheapest(&[], "diff"), None);
+    }
+}
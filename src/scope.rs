@@ -0,0 +1,281 @@
+use crate::config::AppConfig;
+use glob::Pattern;
+use std::path::{Path, PathBuf};
+
+/// Directories never searched for Nx `project.json` files
+const SKIPPED_DIRS: &[&str] = &["node_modules", ".git", "target", "dist", "build", ".nx"];
+
+/// How deep below the repo root Nx `project.json` files are searched for
+const NX_MAX_DEPTH: usize = 4;
+
+/// Paths of the files touched by a unified diff, in diff order
+pub fn files_in_diff(diff: &str) -> Vec<String> {
+    diff.lines()
+        .filter_map(|line| line.strip_prefix("diff --git a/"))
+        .filter_map(|rest| rest.split(" b/").next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `glob=scope` entries, skipping malformed ones
+pub fn parse_scope_map(entries: &[String]) -> Vec<(Pattern, String)> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let (glob, scope) = entry.rsplit_once('=')?;
+            let scope = scope.trim();
+            if scope.is_empty() {
+                return None;
+            }
+            let pattern = Pattern::new(glob.trim()).ok()?;
+            Some((pattern, scope.to_string()))
+        })
+        .collect()
+}
+
+/// Scope owning the most files according to the `glob=scope` map. The first matching
+/// entry wins for each file; ties go to the scope listed first in the map.
+pub fn scope_from_map(entries: &[String], files: &[String]) -> Option<String> {
+    let map = parse_scope_map(entries);
+    if map.is_empty() {
+        return None;
+    }
+
+    let mut counts: Vec<(String, usize)> = map.iter().map(|(_, s)| (s.clone(), 0)).collect();
+    for file in files {
+        if let Some((_, scope)) = map.iter().find(|(p, _)| p.matches(file)) {
+            if let Some(entry) = counts.iter_mut().find(|(s, _)| s == scope) {
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let best = counts.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    if best == 0 {
+        return None;
+    }
+    counts
+        .into_iter()
+        .find(|(_, n)| *n == best)
+        .map(|(scope, _)| scope)
+}
+
+/// Scope to force for a diff, if one can be determined: the `scope_map` table first,
+/// then the workspace package owning most of the changed files. With allowed scopes
+/// (`ACR_ALLOWED_SCOPES` or commitlint `scope-enum`), the scope is mapped to the closest allowed
+/// one, or dropped when none is close.
+pub fn detect_scope(cfg: &AppConfig, diff: &str) -> Option<String> {
+    let files = files_in_diff(diff);
+    let scope = match scope_from_map(&cfg.scope_map, &files) {
+        Some(scope) => scope,
+        None if cfg.auto_scope => {
+            let root = crate::git::find_repo_root().ok()?;
+            scope_from_packages(&workspace_packages(Path::new(&root)), &files)?
+        }
+        None => return None,
+    };
+    let allowed = crate::postprocess::effective_allowed_scopes(cfg);
+    if allowed.is_empty() {
+        return Some(scope);
+    }
+    crate::postprocess::closest_allowed(&scope, &allowed)
+}
+
+/// Package owning the most files, by longest matching directory prefix
+pub fn scope_from_packages(packages: &[(String, String)], files: &[String]) -> Option<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for file in files {
+        let owner = packages
+            .iter()
+            .filter(|(dir, _)| file.starts_with(&format!("{dir}/")))
+            .max_by_key(|(dir, _)| dir.len());
+        if let Some((_, name)) = owner {
+            match counts.iter_mut().find(|(n, _)| n == name) {
+                Some(entry) => entry.1 += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+    }
+    let best = counts.iter().map(|(_, n)| *n).max()?;
+    counts
+        .into_iter()
+        .find(|(_, n)| *n == best)
+        .map(|(name, _)| name.to_string())
+}
+
+/// `(directory relative to root, scope name)` for every package of a Cargo, npm/yarn,
+/// pnpm or Nx workspace rooted at `root`
+pub fn workspace_packages(root: &Path) -> Vec<(String, String)> {
+    let mut packages: Vec<(String, String)> = Vec::new();
+    let mut add = |dir: &Path, name: String| {
+        let Ok(rel) = dir.strip_prefix(root) else {
+            return;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if rel.is_empty() || name.is_empty() || packages.iter().any(|(d, _)| *d == rel) {
+            return;
+        }
+        packages.push((rel, name));
+    };
+
+    for dir in cargo_members(root) {
+        if let Some(name) = cargo_package_name(&dir) {
+            add(&dir, name);
+        }
+    }
+    for dir in npm_members(root) {
+        if let Some(name) = npm_package_name(&dir) {
+            add(&dir, name);
+        }
+    }
+    if root.join("nx.json").is_file() {
+        for (dir, name) in nx_projects(root, root, 0) {
+            add(&dir, name);
+        }
+    }
+    packages
+}
+
+fn expand_member_globs(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    patterns
+        .iter()
+        .filter(|p| !p.starts_with('!'))
+        .filter_map(|p| glob::glob(&root.join(p.trim_end_matches('/')).to_string_lossy()).ok())
+        .flat_map(|paths| paths.filter_map(Result::ok))
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+fn cargo_members(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = std::fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(table) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let members: Vec<String> = table
+        .get("workspace")
+        .and_then(|w| w.get("members"))
+        .and_then(|m| m.as_array())
+        .map(|m| {
+            m.iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    expand_member_globs(root, &members)
+}
+
+fn cargo_package_name(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let table = content.parse::<toml::Table>().ok()?;
+    table
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn npm_members(root: &Path) -> Vec<PathBuf> {
+    let mut patterns: Vec<String> = Vec::new();
+
+    if let Some(json) = std::fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+    {
+        let workspaces = json.get("workspaces");
+        let list = workspaces
+            .and_then(|w| w.as_array())
+            .or_else(|| workspaces.and_then(|w| w.get("packages")?.as_array()));
+        if let Some(list) = list {
+            patterns.extend(list.iter().filter_map(|v| v.as_str().map(str::to_string)));
+        }
+    }
+
+    // pnpm-workspace.yaml: `packages:` followed by `- 'glob'` items
+    if let Ok(content) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        let mut in_packages = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if !line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+                in_packages = trimmed.starts_with("packages:");
+                continue;
+            }
+            if in_packages {
+                if let Some(item) = trimmed.strip_prefix('-') {
+                    patterns.push(item.trim().trim_matches(['\'', '"']).to_string());
+                }
+            }
+        }
+    }
+
+    expand_member_globs(root, &patterns)
+}
+
+fn npm_package_name(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let name = json.get("name")?.as_str()?;
+    // "@org/api" -> "api"
+    Some(name.rsplit('/').next().unwrap_or(name).to_string())
+}
+
+fn nx_projects(root: &Path, dir: &Path, depth: usize) -> Vec<(PathBuf, String)> {
+    let mut found = Vec::new();
+    if depth > NX_MAX_DEPTH {
+        return found;
+    }
+    if dir != root {
+        if let Some(name) = std::fs::read_to_string(dir.join("project.json"))
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|json| {
+                json.get("name")
+                    .and_then(|n| n.as_str())
+                    .map(str::to_string)
+                    .or_else(|| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+            })
+        {
+            found.push((dir.to_path_buf(), name));
+        }
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+            found.extend(nx_projects(root, &path, depth + 1));
+        }
+    }
+    found
+}
+
+/// Replace (or insert) the scope in a conventional commit header
+pub fn apply_scope(message: &str, scope: Option<&str>) -> String {
+    let Some(scope) = scope else {
+        return message.to_string();
+    };
+    let (header, rest) = match message.split_once('\n') {
+        Some((header, rest)) => (header, Some(rest)),
+        None => (message, None),
+    };
+    let Some(parsed) = crate::lint::parse_header(header) else {
+        return message.to_string();
+    };
+
+    let rebuilt = format!(
+        "{}{}({}){}: {}",
+        parsed.prefix,
+        parsed.commit_type,
+        scope,
+        if parsed.breaking { "!" } else { "" },
+        parsed.description
+    );
+    match rest {
+        Some(rest) => format!("{rebuilt}\n{rest}"),
+        None => rebuilt,
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Context, Result};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::sync::{Mutex, OnceLock};
+
+/// Prefix of encrypted values: `enc:v1:` + base64(salt ‖ nonce ‖ ciphertext)
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Passphrase used to encrypt and decrypt stored API keys
+pub const PASSPHRASE_ENV: &str = "ACR_SECRETS_PASSPHRASE";
+/// File whose (trimmed) contents are used as the passphrase
+pub const KEY_FILE_ENV: &str = "ACR_SECRETS_KEY_FILE";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+/// Decrypted values by ciphertext, so the config can be loaded repeatedly without re-deriving keys
+static DECRYPTED: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypt `plaintext` with a key derived from `passphrase` (Argon2id, random salt)
+pub fn encrypt_with(plaintext: &str, passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = cipher_for(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+
+    let mut payload = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    payload.extend_from_slice(&salt);
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{ENCRYPTED_PREFIX}{}", STANDARD.encode(payload)))
+}
+
+/// Decrypt a value produced by `encrypt_with`
+pub fn decrypt_with(value: &str, passphrase: &str) -> Result<String> {
+    let encoded = value
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .context("Value is not an encrypted secret")?;
+    let payload = STANDARD
+        .decode(encoded.trim())
+        .context("Encrypted secret is not valid base64")?;
+    if payload.len() <= SALT_LEN + NONCE_LEN {
+        bail!("Encrypted secret is truncated");
+    }
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let plaintext = cipher_for(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt secret: wrong passphrase?"))?;
+    String::from_utf8(plaintext).context("Decrypted secret is not UTF-8")
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {e}"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Passphrase from `ACR_SECRETS_PASSPHRASE`, the file in `ACR_SECRETS_KEY_FILE`, or a prompt
+/// (asked once per run). With `confirm`, used when encrypting, the prompt asks for it twice: a
+/// typo there would lock the keys behind a passphrase nobody knows.
+pub fn passphrase(confirm: bool) -> Result<String> {
+    if let Some(p) = PASSPHRASE.get() {
+        return Ok(p.clone());
+    }
+    let passphrase = if let Ok(p) = std::env::var(PASSPHRASE_ENV) {
+        p
+    } else if let Some(path) = std::env::var_os(KEY_FILE_ENV) {
+        std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read key file {}", path.to_string_lossy()))?
+            .trim()
+            .to_string()
+    } else if std::io::stdin().is_terminal() {
+        let prompt = inquire::Password::new("Passphrase for encrypted API keys:");
+        let prompt = if confirm {
+            prompt
+                .with_custom_confirmation_message("Repeat the passphrase:")
+                .with_custom_confirmation_error_message("The passphrases don't match.")
+        } else {
+            prompt.without_confirmation()
+        };
+        prompt
+            .prompt()
+            .context("Passphrase is required to use encrypted API keys")?
+    } else {
+        bail!("API keys are encrypted. Set {PASSPHRASE_ENV} or {KEY_FILE_ENV} to decrypt them");
+    };
+    if passphrase.is_empty() {
+        bail!("Passphrase for encrypted API keys must not be empty");
+    }
+    Ok(PASSPHRASE.get_or_init(|| passphrase).clone())
+}
+
+/// Encrypt a secret for storage; empty and already-encrypted values are kept as they are
+pub fn seal(value: &str) -> Result<String> {
+    if value.is_empty() || is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    encrypt_with(value, &passphrase(true)?)
+}
+
+/// Decrypt a stored secret; plaintext values pass through unchanged
+pub fn unseal(value: &str) -> Result<String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let mut cache = DECRYPTED.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(plaintext) = cache.get(value) {
+        return Ok(plaintext.clone());
+    }
+    let plaintext = decrypt_with(value, &passphrase(false)?)?;
+    cache.insert(value.to_string(), plaintext.clone());
+    Ok(plaintext)
+}
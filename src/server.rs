@@ -0,0 +1,230 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use crate::config::AppConfig;
+use crate::i18n::t;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A method that ran and failed, such as an LLM call or a git command
+const OPERATION_FAILED: i64 = -32000;
+
+/// Methods `cgen serve` answers
+pub const METHODS: &[&str] = &[
+    "generate-from-diff",
+    "generate-from-staged",
+    "lint",
+    "list-presets",
+];
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn failed(e: anyhow::Error) -> Self {
+        RpcError::new(OPERATION_FAILED, format!("{e:#}"))
+    }
+}
+
+/// Answer JSON-RPC 2.0 requests read one per line from stdin, each response on its own line
+/// of stdout, until stdin closes. Progress and notes stay off stdout.
+pub fn serve_stdio(cfg: &AppConfig) -> Result<()> {
+    crate::ui::set_quiet();
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context("Failed to read a request from stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(cfg, &line) {
+            writeln!(stdout, "{response}").context("Failed to write a response")?;
+            stdout.flush().context("Failed to write a response")?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one request line. Notifications (requests without an `id`) get none.
+pub fn handle_line(cfg: &AppConfig, line: &str) -> Option<String> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Parse error: {e}"));
+            return Some(response(Value::Null, Err(error)));
+        }
+    };
+    let method = request.get("method").and_then(Value::as_str);
+    let Some(method) = method.filter(|_| request.get("jsonrpc") == Some(&json!("2.0"))) else {
+        let error = RpcError::new(
+            INVALID_REQUEST,
+            "Invalid request: expected an object with \"jsonrpc\": \"2.0\" and a method",
+        );
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        return Some(response(id, Err(error)));
+    };
+    let params = request.get("params").unwrap_or(&Value::Null);
+    let _span = tracing::debug_span!("rpc", method).entered();
+    let result = dispatch(cfg, method, params);
+    let id = request.get("id")?.clone();
+    Some(response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> String {
+    let body = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    };
+    body.to_string()
+}
+
+fn dispatch(cfg: &AppConfig, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "generate-from-diff" => generate(cfg, &string_param(params, "diff")?),
+        "generate-from-staged" => {
+            let diff =
+                crate::git::get_staged_diff_filtered(&cfg.diff_exclude_globs, cfg.exclude_lfs)
+                    .map_err(RpcError::failed)?;
+            generate(cfg, &diff)
+        }
+        "lint" => {
+            let message = string_param(params, "message")?;
+            let rules = crate::lint::LintRules::from_config(cfg);
+            let violations: Vec<Value> = crate::lint::lint_message(&message, &rules)
+                .into_iter()
+                .map(|v| json!({ "rule": v.rule, "message": v.message }))
+                .collect();
+            Ok(json!({ "valid": violations.is_empty(), "violations": violations }))
+        }
+        "list-presets" => {
+            let file = crate::preset::load_presets().map_err(RpcError::failed)?;
+            // API keys stay out of the listing
+            let presets: Vec<Value> = file
+                .presets
+                .iter()
+                .map(|p| {
+                    json!({
+                        "id": p.id,
+                        "name": p.name,
+                        "provider": p.fields.provider,
+                        "model": p.fields.model,
+                        "tags": p.tags,
+                    })
+                })
+                .collect();
+            Ok(json!({ "presets": presets }))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{method}'; expected {}", METHODS.join(", ")),
+        )),
+    }
+}
+
+fn string_param(params: &Value, name: &str) -> Result<String, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing string param '{name}'")))
+}
+
+/// Generate a message for `diff` the way `cgen --dry-run` does, without the review step:
+/// routing, scope detection, fallback, post-processing and the commit template all apply
+fn generate(cfg: &AppConfig, diff: &str) -> Result<Value, RpcError> {
+    if diff.trim().is_empty() {
+        return Err(RpcError::new(INVALID_PARAMS, "The diff is empty"));
+    }
+    let routed = crate::routing::route(cfg, diff).map_err(RpcError::failed)?;
+    let (cfg, preset) = match &routed {
+        Some((routed, name)) => (routed, Some(name.as_str())),
+        None => (cfg, None),
+    };
+    if cfg.api_key.is_empty() {
+        return Err(RpcError::new(
+            OPERATION_FAILED,
+            "No API key configured. Run `cgen config` or set ACR_API_KEY",
+        ));
+    }
+    // There is nobody to ask: `confirm` refuses the request, `warn` only warns on stderr
+    if let Some(warning) =
+        crate::usage::check_request_budget(cfg, diff).map_err(RpcError::failed)?
+    {
+        if !cfg.budget_action.eq_ignore_ascii_case("warn") {
+            return Err(RpcError::new(OPERATION_FAILED, warning.message()));
+        }
+        eprintln!("{} {}", t("warning").yellow().bold(), warning.message());
+    }
+    let scope = crate::scope::detect_scope(cfg, diff);
+    let system_prompt = crate::prompt::system_prompt_for(cfg, scope.as_deref());
+    let (raw, fallback) = crate::provider::call_llm_with_fallback(cfg, &system_prompt, diff)
+        .map_err(RpcError::failed)?;
+    let message = crate::prompt::finalize_commit_message(cfg, &raw, scope.as_deref());
+    Ok(json!({
+        "message": crate::prompt::apply_commit_template(cfg, &message),
+        "preset": preset,
+        "fallback": fallback,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(line: &str) -> Value {
+        let cfg = AppConfig::default();
+        serde_json::from_str(&handle_line(&cfg, line).expect("a response")).unwrap()
+    }
+
+    #[test]
+    fn test_lint_reports_violations() {
+        let ok =
+            call(r#"{"jsonrpc":"2.0","id":1,"method":"lint","params":{"message":"feat: add x"}}"#);
+        assert_eq!(ok["id"], 1);
+        assert_eq!(ok["result"]["valid"], true);
+
+        let bad =
+            call(r#"{"jsonrpc":"2.0","id":"a","method":"lint","params":{"message":"added x"}}"#);
+        assert_eq!(bad["id"], "a");
+        assert_eq!(bad["result"]["valid"], false);
+        assert!(bad["result"]["violations"][0]["rule"].is_string());
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        assert_eq!(call("{not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(
+            call(r#"{"id":1,"method":"lint"}"#)["error"]["code"],
+            INVALID_REQUEST
+        );
+        let unknown = call(r#"{"jsonrpc":"2.0","id":2,"method":"commit"}"#);
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(unknown["id"], 2);
+        let missing = call(r#"{"jsonrpc":"2.0","id":3,"method":"generate-from-diff"}"#);
+        assert_eq!(missing["error"]["code"], INVALID_PARAMS);
+        let empty =
+            call(r#"{"jsonrpc":"2.0","id":4,"method":"generate-from-diff","params":{"diff":" "}}"#);
+        assert_eq!(empty["error"]["code"], INVALID_PARAMS);
+
+        // Notifications are answered with nothing
+        let cfg = AppConfig::default();
+        let notification = r#"{"jsonrpc":"2.0","method":"lint","params":{"message":"x"}}"#;
+        assert!(handle_line(&cfg, notification).is_none());
+    }
+}
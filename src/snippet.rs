@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use glob::Pattern;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// Repository directory whose `*.md` files are appended to the system prompt
+pub const REPO_SNIPPET_DIR: &str = ".cgen";
+
+/// A prompt fragment appended to the system prompt, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSnippet {
+    /// Human-readable origin shown by `cgen prompt` (e.g. `repo .cgen/prompt.md`)
+    pub source: String,
+    /// File stem used by the include/exclude filters
+    pub name: String,
+    pub text: String,
+}
+
+/// Directory of global snippets, next to the global config file
+pub fn global_snippet_dir() -> Option<PathBuf> {
+    crate::config::global_config_path().and_then(|p| p.parent().map(|d| d.join("prompts")))
+}
+
+/// Every non-blank `*.md` file in `dir`, sorted by file name
+pub fn load_dir(dir: &Path, source_prefix: &str) -> Vec<PromptSnippet> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(path).ok()?;
+            let file_name = path.file_name()?.to_string_lossy();
+            snippet(format!("{source_prefix}/{file_name}"), path, &text)
+        })
+        .collect()
+}
+
+/// Load a snippet passed with `--prompt-file`
+pub fn load_file(path: &Path) -> Result<PromptSnippet> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read prompt file {}", path.display()))?;
+    snippet(format!("--prompt-file {}", path.display()), path, &text)
+        .with_context(|| format!("Prompt file {} is empty", path.display()))
+}
+
+fn snippet(source: String, path: &Path, text: &str) -> Option<PromptSnippet> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(PromptSnippet {
+        source,
+        name: path.file_stem()?.to_string_lossy().to_string(),
+        text: text.to_string(),
+    })
+}
+
+/// Keep snippets whose name matches `include` (when non-empty) and none of `exclude`.
+/// Entries are glob patterns matched against the file stem.
+pub fn filter(
+    snippets: Vec<PromptSnippet>,
+    include: &[String],
+    exclude: &[String],
+) -> Vec<PromptSnippet> {
+    let compile = |globs: &[String]| -> Vec<Pattern> {
+        globs.iter().filter_map(|g| Pattern::new(g).ok()).collect()
+    };
+    let include = compile(include);
+    let exclude = compile(exclude);
+    snippets
+        .into_iter()
+        .filter(|s| include.is_empty() || include.iter().any(|p| p.matches(&s.name)))
+        .filter(|s| !exclude.iter().any(|p| p.matches(&s.name)))
+        .collect()
+}
+
+/// Global snippets, then the current repository's, filtered by the configured lists
+pub fn load_layered(cfg: &AppConfig) -> Vec<PromptSnippet> {
+    let mut snippets = Vec::new();
+    if let Some(dir) = global_snippet_dir() {
+        snippets.extend(load_dir(&dir, "global prompts"));
+    }
+    if let Ok(root) = crate::git::find_repo_root() {
+        snippets.extend(load_dir(
+            &Path::new(&root).join(REPO_SNIPPET_DIR),
+            &format!("repo {REPO_SNIPPET_DIR}"),
+        ));
+    }
+    filter(snippets, &cfg.prompt_include, &cfg.prompt_exclude)
+}
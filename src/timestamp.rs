@@ -0,0 +1,72 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` of a Unix timestamp
+pub fn iso8601(unix_secs: u64) -> String {
+    let (year, month, day) = civil_date(unix_secs);
+    let secs = unix_secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// `YYYY-MM-DD` of a Unix timestamp (UTC)
+pub fn date(unix_secs: u64) -> String {
+    let (year, month, day) = civil_date(unix_secs);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `YYYY-MM` of a Unix timestamp (UTC)
+pub fn month(unix_secs: u64) -> String {
+    let (year, month, _) = civil_date(unix_secs);
+    format!("{year:04}-{month:02}")
+}
+
+/// Year, month and day in the proleptic Gregorian calendar (Howard Hinnant's civil_from_days)
+fn civil_date(unix_secs: u64) -> (i64, i64, i64) {
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_month() {
+        assert_eq!(month(0), "1970-01");
+        assert_eq!(month(951_782_400), "2000-02"); // 2000-02-29
+        assert_eq!(month(1_767_225_599), "2025-12"); // 2025-12-31 23:59:59
+        assert_eq!(month(1_767_225_600), "2026-01");
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_767_225_599), "2025-12-31");
+    }
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso8601(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
+}
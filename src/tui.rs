@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::Frame;
+
+use crate::i18n::t;
+use crate::ui::{classify_diff_line, DiffLine};
+
+/// What the user picked on the review screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewChoice {
+    Accept,
+    Regenerate,
+    Refine,
+    Edit,
+    Cancel,
+}
+
+/// The choice a key press makes, or `None` for keys that scroll or do nothing
+pub fn choice_for_key(code: KeyCode, modifiers: KeyModifiers) -> Option<ReviewChoice> {
+    match code {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(ReviewChoice::Cancel)
+        }
+        KeyCode::Enter | KeyCode::Char('a') => Some(ReviewChoice::Accept),
+        KeyCode::Char('r') => Some(ReviewChoice::Regenerate),
+        KeyCode::Char('f') => Some(ReviewChoice::Refine),
+        KeyCode::Char('e') => Some(ReviewChoice::Edit),
+        KeyCode::Esc | KeyCode::Char('q') => Some(ReviewChoice::Cancel),
+        _ => None,
+    }
+}
+
+/// A diff line colored the way `git diff --color` shows it
+pub fn diff_line(line: &str) -> Line<'_> {
+    let style = match classify_diff_line(line) {
+        DiffLine::Header => Style::default().add_modifier(Modifier::BOLD),
+        DiffLine::Hunk => Style::default().fg(Color::Cyan),
+        DiffLine::Added => Style::default().fg(Color::Green),
+        DiffLine::Removed => Style::default().fg(Color::Red),
+        DiffLine::Context => Style::default(),
+    };
+    Line::styled(line, style)
+}
+
+/// Show `diff` and `message` side by side until the user picks what to do with the message.
+/// Arrow keys, PgUp/PgDn, Home/End (or j/k/g/G) scroll the diff.
+pub fn review(diff: &str, message: &str) -> Result<ReviewChoice> {
+    let lines: Vec<Line> = diff.lines().map(diff_line).collect();
+    let last = lines.len().saturating_sub(1);
+    let mut top = 0usize;
+    let mut page = 1usize;
+
+    let mut terminal = ratatui::try_init().context("Failed to open the review screen")?;
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| page = draw(frame, &lines, top, message)) {
+            break Err(e).context("Failed to draw the review screen");
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e).context("Failed to read a key"),
+        };
+        if let Some(choice) = choice_for_key(key.code, key.modifiers) {
+            break Ok(choice);
+        }
+        top = match key.code {
+            KeyCode::Down | KeyCode::Char('j') => top + 1,
+            KeyCode::Up | KeyCode::Char('k') => top.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => top + page,
+            KeyCode::PageUp => top.saturating_sub(page),
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => last,
+            _ => top,
+        }
+        .min(last);
+    };
+    ratatui::restore();
+    result
+}
+
+/// Draw the screen with the diff starting at line `top`. Returns how many diff lines fit.
+fn draw(frame: &mut Frame, lines: &[Line], top: usize, message: &str) -> usize {
+    let [main, help] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [diff_area, message_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+
+    let page = Block::bordered().inner(diff_area).height.max(1) as usize;
+    let visible: Vec<Line> = lines.iter().skip(top).take(page).cloned().collect();
+    let title = format!(
+        " {} ({}/{}) ",
+        t("tui_diff_title"),
+        top + 1,
+        lines.len().max(1)
+    );
+    frame.render_widget(
+        Paragraph::new(visible).block(Block::bordered().title(title)),
+        diff_area,
+    );
+    frame.render_widget(
+        Paragraph::new(message)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} ", t("tui_message_title")))),
+        message_area,
+    );
+    frame.render_widget(
+        Paragraph::new(t("tui_help")).style(Style::default().add_modifier(Modifier::DIM)),
+        help,
+    );
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choice_for_key() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            choice_for_key(KeyCode::Enter, none),
+            Some(ReviewChoice::Accept)
+        );
+        assert_eq!(
+            choice_for_key(KeyCode::Char('f'), none),
+            Some(ReviewChoice::Refine)
+        );
+        assert_eq!(
+            choice_for_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(ReviewChoice::Cancel)
+        );
+        assert_eq!(choice_for_key(KeyCode::Char('c'), none), None);
+        assert_eq!(choice_for_key(KeyCode::Down, none), None);
+    }
+
+    #[test]
+    fn test_draw_shows_diff_and_message() {
+        let backend = ratatui::backend::TestBackend::new(60, 8);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        let lines: Vec<Line> = "+one\n+two\n+three".lines().map(diff_line).collect();
+        let mut page = 0;
+        terminal
+            .draw(|frame| page = draw(frame, &lines, 1, "feat: add numbers"))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert_eq!(page, 5);
+        assert!(screen.contains("+two") && !screen.contains("+one"));
+        assert!(screen.contains("feat: add"));
+    }
+
+    #[test]
+    fn test_diff_line_colors() {
+        let fg = |line| diff_line(line).style.fg;
+        assert_eq!(fg("+added"), Some(Color::Green));
+        assert_eq!(fg("-removed"), Some(Color::Red));
+        assert_eq!(fg("@@ -1 +1 @@"), Some(Color::Cyan));
+        assert_eq!(fg("+++ b/file"), None);
+        assert_eq!(fg(" context"), None);
+    }
+}
@@ -1,18 +1,465 @@
-use inquire::Select;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use inquire::{Confirm, Select, Text};
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
 
-/// Replacement for `inquire::Confirm` — presents a Select with "Yes" / "No" choices.
-/// Returns `default_val` on cancellation (Esc/Ctrl-C).
+use crate::i18n::t;
+
+/// Force colors on or off for cgen's output and its prompts, overriding the `NO_COLOR` and
+/// terminal detection `colored` does on its own
+pub fn set_colors(enabled: bool) {
+    colored::control::set_override(enabled);
+    if !enabled {
+        inquire::set_global_render_config(inquire::ui::RenderConfig::empty());
+    }
+}
+
+/// How a yes/no question was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    No,
+    /// Esc or Ctrl-C: neither yes nor no
+    Cancelled,
+}
+
+/// Ask a yes/no question. `y`/`n` (or the locale's words) then Enter answers it, Enter alone
+/// takes `default_val`, shown capitalized in the `Y/n` hint.
+pub fn ask(prompt: &str, default_val: bool) -> Answer {
+    let (yes, no) = (t("yes"), t("no"));
+    let parser = |input: &str| parse_answer(input, &yes, &no).ok_or(());
+    let hint = |default: bool| answer_hint(&yes, &no, default);
+    let formatter = |answer: bool| if answer { yes.clone() } else { no.clone() };
+    let error = t("confirm_invalid");
+    match Confirm::new(prompt)
+        .with_default(default_val)
+        .with_parser(&parser)
+        .with_default_value_formatter(&hint)
+        .with_formatter(&formatter)
+        .with_error_message(&error)
+        .prompt()
+    {
+        Ok(true) => Answer::Yes,
+        Ok(false) => Answer::No,
+        Err(_) => Answer::Cancelled,
+    }
+}
+
+/// `ask` for callers that only go ahead on a yes: a cancelled question counts as declined
 pub fn confirm(prompt: &str, default_val: bool) -> bool {
-    let choices = if default_val {
-        vec!["Yes", "No"]
+    ask(prompt, default_val) == Answer::Yes
+}
+
+/// Read a typed answer: the locale's words or their first letter, and English `y`/`n`
+fn parse_answer(input: &str, yes: &str, no: &str) -> Option<bool> {
+    let input = input.trim().to_lowercase();
+    let matches = |word: &str, english: &str| {
+        let word = word.to_lowercase();
+        input == word
+            || word.chars().next().is_some_and(|c| input == c.to_string())
+            || input == english
+            || input == english[..1]
+    };
+    if matches(yes, "yes") {
+        Some(true)
+    } else if matches(no, "no") {
+        Some(false)
     } else {
-        vec!["No", "Yes"]
+        None
+    }
+}
+
+/// `y/N`-style hint from the first letters of the locale's words
+fn answer_hint(yes: &str, no: &str, default_val: bool) -> String {
+    let initial = |word: &str, upper: bool| {
+        let c = word.chars().next().unwrap_or('?');
+        if upper {
+            c.to_uppercase().to_string()
+        } else {
+            c.to_lowercase().to_string()
+        }
+    };
+    format!(
+        "{}/{}",
+        initial(yes, default_val),
+        initial(no, !default_val)
+    )
+}
+
+/// Let the user edit a message in their git editor, falling back to an inline prompt
+/// when no editor is configured. Returns `None` if the inline prompt is cancelled; an
+/// emptied editor buffer comes back as an empty message.
+pub fn edit_message(message: &str) -> Result<Option<String>> {
+    match crate::git::resolve_editor() {
+        Some(editor) => open_in_editor(&editor, message).map(Some),
+        None => Ok(Text::new(&t("edit_message"))
+            .with_initial_value(message)
+            .prompt()
+            .ok()),
+    }
+}
+
+/// Write `message` to a temp file, open it with `editor` and read the result back.
+/// The editor string is run through the shell so values like `code --wait` work.
+/// As in git's own editor flow, lines starting with `#` are dropped; an empty result
+/// means the user cancelled.
+pub fn open_in_editor(editor: &str, message: &str) -> Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("cgen-")
+        .suffix("-COMMIT_EDITMSG")
+        .tempfile()
+        .context("Failed to create a temp file for the editor")?;
+    write!(file, "{}\n\n# {}\n", message.trim_end(), t("editor_hint"))
+        .and_then(|_| file.flush())
+        .with_context(|| format!("Failed to write {}", file.path().display()))?;
+
+    let status = editor_command(editor, &file.path().to_string_lossy())
+        .status()
+        .with_context(|| format!("Failed to launch editor '{editor}'"))?;
+    if !status.success() {
+        bail!("Editor '{editor}' exited with status {status}");
+    }
+    // Read by path: editors often replace the file instead of writing through our handle
+    let edited = std::fs::read_to_string(file.path())
+        .with_context(|| format!("Failed to read {}", file.path().display()))?;
+    Ok(strip_comments(&edited))
+}
+
+/// `text` without `#` comment lines and surrounding blank lines
+fn strip_comments(text: &str) -> String {
+    let kept: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+    match kept.join("\n").trim() {
+        "" => String::new(),
+        message => format!("{message}\n"),
+    }
+}
+
+#[cfg(unix)]
+fn editor_command(editor: &str, path: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", &format!("{editor} \"$@\""), editor, path]);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn editor_command(editor: &str, path: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", &format!("{editor} \"{path}\"")]);
+    cmd
+}
+
+/// Kinds of unified diff lines, for coloring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine {
+    /// `diff --git`, `index`, `---` and `+++` lines
+    Header,
+    /// `@@ ... @@`
+    Hunk,
+    Added,
+    Removed,
+    Context,
+}
+
+pub fn classify_diff_line(line: &str) -> DiffLine {
+    if line.starts_with("diff --git")
+        || line.starts_with("index ")
+        || line.starts_with("+++")
+        || line.starts_with("---")
+    {
+        DiffLine::Header
+    } else if line.starts_with("@@") {
+        DiffLine::Hunk
+    } else if line.starts_with('+') {
+        DiffLine::Added
+    } else if line.starts_with('-') {
+        DiffLine::Removed
+    } else {
+        DiffLine::Context
+    }
+}
+
+/// Color a unified diff the way `git diff --color` does
+pub fn colorize_diff(diff: &str) -> String {
+    let mut out = String::with_capacity(diff.len());
+    for line in diff.lines() {
+        let colored = match classify_diff_line(line) {
+            DiffLine::Header => line.bold().to_string(),
+            DiffLine::Hunk => line.cyan().to_string(),
+            DiffLine::Added => line.green().to_string(),
+            DiffLine::Removed => line.red().to_string(),
+            DiffLine::Context => line.to_string(),
+        };
+        out.push_str(&colored);
+        out.push('\n');
+    }
+    out
+}
+
+static PAGER_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Print long output directly from now on (`--no-pager`)
+pub fn disable_pager() {
+    PAGER_DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn pager_disabled() -> bool {
+    PAGER_DISABLED.load(Ordering::Relaxed)
+}
+
+/// Characters drawn for trees, arrows, separators and the spinner
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Tree connector to an item with siblings below it
+    pub branch: &'static str,
+    /// Tree connector to the last item
+    pub last: &'static str,
+    /// Continues a tree past a nested branch
+    pub pipe: &'static str,
+    /// Marks an expanded group
+    pub open: &'static str,
+    /// Marks a collapsed group
+    pub closed: &'static str,
+    pub arrow: &'static str,
+    pub rule: &'static str,
+    pub dot: &'static str,
+    /// Spinner frames for indicatif's `tick_chars`; the last one is shown when finished
+    pub spinner: &'static str,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    branch: "\u{251C}\u{2500}\u{2500}",
+    last: "\u{2514}\u{2500}\u{2500}",
+    pipe: "\u{2502}",
+    open: "\u{25BC}",
+    closed: "\u{25B6}",
+    arrow: "\u{2192}",
+    rule: "\u{2500}\u{2500}",
+    dot: "\u{00B7}",
+    spinner: "\u{2801}\u{2802}\u{2804}\u{2840}\u{2880}\u{2820}\u{2810}\u{2808} ",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    branch: "|--",
+    last: "`--",
+    pipe: "|",
+    open: "v",
+    closed: ">",
+    arrow: "->",
+    rule: "--",
+    dot: "-",
+    spinner: "|/-\\ ",
+};
+
+static ASCII_UI: AtomicBool = AtomicBool::new(false);
+
+/// Draw with ASCII only from now on (`ACR_ASCII_UI`)
+pub fn set_ascii() {
+    ASCII_UI.store(true, Ordering::Relaxed);
+}
+
+pub fn glyphs() -> &'static Glyphs {
+    if ASCII_UI.load(Ordering::Relaxed) {
+        &ASCII_GLYPHS
+    } else {
+        &UNICODE_GLYPHS
+    }
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Print only the final message and errors from now on (`--quiet`)
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// How many times `--verbose` was given
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+static LOG_FILE: Mutex<Option<std::fs::File>> = Mutex::new(None);
+
+/// Append the debug log to `path` from now on (`--log-file`)
+pub fn set_log_file(path: &std::path::Path) -> Result<()> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    log(&format!(
+        "cgen {} started: {}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::args().collect::<Vec<_>>().join(" ")
+    ));
+    Ok(())
+}
+
+/// [`log`] a formatted line and emit it as a `tracing` debug event of the calling module, so
+/// `RUST_LOG=auto_commit_rs::git=debug` can pick it out
+macro_rules! log_event {
+    ($($arg:tt)*) => {{
+        let text = format!($($arg)*);
+        tracing::debug!("{text}");
+        $crate::ui::log(&text);
+    }};
+}
+
+/// [`debug`] a formatted line and emit it as a `tracing` debug event of the calling module
+macro_rules! debug_event {
+    ($($arg:tt)*) => {{
+        let text = format!($($arg)*);
+        tracing::debug!("{text}");
+        $crate::ui::debug(&text);
+    }};
+}
+
+pub(crate) use {debug_event, log_event};
+
+/// Write a timestamped line to the `--log-file`, if there is one. Callers mask secrets first.
+pub fn log(text: &str) {
+    let mut file = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(file) = file.as_mut() {
+        let now = crate::timestamp::iso8601(crate::timestamp::now_unix());
+        // Logging is best-effort and must never fail the command
+        let _ = writeln!(file, "{now} {text}");
+    }
+}
+
+/// Log a `debug:` line, such as a provider request or a fallback decision, to the
+/// `--log-file` and, with `-vv`, to stderr. Callers mask secrets first.
+pub fn debug(text: &str) {
+    log(text);
+    if verbosity() >= 2 {
+        eprintln!("  {} {}", t("debug").blue().bold(), text.dimmed());
+    }
+}
+
+/// Print an indented `note:` line, unless in quiet mode
+pub fn note(text: &str) {
+    if !quiet() {
+        println!("  {} {}", t("note").yellow().bold(), text);
+    }
+}
+
+/// Show `text` through `$PAGER` (`less -R` on Unix when unset) if stdout is a terminal.
+/// Without a terminal, with `--no-pager` or when no pager starts, the text is printed as is.
+pub fn page(text: &str) -> Result<()> {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .or_else(|| cfg!(unix).then(|| "less -R".to_string()));
+    let Some(pager) = pager.filter(|_| std::io::stdout().is_terminal() && !pager_disabled()) else {
+        print!("{text}");
+        return Ok(());
+    };
+
+    let mut cmd = shell_command(&pager);
+    if std::env::var_os("LESS").is_none() {
+        // Like git: quit when it fits on one screen, keep colors, don't clear the screen
+        cmd.env("LESS", "FRX");
+    }
+    let Ok(mut child) = cmd.stdin(Stdio::piped()).spawn() else {
+        print!("{text}");
+        return Ok(());
     };
-    match Select::new(prompt, choices).prompt() {
-        Ok("Yes") => true,
-        Ok("No") => false,
-        _ => default_val,
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which is not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait().context("Failed to wait for the pager")?;
+    Ok(())
+}
+
+/// Run `command_line` through the platform shell
+#[cfg(unix)]
+pub fn shell_command(command_line: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command_line]);
+    cmd
+}
+
+#[cfg(not(unix))]
+pub fn shell_command(command_line: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command_line]);
+    cmd
+}
+
+/// Clipboard commands to try, in order
+#[cfg(target_os = "macos")]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// Copy `text` to the system clipboard with the first clipboard tool that works
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    for args in CLIPBOARD_COMMANDS {
+        let child = Command::new(args[0])
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .context("Failed to write to clipboard")?;
+        }
+        if child.wait().is_ok_and(|s| s.success()) {
+            return Ok(());
+        }
     }
+    let tools: Vec<&str> = CLIPBOARD_COMMANDS.iter().map(|args| args[0]).collect();
+    bail!("No clipboard tool found (tried {})", tools.join(", "))
+}
+
+/// Fuzzy scorer for `Select`/`MultiSelect` that matches what the user sees: color codes and
+/// tree characters in an option are ignored, so typing "push" finds "├─ Post-commit push".
+pub fn fuzzy_score<T>(input: &str, _option: &T, value: &str, idx: usize) -> Option<i64> {
+    let visible = strip_tree_chars(&strip_ansi(value));
+    Select::<String>::DEFAULT_SCORER(input, &visible, &visible, idx)
+}
+
+/// `s` without ANSI color escape sequences
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end at the first letter, e.g. `\x1b[1;32m`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
 }
 
 /// Strip tree-drawing Unicode characters from a string for cleaner display.
@@ -36,6 +483,120 @@ pub fn strip_tree_chars(s: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_log_file_appends_timestamped_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cgen.log");
+        std::fs::write(&path, "earlier run\n").unwrap();
+        set_log_file(&path).unwrap();
+        debug("POST https://llm.example/v1");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "earlier run");
+        assert!(lines[1].contains(" cgen ") && lines[1].contains(" started: "));
+        assert!(lines
+            .iter()
+            .any(|l| l.ends_with("Z POST https://llm.example/v1")));
+        assert!(set_log_file(&dir.path().join("missing").join("cgen.log")).is_err());
+    }
+
+    #[test]
+    fn test_ascii_glyphs_are_ascii() {
+        let g = &ASCII_GLYPHS;
+        for glyph in [
+            g.branch, g.last, g.pipe, g.open, g.closed, g.arrow, g.rule, g.dot, g.spinner,
+        ] {
+            assert!(glyph.is_ascii(), "{glyph}");
+        }
+        // Unicode tree labels are still stripped for fuzzy matching
+        let label = format!("{} {} Provider", UNICODE_GLYPHS.pipe, UNICODE_GLYPHS.branch);
+        assert_eq!(strip_tree_chars(&label), "Provider");
+    }
+
+    #[test]
+    fn test_parse_answer_accepts_locale_words_and_english_letters() {
+        assert_eq!(parse_answer("Y", "Yes", "No"), Some(true));
+        assert_eq!(parse_answer(" no ", "Yes", "No"), Some(false));
+        assert_eq!(parse_answer("s", "Sim", "Não"), Some(true));
+        assert_eq!(parse_answer("NÃO", "Sim", "Não"), Some(false));
+        assert_eq!(parse_answer("y", "Sim", "Não"), Some(true));
+        assert_eq!(parse_answer("maybe", "Yes", "No"), None);
+        assert_eq!(answer_hint("Yes", "No", false), "y/N");
+        assert_eq!(answer_hint("Sim", "Não", true), "S/n");
+    }
+
+    #[test]
+    fn test_fuzzy_score_ignores_colors_and_tree_chars() {
+        let label = format!("├─ {}", "Post-commit push".cyan());
+        assert!(fuzzy_score("pcp", &(), &label, 0).is_some());
+        // "m" only appears in the color codes
+        assert!(fuzzy_score("m", &(), &"Post".cyan().to_string(), 0).is_none());
+        assert_eq!(strip_ansi(&"x".red().bold().to_string()), "x");
+    }
+
+    #[test]
+    fn test_colorize_diff_colors_added_and_removed_lines() {
+        colored::control::set_override(true);
+        let out = colorize_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new\n same");
+        colored::control::unset_override();
+        assert!(out.contains(&"-old".red().to_string()));
+        assert!(out.contains(&"+new".green().to_string()));
+        assert!(out.contains(&"+++ b/x".bold().to_string()));
+        assert!(out.ends_with(" same\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_in_editor_reads_back_edited_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("fake-editor.sh");
+        std::fs::write(&script, "#!/bin/sh\nprintf 'fix: edited\\n' > \"$1\"\n").unwrap();
+        let editor = format!("sh {}", script.display());
+
+        let edited = open_in_editor(&editor, "feat: original").unwrap();
+        assert_eq!(edited, "fix: edited\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_in_editor_keeps_message_when_untouched() {
+        let edited = open_in_editor("true", "feat: original").unwrap();
+        assert_eq!(edited, "feat: original\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_in_editor_strips_comments_and_empty_means_cancel() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("fake-editor.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nprintf '# note\\nfix: edited\\n\\nbody\\n# more\\n' > \"$1\"\n",
+        )
+        .unwrap();
+        let edited = open_in_editor(&format!("sh {}", script.display()), "feat: x").unwrap();
+        assert_eq!(edited, "fix: edited\n\nbody\n");
+
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nprintf '# only comments\\n\\n' > \"$1\"\n",
+        )
+        .unwrap();
+        let emptied = open_in_editor(&format!("sh {}", script.display()), "feat: x").unwrap();
+        assert_eq!(emptied, "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_in_editor_fails_on_editor_error() {
+        let result = open_in_editor("false", "feat: original");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("exited with status"));
+    }
+
     #[test]
     fn test_strip_tree_chars_branch() {
         let input = "  ├── Provider              groq";
@@ -0,0 +1,170 @@
+use crate::git::ResetMode;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Undos kept in `undo.toml` across all repositories; older ones are dropped first
+const MAX_ENTRIES: usize = 20;
+
+/// A `cgen undo`, with enough to put the commit back
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UndoEntry {
+    pub repo_path: String,
+    /// HEAD before the undo
+    pub undone: String,
+    /// HEAD after the undo
+    pub target: String,
+    /// Tree the index must match to redo: `undone`'s after a soft undo, `target`'s after a
+    /// mixed one
+    pub tree: String,
+    /// The undo also unstaged the changes (`--mixed`), so redo stages them again
+    #[serde(default)]
+    pub mixed: bool,
+    pub message: String,
+    /// ISO 8601, UTC
+    pub timestamp: String,
+}
+
+/// Contents of `undo.toml`, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UndoJournal {
+    #[serde(default)]
+    pub entries: Vec<UndoEntry>,
+}
+
+fn journal_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("undo.toml"))
+}
+
+pub fn load() -> Result<UndoJournal> {
+    let Some(path) = journal_path().filter(|p| p.exists()) else {
+        return Ok(UndoJournal::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save(journal: &UndoJournal) -> Result<()> {
+    let path = journal_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string_pretty(journal).context("Failed to serialize undo journal")?;
+    let tmp_path = path.with_extension("toml.tmp");
+    std::fs::write(&tmp_path, &content)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to rename temp file to {}", path.display()))
+}
+
+/// Whether an undo in `mode` can be redone. A discard throws the changes away, so it is
+/// never journaled.
+pub fn redoable(mode: ResetMode) -> bool {
+    mode != ResetMode::Hard
+}
+
+/// How `cgen redo` moves HEAD back to `entry.undone`: a soft undo left the changes staged, a
+/// mixed one left them unstaged in the working tree and the index has to follow HEAD
+pub fn redo_mode(entry: &UndoEntry) -> ResetMode {
+    if entry.mixed {
+        ResetMode::Mixed
+    } else {
+        ResetMode::Soft
+    }
+}
+
+/// Remember an undo so `cgen redo` can reverse it
+pub fn record(entry: UndoEntry) -> Result<()> {
+    let mut journal = load()?;
+    journal.entries.push(entry);
+    let excess = journal.entries.len().saturating_sub(MAX_ENTRIES);
+    journal.entries.drain(..excess);
+    save(&journal)
+}
+
+/// The latest undo in `repo_path`, if any
+pub fn last_for(repo_path: &str) -> Result<Option<UndoEntry>> {
+    Ok(load()?
+        .entries
+        .into_iter()
+        .rev()
+        .find(|e| e.repo_path == repo_path))
+}
+
+/// Forget the latest undo in `repo_path` once it has been redone
+pub fn remove_last_for(repo_path: &str) -> Result<()> {
+    let mut journal = load()?;
+    if let Some(idx) = journal
+        .entries
+        .iter()
+        .rposition(|e| e.repo_path == repo_path)
+    {
+        journal.entries.remove(idx);
+        save(&journal)?;
+    }
+    Ok(())
+}
+
+/// Why the latest undo cannot be redone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedoBlocker {
+    /// HEAD moved since the undo (new commits, checkout, reset)
+    HeadMoved,
+    /// The staged changes are not the ones the undone commit had
+    IndexChanged,
+}
+
+/// Whether `entry` can be redone with HEAD at `head` and the index at `index_tree`
+pub fn check_redo(entry: &UndoEntry, head: &str, index_tree: &str) -> Option<RedoBlocker> {
+    if head != entry.target {
+        Some(RedoBlocker::HeadMoved)
+    } else if index_tree != entry.tree {
+        Some(RedoBlocker::IndexChanged)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> UndoEntry {
+        UndoEntry {
+            repo_path: "/repo".into(),
+            undone: "bbb".into(),
+            target: "aaa".into(),
+            tree: "t1".into(),
+            mixed: false,
+            message: "feat: x".into(),
+            timestamp: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_check_redo() {
+        assert_eq!(check_redo(&entry(), "aaa", "t1"), None);
+        assert_eq!(
+            check_redo(&entry(), "ccc", "t1"),
+            Some(RedoBlocker::HeadMoved)
+        );
+        assert_eq!(
+            check_redo(&entry(), "aaa", "t2"),
+            Some(RedoBlocker::IndexChanged)
+        );
+    }
+
+    #[test]
+    fn test_redo_follows_the_undo_mode() {
+        assert!(redoable(ResetMode::Soft) && redoable(ResetMode::Mixed));
+        assert!(!redoable(ResetMode::Hard));
+        assert_eq!(redo_mode(&entry()), ResetMode::Soft);
+        let mixed = UndoEntry {
+            mixed: true,
+            ..entry()
+        };
+        assert_eq!(redo_mode(&mixed), ResetMode::Mixed);
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::i18n::{t, tf};
+
 const GITHUB_REPO: &str = "gtkacz/smart-commit-rs";
 const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -11,48 +16,202 @@ pub struct VersionCheck {
     pub update_available: bool,
 }
 
-/// Fetch the latest release tag from GitHub API with a short timeout
-pub fn fetch_latest_version() -> Result<String> {
+/// Which releases count as updates (`ACR_UPDATE_CHANNEL`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateChannel {
+    /// Full releases only
+    #[default]
+    Stable,
+    /// Full releases plus `-beta` and `-rc` pre-releases
+    Beta,
+    /// Every release, including nightly and alpha builds
+    Nightly,
+}
+
+impl UpdateChannel {
+    /// Unknown values fall back to stable
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "beta" => UpdateChannel::Beta,
+            "nightly" => UpdateChannel::Nightly,
+            _ => UpdateChannel::Stable,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+            UpdateChannel::Nightly => "nightly",
+        }
+    }
+
+    /// Whether a release with this version belongs to the channel
+    pub fn accepts(self, version: &SemVer) -> bool {
+        let Some(first) = version.pre.first() else {
+            return true;
+        };
+        match self {
+            UpdateChannel::Stable => false,
+            UpdateChannel::Beta => {
+                let first = first.to_ascii_lowercase();
+                first.starts_with("beta") || first.starts_with("rc")
+            }
+            UpdateChannel::Nightly => true,
+        }
+    }
+}
+
+/// A `major.minor.patch[-pre][+build]` version, ordered by semver precedence: a pre-release
+/// sorts before its release, and build metadata is ignored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Dot-separated pre-release identifiers, empty for a release
+    pub pre: Vec<String>,
+}
+
+impl SemVer {
+    /// A release version without pre-release identifiers
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        SemVer {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+        }
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {
+                    for (a, b) in self.pre.iter().zip(&other.pre) {
+                        // Numeric identifiers compare numerically and sort before alphanumeric ones
+                        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                            (Ok(a), Ok(b)) => a.cmp(&b),
+                            (Ok(_), Err(_)) => Ordering::Less,
+                            (Err(_), Ok(_)) => Ordering::Greater,
+                            (Err(_), Err(_)) => a.cmp(b),
+                        };
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                    self.pre.len().cmp(&other.pre.len())
+                }
+            })
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn github_get(agent: &ureq::Agent, url: &str) -> Result<serde_json::Value> {
+    agent
+        .get(url)
+        .set("User-Agent", "cgen")
+        .set("Accept", "application/vnd.github.v3+json")
+        .call()
+        .context("Failed to reach GitHub API")?
+        .into_json()
+        .context("Failed to parse GitHub API response")
+}
+
+/// The newest published release on `channel`, as returned by the GitHub API
+fn fetch_release(agent: &ureq::Agent, channel: UpdateChannel) -> Result<serde_json::Value> {
     let url = format!(
-        "https://api.github.com/repos/{}/releases/latest",
+        "https://api.github.com/repos/{}/releases?per_page=50",
         GITHUB_REPO
     );
+    let releases = github_get(agent, &url)?;
+    let releases = releases
+        .as_array()
+        .context("Unexpected GitHub releases response")?;
+    pick_release(releases, channel)
+        .cloned()
+        .with_context(|| format!("No {} release found", channel.name()))
+}
+
+/// The release with the highest version among those `channel` accepts, skipping drafts.
+/// Stable also skips releases GitHub marks as pre-releases.
+pub fn pick_release(
+    releases: &[serde_json::Value],
+    channel: UpdateChannel,
+) -> Option<&serde_json::Value> {
+    releases
+        .iter()
+        .filter(|r| !r["draft"].as_bool().unwrap_or(false))
+        .filter(|r| channel != UpdateChannel::Stable || !r["prerelease"].as_bool().unwrap_or(false))
+        .filter_map(|r| Some((parse_semver(r["tag_name"].as_str()?)?, r)))
+        .filter(|(version, _)| channel.accepts(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, r)| r)
+}
+
+/// Fetch the latest release tag on `channel` from GitHub API with a short timeout
+pub fn fetch_latest_version(channel: UpdateChannel) -> Result<String> {
     let agent = ureq::AgentBuilder::new()
         .timeout(Duration::from_secs(5))
         .build();
-    let response: serde_json::Value = agent
-        .get(&url)
-        .set("User-Agent", "cgen")
-        .set("Accept", "application/vnd.github.v3+json")
-        .call()
-        .context("Failed to reach GitHub API")?
-        .into_json()
-        .context("Failed to parse GitHub API response")?;
+    let release = fetch_release(&agent, channel)?;
 
-    let tag = response["tag_name"]
+    let tag = release["tag_name"]
         .as_str()
         .context("No tag_name in GitHub release response")?;
 
     Ok(tag.to_string())
 }
 
-/// Parse a version string (strips leading 'v' if present) into (major, minor, patch)
-pub fn parse_semver(version: &str) -> Option<(u64, u64, u64)> {
+/// Parse a version string (strips leading 'v' if present), with optional pre-release and
+/// build metadata
+pub fn parse_semver(version: &str) -> Option<SemVer> {
     let v = version.strip_prefix('v').unwrap_or(version);
-    let parts: Vec<&str> = v.split('.').collect();
+    let v = v.split_once('+').map_or(v, |(v, _build)| v);
+    let (core, pre) = match v.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (v, None),
+    };
+    let parts: Vec<&str> = core.split('.').collect();
     if parts.len() != 3 {
         return None;
     }
-    Some((
-        parts[0].parse().ok()?,
-        parts[1].parse().ok()?,
-        parts[2].parse().ok()?,
-    ))
+    let pre: Vec<String> = match pre {
+        Some(pre) => pre.split('.').map(str::to_string).collect(),
+        None => Vec::new(),
+    };
+    let valid_identifier =
+        |id: &String| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !pre.iter().all(valid_identifier) {
+        return None;
+    }
+    Some(SemVer {
+        major: parts[0].parse().ok()?,
+        minor: parts[1].parse().ok()?,
+        patch: parts[2].parse().ok()?,
+        pre,
+    })
 }
 
-/// Check if a newer version is available on GitHub
-pub fn check_version() -> Result<VersionCheck> {
-    let latest = fetch_latest_version()?;
+/// Check if a newer version is available on GitHub on `channel`
+pub fn check_version(channel: UpdateChannel) -> Result<VersionCheck> {
+    let latest = fetch_latest_version(channel)?;
+    Ok(compare_with_current(latest))
+}
+
+fn compare_with_current(latest: String) -> VersionCheck {
     let current = CURRENT_VERSION.to_string();
 
     let update_available = match (parse_semver(&latest), parse_semver(&current)) {
@@ -60,25 +219,121 @@ pub fn check_version() -> Result<VersionCheck> {
         _ => false,
     };
 
-    Ok(VersionCheck {
+    VersionCheck {
         latest,
         current,
         update_available,
-    })
+    }
+}
+
+/// The last successful update check, kept in `update-check.toml`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedCheck {
+    /// Unix seconds
+    pub checked_at: u64,
+    pub channel: String,
+    pub latest: String,
+}
+
+impl CachedCheck {
+    /// Whether this check can stand in for asking GitHub again at `now`
+    pub fn is_fresh(&self, channel: UpdateChannel, max_age_hours: usize, now: u64) -> bool {
+        self.channel == channel.name()
+            && now.saturating_sub(self.checked_at) < max_age_hours as u64 * 60 * 60
+    }
 }
 
-/// Run the appropriate update command for the current platform
-pub fn run_update() -> Result<()> {
-    if is_cargo_available() {
-        println!("{}", "Updating via cargo...".cyan().bold());
+fn check_cache_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("update-check.toml"))
+}
+
+fn load_cached_check() -> Option<CachedCheck> {
+    let content = std::fs::read_to_string(check_cache_path()?).ok()?;
+    toml::from_str(&content).ok()
+}
+
+fn save_cached_check(check: &CachedCheck) -> Result<()> {
+    let path = check_cache_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string(check).context("Failed to serialize the update check")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `check_version`, answered from the last check while it is younger than `max_age_hours`
+/// (0 always asks GitHub)
+pub fn check_version_cached(channel: UpdateChannel, max_age_hours: usize) -> Result<VersionCheck> {
+    let now = crate::timestamp::now_unix();
+    if let Some(cached) = load_cached_check().filter(|c| c.is_fresh(channel, max_age_hours, now)) {
+        return Ok(compare_with_current(cached.latest));
+    }
+    let latest = fetch_latest_version(channel)?;
+    // Only costs an extra request next time
+    let _ = save_cached_check(&CachedCheck {
+        checked_at: now,
+        channel: channel.name().to_string(),
+        latest: latest.clone(),
+    });
+    Ok(compare_with_current(latest))
+}
+
+/// Release asset built for this platform, as named by the release workflow
+pub fn release_artifact() -> Option<&'static str> {
+    artifact_for(std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn artifact_for(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("linux", "x86_64") => Some("cgen-linux-amd64"),
+        ("macos", "x86_64") => Some("cgen-macos-amd64"),
+        ("macos", "aarch64") => Some("cgen-macos-arm64"),
+        ("windows", "x86_64") => Some("cgen-windows-amd64.exe"),
+        _ => None,
+    }
+}
+
+/// Largest binary `cgen update` will download
+const MAX_DOWNLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Install the latest release on `channel`. A cargo install is updated with cargo (through
+/// cargo-binstall when it is installed); otherwise
+/// the release binary is downloaded and swapped in, falling back to the install scripts
+/// (stable only) on platforms without a prebuilt binary.
+pub fn run_update(channel: UpdateChannel) -> Result<()> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(60))
+        .build();
+    let release = fetch_release(&agent, channel)?;
+    let tag = release["tag_name"]
+        .as_str()
+        .context("No tag_name in GitHub release response")?;
+
+    if installed_with_cargo() && is_cargo_available() {
+        // cargo only picks pre-releases when asked for one by exact version
+        let version = format!("={}", tag.strip_prefix('v').unwrap_or(tag));
+        // cargo-binstall fetches the prebuilt release binary instead of compiling from source
+        let (tool, args) = if is_binstall_available() {
+            println!("{}", "Updating via cargo binstall...".cyan().bold());
+            ("cargo binstall", vec!["binstall", "-y", "auto-commit-rs"])
+        } else {
+            println!("{}", "Updating via cargo...".cyan().bold());
+            ("cargo install", vec!["install", "auto-commit-rs"])
+        };
         let status = std::process::Command::new("cargo")
-            .args(["install", "smart-commit-rs"])
+            .args(args)
+            .args(["--version", &version])
             .status()
-            .context("Failed to run cargo install")?;
+            .with_context(|| format!("Failed to run {tool}"))?;
 
         if !status.success() {
-            anyhow::bail!("cargo install failed with exit code {}", status);
+            anyhow::bail!("{} failed with exit code {}", tool, status);
         }
+    } else if let Some(artifact) = release_artifact() {
+        self_update(&agent, &release, artifact)?;
+    } else if channel != UpdateChannel::Stable {
+        anyhow::bail!("The install scripts only install stable releases; build {tag} from source");
     } else {
         run_platform_installer()?;
     }
@@ -87,9 +342,129 @@ pub fn run_update() -> Result<()> {
     Ok(())
 }
 
+/// Whether the running binary lives in cargo's bin directory
+fn installed_with_cargo() -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".cargo")));
+    cargo_home.is_some_and(|home| exe.starts_with(home.join("bin")))
+}
+
+/// Download `release`'s binary, check it against its published SHA-256 and replace the
+/// running executable with it
+fn self_update(agent: &ureq::Agent, release: &serde_json::Value, artifact: &str) -> Result<()> {
+    let tag = release["tag_name"].as_str().unwrap_or("latest");
+
+    let assets = release["assets"]
+        .as_array()
+        .context("No assets in GitHub release response")?;
+    let asset_url = |name: &str| {
+        assets
+            .iter()
+            .find(|a| a["name"].as_str() == Some(name))
+            .and_then(|a| a["browser_download_url"].as_str())
+            .map(str::to_string)
+    };
+    let binary_url =
+        asset_url(artifact).with_context(|| format!("Release {tag} has no {artifact} binary"))?;
+    let checksum_url = asset_url(&format!("{artifact}.sha256"))
+        .with_context(|| format!("Release {tag} has no checksum for {artifact}"))?;
+
+    println!(
+        "{}",
+        format!("Downloading {artifact} {tag}...").cyan().bold()
+    );
+    let checksum = String::from_utf8(download(agent, &checksum_url)?)
+        .context("Checksum file is not valid UTF-8")?;
+    let expected = parse_checksum(&checksum).context("Malformed checksum file")?;
+    let binary = download(agent, &binary_url)?;
+    verify_checksum(&binary, &expected)?;
+
+    // Stage the binary in a fresh private file next to the executable, so the final rename
+    // stays on one filesystem and a failed update never leaves a half-written binary behind
+    let exe = std::env::current_exe()
+        .and_then(std::fs::canonicalize)
+        .context("Could not locate the running cgen binary")?;
+    let dir = exe
+        .parent()
+        .context("The cgen binary has no parent directory")?;
+    let mut staged = tempfile::Builder::new()
+        .prefix(".cgen-update-")
+        .tempfile_in(dir)
+        .with_context(|| format!("Failed to create a temp file in {}", dir.display()))?;
+    staged
+        .write_all(&binary)
+        .and_then(|_| staged.as_file().sync_all())
+        .with_context(|| format!("Failed to write {}", staged.path().display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        staged
+            .as_file()
+            .set_permissions(std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {} executable", staged.path().display()))?;
+        staged
+            .persist(&exe)
+            .map_err(|e| e.error)
+            .context("Failed to replace the cgen binary")?;
+        Ok(())
+    }
+    // A running executable can't be overwritten on Windows; self_replace moves it aside first
+    #[cfg(not(unix))]
+    {
+        self_replace::self_replace(staged.path()).context("Failed to replace the cgen binary")
+    }
+}
+
+fn download(agent: &ureq::Agent, url: &str) -> Result<Vec<u8>> {
+    let response = agent
+        .get(url)
+        .set("User-Agent", "cgen")
+        .call()
+        .with_context(|| format!("Failed to download {url}"))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_BYTES)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to download {url}"))?;
+    Ok(bytes)
+}
+
+/// The hash from a `sha256sum` line (`<hex>  <file>`), lowercased
+pub fn parse_checksum(content: &str) -> Option<String> {
+    let hash = content.split_whitespace().next()?;
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+/// Fail unless `bytes` hash to the hex SHA-256 `expected`
+pub fn verify_checksum(bytes: &[u8], expected: &str) -> Result<()> {
+    use sha2::{Digest, Sha256};
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if actual != expected {
+        anyhow::bail!("Checksum mismatch: expected {expected}, got {actual}");
+    }
+    Ok(())
+}
+
 fn is_cargo_available() -> bool {
+    cargo_runs(&["--version"])
+}
+
+fn is_binstall_available() -> bool {
+    cargo_runs(&["binstall", "-V"])
+}
+
+fn cargo_runs(args: &[&str]) -> bool {
     std::process::Command::new("cargo")
-        .arg("--version")
+        .args(args)
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
         .status()
@@ -131,14 +506,86 @@ fn run_platform_installer() -> Result<()> {
     Ok(())
 }
 
+/// Releases the user asked not to hear about, kept in `update-reminders.toml` next to the
+/// update-check cache rather than in the config
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Reminders {
+    /// Release the update warning stays quiet about (`cgen update --skip`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_version: Option<String>,
+    /// Unix time until which the update warning is snoozed (`cgen update --snooze`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<u64>,
+}
+
+fn reminders_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("update-reminders.toml"))
+}
+
+pub fn load_reminders() -> Reminders {
+    reminders_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_reminders(reminders: &Reminders) -> Result<()> {
+    let path = reminders_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string(reminders).context("Failed to serialize update reminders")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Whether the warning for `latest` should stay quiet: the user skipped that release or
+/// snoozed reminders past `now` (unix seconds)
+pub fn warning_suppressed(reminders: &Reminders, latest: &str, now: u64) -> bool {
+    let skipped = reminders.skipped_version.as_deref().is_some_and(|skipped| {
+        match (parse_semver(skipped), parse_semver(latest)) {
+            (Some(skipped), Some(latest)) => skipped == latest,
+            _ => skipped == latest,
+        }
+    });
+    skipped || reminders.snoozed_until.is_some_and(|until| until > now)
+}
+
+/// Stop announcing `version` (a newer release is announced again)
+pub fn skip_version(version: &str) -> Result<()> {
+    let mut reminders = load_reminders();
+    reminders.skipped_version = Some(version.to_string());
+    save_reminders(&reminders)
+}
+
+/// Keep the update warning quiet for `days` days
+pub fn snooze(days: u64) -> Result<()> {
+    let until = days
+        .checked_mul(24 * 60 * 60)
+        .and_then(|secs| crate::timestamp::now_unix().checked_add(secs))
+        .filter(|&until| i64::try_from(until).is_ok())
+        .context("Snooze is too long")?;
+    let mut reminders = load_reminders();
+    reminders.snoozed_until = Some(until);
+    save_reminders(&reminders)
+}
+
 /// Print a warning that a newer version is available
 pub fn print_update_warning(latest: &str) {
     eprintln!(
-        "\n{}  {} → {}  (run {} to update)",
-        "Update available!".yellow().bold(),
+        "\n{}  {} {} {}  {}",
+        t("update_available").yellow().bold(),
         CURRENT_VERSION.dimmed(),
+        crate::ui::glyphs().arrow,
         latest.green(),
-        "cgen update".cyan(),
+        tf(
+            "update_run_hint",
+            &[
+                ("command", &"cgen update".cyan().to_string()),
+                ("skip", &"cgen update --skip".cyan().to_string()),
+                ("snooze", &"--snooze <DAYS>".cyan().to_string()),
+            ]
+        ),
     );
 }
 
@@ -169,13 +616,13 @@ mod tests {
     #[test]
     fn test_parse_semver_basic() {
         let v = parse_semver("1.2.3").unwrap();
-        assert_eq!(v, (1, 2, 3));
+        assert_eq!((v.major, v.minor, v.patch), (1, 2, 3));
     }
 
     #[test]
     fn test_parse_semver_with_v() {
         let v = parse_semver("v1.2.3").unwrap();
-        assert_eq!(v, (1, 2, 3));
+        assert_eq!((v.major, v.minor, v.patch), (1, 2, 3));
     }
 
     #[test]
@@ -195,13 +642,13 @@ mod tests {
     #[test]
     fn test_parse_semver_large_numbers() {
         let v = parse_semver("100.200.300").unwrap();
-        assert_eq!(v, (100, 200, 300));
+        assert_eq!((v.major, v.minor, v.patch), (100, 200, 300));
     }
 
     #[test]
     fn test_parse_semver_zeros() {
         let v = parse_semver("0.0.0").unwrap();
-        assert_eq!(v, (0, 0, 0));
+        assert_eq!((v.major, v.minor, v.patch), (0, 0, 0));
     }
 
     #[test]
@@ -261,6 +708,129 @@ mod tests {
         assert!(!update_available); // Falls back to false for invalid
     }
 
+    #[test]
+    fn test_parse_semver_pre_release_and_build() {
+        let v = parse_semver("v1.2.3-beta.2+build.5").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (1, 2, 3));
+        assert_eq!(v.pre, vec!["beta", "2"]);
+        assert!(parse_semver("1.2.3-").is_none());
+        assert!(parse_semver("1.2.3-beta..1").is_none());
+    }
+
+    #[test]
+    fn test_semver_pre_release_precedence() {
+        // The ordering example from the semver spec
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1-nightly.1",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(
+                parse_semver(pair[0]).unwrap() < parse_semver(pair[1]).unwrap(),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert_eq!(
+            parse_semver("1.0.0+a").unwrap(),
+            parse_semver("1.0.0+b").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_update_channel_accepts() {
+        let release = parse_semver("1.3.0").unwrap();
+        let beta = parse_semver("1.3.0-beta.1").unwrap();
+        let rc = parse_semver("1.3.0-rc.1").unwrap();
+        let nightly = parse_semver("1.3.0-nightly.20260101").unwrap();
+        assert!(UpdateChannel::Stable.accepts(&release));
+        assert!(!UpdateChannel::Stable.accepts(&beta));
+        assert!(UpdateChannel::Beta.accepts(&beta) && UpdateChannel::Beta.accepts(&rc));
+        assert!(!UpdateChannel::Beta.accepts(&nightly));
+        assert!(UpdateChannel::Nightly.accepts(&nightly));
+        assert_eq!(UpdateChannel::parse("Beta"), UpdateChannel::Beta);
+        assert_eq!(UpdateChannel::parse("bogus"), UpdateChannel::Stable);
+    }
+
+    #[test]
+    fn test_pick_release_by_channel() {
+        let releases = vec![
+            serde_json::json!({"tag_name": "v1.3.0-nightly.1", "prerelease": true}),
+            serde_json::json!({"tag_name": "v1.3.0-beta.2", "prerelease": true}),
+            serde_json::json!({"tag_name": "v1.4.0", "draft": true}),
+            serde_json::json!({"tag_name": "v1.2.2", "prerelease": false}),
+        ];
+        let tag = |channel| pick_release(&releases, channel).unwrap()["tag_name"].clone();
+        assert_eq!(tag(UpdateChannel::Stable), "v1.2.2");
+        assert_eq!(tag(UpdateChannel::Beta), "v1.3.0-beta.2");
+        assert_eq!(tag(UpdateChannel::Nightly), "v1.3.0-nightly.1");
+    }
+
+    #[test]
+    fn test_warning_suppressed_by_skip_or_snooze() {
+        let mut reminders = Reminders::default();
+        assert!(!warning_suppressed(&reminders, "v1.3.0", 100));
+
+        reminders.skipped_version = Some("v1.3.0".into());
+        assert!(warning_suppressed(&reminders, "1.3.0", 100));
+        assert!(!warning_suppressed(&reminders, "v1.3.1", 100));
+
+        reminders.snoozed_until = Some(200);
+        assert!(warning_suppressed(&reminders, "v1.3.1", 100));
+        assert!(!warning_suppressed(&reminders, "v1.3.1", 200));
+    }
+
+    #[test]
+    fn test_cached_check_freshness() {
+        let cached = CachedCheck {
+            checked_at: 1_000_000,
+            channel: "stable".into(),
+            latest: "v1.3.0".into(),
+        };
+        let hour = 60 * 60;
+        assert!(cached.is_fresh(UpdateChannel::Stable, 24, 1_000_000 + 23 * hour));
+        assert!(!cached.is_fresh(UpdateChannel::Stable, 24, 1_000_000 + 24 * hour));
+        assert!(!cached.is_fresh(UpdateChannel::Beta, 24, 1_000_000));
+        assert!(!cached.is_fresh(UpdateChannel::Stable, 0, 1_000_000));
+    }
+
+    #[test]
+    fn test_artifact_for_known_platforms() {
+        assert_eq!(artifact_for("linux", "x86_64"), Some("cgen-linux-amd64"));
+        assert_eq!(artifact_for("macos", "aarch64"), Some("cgen-macos-arm64"));
+        assert_eq!(
+            artifact_for("windows", "x86_64"),
+            Some("cgen-windows-amd64.exe")
+        );
+        assert_eq!(artifact_for("linux", "aarch64"), None);
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let hash = "A".repeat(64);
+        assert_eq!(
+            parse_checksum(&format!("{hash}  cgen-linux-amd64\n")),
+            Some("a".repeat(64))
+        );
+        assert_eq!(parse_checksum("abc  cgen"), None);
+        assert_eq!(parse_checksum(""), None);
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(verify_checksum(b"", empty).is_ok());
+        assert!(verify_checksum(b"tampered", empty).is_err());
+    }
+
     #[test]
     fn test_print_update_warning_no_panic() {
         // Just ensure it doesn't panic
@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::AppConfig;
+use crate::i18n::tf;
+use crate::provider::TokenUsage;
+
+/// Tokens and estimated spend for one calendar month (UTC)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MonthUsage {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Contents of `usage.toml`: `YYYY-MM` → usage
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UsageFile {
+    #[serde(default)]
+    pub months: BTreeMap<String, MonthUsage>,
+}
+
+/// A run that would take this month's spend over `ACR_MONTHLY_BUDGET_USD`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetWarning {
+    pub spent: f64,
+    pub budget: f64,
+    /// Estimated cost of the run, when the model's prices are known
+    pub estimate: Option<f64>,
+}
+
+impl BudgetWarning {
+    /// The warning shown to the user, with the run's estimate when there is one
+    pub fn message(&self) -> String {
+        let spent = format!("{:.2}", self.spent);
+        let budget = format!("{:.2}", self.budget);
+        match self.estimate {
+            Some(estimate) => tf(
+                "budget_exceeded_estimate",
+                &[
+                    ("spent", &spent),
+                    ("budget", &budget),
+                    ("estimate", &format!("{estimate:.4}")),
+                ],
+            ),
+            None => tf("budget_exceeded", &[("spent", &spent), ("budget", &budget)]),
+        }
+    }
+}
+
+fn usage_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("usage.toml"))
+}
+
+pub fn load() -> Result<UsageFile> {
+    let Some(path) = usage_path().filter(|p| p.exists()) else {
+        return Ok(UsageFile::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save(file: &UsageFile) -> Result<()> {
+    let path = usage_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string_pretty(file).context("Failed to serialize usage")?;
+    let tmp_path = path.with_extension("toml.tmp");
+    std::fs::write(&tmp_path, &content)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to rename temp file to {}", path.display()))
+}
+
+/// The current month as `YYYY-MM` (UTC)
+pub fn current_month() -> String {
+    crate::timestamp::month(crate::timestamp::now_unix())
+}
+
+/// The monthly budget, when one is set
+pub fn budget(cfg: &AppConfig) -> Option<f64> {
+    cfg.monthly_budget_usd
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|b| *b > 0.0)
+}
+
+/// Add a request to this month's totals. Nothing is recorded unless a budget is set.
+pub fn record(cfg: &AppConfig, usage: &TokenUsage) -> Result<()> {
+    if budget(cfg).is_none() {
+        return Ok(());
+    }
+    let mut file = load()?;
+    let month = file.months.entry(current_month()).or_default();
+    month.requests += 1;
+    month.input_tokens += usage.input_tokens;
+    month.output_tokens += usage.output_tokens;
+    month.cost_usd += crate::provider::cost_usd(&cfg.model, usage).unwrap_or(0.0);
+    save(&file)
+}
+
+/// This month's totals so far
+pub fn this_month() -> Result<MonthUsage> {
+    Ok(load()?.months.remove(&current_month()).unwrap_or_default())
+}
+
+/// Whether a run estimated at `estimate` USD would exceed the budget. Without an estimate
+/// (unknown prices) only an already spent budget counts.
+pub fn check_budget(cfg: &AppConfig, estimate: Option<f64>) -> Result<Option<BudgetWarning>> {
+    let Some(budget) = budget(cfg) else {
+        return Ok(None);
+    };
+    let spent = this_month()?.cost_usd;
+    let over = match estimate {
+        Some(estimate) => spent + estimate > budget,
+        None => spent >= budget,
+    };
+    Ok(over.then_some(BudgetWarning {
+        spent,
+        budget,
+        estimate,
+    }))
+}
+
+/// [`check_budget`] for one request sending `diff` to `cfg`'s model, estimated from its size
+pub fn check_request_budget(cfg: &AppConfig, diff: &str) -> Result<Option<BudgetWarning>> {
+    let estimate =
+        crate::provider::cost_usd(&cfg.model, &crate::provider::estimate_usage(cfg, diff));
+    check_budget(cfg, estimate)
+}
+
+/// Runs kept in `metrics.toml`; older ones are dropped first
+const MAX_RUNS: usize = 1000;
+
+/// One LLM request made during a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestMetric {
+    pub provider: String,
+    pub model: String,
+    pub latency_ms: u64,
+    pub success: bool,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+}
+
+/// How the user finished with the generated message
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Accepted,
+    Edited,
+    Cancelled,
+}
+
+/// One message generation: every request it made and how it ended. Each successful request
+/// except the last produced a message the user asked to regenerate or refine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunMetric {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub outcome: Outcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+    #[serde(default)]
+    pub requests: Vec<RequestMetric>,
+}
+
+/// Contents of `metrics.toml`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MetricsFile {
+    #[serde(default)]
+    pub runs: Vec<RunMetric>,
+}
+
+/// Totals for one provider and model in `cgen stats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelStats {
+    pub provider: String,
+    pub model: String,
+    pub requests: u64,
+    pub failures: u64,
+    /// Sum over successful requests
+    pub latency_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub accepted: u64,
+    pub edited: u64,
+    pub regenerated: u64,
+    pub cancelled: u64,
+}
+
+impl ModelStats {
+    pub fn avg_latency_ms(&self) -> u64 {
+        self.latency_ms
+            .checked_div(self.requests - self.failures)
+            .unwrap_or(0)
+    }
+}
+
+/// Requests made since the last `take_requests`, waiting to be attached to a run
+static PENDING: Mutex<Vec<RequestMetric>> = Mutex::new(Vec::new());
+
+fn metrics_path() -> Option<PathBuf> {
+    crate::cache::cache_dir().map(|d| d.join("metrics.toml"))
+}
+
+/// Remember a request for the current run when metrics are enabled. `usage` is `None` for a
+/// failed request.
+pub fn note_request(cfg: &AppConfig, latency: Duration, usage: Option<TokenUsage>) {
+    if !cfg.usage_metrics {
+        return;
+    }
+    let metric = RequestMetric {
+        provider: cfg.provider.clone(),
+        model: cfg.model.clone(),
+        latency_ms: latency.as_millis() as u64,
+        success: usage.is_some(),
+        input_tokens: usage.map_or(0, |u| u.input_tokens),
+        output_tokens: usage.map_or(0, |u| u.output_tokens),
+    };
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(metric);
+    }
+}
+
+/// Requests noted so far, clearing the list
+pub fn take_requests() -> Vec<RequestMetric> {
+    PENDING
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default()
+}
+
+pub fn load_metrics() -> Result<MetricsFile> {
+    let Some(path) = metrics_path().filter(|p| p.exists()) else {
+        return Ok(MetricsFile::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Store a finished run with the requests noted for it. Nothing is written unless
+/// `ACR_USAGE_METRICS` is enabled.
+pub fn record_run(cfg: &AppConfig, outcome: Outcome, fallback: Option<String>) -> Result<()> {
+    let requests = take_requests();
+    if !cfg.usage_metrics || requests.is_empty() {
+        return Ok(());
+    }
+    let mut file = load_metrics()?;
+    file.runs.push(RunMetric {
+        timestamp: crate::timestamp::now_unix(),
+        outcome,
+        fallback,
+        requests,
+    });
+    let excess = file.runs.len().saturating_sub(MAX_RUNS);
+    file.runs.drain(..excess);
+
+    let path = metrics_path().context("Could not determine cache directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = toml::to_string_pretty(&file).context("Failed to serialize metrics")?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Delete all recorded metrics
+pub fn clear_metrics() -> Result<()> {
+    match metrics_path().filter(|p| p.exists()) {
+        Some(path) => std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove {}", path.display())),
+        None => Ok(()),
+    }
+}
+
+/// Per provider and model totals, busiest first
+pub fn summarize(runs: &[RunMetric]) -> Vec<ModelStats> {
+    let mut stats: BTreeMap<(String, String), ModelStats> = BTreeMap::new();
+    for run in runs {
+        let last_success = run.requests.iter().rposition(|r| r.success);
+        for (i, request) in run.requests.iter().enumerate() {
+            let entry = stats
+                .entry((request.provider.clone(), request.model.clone()))
+                .or_insert_with(|| ModelStats {
+                    provider: request.provider.clone(),
+                    model: request.model.clone(),
+                    ..ModelStats::default()
+                });
+            entry.requests += 1;
+            if !request.success {
+                entry.failures += 1;
+                continue;
+            }
+            entry.latency_ms += request.latency_ms;
+            entry.input_tokens += request.input_tokens;
+            entry.output_tokens += request.output_tokens;
+            if Some(i) != last_success {
+                entry.regenerated += 1;
+                continue;
+            }
+            match run.outcome {
+                Outcome::Accepted => entry.accepted += 1,
+                Outcome::Edited => entry.edited += 1,
+                Outcome::Cancelled => entry.cancelled += 1,
+            }
+        }
+    }
+    let mut stats: Vec<ModelStats> = stats.into_values().collect();
+    stats.sort_by_key(|s| std::cmp::Reverse(s.requests));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str, success: bool) -> RequestMetric {
+        RequestMetric {
+            provider: "openai".into(),
+            model: model.into(),
+            latency_ms: 400,
+            success,
+            input_tokens: 100,
+            output_tokens: 10,
+        }
+    }
+
+    #[test]
+    fn test_summarize_attributes_outcomes_to_the_final_request() {
+        let runs = vec![
+            RunMetric {
+                timestamp: 0,
+                outcome: Outcome::Edited,
+                fallback: Some("Backup".into()),
+                requests: vec![
+                    request("gpt-4o", false),
+                    request("gpt-4o-mini", true),
+                    request("gpt-4o-mini", true),
+                ],
+            },
+            RunMetric {
+                timestamp: 1,
+                outcome: Outcome::Accepted,
+                fallback: None,
+                requests: vec![request("gpt-4o", true)],
+            },
+        ];
+        let stats = summarize(&runs);
+        let mini = stats.iter().find(|s| s.model == "gpt-4o-mini").unwrap();
+        assert_eq!((mini.requests, mini.regenerated, mini.edited), (2, 1, 1));
+        assert_eq!(mini.avg_latency_ms(), 400);
+        let full = stats.iter().find(|s| s.model == "gpt-4o").unwrap();
+        assert_eq!((full.requests, full.failures, full.accepted), (2, 1, 1));
+        assert_eq!(full.input_tokens, 100);
+    }
+}
@@ -0,0 +1,589 @@
+use glob::Pattern;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use crate::config::{self, AppConfig, ConfigScope, ENV_FIELD_MAP, GLOBAL_ONLY};
+
+/// Model name prefixes of first-party providers, used to spot a model set for the wrong provider
+const MODEL_FAMILIES: &[(&str, &[&str])] = &[
+    ("openai", &["gpt-", "o1", "o3", "o4", "chatgpt-"]),
+    ("anthropic", &["claude-"]),
+    ("gemini", &["gemini-"]),
+    ("grok", &["grok-"]),
+    ("deepseek", &["deepseek-"]),
+    (
+        "mistral",
+        &["mistral-", "codestral-", "ministral-", "pixtral-"],
+    ),
+];
+
+/// `ACR_*` environment variables that are not settings
+const NON_SETTING_VARS: &[&str] = &[
+    "ACR_CONFIG_HOME",
+    crate::secret::PASSPHRASE_ENV,
+    crate::secret::KEY_FILE_ENV,
+];
+
+/// A problem found in one config layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// File (or `environment`) the broken value comes from
+    pub source: String,
+    /// Setting name as written in that source; empty for file-level problems
+    pub key: String,
+    pub message: String,
+    /// Suspicious but usable; does not fail `cgen config validate`
+    pub warning: bool,
+}
+
+impl Issue {
+    fn error(source: &str, key: &str, message: impl Into<String>) -> Self {
+        Self {
+            source: source.to_string(),
+            key: key.to_string(),
+            message: message.into(),
+            warning: false,
+        }
+    }
+
+    fn warning(source: &str, key: &str, message: impl Into<String>) -> Self {
+        Self {
+            warning: true,
+            ..Self::error(source, key, message)
+        }
+    }
+}
+
+/// Check every config layer and the files it references (presets, profiles, credentials,
+/// gitmoji set)
+pub fn validate_all() -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // Last layer that set each suffix, for attributing cross-field problems
+    let mut origins: HashMap<&'static str, (String, String)> = HashMap::new();
+
+    if let Some(path) = config::global_config_path().filter(|p| p.exists()) {
+        let source = path.display().to_string();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let found = validate_toml(&source, &content, &mut issues);
+                for (suffix, key) in found {
+                    origins.insert(suffix, (source.clone(), key));
+                }
+            }
+            Err(e) => issues.push(Issue::error(&source, "", format!("cannot read: {e}"))),
+        }
+    }
+
+    for scope in config::REPO_LAYERS {
+        let Some(path) = config::repo_file_path(scope).ok().filter(|p| p.exists()) else {
+            continue;
+        };
+        let source = path.display().to_string();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                for (suffix, key) in validate_toml(&source, &content, &mut issues) {
+                    if GLOBAL_ONLY.contains(&suffix) {
+                        issues.push(Issue::warning(
+                            &source,
+                            &key,
+                            "global-only setting; ignored in repository config",
+                        ));
+                    } else if scope == ConfigScope::Team && suffix == "API_KEY" {
+                        issues.push(Issue::error(
+                            &source,
+                            &key,
+                            format!(
+                                "API keys must not be committed; move it to {}",
+                                config::PERSONAL_CONFIG_FILE
+                            ),
+                        ));
+                    } else if scope == ConfigScope::Team
+                        && !config::team_may_set(suffix, &table_string(&content, &key))
+                    {
+                        issues.push(Issue::warning(
+                            &source,
+                            &key,
+                            format!(
+                                "decides where API keys are sent; ignored in the team config, \
+                                 move it to {}",
+                                config::PERSONAL_CONFIG_FILE
+                            ),
+                        ));
+                    } else {
+                        origins.insert(suffix, (source.clone(), key));
+                    }
+                }
+            }
+            Err(e) => issues.push(Issue::error(&source, "", format!("cannot read: {e}"))),
+        }
+    }
+
+    if let Ok(root) = crate::git::find_repo_root() {
+        let path = PathBuf::from(root).join(".env");
+        if path.exists() {
+            let source = path.display().to_string();
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    let vars = dotenv_pairs(&content);
+                    for (suffix, key) in validate_env_vars(&source, &vars, true, &mut issues) {
+                        origins.insert(suffix, (source.clone(), key));
+                    }
+                }
+                Err(e) => issues.push(Issue::error(&source, "", format!("cannot read: {e}"))),
+            }
+        }
+    }
+
+    let vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(k, _)| k.starts_with("ACR_") && !NON_SETTING_VARS.contains(&k.as_str()))
+        .collect();
+    for (suffix, key) in validate_env_vars("environment", &vars, false, &mut issues) {
+        origins.insert(suffix, ("environment".to_string(), key));
+    }
+
+    let presets = match crate::preset::load_presets() {
+        Ok(presets) => Some(presets),
+        Err(e) => {
+            issues.push(Issue::error("presets.toml", "", format!("{e:#}")));
+            None
+        }
+    };
+    if let Some(presets) = &presets {
+        validate_presets(presets, &mut issues);
+    }
+    let profiles = match crate::profile::load_profiles() {
+        Ok(profiles) => Some(profiles),
+        Err(e) => {
+            issues.push(Issue::error("profiles.toml", "", format!("{e:#}")));
+            None
+        }
+    };
+    if let Some(profiles) = &profiles {
+        validate_profiles(profiles, &mut issues);
+    }
+    if let Err(e) = crate::credentials::load_credentials() {
+        issues.push(Issue::error("credentials.toml", "", format!("{e:#}")));
+    }
+    if let Err(e) = crate::gitmoji::load_custom_set() {
+        issues.push(Issue::error("gitmoji.toml", "", format!("{e:#}")));
+    }
+
+    // Only check combinations when each layer is valid on its own, so errors are not repeated
+    if issues.iter().any(|i| !i.warning) {
+        return issues;
+    }
+    match AppConfig::load() {
+        Ok(cfg) => {
+            let origin = |suffix: &str| {
+                origins
+                    .get(suffix)
+                    .cloned()
+                    .unwrap_or_else(|| ("defaults".to_string(), format!("ACR_{suffix}")))
+            };
+            issues.extend(validate_combinations(&cfg, origin));
+            if let Some(profiles) = &profiles {
+                if !cfg.profile.is_empty() && !profiles.profiles.contains_key(&cfg.profile) {
+                    let (source, key) = origin("PROFILE");
+                    issues.push(Issue::error(
+                        &source,
+                        &key,
+                        format!("profile '{}' is not defined in profiles.toml", cfg.profile),
+                    ));
+                }
+            }
+        }
+        Err(e) => issues.push(Issue::error("config", "", format!("{e:#}"))),
+    }
+    issues
+}
+
+/// String value of top-level `key` in the TOML `content`, or "" when it has none
+fn table_string(content: &str, key: &str) -> String {
+    content
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|table| table.get(key)?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Check a `config.toml` or `.cgen.toml`: syntax, unknown keys, value types and values.
+/// Returns the suffixes it sets along with the key as written.
+pub fn validate_toml(
+    source: &str,
+    content: &str,
+    issues: &mut Vec<Issue>,
+) -> Vec<(&'static str, String)> {
+    let mut table: toml::Table = match content.parse() {
+        Ok(table) => table,
+        Err(e) => {
+            issues.push(Issue::error(source, "", format!("invalid TOML: {e}")));
+            return Vec::new();
+        }
+    };
+    // Report only what the automatic migration in `AppConfig::load` cannot fix
+    config::migrate_table(&mut table);
+    let defaults = match toml::Value::try_from(AppConfig::default()) {
+        Ok(toml::Value::Table(t)) => t,
+        _ => toml::Table::new(),
+    };
+
+    let sections = config::take_directory_profiles(&mut table);
+    let mut found = Vec::new();
+    for (key, value) in &table {
+        if key == "config_version" {
+            continue;
+        }
+        if let Some(suffix) = validate_entry(source, key, key, value, &defaults, issues) {
+            found.push((suffix, key.clone()));
+        }
+    }
+    for (pattern, section) in &sections {
+        if glob::Pattern::new(pattern.trim_start_matches('~')).is_err() {
+            issues.push(Issue::error(
+                source,
+                &format!("profile.\"{pattern}\""),
+                "invalid directory pattern",
+            ));
+        }
+        for (key, value) in section {
+            let label = format!("profile.\"{pattern}\".{key}");
+            if key == "preset" {
+                if !value.is_str() {
+                    issues.push(Issue::error(source, &label, "expected a preset name"));
+                }
+                continue;
+            }
+            if let Some(suffix) = validate_entry(source, &label, key, value, &defaults, issues) {
+                if suffix == "API_KEY" || GLOBAL_ONLY.contains(&suffix) {
+                    issues.push(Issue::error(
+                        source,
+                        &label,
+                        "cannot be set by a directory profile",
+                    ));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Check one `key = value` entry against the config schema. Returns its suffix when the key is
+/// known and the value has the right type.
+fn validate_entry(
+    source: &str,
+    label: &str,
+    key: &str,
+    value: &toml::Value,
+    defaults: &toml::Table,
+    issues: &mut Vec<Issue>,
+) -> Option<&'static str> {
+    let Some((suffix, _)) = ENV_FIELD_MAP.iter().find(|(_, field)| *field == key) else {
+        issues.push(Issue::error(source, label, "unknown setting"));
+        return None;
+    };
+    // auto_update has no default value, so it is absent from the defaults table
+    let expected = defaults.get(key).map_or("boolean", toml::Value::type_str);
+    if value.type_str() != expected {
+        issues.push(Issue::error(
+            source,
+            label,
+            format!("expected a {expected}, found a {}", value.type_str()),
+        ));
+        return None;
+    }
+    if let Err(message) = validate_value(suffix, &config::toml_to_field_string(value)) {
+        issues.push(Issue::error(source, label, message));
+    }
+    Some(suffix)
+}
+
+/// Check `ACR_*` variables from a `.env` file or the process environment.
+/// Returns the suffixes they set along with the variable name.
+pub fn validate_env_vars(
+    source: &str,
+    vars: &[(String, String)],
+    from_local: bool,
+    issues: &mut Vec<Issue>,
+) -> Vec<(&'static str, String)> {
+    let mut found = Vec::new();
+    for (key, value) in vars {
+        let Some(suffix) = key
+            .strip_prefix("ACR_")
+            .and_then(|s| ENV_FIELD_MAP.iter().find(|(suffix, _)| *suffix == s))
+            .map(|(suffix, _)| *suffix)
+        else {
+            if key.starts_with("ACR_") {
+                issues.push(Issue::error(source, key, "unknown setting"));
+            }
+            continue;
+        };
+        if from_local && GLOBAL_ONLY.contains(&suffix) {
+            issues.push(Issue::warning(
+                source,
+                key,
+                "global-only setting; ignored in .env",
+            ));
+            continue;
+        }
+        if let Err(message) = validate_value(suffix, value) {
+            issues.push(Issue::error(source, key, message));
+        }
+        found.push((suffix, key.clone()));
+    }
+    found
+}
+
+/// Check a single value in the string form used by `.env` files and `set_field`
+pub fn validate_value(suffix: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match suffix {
+        "ONE_LINER"
+        | "USE_GITMOJI"
+        | "REVIEW_COMMIT"
+        | "REVIEW_TUI"
+        | "SHOW_DIFF"
+        | "ASCII_UI"
+        | "ENCRYPT_SECRETS"
+        | "TEMPLATE_COMMANDS"
+        | "SUPPRESS_TOOL_OUTPUT"
+        | "WARN_STAGED_FILES_ENABLED"
+        | "CONFIRM_NEW_VERSION"
+        | "AUTO_UPDATE"
+        | "FALLBACK_ENABLED"
+        | "TRACK_GENERATED_COMMITS"
+        | "USAGE_METRICS"
+        | "REPO_HISTORY"
+        | "AUTO_STASH"
+        | "EXCLUDE_LFS"
+        | "PRE_COMMIT_HOOK"
+        | "AUTO_SET_UPSTREAM"
+        | "WARN_DEFAULT_BRANCH"
+        | "POST_PROCESS"
+        | "LOWERCASE_SUBJECT"
+        | "AUTO_SCOPE"
+        | "GITMOJI_DETERMINISTIC"
+        | "SKIP_CI" => one_of(value, &["1", "0", "true", "false"]),
+        "GITMOJI_FORMAT" => one_of(value, &["unicode", "shortcode"]),
+        "POST_COMMIT_PUSH" => one_of(value, &["never", "ask", "always"]),
+        "MESSAGE_STYLE" => one_of(value, &["default", "detailed"]),
+        "REASONING_EFFORT" => one_of(value, &["", "minimal", "low", "medium", "high"]),
+        "BUDGET_ACTION" => one_of(value, &["warn", "confirm"]),
+        "ROUTING" => one_of(value, crate::routing::POLICIES),
+        "FALLBACK_POLICY" => crate::provider::parse_fallback_policy(value).map(|_| ()),
+        "UPDATE_CHANNEL" => one_of(value, &["stable", "beta", "nightly"]),
+        "MONTHLY_BUDGET_USD" if !value.is_empty() => match value.parse::<f64>() {
+            Ok(budget) if budget >= 0.0 => Ok(()),
+            _ => Err(format!("'{value}' is not a non-negative amount")),
+        },
+        "SEED" if !value.is_empty() => value
+            .parse::<u64>()
+            .map(|_| ())
+            .map_err(|_| format!("'{value}' is not a non-negative integer")),
+        "WARN_STAGED_FILES_THRESHOLD"
+        | "WARN_STAGED_FILES_MAX_LINES"
+        | "WARN_STAGED_FILES_MAX_BYTES"
+        | "MAX_PAYLOAD_BYTES"
+        | "CONTEXT_WINDOW"
+        | "CACHE_MAX_COMMITS"
+        | "CACHE_MAX_BYTES"
+        | "UPDATE_CHECK_HOURS"
+        | "REQUEST_TIMEOUT_SECS"
+        | "FALLBACK_MAX_ATTEMPTS"
+        | "FALLBACK_COOLDOWN_MINS"
+        | "SUBJECT_MAX_LENGTH" => value
+            .parse::<usize>()
+            .map(|_| ())
+            .map_err(|_| format!("'{value}' is not a non-negative number")),
+        "LOCALE" => AppConfig::default()
+            .set_field("LOCALE", value)
+            .map_err(|e| e.to_string()),
+        "PROVIDER" if value.is_empty() => Err("provider must not be empty".into()),
+        "API_URL" if !value.is_empty() => {
+            if value.starts_with("http://")
+                || value.starts_with("https://")
+                || value.starts_with('$')
+            {
+                Ok(())
+            } else {
+                Err(format!("'{value}' is not an http(s) URL"))
+            }
+        }
+        "API_HEADERS" => validate_headers(value),
+        "DIFF_EXCLUDE_GLOBS" | "PROMPT_INCLUDE" | "PROMPT_EXCLUDE" => {
+            list(value).try_for_each(|glob| {
+                Pattern::new(glob)
+                    .map(|_| ())
+                    .map_err(|e| format!("invalid glob '{glob}': {e}"))
+            })
+        }
+        "SCOPE_MAP" => list(value).try_for_each(|entry| {
+            let (glob, scope) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| format!("'{entry}' is not a glob=scope entry"))?;
+            if scope.trim().is_empty() {
+                return Err(format!("'{entry}' has an empty scope"));
+            }
+            Pattern::new(glob.trim())
+                .map(|_| ())
+                .map_err(|e| format!("invalid glob '{}': {e}", glob.trim()))
+        }),
+        "TYPE_TEMPLATES" => value
+            .split(';')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .try_for_each(|entry| match entry.split_once('=') {
+                Some((t, _)) if !t.trim().is_empty() => Ok(()),
+                _ => Err(format!("'{entry}' is not a type=template entry")),
+            }),
+        _ => Ok(()),
+    }
+}
+
+fn one_of(value: &str, allowed: &[&str]) -> Result<(), String> {
+    if allowed.iter().any(|a| a.eq_ignore_ascii_case(value)) {
+        Ok(())
+    } else {
+        Err(format!("'{value}' is not one of: {}", allowed.join(", ")))
+    }
+}
+
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// `Name: value` pairs separated by commas, as the provider sends them
+fn validate_headers(value: &str) -> Result<(), String> {
+    list(value).try_for_each(|pair| match pair.split_once(':') {
+        Some((name, _))
+            if !name.trim().is_empty()
+                && name
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_$".contains(c)) =>
+        {
+            Ok(())
+        }
+        _ => Err(format!("'{pair}' is not a `Name: value` header")),
+    })
+}
+
+/// Problems that only show up once all layers are merged, attributed with `origin`
+pub fn validate_combinations(
+    cfg: &AppConfig,
+    origin: impl Fn(&str) -> (String, String),
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if !crate::provider::is_builtin_provider(&cfg.provider) && cfg.api_url.is_empty() {
+        let (source, key) = origin("PROVIDER");
+        issues.push(Issue::error(
+            &source,
+            &key,
+            format!(
+                "'{}' is not a built-in provider; set ACR_API_URL for custom providers",
+                cfg.provider
+            ),
+        ));
+    }
+    if let Some(family) = model_family(&cfg.model) {
+        let first_party = MODEL_FAMILIES.iter().any(|(p, _)| *p == cfg.provider);
+        if first_party && family != cfg.provider && cfg.api_url.is_empty() {
+            let (source, key) = origin("MODEL");
+            issues.push(Issue::warning(
+                &source,
+                &key,
+                format!(
+                    "'{}' looks like a model for provider '{family}', but the provider is '{}'",
+                    cfg.model, cfg.provider
+                ),
+            ));
+        }
+    }
+    if !cfg.commit_template.contains("$msg") {
+        let (source, key) = origin("COMMIT_TEMPLATE");
+        issues.push(Issue::warning(
+            &source,
+            &key,
+            "template has no $msg placeholder; the generated message is dropped",
+        ));
+    }
+    issues
+}
+
+fn model_family(model: &str) -> Option<&'static str> {
+    let model = model.to_ascii_lowercase();
+    MODEL_FAMILIES
+        .iter()
+        .find(|(_, prefixes)| prefixes.iter().any(|p| model.starts_with(p)))
+        .map(|(provider, _)| *provider)
+}
+
+fn validate_presets(presets: &crate::preset::PresetsFile, issues: &mut Vec<Issue>) {
+    for preset in &presets.presets {
+        let key = format!("preset '{}'", preset.name);
+        let fields = [
+            ("PROVIDER", &preset.fields.provider),
+            ("API_URL", &preset.fields.api_url),
+            ("API_HEADERS", &preset.fields.api_headers),
+        ];
+        for (suffix, value) in fields {
+            if let Err(message) = validate_value(suffix, value) {
+                issues.push(Issue::error("presets.toml", &key, message));
+            }
+        }
+        if let Some(t) = preset.fields.overrides.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                issues.push(Issue::error(
+                    "presets.toml",
+                    &key,
+                    format!("temperature {t} is outside 0-2"),
+                ));
+            }
+        }
+    }
+    for id in &presets.routing.presets {
+        if !presets.presets.iter().any(|p| p.id == *id) {
+            issues.push(Issue::error(
+                "presets.toml",
+                "routing.presets",
+                format!("references preset id {id}, which does not exist"),
+            ));
+        }
+    }
+    for id in &presets.fallback.order {
+        if !presets.presets.iter().any(|p| p.id == *id) {
+            issues.push(Issue::error(
+                "presets.toml",
+                "fallback.order",
+                format!("references preset id {id}, which does not exist"),
+            ));
+        }
+    }
+}
+
+fn validate_profiles(profiles: &crate::profile::ProfilesFile, issues: &mut Vec<Issue>) {
+    for (name, settings) in &profiles.profiles {
+        let mut cfg = AppConfig::default();
+        if let Err(e) = crate::profile::apply_profile(&mut cfg, settings) {
+            issues.push(Issue::error(
+                "profiles.toml",
+                &format!("profiles.{name}"),
+                format!("{e:#}"),
+            ));
+        }
+    }
+}
+
+fn dotenv_pairs(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (k, v) = l.split_once('=')?;
+            let v = v.trim().trim_matches('"').trim_matches('\'');
+            Some((k.trim().to_string(), v.to_string()))
+        })
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use auto_commit_rs::bundle::{self, Bundle, BUNDLE_VERSION};
+use auto_commit_rs::config::{self, AppConfig, ConfigScope};
+use auto_commit_rs::preset::{self, LlmPresetFields, Preset};
+use common::EnvGuard;
+use serial_test::serial;
+use tempfile::TempDir;
+
+fn preset(name: &str, model: &str, key: &str) -> Preset {
+    Preset {
+        id: 7,
+        name: name.into(),
+        tags: Vec::new(),
+        fields: LlmPresetFields {
+            provider: "openai".into(),
+            model: model.into(),
+            api_key: key.into(),
+            api_url: String::new(),
+            api_headers: String::new(),
+            overrides: Default::default(),
+        },
+    }
+}
+
+#[test]
+fn export_keeps_only_changed_settings_and_drops_keys() {
+    let cfg = AppConfig {
+        model: "gpt-4o-mini".into(),
+        api_key: "sk-secret".into(),
+        one_liner: false,
+        ..AppConfig::default()
+    };
+    let presets = [preset("Work", "gpt-4o", "sk-work")];
+
+    let bundle = bundle::export(&cfg, Some(&presets), false).expect("export");
+    assert_eq!(bundle.bundle_version, BUNDLE_VERSION);
+    let keys: Vec<&str> = bundle.config.keys().map(String::as_str).collect();
+    assert_eq!(keys, vec!["model", "one_liner"]);
+    assert_eq!(bundle.presets[0].id, 0);
+    assert!(bundle.presets[0].fields.api_key.is_empty());
+
+    let content = bundle::to_toml(&bundle).expect("serialize");
+    assert!(!content.contains("sk-"));
+    let parsed = bundle::parse(&content).expect("parse");
+    assert_eq!(parsed.config, bundle.config);
+
+    let with_keys = bundle::export(&cfg, None, true).expect("export");
+    assert!(with_keys.config.contains_key("api_key"));
+    assert!(with_keys.presets.is_empty());
+}
+
+#[test]
+fn parse_rejects_newer_bundles_and_unknown_settings() {
+    let newer = format!("bundle_version = {}\n", BUNDLE_VERSION + 1);
+    assert!(bundle::parse(&newer).is_err());
+    assert!(bundle::parse("bundle_version = 1\n[config]\nmodle = \"x\"\n").is_err());
+}
+
+#[test]
+#[serial]
+fn import_applies_new_values_and_asks_about_conflicts() {
+    let cfg_dir = TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+
+    config::save_setting("MODEL", "mine", ConfigScope::Global).expect("set model");
+    config::save_setting("PROVIDER", "groq", ConfigScope::Global).expect("set provider");
+    let mut file = preset::load_presets().expect("load presets");
+    preset::create_preset(
+        &mut file,
+        Some("Work".into()),
+        preset("Work", "old", "sk-work").fields,
+    );
+    preset::save_presets(&file).expect("save presets");
+
+    let mut bundle = Bundle {
+        bundle_version: BUNDLE_VERSION,
+        presets: vec![
+            preset("Work", "new", ""),
+            preset("Personal", "llama", "sk-personal"),
+        ],
+        ..Bundle::default()
+    };
+    bundle.config.insert("model".into(), "theirs".into());
+    bundle.config.insert("provider".into(), "groq".into());
+    bundle.config.insert("one_liner".into(), false.into());
+
+    let mut asked = Vec::new();
+    let summary = bundle::import(&bundle, |conflict| {
+        asked.push(conflict.label.clone());
+        Ok(conflict.label != "model")
+    })
+    .expect("import");
+
+    assert_eq!(asked, vec!["model", "preset \"Work\""]);
+    assert_eq!(summary.settings, 1);
+    assert_eq!(summary.presets, 2);
+    assert_eq!(summary.kept, 1);
+
+    let global = config::settings_in(ConfigScope::Global).expect("global settings");
+    assert_eq!(global.get("MODEL").map(String::as_str), Some("mine"));
+    assert_eq!(global.get("ONE_LINER").map(String::as_str), Some("0"));
+
+    let file = preset::load_presets().expect("reload presets");
+    let work = file
+        .presets
+        .iter()
+        .find(|p| p.name == "Work")
+        .expect("work");
+    assert_eq!(work.fields.model, "new");
+    assert_eq!(work.fields.api_key, "sk-work", "existing key is kept");
+    assert!(file.presets.iter().any(|p| p.name == "Personal"));
+}
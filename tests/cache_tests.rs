@@ -1,7 +1,9 @@
 mod common;
 
 use auto_commit_rs::cache::{
-    get_head_hash, record_commit, CacheIndex, CacheIndexEntry, CachedCommit, RepoCache,
+    append_repo_history, clear, get_head_hash, load_repo_cache, prune, record_commit,
+    repo_summaries, search, tracked_repos, CacheIndex, CacheIndexEntry, CacheLimits, CachedCommit,
+    HistoryFilter, RepoCache, RepoHistoryFile,
 };
 use common::{commit_file, init_git_repo, DirGuard, EnvGuard};
 use serial_test::serial;
@@ -27,16 +29,15 @@ fn record_commit_creates_cache_files() {
 
     record_commit(
         &repo.path().to_string_lossy(),
-        &hash,
-        "initial commit preview",
+        CachedCommit::new(&hash, "initial commit preview"),
     )
     .expect("record_commit should succeed");
 
-    // Verify index was created
+    // Verify the database was created
     let cache_dir = _cfg_dir.path().join("cgen").join("cache");
     assert!(
-        cache_dir.join("index.toml").exists(),
-        "index.toml should exist"
+        cache_dir.join("history.db").exists(),
+        "history.db should exist"
     );
 }
 
@@ -52,19 +53,13 @@ fn record_commit_multiple_entries() {
 
     let repo_path = repo.path().to_string_lossy().to_string();
 
-    record_commit(&repo_path, &h1, "first").expect("record first");
-    record_commit(&repo_path, &h2, "second").expect("record second");
+    record_commit(&repo_path, CachedCommit::new(&h1, "first")).expect("record first");
+    record_commit(&repo_path, CachedCommit::new(&h2, "second")).expect("record second");
 
-    // Verify that both commits are recorded (by reading the cache file)
-    let cache_dir = _cfg_dir.path().join("cgen").join("cache");
-    let entries: Vec<_> = fs::read_dir(&cache_dir)
-        .expect("read cache dir")
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "toml"))
-        .filter(|e| e.file_name() != "index.toml")
-        .collect();
-
-    assert!(!entries.is_empty(), "should have at least one cache file");
+    // Verify that both commits are recorded, oldest first
+    let cache = load_repo_cache(&repo_path).expect("load repo cache");
+    let hashes: Vec<_> = cache.commits.iter().map(|c| c.hash.as_str()).collect();
+    assert_eq!(hashes, [h1.as_str(), h2.as_str()]);
 }
 
 #[test]
@@ -78,19 +73,26 @@ fn record_commit_different_repos() {
     let h1 = commit_file(repo1.path(), "a.txt", "1", "repo1 commit");
     let h2 = commit_file(repo2.path(), "a.txt", "2", "repo2 commit");
 
-    record_commit(&repo1.path().to_string_lossy(), &h1, "repo1").expect("record repo1");
-    record_commit(&repo2.path().to_string_lossy(), &h2, "repo2").expect("record repo2");
-
-    // Verify index has two entries
-    let index_path = _cfg_dir
-        .path()
-        .join("cgen")
-        .join("cache")
-        .join("index.toml");
-    let content = fs::read_to_string(&index_path).expect("read index");
-    let index: CacheIndex = toml::from_str(&content).expect("parse index");
-
-    assert_eq!(index.repos.len(), 2, "should have two repo entries");
+    record_commit(
+        &repo1.path().to_string_lossy(),
+        CachedCommit::new(&h1, "repo1"),
+    )
+    .expect("record repo1");
+    record_commit(
+        &repo2.path().to_string_lossy(),
+        CachedCommit::new(&h2, "repo2"),
+    )
+    .expect("record repo2");
+
+    // Verify both repos are tracked, most recent first
+    let repos = tracked_repos().expect("tracked repos");
+    assert_eq!(
+        repos,
+        [
+            repo2.path().to_string_lossy().to_string(),
+            repo1.path().to_string_lossy().to_string()
+        ]
+    );
 }
 
 #[test]
@@ -125,6 +127,7 @@ fn cached_commit_short_hash_display() {
     let commit = CachedCommit {
         hash: "abcdef1234567890".into(),
         message_preview: "feat: test".into(),
+        ..CachedCommit::default()
     };
     // Just verify the hash can be sliced for display (used in UI)
     let short = &commit.hash[..7.min(commit.hash.len())];
@@ -136,6 +139,7 @@ fn cached_commit_short_hash_handles_small_hash() {
     let commit = CachedCommit {
         hash: "abc".into(),
         message_preview: "test".into(),
+        ..CachedCommit::default()
     };
     let short = &commit.hash[..7.min(commit.hash.len())];
     assert_eq!(short, "abc");
@@ -154,7 +158,10 @@ fn record_commit_without_config_home_uses_dirs() {
 
     // This should use dirs::config_dir() as fallback
     // We don't assert file creation since it depends on the system config dir
-    let result = record_commit(&repo.path().to_string_lossy(), &hash, "test");
+    let result = record_commit(
+        &repo.path().to_string_lossy(),
+        CachedCommit::new(&hash, "test"),
+    );
 
     // Should succeed or fail gracefully
     if let Err(e) = result {
@@ -205,3 +212,240 @@ fn get_head_hash_updates_after_commit() {
     assert_eq!(get_head_hash().unwrap(), hash2);
     assert_ne!(hash1, hash2);
 }
+
+#[test]
+fn cached_commit_new_keeps_full_message_and_timestamp() {
+    let commit = CachedCommit::new("abcdef1234", "feat: add login\n\n- adds the form\n");
+    assert_eq!(commit.message_preview, "feat: add login");
+    assert!(commit.message.ends_with("- adds the form\n"));
+    assert_eq!(commit.timestamp.len(), "2026-01-01T00:00:00Z".len());
+    assert!(commit.generated_by().is_none());
+
+    let commit = CachedCommit {
+        provider: "openai".into(),
+        model: "gpt-4o-mini".into(),
+        fallback: Some("Backup".into()),
+        ..commit
+    };
+    assert_eq!(
+        commit.generated_by().as_deref(),
+        Some("openai/gpt-4o-mini (fallback: Backup)")
+    );
+}
+
+#[test]
+fn repo_cache_reads_entries_without_details() {
+    let cache: RepoCache = toml::from_str(
+        "repo_path = \"/repo\"\n\n[[commits]]\nhash = \"aaa\"\nmessage_preview = \"old\"\n",
+    )
+    .expect("parse old cache");
+    assert_eq!(cache.commits[0].message_preview, "old");
+    assert!(cache.commits[0].message.is_empty());
+}
+
+#[test]
+#[serial]
+fn prune_caps_commits_per_repo_and_drops_missing_repos() {
+    let (_cfg_dir, _env) = setup_cache_env();
+    let kept = TempDir::new().expect("tempdir");
+    let gone = TempDir::new().expect("tempdir");
+    let kept_path = kept.path().to_string_lossy().to_string();
+    for i in 0..5 {
+        record_commit(&kept_path, CachedCommit::new(&format!("h{i}"), "msg")).unwrap();
+    }
+    record_commit(
+        &gone.path().to_string_lossy(),
+        CachedCommit::new("x", "msg"),
+    )
+    .unwrap();
+    drop(gone);
+
+    let limits = CacheLimits {
+        max_commits_per_repo: 3,
+        max_bytes: 0,
+    };
+    let summary = prune(&limits, true).expect("prune");
+    assert_eq!(summary.repos_removed, 1);
+    assert_eq!(summary.commits_removed, 3);
+
+    assert_eq!(tracked_repos().unwrap(), [kept_path.as_str()]);
+    let cache = load_repo_cache(&kept_path).unwrap();
+    let hashes: Vec<_> = cache.commits.iter().map(|c| c.hash.as_str()).collect();
+    assert_eq!(hashes, ["h2", "h3", "h4"]);
+}
+
+#[test]
+#[serial]
+fn prune_drops_least_recently_used_repos_then_oldest_commits() {
+    let (_cfg_dir, _env) = setup_cache_env();
+    let message = "feat: something\n\n".to_string() + &"body ".repeat(40);
+    record_commit("/old", CachedCommit::new("old", &message)).unwrap();
+    for i in 0..20 {
+        record_commit("/new", CachedCommit::new(&format!("h{i:02}"), &message)).unwrap();
+    }
+
+    let limits = CacheLimits {
+        max_commits_per_repo: 0,
+        max_bytes: 2000,
+    };
+    let summary = prune(&limits, false).expect("prune");
+    assert_eq!(summary.repos_removed, 1);
+    assert!(summary.bytes_freed > 0);
+
+    assert_eq!(tracked_repos().unwrap(), ["/new"]);
+    let cache = load_repo_cache("/new").unwrap();
+    assert!(!cache.commits.is_empty() && cache.commits.len() < 20);
+    assert_eq!(cache.commits.last().unwrap().hash, "h19");
+}
+
+#[test]
+#[serial]
+fn clear_removes_history_but_keeps_other_cache_files() {
+    let (cfg_dir, _env) = setup_cache_env();
+    record_commit("/repo", CachedCommit::new("a", "msg")).unwrap();
+    let usage = cfg_dir.path().join("cgen").join("cache").join("usage.toml");
+    fs::write(&usage, "").unwrap();
+
+    assert_eq!(clear().expect("clear"), 1);
+    assert!(tracked_repos().unwrap().is_empty());
+    assert!(usage.exists());
+}
+
+#[test]
+#[serial]
+fn toml_cache_is_imported_into_the_database() {
+    let (cfg_dir, _env) = setup_cache_env();
+    let cache_dir = cfg_dir.path().join("cgen").join("cache");
+    fs::create_dir_all(&cache_dir).unwrap();
+    let index = CacheIndex {
+        repos: vec![CacheIndexEntry {
+            repo_path: "/repo".into(),
+            cache_file: "a1b2c3d4e5f67890.toml".into(),
+        }],
+    };
+    fs::write(
+        cache_dir.join("index.toml"),
+        toml::to_string_pretty(&index).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        cache_dir.join("a1b2c3d4e5f67890.toml"),
+        "repo_path = \"/repo\"\n\n[[commits]]\nhash = \"aaa\"\nmessage_preview = \"old\"\n",
+    )
+    .unwrap();
+
+    let cache = load_repo_cache("/repo").expect("load repo cache");
+    assert_eq!(cache.commits.len(), 1);
+    assert_eq!(cache.commits[0].message_preview, "old");
+    assert!(!cache_dir.join("index.toml").exists());
+    assert!(!cache_dir.join("a1b2c3d4e5f67890.toml").exists());
+}
+
+#[test]
+#[serial]
+fn search_matches_hash_message_and_repo_path() {
+    let (_cfg_dir, _env) = setup_cache_env();
+    record_commit(
+        "/work/api",
+        CachedCommit::new("abc123", "feat: add Login form"),
+    )
+    .unwrap();
+    record_commit(
+        "/work/api",
+        CachedCommit::new("def456", "fix: typo\n\nlogin page"),
+    )
+    .unwrap();
+    record_commit("/home/site", CachedCommit::new("fed789", "docs: readme")).unwrap();
+
+    let hashes = |entries: Vec<auto_commit_rs::cache::HistoryEntry>| -> Vec<String> {
+        entries.into_iter().map(|e| e.commit.hash).collect()
+    };
+    let text = |t: &str| HistoryFilter {
+        text: Some(t.into()),
+        branch: None,
+    };
+    assert_eq!(
+        hashes(search(&text("LOGIN"), None).unwrap()),
+        ["abc123", "def456"]
+    );
+    assert_eq!(hashes(search(&text("fed7"), None).unwrap()), ["fed789"]);
+    assert_eq!(hashes(search(&text("site"), None).unwrap()), ["fed789"]);
+    assert!(search(&text("site"), Some("/work/api")).unwrap().is_empty());
+    assert_eq!(
+        hashes(search(&text("typo"), Some("/work/api")).unwrap()),
+        ["def456"]
+    );
+}
+
+#[test]
+#[serial]
+fn search_filters_by_branch() {
+    let (_cfg_dir, _env) = setup_cache_env();
+    let on = |hash: &str, branch: &str| CachedCommit {
+        branch: branch.into(),
+        ..CachedCommit::new(hash, "feat: work")
+    };
+    record_commit("/repo", on("a1", "main")).unwrap();
+    record_commit("/repo", on("b1", "feat/login")).unwrap();
+    record_commit("/repo", on("a2", "main")).unwrap();
+
+    let filter = HistoryFilter {
+        text: None,
+        branch: Some("main".into()),
+    };
+    let hashes: Vec<_> = search(&filter, Some("/repo"))
+        .unwrap()
+        .into_iter()
+        .map(|e| e.commit.hash)
+        .collect();
+    assert_eq!(hashes, ["a1", "a2"]);
+
+    let filter = HistoryFilter {
+        text: Some("b1".into()),
+        branch: Some("main".into()),
+    };
+    assert!(search(&filter, Some("/repo")).unwrap().is_empty());
+}
+
+#[test]
+#[serial]
+fn repo_summaries_list_latest_commit_most_recent_first() {
+    let (_cfg_dir, _env) = setup_cache_env();
+    record_commit("/a", CachedCommit::new("a1", "feat: one")).unwrap();
+    record_commit("/b", CachedCommit::new("b1", "feat: two")).unwrap();
+    record_commit("/a", CachedCommit::new("a2", "fix: three")).unwrap();
+
+    let repos = repo_summaries().expect("summaries");
+    let summary: Vec<_> = repos
+        .iter()
+        .map(|r| (r.repo_path.as_str(), r.commits, r.last.hash.as_str()))
+        .collect();
+    assert_eq!(summary, [("/a", 2, "a2"), ("/b", 1, "b1")]);
+    assert_eq!(repos[0].last.message_preview, "fix: three");
+}
+
+#[test]
+fn repo_history_appends_entries_without_the_full_message() {
+    let repo = TempDir::new().expect("tempdir");
+    let root = repo.path().to_string_lossy().to_string();
+    let first = CachedCommit {
+        provider: "openai".into(),
+        model: "gpt-4o-mini".into(),
+        branch: "main".into(),
+        ..CachedCommit::new("aaa", "feat: add login\n\n- secret-looking body")
+    };
+    append_repo_history(&root, &first).expect("append first");
+    let second = CachedCommit {
+        fallback: Some("Backup".into()),
+        ..CachedCommit::new("bbb", "fix: typo")
+    };
+    append_repo_history(&root, &second).expect("append second");
+
+    let content = fs::read_to_string(repo.path().join(".cgen/history.toml")).unwrap();
+    assert!(!content.contains("secret-looking"));
+    let file: RepoHistoryFile = toml::from_str(&content).expect("parse history file");
+    assert_eq!(file.commits.len(), 2);
+    assert_eq!(file.commits[0].subject, "feat: add login");
+    assert_eq!(file.commits[0].model, "gpt-4o-mini");
+    assert_eq!(file.commits[1].fallback.as_deref(), Some("Backup"));
+}
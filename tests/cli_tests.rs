@@ -1,16 +1,249 @@
-use auto_commit_rs::cli::{Cli, Command};
+use auto_commit_rs::cli::{Cli, Command, ConfigAction, PresetAction};
+use auto_commit_rs::config::ConfigScope;
 use clap::Parser;
 
 #[test]
 fn parses_prompt_subcommand() {
     let cli = Cli::try_parse_from(["cgen", "prompt"]).expect("prompt should parse");
-    assert!(matches!(cli.command, Some(Command::Prompt)));
+    assert!(matches!(cli.command, Some(Command::Prompt { diff: false })));
+}
+
+#[test]
+fn parses_serve_stdio() {
+    let cli = Cli::try_parse_from(["cgen", "serve", "--stdio"]).expect("serve should parse");
+    assert!(matches!(cli.command, Some(Command::Serve { stdio: true })));
+    assert!(Cli::try_parse_from(["cgen", "serve"]).is_err());
+}
+
+#[test]
+fn parses_prompt_diff_flag() {
+    let cli = Cli::try_parse_from(["cgen", "prompt", "--diff"]).expect("--diff should parse");
+    assert!(matches!(cli.command, Some(Command::Prompt { diff: true })));
+}
+
+#[test]
+fn parses_repeatable_exclude_flag() {
+    let cli = Cli::try_parse_from(["cgen", "--exclude", "*.snap", "--exclude", "dist/**"])
+        .expect("exclude should parse");
+    assert_eq!(cli.exclude, vec!["*.snap", "dist/**"]);
+}
+
+#[test]
+fn parses_repo_flag() {
+    let cli = Cli::try_parse_from(["cgen", "-C", "../other", "history"]).expect("-C should parse");
+    assert_eq!(cli.repo, Some(std::path::PathBuf::from("../other")));
+    let cli = Cli::try_parse_from(["cgen", "--repo", "x", "--dry-run"]).expect("--repo parses");
+    assert_eq!(cli.repo, Some(std::path::PathBuf::from("x")));
+}
+
+#[test]
+fn parses_update_skip_and_snooze() {
+    let cli = Cli::try_parse_from(["cgen", "update", "--snooze", "7"]).expect("snooze parses");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Update {
+            skip: false,
+            snooze: Some(7)
+        })
+    ));
+    assert!(Cli::try_parse_from(["cgen", "update", "--skip", "--snooze", "7"]).is_err());
+}
+
+#[test]
+fn parses_no_pager_flag() {
+    let cli = Cli::try_parse_from(["cgen", "--no-pager", "prompt"]).expect("--no-pager parses");
+    assert!(cli.no_pager);
+    assert!(matches!(cli.command, Some(Command::Prompt { diff: false })));
+}
+
+#[test]
+fn parses_color_flags() {
+    use auto_commit_rs::cli::ColorChoice;
+    let cli = Cli::try_parse_from(["cgen"]).expect("no flags parse");
+    assert_eq!(cli.color, ColorChoice::Auto);
+    let cli = Cli::try_parse_from(["cgen", "--color=never"]).expect("--color parses");
+    assert_eq!(cli.color, ColorChoice::Never);
+    assert!(
+        Cli::try_parse_from(["cgen", "--no-color"])
+            .unwrap()
+            .no_color
+    );
+    assert!(Cli::try_parse_from(["cgen", "--color", "sometimes"]).is_err());
+    assert!(Cli::try_parse_from(["cgen", "--color", "always", "--no-color"]).is_err());
+}
+
+#[test]
+fn parses_quiet_flag() {
+    let cli = Cli::try_parse_from(["cgen", "-q", "--dry-run"]).expect("-q parses");
+    assert!(cli.quiet && cli.dry_run);
+    assert!(!cli.dry_run_request);
+    assert!(
+        Cli::try_parse_from(["cgen", "--dry-run-request"])
+            .unwrap()
+            .dry_run_request
+    );
+    assert!(Cli::try_parse_from(["cgen", "--quiet"]).unwrap().quiet);
+    assert!(Cli::try_parse_from(["cgen", "--quiet", "--verbose"]).is_err());
+}
+
+#[test]
+fn parses_log_file_flag() {
+    let cli = Cli::try_parse_from(["cgen", "--log-file", "cgen.log", "--dry-run"])
+        .expect("--log-file parses");
+    assert_eq!(cli.log_file, Some(std::path::PathBuf::from("cgen.log")));
+    assert!(Cli::try_parse_from(["cgen"]).unwrap().log_file.is_none());
+}
+
+#[test]
+fn parses_repeated_verbose_flag() {
+    assert_eq!(Cli::try_parse_from(["cgen"]).unwrap().verbose, 0);
+    assert_eq!(
+        Cli::try_parse_from(["cgen", "--verbose"]).unwrap().verbose,
+        1
+    );
+    assert_eq!(Cli::try_parse_from(["cgen", "-vv"]).unwrap().verbose, 2);
+    assert_eq!(
+        Cli::try_parse_from(["cgen", "-v", "-v"]).unwrap().verbose,
+        2
+    );
+}
+
+#[test]
+fn parses_seed_flag() {
+    let cli = Cli::try_parse_from(["cgen", "--seed", "42"]).expect("seed should parse");
+    assert_eq!(cli.seed, Some(42));
+    assert!(Cli::try_parse_from(["cgen", "--seed", "-1"]).is_err());
+}
+
+#[test]
+fn parses_history_grep() {
+    let cli = Cli::try_parse_from(["cgen", "history", "--grep", "login"]).expect("should parse");
+    assert!(matches!(
+        cli.command,
+        Some(Command::History { grep: Some(ref text), branch: None }) if text == "login"
+    ));
+}
+
+#[test]
+fn parses_history_branch_with_and_without_name() {
+    let cli = Cli::try_parse_from(["cgen", "history", "--branch", "feat/x"]).expect("parse");
+    assert!(matches!(
+        cli.command,
+        Some(Command::History { branch: Some(ref b), .. }) if b == "feat/x"
+    ));
+    let cli = Cli::try_parse_from(["cgen", "history", "--branch"]).expect("parse");
+    assert!(matches!(
+        cli.command,
+        Some(Command::History { branch: Some(ref b), .. }) if b.is_empty()
+    ));
 }
 
 #[test]
 fn parses_config_subcommand_without_scope_flag() {
     let cli = Cli::try_parse_from(["cgen", "config"]).expect("config should parse");
-    assert!(matches!(cli.command, Some(Command::Config)));
+    assert!(matches!(
+        cli.command,
+        Some(Command::Config { action: None })
+    ));
+}
+
+#[test]
+fn parses_non_interactive_config_actions() {
+    let cli = Cli::try_parse_from(["cgen", "config", "set", "provider", "openai", "--local"])
+        .expect("config set should parse");
+    match cli.command {
+        Some(Command::Config {
+            action: Some(ConfigAction::Set { key, value, scope }),
+        }) => {
+            assert_eq!(key, "provider");
+            assert_eq!(value, "openai");
+            assert_eq!(scope.scope(), Some(ConfigScope::Repo));
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["cgen", "config", "get", "MODEL", "--global"])
+        .expect("config get should parse");
+    match cli.command {
+        Some(Command::Config {
+            action: Some(ConfigAction::Get { scope, reveal, .. }),
+        }) => {
+            assert_eq!(scope.scope(), Some(ConfigScope::Global));
+            assert!(!reveal);
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["cgen", "config", "get", "API_KEY", "--reveal"])
+        .expect("config get --reveal should parse");
+    match cli.command {
+        Some(Command::Config {
+            action: Some(ConfigAction::Get { reveal, .. }),
+        }) => assert!(reveal),
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["cgen", "config", "unset", "model", "--env"])
+        .expect("config unset should parse");
+    match cli.command {
+        Some(Command::Config {
+            action: Some(ConfigAction::Unset { scope, .. }),
+        }) => assert_eq!(scope.scope(), Some(ConfigScope::Env)),
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    assert!(Cli::try_parse_from(["cgen", "config", "list", "--local", "--global"]).is_err());
+    assert!(Cli::try_parse_from(["cgen", "config", "list", "--local", "--env"]).is_err());
+    assert!(Cli::try_parse_from(["cgen", "config", "list", "--team", "--personal"]).is_err());
+
+    let cli = Cli::try_parse_from(["cgen", "config", "list", "--team"])
+        .expect("config list should parse");
+    match cli.command {
+        Some(Command::Config {
+            action: Some(ConfigAction::List { scope }),
+        }) => assert_eq!(scope.scope(), Some(ConfigScope::Team)),
+        other => panic!("unexpected command: {other:?}"),
+    }
+}
+
+#[test]
+fn parses_preset_export_and_import() {
+    let cli = Cli::try_parse_from(["cgen", "preset"]).expect("preset should parse");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Preset { action: None })
+    ));
+
+    let cli = Cli::try_parse_from(["cgen", "preset", "export", "Corp", "--out", "presets.toml"])
+        .expect("preset export should parse");
+    match cli.command {
+        Some(Command::Preset {
+            action: Some(PresetAction::Export { presets, out, keys }),
+        }) => {
+            assert_eq!(presets, vec!["Corp".to_string()]);
+            assert_eq!(out, Some("presets.toml".into()));
+            assert!(!keys);
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["cgen", "preset", "import", "https://example.com/p.toml"])
+        .expect("preset import should parse");
+    match cli.command {
+        Some(Command::Preset {
+            action: Some(PresetAction::Import { source }),
+        }) => assert_eq!(source, "https://example.com/p.toml"),
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["cgen", "preset", "test", "Corp", "3"])
+        .expect("preset test should parse");
+    match cli.command {
+        Some(Command::Preset {
+            action: Some(PresetAction::Test { presets }),
+        }) => assert_eq!(presets, vec!["Corp".to_string(), "3".to_string()]),
+        other => panic!("unexpected command: {other:?}"),
+    }
 }
 
 #[test]
@@ -22,3 +255,37 @@ fn rejects_removed_config_global_flag() {
         "expected clap to mention removed --global flag, got: {rendered}"
     );
 }
+
+#[test]
+fn parses_undo_count_and_modes() {
+    let cli = Cli::try_parse_from(["cgen", "undo", "-n", "3", "--mixed"]).expect("should parse");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Undo {
+            count: 3,
+            mixed: true,
+            discard: false
+        })
+    ));
+    assert!(Cli::try_parse_from(["cgen", "undo", "-n", "0"]).is_err());
+    assert!(Cli::try_parse_from(["cgen", "undo", "--mixed", "--discard"]).is_err());
+}
+
+#[test]
+fn parses_alter_force() {
+    let cli = Cli::try_parse_from(["cgen", "alter", "abc123", "--force"]).expect("alter --force");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Alter { ref commits, force: true }) if commits == &["abc123"]
+    ));
+}
+
+#[test]
+fn parses_allow_empty_with_context() {
+    let cli = Cli::try_parse_from(["cgen", "--allow-empty", "--context", "retrigger CI"])
+        .expect("allow-empty should parse");
+    assert!(cli.allow_empty);
+    assert_eq!(cli.context.as_deref(), Some("retrigger CI"));
+    assert!(cli.extra_args.is_empty());
+    assert!(Cli::try_parse_from(["cgen", "--context", "why"]).is_err());
+}
@@ -0,0 +1,78 @@
+use auto_commit_rs::commitlint::{load_from_dir, parse_rules, CommitlintRules};
+
+#[test]
+fn parses_json_rules() {
+    let rules = parse_rules(
+        r#"{
+  "rules": {
+    "type-enum": [2, "always", ["feat", "fix", "chore"]],
+    "scope-enum": [2, "always", ["api", "cli"]],
+    "header-max-length": [2, "always", 60],
+    "subject-case": [2, "always", "lower-case"]
+  }
+}"#,
+    );
+    assert_eq!(rules.type_enum, vec!["feat", "fix", "chore"]);
+    assert_eq!(rules.scope_enum, vec!["api", "cli"]);
+    assert_eq!(rules.header_max_length, Some(60));
+    assert!(rules.lowercase_subject);
+}
+
+#[test]
+fn parses_flow_yaml_rules() {
+    let rules = parse_rules(
+        "rules:\n  type-enum: [2, always, [feat, fix]]\n  subject-max-length: [1, always, 50]\n",
+    );
+    assert_eq!(rules.type_enum, vec!["feat", "fix"]);
+    assert_eq!(rules.header_max_length, Some(50));
+    assert!(!rules.lowercase_subject);
+}
+
+#[test]
+fn parses_js_config_extending_conventional() {
+    let rules = parse_rules(
+        r#"import { RuleConfigSeverity } from '@commitlint/types';
+export default {
+  extends: ['@commitlint/config-conventional'],
+  rules: {
+    'scope-enum': [RuleConfigSeverity.Error, 'always', ['core', 'docs']],
+    'header-max-length': [RuleConfigSeverity.Error, 'always', 72],
+    'body-max-line-length': [RuleConfigSeverity.Disabled, 'always', Infinity],
+  },
+};"#,
+    );
+    assert!(rules.type_enum.contains(&"feat".to_string()));
+    assert!(rules.type_enum.contains(&"refactor".to_string()));
+    assert_eq!(rules.scope_enum, vec!["core", "docs"]);
+    assert_eq!(rules.header_max_length, Some(72));
+    assert_eq!(rules.body_max_line_length, None);
+    assert!(rules.lowercase_subject);
+}
+
+#[test]
+fn disabled_rules_are_ignored() {
+    let rules = parse_rules(
+        r#"{"extends": ["@commitlint/config-conventional"], "rules": {"type-enum": [0], "subject-case": [0]}}"#,
+    );
+    assert!(rules.type_enum.is_empty());
+    assert!(!rules.lowercase_subject);
+}
+
+#[test]
+fn unrelated_config_yields_no_rules() {
+    assert_eq!(parse_rules("{}"), CommitlintRules::default());
+}
+
+#[test]
+fn load_from_dir_finds_known_file_names() {
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    assert!(load_from_dir(dir.path()).is_none());
+
+    std::fs::write(
+        dir.path().join("commitlint.config.js"),
+        "module.exports = { rules: { 'type-enum': [2, 'always', ['feat']] } };",
+    )
+    .expect("write config");
+    let rules = load_from_dir(dir.path()).expect("rules");
+    assert_eq!(rules.type_enum, vec!["feat"]);
+}
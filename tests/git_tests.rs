@@ -3,7 +3,7 @@ mod common;
 use auto_commit_rs::git;
 use serial_test::serial;
 
-use crate::common::{commit_file, git_ok, git_stdout, write_file, DirGuard};
+use crate::common::{commit_file, git_ok, git_stdout, write_file, DirGuard, EnvGuard};
 
 #[test]
 fn compute_next_minor_tag_handles_valid_and_invalid_input() {
@@ -39,6 +39,146 @@ fn staged_diff_and_files_behave_for_empty_and_non_empty_index() {
     assert!(diff.contains("src.txt"));
 }
 
+#[test]
+#[serial]
+fn git_dir_and_work_tree_env_select_the_repository() {
+    let repo = common::init_git_repo();
+    let elsewhere = tempfile::TempDir::new().expect("tempdir");
+    let _cwd = DirGuard::enter(elsewhere.path());
+    let git_dir = repo.path().join(".git");
+    let _env = common::EnvGuard::set(&[
+        ("GIT_DIR", git_dir.to_str().unwrap()),
+        ("GIT_WORK_TREE", repo.path().to_str().unwrap()),
+    ]);
+
+    write_file(&repo.path().join("src.txt"), "hello");
+    git::command()
+        .args(["add", "src.txt"])
+        .status()
+        .expect("git add");
+    let files = git::list_staged_files().expect("list staged files");
+    assert_eq!(files, vec!["src.txt".to_string()]);
+}
+
+#[test]
+#[serial]
+fn staged_diff_summarizes_submodule_bumps() {
+    let lib = common::init_git_repo();
+    let first = commit_file(lib.path(), "lib.txt", "one", "Initial lib");
+    commit_file(lib.path(), "lib.txt", "two", "Teach lib to count to two");
+
+    let repo = common::init_git_repo();
+    commit_file(repo.path(), "README.md", "app", "Initial app");
+    git_ok(
+        repo.path(),
+        [
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            lib.path().to_str().unwrap(),
+            "lib",
+        ],
+    );
+    let sub = repo.path().join("lib");
+    git_ok(&sub, ["checkout", "-q", &first]);
+    git_ok(repo.path(), ["commit", "-qam", "Add lib"]);
+    git_ok(&sub, ["checkout", "-q", "-"]);
+    git_ok(repo.path(), ["add", "lib"]);
+
+    let _cwd = DirGuard::enter(repo.path());
+    let diff = git::get_staged_diff_filtered(&[], false).expect("staged diff");
+    assert!(diff.contains("Submodule lib "), "{diff}");
+    assert!(diff.contains("Teach lib to count to two"), "{diff}");
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn pre_commit_hook_runs_and_reports_reformatted_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    assert_eq!(
+        git::run_pre_commit_hook(true).expect("no hook"),
+        git::PreCommitOutcome::Missing
+    );
+
+    // A formatter-style hook: rewrites the file and fails when it had to change it
+    let hook = repo.path().join(".git/hooks/pre-commit");
+    write_file(
+        &hook,
+        "#!/bin/sh\ngrep -q formatted code.txt && exit 0\necho formatted > code.txt\nexit 1\n",
+    );
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+    write_file(&repo.path().join("code.txt"), "messy");
+    git_ok(repo.path(), ["add", "code.txt"]);
+
+    assert_eq!(
+        git::run_pre_commit_hook(true).expect("hook runs"),
+        git::PreCommitOutcome::Failed
+    );
+    let modified = git::unstaged_files().expect("unstaged files");
+    assert_eq!(modified, vec!["code.txt".to_string()]);
+    git::stage_files(&modified).expect("restage");
+    assert!(git::get_staged_diff().unwrap().contains("+formatted"));
+    assert_eq!(
+        git::run_pre_commit_hook(true).expect("hook runs"),
+        git::PreCommitOutcome::Passed
+    );
+}
+
+#[test]
+#[serial]
+fn in_progress_operation_detects_stopped_merges_and_cherry_picks() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "file.txt", "base", "Base");
+    git_ok(repo.path(), ["checkout", "-q", "-b", "other"]);
+    let theirs = commit_file(repo.path(), "file.txt", "theirs", "Theirs");
+    git_ok(repo.path(), ["checkout", "-q", "-"]);
+    commit_file(repo.path(), "file.txt", "ours", "Ours");
+    assert_eq!(git::in_progress_operation().unwrap(), None);
+
+    let conflicted = |args: &[&str]| {
+        let status = git::command().args(args).output().expect("run git").status;
+        assert!(!status.success(), "{args:?} should conflict");
+    };
+    conflicted(&["merge", "other"]);
+    assert_eq!(
+        git::in_progress_operation().unwrap(),
+        Some(git::InProgressOperation::Merge)
+    );
+    git_ok(repo.path(), ["merge", "--abort"]);
+
+    conflicted(&["cherry-pick", &theirs]);
+    assert_eq!(
+        git::in_progress_operation().unwrap(),
+        Some(git::InProgressOperation::CherryPick)
+    );
+}
+
+#[test]
+#[serial]
+fn head_reads_match_the_git_cli() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let head = commit_file(repo.path(), "file.txt", "one", "One");
+
+    assert_eq!(git::head_hash().expect("head hash"), head);
+    assert_eq!(
+        git::current_branch().expect("branch"),
+        git_stdout(repo.path(), ["rev-parse", "--abbrev-ref", "HEAD"])
+    );
+    assert!(git::commit_exists(&head));
+    assert!(git::commit_exists(&head[..7]));
+    assert!(!git::commit_exists(&"0".repeat(40)));
+
+    git_ok(repo.path(), ["checkout", "-q", "--detach"]);
+    assert_eq!(git::current_branch().expect("branch"), "HEAD");
+}
+
 #[test]
 #[serial]
 fn commit_and_undo_roundtrip() {
@@ -81,6 +221,23 @@ fn latest_tag_and_create_tag_work() {
         git::get_latest_tag().expect("latest tag"),
         Some("0.2.0".to_string())
     );
+
+    // Version order, not byte order, and annotated tags count too
+    git_ok(repo.path(), ["tag", "-a", "0.10.0", "-m", "release"]);
+    assert_eq!(
+        git::get_latest_tag().expect("latest tag"),
+        Some("0.10.0".to_string())
+    );
+}
+
+#[test]
+fn version_cmp_matches_git_version_sort() {
+    use std::cmp::Ordering;
+    assert_eq!(git::version_cmp("v1.9.0", "v1.10.0"), Ordering::Less);
+    assert_eq!(git::version_cmp("1.2.10", "1.2.9"), Ordering::Greater);
+    assert_eq!(git::version_cmp("1.0", "1.0.1"), Ordering::Less);
+    assert_eq!(git::version_cmp("release-2", "release-2"), Ordering::Equal);
+    assert_eq!(git::version_cmp("a1", "b0"), Ordering::Less);
 }
 
 #[test]
@@ -102,6 +259,30 @@ fn commit_and_range_diff_and_head_checks_work() {
     assert!(!git::is_head_commit(&first).expect("head check"));
 }
 
+#[test]
+#[serial]
+fn get_commit_messages_handles_single_refs_and_ranges() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let first = commit_file(repo.path(), "a.txt", "one", "feat: first");
+    let second = commit_file(repo.path(), "a.txt", "two", "fix: second\n\nwith a body");
+    let third = commit_file(repo.path(), "a.txt", "three", "docs: third");
+
+    let head = git::get_commit_messages("HEAD").expect("head message");
+    assert_eq!(head, vec![(third.clone(), "docs: third".to_string())]);
+
+    let range = git::get_commit_messages(&format!("{first}..HEAD")).expect("range messages");
+    assert_eq!(
+        range,
+        vec![
+            (third, "docs: third".to_string()),
+            (second, "fix: second\n\nwith a body".to_string()),
+        ]
+    );
+
+    assert!(git::get_commit_messages("does-not-exist").is_err());
+}
+
 #[test]
 #[serial]
 fn upstream_and_pushed_detection_work() {
@@ -123,6 +304,137 @@ fn upstream_and_pushed_detection_work() {
     assert!(git::commit_is_pushed(&head).expect("commit pushed check"));
 }
 
+#[test]
+#[serial]
+fn force_push_with_lease_replaces_rewritten_history() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let pushed = commit_file(repo.path(), "tracked.txt", "v1", "feat: tracked");
+    assert_eq!(git::upstream().expect("no upstream"), None);
+
+    let remote = tempfile::TempDir::new().expect("temp remote");
+    git_ok(remote.path(), ["init", "--bare"]);
+    let remote_url = remote.path().to_string_lossy().replace('\\', "/");
+    git_ok(repo.path(), ["remote", "add", "origin", &remote_url]);
+    git_ok(repo.path(), ["push", "-u", "origin", "HEAD"]);
+
+    let upstream = git::upstream().expect("upstream").expect("has upstream");
+    assert_eq!(upstream.remote, "origin");
+    assert_eq!(upstream.expected, pushed);
+    assert_eq!(
+        git::remote_branch_commit(&upstream).expect("ls-remote"),
+        Some(pushed.clone())
+    );
+
+    let rewritten = git::rewrite_commit_message("HEAD", "feat: reworded", true).expect("rewrite");
+    assert_eq!(rewritten, git_stdout(repo.path(), ["rev-parse", "HEAD"]));
+    git::force_push_with_lease(&upstream, true).expect("force push");
+    assert_eq!(
+        git::remote_branch_commit(&upstream).expect("ls-remote"),
+        Some(rewritten)
+    );
+
+    // The lease no longer matches the remote, so a second force-push is refused
+    git::rewrite_commit_message("HEAD", "feat: reworded again", true).expect("rewrite");
+    assert!(git::force_push_with_lease(&upstream, true).is_err());
+}
+
+#[test]
+#[serial]
+fn push_to_remote_pushes_each_named_remote() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let head = commit_file(repo.path(), "tracked.txt", "v1", "feat: tracked");
+    let branch = git::current_branch().expect("branch");
+
+    let remotes: Vec<tempfile::TempDir> = (0..2)
+        .map(|_| tempfile::TempDir::new().expect("temp remote"))
+        .collect();
+    for (name, remote) in ["origin", "upstream"].iter().zip(&remotes) {
+        git_ok(remote.path(), ["init", "--bare"]);
+        let url = remote.path().to_string_lossy().replace('\\', "/");
+        git_ok(repo.path(), ["remote", "add", name, &url]);
+    }
+    assert_eq!(git::remotes().expect("remotes"), vec!["origin", "upstream"]);
+
+    for name in ["origin", "upstream"] {
+        git::push_to_remote(name, false, true).expect("push");
+    }
+    for remote in &remotes {
+        assert_eq!(git_stdout(remote.path(), ["rev-parse", &branch]), head);
+    }
+}
+
+#[test]
+#[serial]
+fn push_to_remote_can_set_the_upstream() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "tracked.txt", "v1", "feat: tracked");
+    let remote = tempfile::TempDir::new().expect("temp remote");
+    git_ok(remote.path(), ["init", "--bare"]);
+    let url = remote.path().to_string_lossy().replace('\\', "/");
+    git_ok(repo.path(), ["remote", "add", "fork", &url]);
+
+    assert_eq!(
+        git::default_push_remote().expect("default remote"),
+        Some("fork".to_string())
+    );
+    assert!(!git::has_upstream_branch().expect("upstream check"));
+    git::push_to_remote("fork", true, true).expect("push -u");
+    let upstream = git::upstream().expect("upstream").expect("has upstream");
+    assert_eq!(upstream.remote, "fork");
+}
+
+#[test]
+#[serial]
+fn default_branch_comes_from_the_remote_head() {
+    let source = common::init_git_repo();
+    commit_file(source.path(), "tracked.txt", "v1", "feat: tracked");
+    let source_branch = git_stdout(source.path(), ["rev-parse", "--abbrev-ref", "HEAD"]);
+
+    let clone = tempfile::TempDir::new().expect("temp clone");
+    let source_url = source.path().to_string_lossy().replace('\\', "/");
+    git_ok(clone.path(), ["clone", "-q", &source_url, "."]);
+    let _cwd = DirGuard::enter(clone.path());
+    assert_eq!(
+        git::default_branch().expect("default branch"),
+        Some(source_branch)
+    );
+
+    git_ok(clone.path(), ["remote", "set-head", "origin", "--delete"]);
+    assert_eq!(git::default_branch().expect("default branch"), None);
+}
+
+#[test]
+#[serial]
+fn ahead_behind_and_pull_rebase_catch_up_with_upstream() {
+    let repo = common::init_git_repo();
+    commit_file(repo.path(), "base.txt", "base", "Base");
+    let remote = tempfile::TempDir::new().expect("temp remote");
+    git_ok(remote.path(), ["init", "--bare"]);
+    let url = remote.path().to_string_lossy().replace('\\', "/");
+    git_ok(repo.path(), ["remote", "add", "origin", &url]);
+    git_ok(repo.path(), ["push", "-q", "-u", "origin", "HEAD"]);
+
+    // Someone else pushes from another clone
+    let other = tempfile::TempDir::new().expect("other clone");
+    git_ok(other.path(), ["clone", "-q", &url, "."]);
+    git_ok(other.path(), ["config", "user.email", "other@example.com"]);
+    git_ok(other.path(), ["config", "user.name", "Other"]);
+    commit_file(other.path(), "theirs.txt", "theirs", "Theirs");
+    git_ok(other.path(), ["push", "-q"]);
+
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "ours.txt", "ours", "Ours");
+    assert_eq!(git::ahead_behind().expect("counts"), (1, 0));
+    git::fetch("origin", true).expect("fetch");
+    assert_eq!(git::ahead_behind().expect("counts"), (1, 1));
+    git::pull_rebase(true).expect("pull --rebase");
+    assert_eq!(git::ahead_behind().expect("counts"), (1, 0));
+    assert!(repo.path().join("theirs.txt").exists());
+}
+
 #[test]
 #[serial]
 fn rewrite_head_commit_message_amends_commit() {
@@ -207,21 +519,67 @@ fn reword_non_head_commit_works() {
     let _c3 = commit_file(repo.path(), "a.txt", "3", "c3");
 
     // We want to reword c2.
-    // git::rewrite_commit_message handles HEAD (amend) and non-HEAD (rebase).
+    // git::rewrite_commit_message handles HEAD (amend) and non-HEAD (commit-tree).
     // It calls reword_non_head_commit for c2.
 
-    git::rewrite_commit_message(&c2, "new message", true).expect("rewrite should succeed");
+    let rewritten =
+        git::rewrite_commit_message(&c2, "new message", true).expect("rewrite should succeed");
 
     // Verify c2 message is changed.
     // Since history changed, we look at HEAD~1
     let msg = git_stdout(repo.path(), ["log", "-1", "--pretty=%s", "HEAD~1"]);
     assert_eq!(msg, "new message");
+    // The returned hash is the reworded commit, not HEAD
+    assert_eq!(rewritten, git_stdout(repo.path(), ["rev-parse", "HEAD~1"]));
 
     // Verify content is still there (c3 content at HEAD)
     let content = std::fs::read_to_string(repo.path().join("a.txt")).expect("read file");
     assert_eq!(content, "3");
 }
 
+#[test]
+#[serial]
+fn reword_non_head_commit_keeps_descendants_and_dirty_tree() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+
+    let _c1 = commit_file(repo.path(), "a.txt", "1", "c1");
+    let c2 = commit_file(repo.path(), "a.txt", "2", "old message");
+    write_file(&repo.path().join("b.txt"), "b");
+    git_ok(repo.path(), ["add", "b.txt"]);
+    git_ok(
+        repo.path(),
+        [
+            "-c",
+            "user.name=Other",
+            "-c",
+            "user.email=other@example.com",
+            "commit",
+            "-m",
+            "c3\n\nbody line",
+        ],
+    );
+    let old_tree = git_stdout(repo.path(), ["rev-parse", "HEAD^{tree}"]);
+    write_file(&repo.path().join("a.txt"), "uncommitted");
+
+    git::rewrite_commit_message(&c2, "new message", true).expect("rewrite should succeed");
+
+    assert_eq!(
+        git_stdout(repo.path(), ["log", "--pretty=%s", "-3"]),
+        "c3\nnew message\nc1"
+    );
+    assert_eq!(
+        git_stdout(repo.path(), ["rev-parse", "HEAD^{tree}"]),
+        old_tree
+    );
+    assert_eq!(
+        git_stdout(repo.path(), ["log", "-1", "--pretty=%an <%ae>%n%b"]),
+        "Other <other@example.com>\nbody line"
+    );
+    let content = std::fs::read_to_string(repo.path().join("a.txt")).expect("read file");
+    assert_eq!(content, "uncommitted");
+}
+
 #[test]
 fn filter_diff_by_globs_excludes_matching_files() {
     let diff = r#"diff --git a/src/main.rs b/src/main.rs
@@ -262,6 +620,34 @@ index ccccccc..ddddddd 100644
     assert!(!filtered.contains("data.csv"));
 }
 
+#[test]
+fn count_changed_lines_ignores_file_headers() {
+    let diff = "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n+more\n context\n";
+    assert_eq!(git::count_changed_lines(diff), 3);
+    assert_eq!(git::count_changed_lines(""), 0);
+}
+
+#[test]
+fn truncate_diff_keeps_every_file_header() {
+    let added: String = (0..50).map(|i| format!("+line {i}\n")).collect();
+    let diff = format!(
+        "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-old\n+new\n\
+         diff --git a/b.rs b/b.rs\n--- a/b.rs\n+++ b/b.rs\n@@ -0,0 +1,50 @@\n{added}"
+    );
+    assert_eq!(
+        git::truncate_diff(&diff, diff.len()).as_deref(),
+        Some(diff.as_str())
+    );
+
+    let truncated = git::truncate_diff(&diff, 300).expect("headers fit");
+    assert!(truncated.len() <= 300);
+    assert!(truncated.contains("+new\n"));
+    assert!(truncated.contains("diff --git a/b.rs b/b.rs"));
+    assert!(truncated.contains("diff lines omitted"));
+
+    assert_eq!(git::truncate_diff(&diff, 40), None);
+}
+
 #[test]
 fn filter_diff_by_globs_returns_full_diff_when_no_patterns() {
     let diff = "diff --git a/foo.json b/foo.json\n+test\n";
@@ -485,3 +871,296 @@ fn get_range_diff_same_commit() {
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("No diff"));
 }
+
+#[test]
+#[serial]
+fn resolve_editor_follows_git_precedence() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let _env = EnvGuard::clear(&["GIT_EDITOR", "VISUAL", "EDITOR"]);
+
+    let _editor = EnvGuard::set(&[("EDITOR", "nano")]);
+    assert_eq!(git::resolve_editor().as_deref(), Some("nano"));
+
+    let _visual = EnvGuard::set(&[("VISUAL", "vim")]);
+    assert_eq!(git::resolve_editor().as_deref(), Some("vim"));
+
+    git_ok(repo.path(), ["config", "core.editor", "code --wait"]);
+    assert_eq!(git::resolve_editor().as_deref(), Some("code --wait"));
+
+    let _git_editor = EnvGuard::set(&[("GIT_EDITOR", "hx")]);
+    assert_eq!(git::resolve_editor().as_deref(), Some("hx"));
+}
+
+#[test]
+fn commit_stat_and_branches_containing_describe_a_commit() {
+    let repo = common::init_git_repo();
+    let path = repo.path().to_string_lossy().to_string();
+    let first = commit_file(repo.path(), "a.txt", "one\ntwo\n", "first");
+
+    let stat = git::commit_stat(&path, &first).expect("stat");
+    assert!(stat.contains("a.txt"));
+    assert!(stat.contains("1 file changed, 2 insertions(+)"));
+    assert_eq!(git::branches_containing(&path, &first).unwrap().len(), 1);
+
+    assert!(git::commit_stat(&path, "0000000000000000000000000000000000000000").is_err());
+}
+
+#[test]
+#[serial]
+fn undone_commit_can_be_restored_while_the_index_matches() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "a.txt", "1", "first");
+    let second = commit_file(repo.path(), "a.txt", "2", "second");
+    let tree = git::commit_tree(&second).expect("tree");
+
+    git::undo_last_commit_soft(true).expect("undo");
+    assert_eq!(git::index_tree().expect("index tree"), tree);
+    assert!(git::commit_exists(&second));
+
+    git::reset_soft_to(&second, true).expect("redo");
+    assert_eq!(git_stdout(repo.path(), ["rev-parse", "HEAD"]), second);
+    assert!(!git::commit_exists(
+        "0000000000000000000000000000000000000000"
+    ));
+}
+
+#[test]
+#[serial]
+fn undo_modes_keep_unstage_or_discard_changes() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "a.txt", "1", "first");
+
+    commit_file(repo.path(), "a.txt", "2", "second");
+    git::undo_last_commit(git::ResetMode::Mixed, true).expect("mixed undo");
+    assert!(git_stdout(repo.path(), ["diff", "--cached", "--name-only"]).is_empty());
+    assert_eq!(git_stdout(repo.path(), ["diff", "--name-only"]), "a.txt");
+
+    git_ok(repo.path(), ["commit", "-am", "second again"]);
+    git::undo_last_commit(git::ResetMode::Hard, true).expect("hard undo");
+    assert!(git_stdout(repo.path(), ["status", "--porcelain"]).is_empty());
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("a.txt")).unwrap(),
+        "1"
+    );
+}
+
+#[test]
+#[serial]
+fn redo_restores_the_undo_mode() {
+    use auto_commit_rs::undo::{check_redo, redo_mode, redoable, UndoEntry};
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let first = commit_file(repo.path(), "a.txt", "1", "first");
+    let second = commit_file(repo.path(), "a.txt", "2", "second");
+    let undo = |mode| {
+        git::undo_last_commit(mode, true).expect("undo");
+        // Mirrors the journal entry `cgen undo` writes
+        UndoEntry {
+            repo_path: String::new(),
+            undone: second.clone(),
+            target: first.clone(),
+            tree: git::index_tree().expect("index tree"),
+            mixed: mode == git::ResetMode::Mixed,
+            message: "second".into(),
+            timestamp: String::new(),
+        }
+    };
+
+    // Soft: the changes are still staged, redo moves HEAD back over them
+    let entry = undo(git::ResetMode::Soft);
+    assert_eq!(entry.tree, git::commit_tree(&second).unwrap());
+    git::reset_to(&second, redo_mode(&entry), true).expect("redo soft");
+    assert_eq!(git_stdout(repo.path(), ["rev-parse", "HEAD"]), second);
+    assert!(git_stdout(repo.path(), ["status", "--porcelain"]).is_empty());
+
+    // Mixed: the index was reset, so the redo check and the redo itself follow it
+    let entry = undo(git::ResetMode::Mixed);
+    assert_eq!(
+        check_redo(&entry, &first, &git::index_tree().unwrap()),
+        None
+    );
+    git::reset_to(&second, redo_mode(&entry), true).expect("redo mixed");
+    assert_eq!(git_stdout(repo.path(), ["rev-parse", "HEAD"]), second);
+    assert!(git_stdout(repo.path(), ["status", "--porcelain"]).is_empty());
+
+    // Discard: nothing is kept to redo with, so it is never journaled
+    assert!(!redoable(git::ResetMode::Hard));
+}
+
+#[test]
+#[serial]
+fn last_commits_and_multi_commit_undo() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let base = commit_file(repo.path(), "a.txt", "1", "base");
+    commit_file(repo.path(), "b.txt", "2", "wip: one");
+    commit_file(repo.path(), "c.txt", "3", "wip: two");
+
+    let commits = git::last_commits(2).expect("last commits");
+    let subjects: Vec<_> = commits.iter().map(|(_, m)| m.as_str()).collect();
+    assert_eq!(subjects, ["wip: two", "wip: one"]);
+    assert_eq!(git::last_commits(10).unwrap().len(), 3);
+
+    git::undo_last_commits(git::ResetMode::Soft, 2, true).expect("undo two");
+    assert_eq!(git_stdout(repo.path(), ["rev-parse", "HEAD"]), base);
+    assert_eq!(
+        git_stdout(repo.path(), ["diff", "--cached", "--name-only"]),
+        "b.txt\nc.txt"
+    );
+}
+
+#[test]
+#[serial]
+fn stash_roundtrip_lets_a_non_head_reword_run_on_a_dirty_tree() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "base.txt", "0", "base");
+    let first = commit_file(repo.path(), "a.txt", "1", "first");
+    commit_file(repo.path(), "b.txt", "2", "second");
+
+    write_file(&repo.path().join("a.txt"), "dirty");
+    write_file(&repo.path().join("staged.txt"), "staged");
+    git_ok(repo.path(), ["add", "staged.txt"]);
+    write_file(&repo.path().join("untracked.txt"), "new");
+    assert!(git::has_uncommitted_changes().unwrap());
+
+    git::stash_push("cgen auto-stash", true).expect("stash");
+    assert!(!git::has_uncommitted_changes().unwrap());
+    git::rewrite_commit_message(&first, "feat: reworded", true).expect("reword");
+    git::stash_pop(true).expect("pop");
+
+    assert_eq!(
+        git_stdout(repo.path(), ["log", "--format=%s", "-1", "HEAD~1"]),
+        "feat: reworded"
+    );
+    assert_eq!(
+        git_stdout(repo.path(), ["diff", "--cached", "--name-only"]),
+        "staged.txt"
+    );
+    assert!(repo.path().join("untracked.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("a.txt")).unwrap(),
+        "dirty"
+    );
+}
+
+#[test]
+#[serial]
+fn linked_worktrees_share_the_main_checkout_identity() {
+    let repo = common::init_git_repo();
+    commit_file(repo.path(), "a.txt", "1", "first");
+    let outside = tempfile::TempDir::new().expect("tempdir");
+    let worktree = outside.path().join("wt");
+    git_ok(
+        repo.path(),
+        [
+            "worktree",
+            "add",
+            "-b",
+            "feature",
+            worktree.to_str().unwrap(),
+        ],
+    );
+
+    let main_root = {
+        let _cwd = DirGuard::enter(repo.path());
+        assert_eq!(
+            git::repo_identity().unwrap(),
+            git::find_repo_root().unwrap()
+        );
+        git::find_repo_root().unwrap()
+    };
+    let _cwd = DirGuard::enter(&worktree);
+    let worktree_root = git::find_repo_root().unwrap();
+    assert_ne!(worktree_root, main_root);
+    assert!(worktree_root.ends_with("wt"));
+    assert_eq!(git::repo_identity().unwrap(), main_root);
+}
+
+#[test]
+#[serial]
+fn reword_refuses_commits_not_encoded_in_utf8() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    commit_file(repo.path(), "a.txt", "1", "first");
+    write_file(&repo.path().join("a.txt"), "2");
+    git_ok(
+        repo.path(),
+        [
+            "-c",
+            "i18n.commitEncoding=ISO-8859-1",
+            "commit",
+            "-am",
+            "latin1",
+        ],
+    );
+    let latin1 = git_stdout(repo.path(), ["rev-parse", "HEAD"]);
+    commit_file(repo.path(), "a.txt", "3", "third");
+    let head = git_stdout(repo.path(), ["rev-parse", "HEAD"]);
+
+    let err =
+        git::rewrite_commit_message(&latin1, "feat: reworded", true).expect_err("encoding header");
+    assert!(
+        format!("{err:#}").contains("not encoded in UTF-8"),
+        "{err:#}"
+    );
+    assert_eq!(git_stdout(repo.path(), ["rev-parse", "HEAD"]), head);
+}
+
+#[test]
+#[serial]
+fn reword_rewrites_signed_commits_without_their_signature() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let first = commit_file(repo.path(), "a.txt", "1", "first");
+    // A commit with a signature header, as `git commit -S` would write it
+    let raw = git_stdout(repo.path(), ["cat-file", "commit", "HEAD"]);
+    let (headers, _) = raw.split_once("\n\n").unwrap();
+    let signed = format!(
+        "{headers}\ngpgsig -----BEGIN PGP SIGNATURE-----\n \n -----END PGP SIGNATURE-----\n\nsigned\n"
+    );
+    let object = repo.path().join("signed-commit");
+    write_file(&object, &signed);
+    let signed_hash = git_stdout(
+        repo.path(),
+        [
+            "hash-object",
+            "-t",
+            "commit",
+            "-w",
+            object.to_str().unwrap(),
+        ],
+    );
+    std::fs::remove_file(&object).unwrap();
+    git_ok(repo.path(), ["reset", "--soft", &signed_hash]);
+    commit_file(repo.path(), "a.txt", "2", "second");
+
+    let rewritten =
+        git::rewrite_commit_message(&signed_hash, "feat: reworded", true).expect("reword");
+    assert_ne!(rewritten, signed_hash);
+    let raw = git_stdout(repo.path(), ["cat-file", "commit", &rewritten]);
+    assert!(!raw.contains("gpgsig"));
+    assert!(raw.ends_with("\n\nfeat: reworded"), "{raw}");
+    assert_eq!(
+        git::commit_tree(&rewritten).unwrap(),
+        git::commit_tree(&first).unwrap()
+    );
+}
+
+#[test]
+#[serial]
+fn reword_signs_the_rewritten_commits_when_commit_gpgsign_is_set() {
+    let repo = common::init_git_repo();
+    let _cwd = DirGuard::enter(repo.path());
+    let first = commit_file(repo.path(), "a.txt", "1", "first");
+    commit_file(repo.path(), "a.txt", "2", "second");
+    git_ok(repo.path(), ["config", "commit.gpgsign", "true"]);
+    // A signing program that always fails proves `commit-tree` was asked to sign
+    git_ok(repo.path(), ["config", "gpg.program", "false"]);
+
+    let err = git::rewrite_commit_message(&first, "feat: reworded", true).expect_err("signs");
+    assert!(format!("{err:#}").contains("commit-tree"), "{err:#}");
+}
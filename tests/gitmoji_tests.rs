@@ -0,0 +1,118 @@
+mod common;
+
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::gitmoji::{
+    apply, custom_set_instructions, emoji_for_type, emoji_for_type_in, load_custom_set, GitmojiSet,
+};
+use serial_test::serial;
+
+use crate::common::EnvGuard;
+
+const CUSTOM_SET: &str = r#"
+[types.feat]
+unicode = "🚀"
+shortcode = ":rocket:"
+
+[types.hotfix]
+unicode = "🚑️"
+shortcode = ":ambulance:"
+
+[[catalog]]
+unicode = "🔥"
+shortcode = ":fire:"
+description = "Remove code or files"
+"#;
+
+fn gitmoji_cfg(format: &str) -> AppConfig {
+    let mut cfg = AppConfig::default();
+    cfg.use_gitmoji = true;
+    cfg.gitmoji_format = format.into();
+    cfg
+}
+
+#[test]
+fn emoji_for_type_honours_format() {
+    assert_eq!(emoji_for_type("feat", "unicode").as_deref(), Some("✨"));
+    assert_eq!(emoji_for_type("FIX", "unicode").as_deref(), Some("🐛"));
+    assert_eq!(
+        emoji_for_type("docs", "shortcode").as_deref(),
+        Some(":memo:")
+    );
+    assert_eq!(emoji_for_type("unknown", "unicode"), None);
+}
+
+#[test]
+fn apply_replaces_or_inserts_mapped_emoji() {
+    let cfg = gitmoji_cfg("unicode");
+    assert_eq!(apply(&cfg, "🎨 feat(api): add x"), "✨ feat(api): add x");
+    assert_eq!(apply(&cfg, "fix: y\n\nbody"), "🐛 fix: y\n\nbody");
+
+    let cfg = gitmoji_cfg("shortcode");
+    assert_eq!(
+        apply(&cfg, ":art: feat!: drop v1"),
+        ":sparkles: feat!: drop v1"
+    );
+}
+
+#[test]
+fn apply_keeps_message_for_unknown_types_or_when_disabled() {
+    let mut cfg = gitmoji_cfg("unicode");
+    assert_eq!(apply(&cfg, "🚀 launch: go"), "🚀 launch: go");
+    assert_eq!(apply(&cfg, "not conventional"), "not conventional");
+
+    cfg.gitmoji_deterministic = false;
+    assert_eq!(apply(&cfg, "🎨 feat: add x"), "🎨 feat: add x");
+
+    cfg.gitmoji_deterministic = true;
+    cfg.use_gitmoji = false;
+    assert_eq!(apply(&cfg, "feat: add x"), "feat: add x");
+}
+
+#[test]
+fn custom_set_overrides_and_extends_built_in_mapping() {
+    let set: GitmojiSet = toml::from_str(CUSTOM_SET).expect("parse set");
+    assert_eq!(
+        emoji_for_type_in(Some(&set), "feat", "unicode").as_deref(),
+        Some("🚀")
+    );
+    assert_eq!(
+        emoji_for_type_in(Some(&set), "hotfix", "shortcode").as_deref(),
+        Some(":ambulance:")
+    );
+    assert_eq!(
+        emoji_for_type_in(Some(&set), "fix", "unicode").as_deref(),
+        Some("🐛")
+    );
+
+    let mut cfg = gitmoji_cfg("unicode");
+    cfg.gitmoji_set = Some(set);
+    assert_eq!(apply(&cfg, "✨ feat: add x"), "🚀 feat: add x");
+}
+
+#[test]
+fn custom_set_instructions_list_mapping_and_catalog() {
+    let set: GitmojiSet = toml::from_str(CUSTOM_SET).expect("parse set");
+    let text = custom_set_instructions(&set, "shortcode").expect("instructions");
+    assert!(text.contains("feat → :rocket:"));
+    assert!(text.contains("hotfix → :ambulance:"));
+    assert!(text.contains(":fire: (Remove code or files)"));
+
+    assert!(custom_set_instructions(&GitmojiSet::default(), "unicode").is_none());
+}
+
+#[test]
+#[serial]
+fn load_custom_set_reads_config_dir() {
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", dir.path().to_str().unwrap())]);
+    assert!(load_custom_set().expect("no file").is_none());
+
+    std::fs::create_dir_all(dir.path().join("cgen")).unwrap();
+    std::fs::write(dir.path().join("cgen").join("gitmoji.toml"), CUSTOM_SET).unwrap();
+    let set = load_custom_set().expect("load").expect("set");
+    assert_eq!(set.types.len(), 2);
+    assert_eq!(set.catalog.len(), 1);
+
+    std::fs::write(dir.path().join("cgen").join("gitmoji.toml"), "types = 3").unwrap();
+    assert!(load_custom_set().is_err());
+}
@@ -0,0 +1,64 @@
+use auto_commit_rs::i18n::{catalog_for, fill, parse_catalog};
+
+const EN: &str = include_str!("../i18n/en.toml");
+const PT_BR: &str = include_str!("../i18n/pt-br.toml");
+
+#[test]
+fn embedded_translations_cover_exactly_the_english_keys() {
+    let en = parse_catalog(EN);
+    let pt_br = parse_catalog(PT_BR);
+    assert!(en.len() > 50);
+
+    let mut missing: Vec<_> = en.keys().filter(|k| !pt_br.contains_key(*k)).collect();
+    let mut unknown: Vec<_> = pt_br.keys().filter(|k| !en.contains_key(*k)).collect();
+    missing.sort();
+    unknown.sort();
+    assert!(missing.is_empty(), "pt-br is missing {missing:?}");
+    assert!(unknown.is_empty(), "pt-br has unknown keys {unknown:?}");
+}
+
+#[test]
+fn catalog_selects_embedded_translation_and_normalizes_locale() {
+    assert_eq!(catalog_for("en", &[])["commit_message"], "Commit message:");
+    assert_eq!(
+        catalog_for("pt-br", &[])["commit_message"],
+        "Mensagem de commit:"
+    );
+    assert_eq!(catalog_for("pt_BR", &[])["yes"], "Sim");
+    assert_eq!(catalog_for("ja", &[])["yes"], "Yes");
+}
+
+#[test]
+fn catalog_overlays_user_files_with_english_fallback() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(
+        dir.path().join("de.toml"),
+        "yes = \"Ja\"\ncommit_message = \"Commit-Nachricht:\"\nbroken = 3\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("pt-br.toml"), "yes = \"Claro\"\n").unwrap();
+    let dirs = vec![dir.path().to_path_buf()];
+
+    let de = catalog_for("de-at", &dirs);
+    assert_eq!(de["yes"], "Ja");
+    assert_eq!(de["commit_message"], "Commit-Nachricht:");
+    assert_eq!(de["no"], "No");
+    assert!(!de.contains_key("broken"));
+
+    let pt_br = catalog_for("pt-br", &dirs);
+    assert_eq!(pt_br["yes"], "Claro");
+    assert_eq!(pt_br["no"], "Não");
+}
+
+#[test]
+fn fill_replaces_named_placeholders() {
+    assert_eq!(
+        fill(
+            "{failed} of {total} commit message(s) failed lint",
+            &[("failed", "2"), ("total", "5")]
+        ),
+        "2 of 5 commit message(s) failed lint"
+    );
+    assert_eq!(fill("no placeholders", &[("x", "y")]), "no placeholders");
+    assert!(parse_catalog("not = [valid").is_empty());
+}
@@ -1,7 +1,7 @@
 mod common;
 
 use auto_commit_rs::config::AppConfig;
-use auto_commit_rs::interpolation::interpolate;
+use auto_commit_rs::interpolation::{builtin, expand, interpolate, interpolate_template};
 use serial_test::serial;
 
 use crate::common::EnvGuard;
@@ -19,6 +19,7 @@ fn interpolate_replaces_known_variables_and_keeps_literals() {
     let result = interpolate(
         "provider=$ACR_PROVIDER model=$ACR_MODEL key=$ACR_API_KEY custom=$CUSTOM_ENV",
         &cfg,
+        "API_URL",
     );
 
     assert_eq!(
@@ -32,7 +33,7 @@ fn interpolate_replaces_known_variables_and_keeps_literals() {
 fn interpolate_replaces_unknown_variables_with_empty_string() {
     let cfg = AppConfig::default();
     let _env = EnvGuard::clear(&["DOES_NOT_EXIST"]);
-    let result = interpolate("before:$DOES_NOT_EXIST:after", &cfg);
+    let result = interpolate("before:$DOES_NOT_EXIST:after", &cfg, "API_URL");
     assert_eq!(result, "before::after");
 }
 
@@ -42,6 +43,122 @@ fn interpolate_overrides_acr_variables_from_config_values() {
     let mut cfg = AppConfig::default();
     cfg.model = "model-from-config".into();
     let _env = EnvGuard::set(&[("ACR_MODEL", "model-from-env")]);
-    let result = interpolate("model=$ACR_MODEL", &cfg);
+    let result = interpolate("model=$ACR_MODEL", &cfg, "API_URL");
     assert_eq!(result, "model=model-from-config");
 }
+
+#[test]
+#[serial]
+fn interpolate_leaves_the_process_environment_alone() {
+    let mut cfg = AppConfig::default();
+    cfg.api_key = "secret".into();
+    let _env = EnvGuard::clear(&["ACR_API_KEY"]);
+    assert_eq!(
+        interpolate("key=$ACR_API_KEY", &cfg, "API_HEADERS"),
+        "key=secret"
+    );
+    assert!(std::env::var_os("ACR_API_KEY").is_none());
+}
+
+fn lookup(name: &str) -> Option<String> {
+    match name {
+        "HOST" => Some("api.example.com".into()),
+        "EMPTY" => Some(String::new()),
+        _ => None,
+    }
+}
+
+#[test]
+fn expand_supports_braces_and_defaults() {
+    assert_eq!(
+        expand("https://${HOST}/v1", &lookup),
+        "https://api.example.com/v1"
+    );
+    assert_eq!(expand("${MISSING:-fallback}", &lookup), "fallback");
+    assert_eq!(expand("${EMPTY:-fallback}", &lookup), "fallback");
+    assert_eq!(expand("${HOST:-unused}", &lookup), "api.example.com");
+    assert_eq!(
+        expand("${MISSING:-$HOST:443}", &lookup),
+        "api.example.com:443"
+    );
+    assert_eq!(expand("${MISSING:-${EMPTY:-x}y}", &lookup), "xy");
+    assert_eq!(expand("${MISSING:-}", &lookup), "");
+}
+
+#[test]
+fn expand_keeps_escaped_and_stray_dollars() {
+    assert_eq!(expand(r"price=\$HOST", &lookup), "price=$HOST");
+    assert_eq!(expand("cost $5 and $", &lookup), "cost $5 and $");
+    assert_eq!(expand("${HOST", &lookup), "${HOST");
+    assert_eq!(expand(r"C:\path", &lookup), r"C:\path");
+}
+
+#[test]
+#[serial]
+fn interpolate_uses_defaults_for_unset_environment_variables() {
+    let cfg = AppConfig::default();
+    let _env = EnvGuard::clear(&["ACR_TEST_REGION"]);
+    let result = interpolate(
+        "https://${ACR_TEST_REGION:-us}.example.com",
+        &cfg,
+        "API_URL",
+    );
+    assert_eq!(result, "https://us.example.com");
+}
+
+#[test]
+fn builtin_variables_include_version_and_date() {
+    assert_eq!(
+        builtin("CGEN_VERSION").as_deref(),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    let date = builtin("DATE").unwrap();
+    assert_eq!(date.len(), 10);
+    assert_eq!(&date[4..5], "-");
+    assert_eq!(builtin("NOT_BUILTIN"), None);
+}
+
+#[test]
+#[serial]
+fn interpolate_template_fills_message_and_keeps_unknown_placeholders() {
+    let _env = EnvGuard::clear(&["ticket"]);
+    let result = interpolate_template(
+        "$msg\n\ncgen $CGEN_VERSION, Bug: $ticket ${ticket} ${ticket:-none} \\$msg",
+        "feat: add $HOME support",
+        &AppConfig::default(),
+        "COMMIT_TEMPLATE",
+    );
+    assert_eq!(
+        result,
+        format!(
+            "feat: add $HOME support\n\ncgen {}, Bug: $ticket ${{ticket}} none $msg",
+            env!("CARGO_PKG_VERSION")
+        )
+    );
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn command_substitution_runs_only_when_enabled() {
+    let mut cfg = AppConfig::default();
+    let template = "$msg\n\nRefs: $(printf 'PROJ-%s\\n' \"$((6 * 7))\")";
+    assert_eq!(
+        interpolate_template(template, "fix: x", &cfg, "COMMIT_TEMPLATE"),
+        "fix: x\n\nRefs: $(printf 'PROJ-%s\\n' \"$((6 * 7))\")"
+    );
+
+    cfg.template_commands = true;
+    assert_eq!(
+        interpolate_template(template, "fix: x", &cfg, "COMMIT_TEMPLATE"),
+        "fix: x\n\nRefs: PROJ-42"
+    );
+    assert_eq!(
+        interpolate("X-Id: $(exit 3)", &cfg, "API_HEADERS"),
+        "X-Id: "
+    );
+    assert_eq!(
+        interpolate(r"cost: \$(echo no)", &cfg, "API_HEADERS"),
+        "cost: $(echo no)"
+    );
+}
@@ -0,0 +1,122 @@
+use auto_commit_rs::commitlint::CommitlintRules;
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::lint::{is_ignored, lint_message, parse_header, LintRules};
+
+fn rules() -> LintRules {
+    LintRules::from_config(&AppConfig::default())
+}
+
+fn rule_ids(message: &str, rules: &LintRules) -> Vec<&'static str> {
+    lint_message(message, rules)
+        .iter()
+        .map(|v| v.rule)
+        .collect()
+}
+
+#[test]
+fn parse_header_extracts_type_scope_and_breaking_marker() {
+    let header = parse_header("feat(api)!: drop v1 endpoints").expect("valid header");
+    assert_eq!(header.commit_type, "feat");
+    assert_eq!(header.scope.as_deref(), Some("api"));
+    assert!(header.breaking);
+    assert_eq!(header.description, "drop v1 endpoints");
+
+    let header = parse_header("🐛 fix: handle empty input").expect("gitmoji header");
+    assert_eq!(header.commit_type, "fix");
+    assert_eq!(header.scope, None);
+    assert!(!header.breaking);
+
+    assert!(parse_header("Update the readme").is_none());
+}
+
+#[test]
+fn conforming_messages_pass() {
+    let rules = rules();
+    assert!(lint_message("feat: add login", &rules).is_empty());
+    assert!(lint_message(
+        "fix(parser): handle nested generics\n\n- Track depth while scanning\n\nRefs: #12",
+        &rules
+    )
+    .is_empty());
+}
+
+#[test]
+fn reports_format_and_subject_violations() {
+    let rules = rules();
+    assert_eq!(rule_ids("Update the readme", &rules), vec!["header-format"]);
+    assert_eq!(rule_ids("Feat: add login", &rules), vec!["type-case"]);
+    assert_eq!(rule_ids("feat(): add login", &rules), vec!["scope-empty"]);
+    assert_eq!(
+        rule_ids("feat: add login.", &rules),
+        vec!["subject-full-stop"]
+    );
+    assert_eq!(rule_ids("", &rules), vec!["header-empty"]);
+}
+
+#[test]
+fn reports_length_and_layout_violations() {
+    let rules = rules();
+    let long_header = format!("feat: {}", "word ".repeat(20));
+    assert_eq!(
+        rule_ids(long_header.trim(), &rules),
+        vec!["header-max-length"]
+    );
+
+    assert_eq!(
+        rule_ids("feat: add login\nno blank line", &rules),
+        vec!["body-leading-blank"]
+    );
+
+    let long_body = format!("feat: add login\n\n{}", "word ".repeat(20));
+    assert_eq!(
+        rule_ids(long_body.trim(), &rules),
+        vec!["body-max-line-length"]
+    );
+
+    let url_body = format!("feat: add login\n\nhttps://example.com/{}", "x".repeat(80));
+    assert!(lint_message(&url_body, &rules).is_empty());
+}
+
+#[test]
+fn subject_case_follows_config() {
+    let mut cfg = AppConfig::default();
+    assert!(lint_message("feat: Add login", &LintRules::from_config(&cfg)).is_empty());
+
+    cfg.lowercase_subject = true;
+    let rules = LintRules::from_config(&cfg);
+    assert_eq!(rule_ids("feat: Add login", &rules), vec!["subject-case"]);
+    assert!(lint_message("feat: README tweaks", &rules).is_empty());
+}
+
+#[test]
+fn git_generated_messages_are_ignored() {
+    assert!(is_ignored("Merge branch 'main' into feature"));
+    assert!(is_ignored("Revert \"feat: add login\""));
+    assert!(is_ignored("fixup! feat: add login"));
+    assert!(lint_message("Merge pull request #1 from a/b", &rules()).is_empty());
+    assert!(!is_ignored("feat: add login"));
+}
+
+#[test]
+fn commitlint_rules_tighten_lint() {
+    let mut cfg = AppConfig::default();
+    cfg.commitlint = Some(CommitlintRules {
+        type_enum: vec!["feat".into(), "fix".into()],
+        scope_enum: vec!["api".into()],
+        header_max_length: Some(30),
+        body_max_line_length: Some(100),
+        lowercase_subject: true,
+    });
+    let rules = LintRules::from_config(&cfg);
+
+    assert!(lint_message("feat(api): add login", &rules).is_empty());
+    assert_eq!(rule_ids("docs: add login", &rules), vec!["type-enum"]);
+    assert_eq!(rule_ids("fix(cli): add login", &rules), vec!["scope-enum"]);
+    assert_eq!(rule_ids("fix: Add login", &rules), vec!["subject-case"]);
+    assert_eq!(
+        rule_ids("fix: add login with a much longer description", &rules),
+        vec!["header-max-length"]
+    );
+    let body = format!("fix: add login\n\n{}", "word ".repeat(18));
+    assert!(lint_message(body.trim(), &rules).is_empty());
+}
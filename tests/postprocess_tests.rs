@@ -0,0 +1,153 @@
+use auto_commit_rs::commitlint::CommitlintRules;
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::postprocess::{apply, format_subject, wrap_body};
+
+#[test]
+fn format_subject_strips_trailing_period() {
+    assert_eq!(
+        format_subject("feat: add login.", 72, false),
+        "feat: add login"
+    );
+    assert_eq!(
+        format_subject("feat: wait for it...", 72, false),
+        "feat: wait for it..."
+    );
+}
+
+#[test]
+fn format_subject_lowercases_first_word_after_header() {
+    assert_eq!(
+        format_subject("feat(api): Add pagination", 72, true),
+        "feat(api): add pagination"
+    );
+    assert_eq!(
+        format_subject("✨ feat: Add thing", 72, true),
+        "✨ feat: add thing"
+    );
+    assert_eq!(
+        format_subject("fix: Update README", 72, true),
+        "fix: update README"
+    );
+    assert_eq!(
+        format_subject("docs: README tweaks", 72, true),
+        "docs: README tweaks"
+    );
+    assert_eq!(format_subject("Fix crash", 72, true), "fix crash");
+    assert_eq!(
+        format_subject("feat: Add pagination", 72, false),
+        "feat: Add pagination"
+    );
+}
+
+#[test]
+fn format_subject_shortens_at_word_boundary() {
+    let subject =
+        "feat(parser): support nested generics, where clauses and trailing commas everywhere";
+    let shortened = format_subject(subject, 50, false);
+    assert!(shortened.chars().count() <= 50);
+    assert_eq!(shortened, "feat(parser): support nested generics, where");
+
+    assert_eq!(format_subject(subject, 0, false), subject);
+}
+
+#[test]
+fn wrap_body_reflows_paragraphs_and_bullets() {
+    let body = "This change rewrites the request retry logic so that transient failures are retried with exponential backoff.\n\n\n- Retry on 429 and 5xx responses using the configured backoff schedule and jitter\n- Log each retry";
+    let wrapped = wrap_body(body, 72);
+    assert_eq!(
+        wrapped,
+        "This change rewrites the request retry logic so that transient failures\n\
+are retried with exponential backoff.\n\n\
+- Retry on 429 and 5xx responses using the configured backoff schedule\n  \
+and jitter\n\
+- Log each retry"
+    );
+}
+
+#[test]
+fn wrap_body_keeps_footers_and_code_verbatim() {
+    let long_footer = format!("Co-authored-by: {}", "x".repeat(80));
+    let body = format!(
+        "```\nlet a_very_long_line_of_code = something_that_should_never_be_wrapped(argument_one, argument_two);\n```\n\n{long_footer}\nBREAKING CHANGE: removes the old API"
+    );
+    assert_eq!(wrap_body(&body, 72), body);
+}
+
+#[test]
+fn apply_normalizes_blank_lines_and_respects_toggle() {
+    let mut cfg = AppConfig::default();
+    let message = "fix: handle empty diff.\nReturn early when nothing is staged.\n\n\n\nRefs: #12";
+    assert_eq!(
+        apply(&cfg, message),
+        "fix: handle empty diff\n\nReturn early when nothing is staged.\n\nRefs: #12"
+    );
+
+    cfg.post_process = false;
+    assert_eq!(apply(&cfg, message), message);
+}
+
+#[test]
+fn apply_honours_commitlint_rules() {
+    let mut cfg = AppConfig::default();
+    cfg.commitlint = Some(CommitlintRules {
+        header_max_length: Some(20),
+        lowercase_subject: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        apply(&cfg, "feat: Add shiny new login page"),
+        "feat: add shiny new"
+    );
+}
+
+#[test]
+fn enforce_allowed_maps_types_and_scopes() {
+    use auto_commit_rs::postprocess::enforce_allowed;
+
+    let mut cfg = AppConfig::default();
+    assert_eq!(enforce_allowed(&cfg, "feature(x): add"), "feature(x): add");
+
+    cfg.allowed_types = vec!["feat".into(), "fix".into(), "docs".into()];
+    cfg.allowed_scopes = vec!["api".into(), "cli".into()];
+
+    assert_eq!(
+        enforce_allowed(&cfg, "feature(API): add login"),
+        "feat(api): add login"
+    );
+    assert_eq!(
+        enforce_allowed(&cfg, "bugfix(clj): handle x"),
+        "fix(cli): handle x"
+    );
+    assert_eq!(
+        enforce_allowed(&cfg, "documentation(website): update\n\nbody"),
+        "docs: update\n\nbody"
+    );
+    assert_eq!(
+        enforce_allowed(&cfg, "fix(api,web)!: drop v1"),
+        "fix(api)!: drop v1"
+    );
+    assert_eq!(
+        enforce_allowed(&cfg, "✨ feat(cli): add"),
+        "✨ feat(cli): add"
+    );
+    // No plausible match: left for the caller to reject
+    assert_eq!(enforce_allowed(&cfg, "chore: bump"), "chore: bump");
+}
+
+#[test]
+fn effective_allowed_lists_prefer_config_over_commitlint() {
+    use auto_commit_rs::postprocess::{effective_allowed_scopes, effective_allowed_types};
+
+    let mut cfg = AppConfig::default();
+    cfg.commitlint = Some(CommitlintRules {
+        type_enum: vec!["feat".into()],
+        scope_enum: vec!["core".into()],
+        ..Default::default()
+    });
+    assert_eq!(effective_allowed_types(&cfg), vec!["feat"]);
+    assert_eq!(effective_allowed_scopes(&cfg), vec!["core"]);
+
+    cfg.allowed_types = vec!["fix".into()];
+    assert_eq!(effective_allowed_types(&cfg), vec!["fix"]);
+    assert_eq!(effective_allowed_scopes(&cfg), vec!["core"]);
+}
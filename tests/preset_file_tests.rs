@@ -2,8 +2,8 @@ mod common;
 
 use auto_commit_rs::preset::{
     create_preset, delete_preset, duplicate_preset, export_presets, find_duplicate, import_presets,
-    load_presets, rename_preset, save_presets, FallbackConfig, LlmPresetFields, Preset,
-    PresetsFile,
+    load_presets, read_import_source, rename_preset, save_presets, FallbackConfig, LlmPresetFields,
+    Preset, PresetOverrides, PresetsFile,
 };
 use common::EnvGuard;
 use serial_test::serial;
@@ -25,6 +25,7 @@ fn sample_fields() -> LlmPresetFields {
         api_key: "test-key".into(),
         api_url: String::new(),
         api_headers: String::new(),
+        overrides: Default::default(),
     }
 }
 
@@ -58,6 +59,39 @@ fn save_and_load_presets_roundtrip() {
     assert_eq!(loaded.next_id, 1);
 }
 
+#[test]
+#[serial]
+fn preset_overrides_roundtrip_and_stay_optional() {
+    let (cfg_dir, _env) = setup_presets_env();
+
+    let mut file = PresetsFile::default();
+    create_preset(&mut file, Some("Plain".into()), sample_fields());
+    let overrides = PresetOverrides {
+        temperature: Some(0.2),
+        max_tokens: Some(2_048),
+        timeout_secs: Some(600),
+        response_path: Some("message.content".into()),
+    };
+    create_preset(
+        &mut file,
+        Some("Local".into()),
+        LlmPresetFields {
+            provider: "ollama".into(),
+            overrides: overrides.clone(),
+            ..sample_fields()
+        },
+    );
+    save_presets(&file).expect("save_presets should succeed");
+
+    let content = fs::read_to_string(cfg_dir.path().join("cgen").join("presets.toml")).unwrap();
+    assert_eq!(content.matches("timeout_secs = 600").count(), 1);
+    assert_eq!(content.matches("temperature").count(), 1);
+
+    let loaded = load_presets().expect("load_presets should succeed");
+    assert!(loaded.presets[0].fields.overrides.is_empty());
+    assert_eq!(loaded.presets[1].fields.overrides, overrides);
+}
+
 #[test]
 #[serial]
 fn save_presets_atomic_write() {
@@ -112,6 +146,7 @@ fn preset_struct_fields() {
     let preset = Preset {
         id: 42,
         name: "My Preset".into(),
+        tags: Vec::new(),
         fields: sample_fields(),
     };
 
@@ -214,6 +249,7 @@ fn preset_serde_with_flatten() {
     let preset = Preset {
         id: 1,
         name: "Test".into(),
+        tags: Vec::new(),
         fields: sample_fields(),
     };
 
@@ -249,3 +285,90 @@ fn fallback_order_manipulation() {
     delete_preset(&mut file, id1);
     assert_eq!(file.fallback.order, vec![id2]);
 }
+
+#[test]
+#[serial]
+fn preset_keys_are_stored_as_credential_references() {
+    let (cfg_dir, _env) = setup_presets_env();
+
+    let mut file = PresetsFile::default();
+    let id = create_preset(&mut file, Some("Work".into()), sample_fields());
+    save_presets(&file).expect("save presets");
+
+    let raw =
+        fs::read_to_string(cfg_dir.path().join("cgen").join("presets.toml")).expect("read presets");
+    assert!(raw.contains(&format!("api_key = \"credential:preset-{id}\"")));
+    assert!(!raw.contains("test-key"));
+    assert_eq!(
+        load_presets().expect("load").presets[0].fields.api_key,
+        "test-key"
+    );
+
+    delete_preset(&mut file, id);
+    save_presets(&file).expect("save presets");
+    let creds = auto_commit_rs::credentials::load_credentials().expect("credentials");
+    assert!(creds.keys.is_empty(), "deleted preset keys are pruned");
+}
+
+#[test]
+fn import_reads_files_and_refuses_plain_http() {
+    let dir = TempDir::new().expect("tempdir");
+    let mut team = PresetsFile::default();
+    create_preset(&mut team, Some("Team".into()), sample_fields());
+    team.presets[0].tags = vec!["work".into()];
+    let path = dir.path().join("team-presets.toml");
+    fs::write(&path, export_presets(&team, &[0], false).unwrap()).expect("write export");
+
+    let data = read_import_source(path.to_str().unwrap()).expect("read file");
+    let mut file = PresetsFile::default();
+    assert_eq!(import_presets(&mut file, &data).unwrap(), 1);
+    assert_eq!(file.presets[0].name, "Team");
+    assert_eq!(
+        file.presets[0].tags,
+        ["work"],
+        "tags travel with the preset"
+    );
+    assert_eq!(
+        import_presets(&mut file, &data).unwrap(),
+        0,
+        "duplicates are skipped"
+    );
+
+    let err = read_import_source("http://example.com/presets.toml").unwrap_err();
+    assert!(err.to_string().contains("https"));
+    assert!(read_import_source(dir.path().join("missing.toml").to_str().unwrap()).is_err());
+}
+
+#[test]
+#[serial]
+fn round_robin_routing_rotates_across_runs() {
+    let (_cfg_dir, _env) = setup_presets_env();
+
+    let mut file = PresetsFile::default();
+    for name in ["A", "B", "C"] {
+        let fields = LlmPresetFields {
+            model: name.to_lowercase(),
+            ..sample_fields()
+        };
+        create_preset(&mut file, Some(name.into()), fields);
+    }
+    file.routing.presets = vec![2, 0];
+    save_presets(&file).expect("save presets");
+
+    let mut cfg = auto_commit_rs::config::AppConfig::default();
+    assert!(auto_commit_rs::routing::route(&cfg, "diff")
+        .unwrap()
+        .is_none());
+
+    cfg.routing = "round-robin".into();
+    let picks: Vec<String> = (0..3)
+        .map(|_| {
+            let (routed, name) = auto_commit_rs::routing::route(&cfg, "diff")
+                .unwrap()
+                .unwrap();
+            assert_eq!(routed.model, name.to_lowercase());
+            name
+        })
+        .collect();
+    assert_eq!(picks, ["C", "A", "C"]);
+}
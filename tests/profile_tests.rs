@@ -0,0 +1,88 @@
+mod common;
+
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::profile::{apply_named, apply_profile, profile_from_config};
+use serial_test::serial;
+
+use crate::common::EnvGuard;
+
+const PROFILES: &str = r#"
+[profiles.terse]
+one_liner = true
+subject_max_length = 50
+allowed_types = ["feat", "fix"]
+
+[profiles.detailed]
+message_style = "detailed"
+one_liner = false
+
+[profiles.detailed.type_templates]
+fix = "$msg\n\nBug: $ticket"
+"#;
+
+#[test]
+fn apply_profile_overrides_prompt_and_format_fields() {
+    let file: auto_commit_rs::profile::ProfilesFile = toml::from_str(PROFILES).expect("parse");
+    let mut cfg = AppConfig::default();
+    cfg.one_liner = false;
+
+    apply_profile(&mut cfg, &file.profiles["terse"]).expect("apply terse");
+    assert!(cfg.one_liner);
+    assert_eq!(cfg.subject_max_length, 50);
+    assert_eq!(cfg.allowed_types, vec!["feat", "fix"]);
+
+    apply_profile(&mut cfg, &file.profiles["detailed"]).expect("apply detailed");
+    assert_eq!(cfg.message_style, "detailed");
+    assert!(!cfg.one_liner);
+    assert_eq!(cfg.type_templates["fix"], "$msg\\n\\nBug: $ticket");
+}
+
+#[test]
+fn apply_profile_rejects_credentials_and_invalid_values() {
+    let mut cfg = AppConfig::default();
+    let secrets: toml::Table = toml::from_str("api_key = \"sk-test\"").unwrap();
+    let err = apply_profile(&mut cfg, &secrets).unwrap_err();
+    assert!(format!("{err:#}").contains("cannot be set by a prompt profile"));
+    assert!(cfg.api_key.is_empty());
+
+    let bad_locale: toml::Table = toml::from_str("locale = \"xx-invalid\"").unwrap();
+    assert!(apply_profile(&mut cfg, &bad_locale).is_err());
+}
+
+#[test]
+fn profile_from_config_round_trips() {
+    let mut cfg = AppConfig::default();
+    cfg.one_liner = false;
+    cfg.gitmoji_format = "shortcode".into();
+    cfg.type_templates
+        .insert("feat".into(), "$msg\n\nRefs: #1".into());
+
+    let snapshot = profile_from_config(&cfg).expect("snapshot");
+    assert!(!snapshot.contains_key("api_key"));
+    assert!(!snapshot.contains_key("provider"));
+
+    let mut other = AppConfig::default();
+    apply_profile(&mut other, &snapshot).expect("apply snapshot");
+    assert!(!other.one_liner);
+    assert_eq!(other.gitmoji_format, "shortcode");
+    assert_eq!(other.type_templates["feat"], "$msg\\n\\nRefs: #1");
+}
+
+#[test]
+#[serial]
+fn apply_named_reads_profiles_file() {
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", dir.path().to_str().unwrap())]);
+    let mut cfg = AppConfig::default();
+
+    let err = apply_named(&mut cfg, "terse").unwrap_err();
+    assert!(err.to_string().contains("no profiles defined"));
+
+    std::fs::create_dir_all(dir.path().join("cgen")).unwrap();
+    std::fs::write(dir.path().join("cgen").join("profiles.toml"), PROFILES).unwrap();
+    apply_named(&mut cfg, "terse").expect("apply");
+    assert_eq!(cfg.subject_max_length, 50);
+
+    let err = apply_named(&mut cfg, "missing").unwrap_err();
+    assert!(err.to_string().contains("Available: detailed, terse"));
+}
@@ -1,5 +1,11 @@
+use auto_commit_rs::commitlint::CommitlintRules;
 use auto_commit_rs::config::AppConfig;
-use auto_commit_rs::prompt::{build_system_prompt, clean_commit_message};
+use auto_commit_rs::prompt::{
+    apply_commit_template, assemble_detailed_message, build_prompt_blocks, build_refine_prompt,
+    build_regenerate_prompt, build_scope_prompt, build_system_prompt, clean_commit_message,
+    empty_commit_input, finalize_commit_message,
+};
+use auto_commit_rs::snippet::PromptSnippet;
 
 #[test]
 fn prompt_includes_core_sections_by_default() {
@@ -99,3 +105,256 @@ fn clean_message_handles_multiline_with_fence() {
         "feat: add search\n\nAdds full-text search support."
     );
 }
+
+#[test]
+fn refine_prompt_includes_previous_message_and_instruction() {
+    let prompt = build_refine_prompt("base rules", "feat: add login\n", "  use scope auth ");
+    assert!(prompt.starts_with("base rules"));
+    assert!(prompt.contains("feat: add login\n\n"));
+    assert!(prompt.ends_with("use scope auth"));
+}
+
+#[test]
+fn regenerate_prompt_is_unchanged_without_rejections() {
+    assert_eq!(build_regenerate_prompt("base rules", &[]), "base rules");
+}
+
+#[test]
+fn regenerate_prompt_lists_every_rejected_message() {
+    let rejected = vec![
+        "feat: add login".to_string(),
+        "feat(auth): add login\n\nWith body".to_string(),
+    ];
+    let prompt = build_regenerate_prompt("base rules", &rejected);
+    assert!(prompt.starts_with("base rules"));
+    assert!(prompt.contains("- feat: add login\n"));
+    assert!(prompt.contains("- feat(auth): add login\n  \n  With body"));
+    assert!(prompt.contains("Do not repeat any of the rejected messages."));
+}
+
+#[test]
+fn detailed_style_replaces_one_liner_instruction() {
+    let mut cfg = AppConfig::default();
+    cfg.message_style = "detailed".into();
+
+    let prompt = build_system_prompt(&cfg);
+    assert!(prompt.contains("SUBJECT:"));
+    assert!(prompt.contains("BODY:"));
+    assert!(prompt.contains("FOOTERS:"));
+    assert!(!prompt.contains("Output ONLY a single-line commit message"));
+    assert!(!prompt.contains("Output only the raw commit message"));
+}
+
+#[test]
+fn assemble_detailed_message_joins_sections_and_wraps_body() {
+    let raw = "SUBJECT:\nfeat(api): add pagination to list endpoints\nBODY:\n\
+- Add cursor-based pagination to every list endpoint so large collections no longer time out\n\
+- Document the new `cursor` query parameter\nFOOTERS:\nRefs: #42";
+
+    let msg = assemble_detailed_message(raw);
+    assert_eq!(
+        msg,
+        "feat(api): add pagination to list endpoints\n\n\
+- Add cursor-based pagination to every list endpoint so large\n  \
+collections no longer time out\n\
+- Document the new `cursor` query parameter\n\n\
+Refs: #42"
+    );
+    assert!(msg.lines().all(|l| l.chars().count() <= 72));
+}
+
+#[test]
+fn assemble_detailed_message_omits_empty_sections() {
+    let raw =
+        "SUBJECT: fix: handle empty input\nBODY:\n- Return early on empty diff\nFOOTERS:\nnone";
+    assert_eq!(
+        assemble_detailed_message(raw),
+        "fix: handle empty input\n\n- Return early on empty diff"
+    );
+}
+
+#[test]
+fn assemble_detailed_message_falls_back_to_raw_without_subject() {
+    let raw = "fix: plain message\n\nsome body";
+    assert_eq!(assemble_detailed_message(raw), raw);
+}
+
+#[test]
+fn finalize_commit_message_only_assembles_in_detailed_style() {
+    let raw = "```\nSUBJECT:\nchore: bump deps\nBODY:\n- Update serde\n```";
+    let mut cfg = AppConfig::default();
+    cfg.post_process = false;
+    assert_eq!(
+        finalize_commit_message(&cfg, raw, None),
+        clean_commit_message(raw)
+    );
+
+    cfg.message_style = "detailed".into();
+    assert_eq!(
+        finalize_commit_message(&cfg, raw, None),
+        "chore: bump deps\n\n- Update serde"
+    );
+}
+
+#[test]
+fn finalize_commit_message_applies_the_scope_before_post_processing() {
+    let mut cfg = AppConfig::default();
+    cfg.post_process = true;
+    cfg.subject_max_length = 30;
+    let message =
+        finalize_commit_message(&cfg, "feat: add a much longer subject line", Some("api"));
+    assert!(message.starts_with("feat(api): "), "{message}");
+    assert!(
+        message.lines().next().unwrap().chars().count() <= 30,
+        "{message}"
+    );
+
+    // A detected scope still has to be an allowed one
+    cfg.allowed_scopes = vec!["web".into()];
+    let message = finalize_commit_message(&cfg, "feat: add x", Some("api"));
+    assert_eq!(message, "feat: add x");
+}
+
+#[test]
+fn prompt_includes_commitlint_rules_when_present() {
+    let mut cfg = AppConfig::default();
+    assert!(!build_system_prompt(&cfg).contains("commit rules"));
+
+    cfg.commitlint = Some(CommitlintRules {
+        type_enum: vec!["feat".into(), "fix".into()],
+        scope_enum: vec!["api".into()],
+        header_max_length: Some(50),
+        body_max_line_length: None,
+        lowercase_subject: true,
+    });
+    let prompt = build_system_prompt(&cfg);
+    assert!(prompt.contains("enforces the following commit rules"));
+    assert!(prompt.contains("type MUST be one of: feat, fix."));
+    assert!(prompt.contains("MUST be one of: api."));
+    assert!(prompt.contains("at most 50 characters"));
+    assert!(prompt.contains("start with a lowercase letter"));
+}
+
+#[test]
+fn prompt_lists_configured_types_and_scopes() {
+    let mut cfg = AppConfig::default();
+    cfg.allowed_types = vec!["feat".into(), "fix".into()];
+    cfg.allowed_scopes = vec!["api".into(), "cli".into()];
+
+    let prompt = build_system_prompt(&cfg);
+    assert!(prompt.contains("type MUST be one of: feat, fix."));
+    assert!(prompt.contains("MUST be one of: api, cli. Never invent other scopes."));
+}
+
+#[test]
+fn scope_prompt_appends_forced_scope() {
+    let prompt = build_scope_prompt("base", "api");
+    assert!(prompt.starts_with("base\n\n"));
+    assert!(prompt.contains("You MUST use exactly `api` as the commit scope."));
+}
+
+#[test]
+fn prompt_includes_custom_gitmoji_set() {
+    let mut cfg = AppConfig::default();
+    cfg.use_gitmoji = true;
+    cfg.gitmoji_set = Some(
+        toml::from_str("[types.feat]\nunicode = \"🚀\"\nshortcode = \":rocket:\"\n")
+            .expect("parse set"),
+    );
+    assert!(build_system_prompt(&cfg)
+        .contains("Use this project's gitmoji for these types: feat → 🚀."));
+
+    cfg.use_gitmoji = false;
+    assert!(!build_system_prompt(&cfg).contains("project's gitmoji"));
+}
+
+#[test]
+fn commit_template_appends_skip_ci_token_when_enabled() {
+    let mut cfg = AppConfig::default();
+    cfg.commit_template = "$msg\n\nRefs: #1".into();
+    assert_eq!(
+        apply_commit_template(&cfg, " docs: fix typo\n"),
+        "docs: fix typo\n\nRefs: #1"
+    );
+
+    cfg.skip_ci = true;
+    assert_eq!(
+        apply_commit_template(&cfg, "docs: fix typo"),
+        "docs: fix typo\n\nRefs: #1\n\n[skip ci]"
+    );
+
+    cfg.skip_ci_token = "***NO_CI***".into();
+    assert_eq!(
+        apply_commit_template(&cfg, "docs: fix typo ***NO_CI***"),
+        "docs: fix typo ***NO_CI***\n\nRefs: #1"
+    );
+}
+
+#[test]
+fn commit_template_is_selected_by_message_type() {
+    let mut cfg = AppConfig::default();
+    cfg.commit_template = "$msg\n\nSigned-off".into();
+    cfg.type_templates
+        .insert("fix".into(), "$msg\\n\\nBug: $ticket".into());
+    cfg.type_templates.insert("feat".into(), "$msg".into());
+
+    assert_eq!(
+        apply_commit_template(&cfg, "fix(api): handle timeouts"),
+        "fix(api): handle timeouts\n\nBug: $ticket"
+    );
+    assert_eq!(
+        apply_commit_template(&cfg, "✨ feat!: drop v1 endpoints"),
+        "✨ feat!: drop v1 endpoints"
+    );
+    assert_eq!(
+        apply_commit_template(&cfg, "docs: update readme"),
+        "docs: update readme\n\nSigned-off"
+    );
+    assert_eq!(
+        apply_commit_template(&cfg, "not conventional"),
+        "not conventional\n\nSigned-off"
+    );
+}
+
+#[test]
+fn prompt_blocks_carry_provenance_and_keep_snippets_before_closing() {
+    let mut cfg = AppConfig::default();
+    cfg.prompt_snippets = vec![
+        PromptSnippet {
+            source: "global prompts/style.md".into(),
+            name: "style".into(),
+            text: "Prefer short subjects.".into(),
+        },
+        PromptSnippet {
+            source: "repo .cgen/prompt.md".into(),
+            name: "prompt".into(),
+            text: "Always mention migration steps.".into(),
+        },
+    ];
+
+    let blocks = build_prompt_blocks(&cfg);
+    let sources: Vec<&str> = blocks.iter().map(|b| b.source.as_str()).collect();
+    assert_eq!(sources.first(), Some(&"built-in base prompt"));
+    assert_eq!(
+        &sources[sources.len() - 3..],
+        &[
+            "global prompts/style.md",
+            "repo .cgen/prompt.md",
+            "built-in closing"
+        ]
+    );
+
+    let prompt = build_system_prompt(&cfg);
+    assert!(prompt.contains("Prefer short subjects.\n\nAlways mention migration steps."));
+    assert!(prompt.ends_with("Output only the raw commit message, nothing else."));
+
+    cfg.llm_system_prompt = "custom".into();
+    assert_eq!(build_prompt_blocks(&cfg)[0].source, "ACR_LLM_SYSTEM_PROMPT");
+}
+
+#[test]
+fn empty_commit_input_carries_the_reason() {
+    let input = empty_commit_input("  retrigger the release pipeline\n");
+    assert!(input.contains("intentionally empty"));
+    assert!(input.ends_with("Reason given by the author: retrigger the release pipeline"));
+}
@@ -2,7 +2,7 @@ mod common;
 
 use crate::common::EnvGuard;
 use auto_commit_rs::config::AppConfig;
-use auto_commit_rs::provider;
+use auto_commit_rs::{provider, usage};
 use mockito::{Matcher, Server};
 use serial_test::serial;
 use std::fs;
@@ -235,6 +235,149 @@ order = [0]
     mock_fallback.assert();
 }
 
+#[test]
+#[serial]
+fn fallback_policy_fails_retries_or_falls_back_by_status() {
+    let mut server = Server::new();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let cgen_dir = cfg_dir.path().join("cgen");
+    fs::create_dir_all(&cgen_dir).expect("create cgen dir");
+    let presets_toml = format!(
+        r#"
+next_id = 1
+[[presets]]
+id = 0
+name = "fallback-preset"
+provider = "custom"
+model = "fallback-model"
+api_key = "fallback-key"
+api_url = "{}/fallback"
+api_headers = ""
+
+[fallback]
+enabled = true
+order = [0]
+"#,
+        server.url()
+    );
+    fs::write(cgen_dir.join("presets.toml"), presets_toml).expect("write presets");
+
+    // A bad key on the primary is reported instead of hidden behind the fallback's output
+    let unauthorized = server
+        .mock("POST", "/unauthorized")
+        .with_status(401)
+        .with_body("invalid key")
+        .create();
+    let fallback = server
+        .mock("POST", "/fallback")
+        .with_status(200)
+        .with_body(r#"{"choices":[{"message":{"content":"fallback success"}}]}"#)
+        .expect(1)
+        .create();
+    let cfg = cfg_for("custom", format!("{}/unauthorized", server.url()));
+    let err = provider::call_llm_with_fallback(&cfg, "system", "diff")
+        .expect_err("401 fails by default")
+        .to_string();
+    assert!(err.contains("HTTP 401"));
+    unauthorized.assert();
+
+    // Retried statuses are resent before falling back
+    let unavailable = server
+        .mock("POST", "/unavailable")
+        .with_status(503)
+        .expect(provider::MAX_RETRIES + 1)
+        .create();
+    let mut cfg = cfg_for("custom", format!("{}/unavailable", server.url()));
+    cfg.fallback_policy = "401=fail,5xx=retry".into();
+    let (msg, preset) = provider::call_llm_with_fallback(&cfg, "system", "diff").expect("fallback");
+    assert_eq!(msg, "fallback success");
+    assert_eq!(preset.as_deref(), Some("fallback-preset"));
+    unavailable.assert();
+    fallback.assert();
+}
+
+#[test]
+#[serial]
+fn fallback_attempts_are_capped_and_failed_presets_cool_down() {
+    let mut server = Server::new();
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let cgen_dir = cfg_dir.path().join("cgen");
+    fs::create_dir_all(&cgen_dir).expect("create cgen dir");
+    let presets_toml = format!(
+        r#"
+next_id = 2
+[[presets]]
+id = 0
+name = "broken"
+provider = "custom"
+model = "broken-model"
+api_key = ""
+api_url = "{url}/broken"
+api_headers = ""
+
+[[presets]]
+id = 1
+name = "working"
+provider = "custom"
+model = "working-model"
+api_key = ""
+api_url = "{url}/working"
+api_headers = ""
+
+[fallback]
+enabled = true
+order = [0, 1]
+"#,
+        url = server.url()
+    );
+    fs::write(cgen_dir.join("presets.toml"), presets_toml).expect("write presets");
+
+    let primary = server
+        .mock("POST", "/primary")
+        .with_status(500)
+        .expect(3)
+        .create();
+    let broken = server
+        .mock("POST", "/broken")
+        .with_status(500)
+        .expect(2)
+        .create();
+    let working = server
+        .mock("POST", "/working")
+        .with_status(200)
+        .with_body(r#"{"choices":[{"message":{"content":"fallback success"}}]}"#)
+        .expect(2)
+        .create();
+    let mut cfg = cfg_for("custom", format!("{}/primary", server.url()));
+
+    cfg.fallback_max_attempts = 1;
+    let err = provider::call_llm_with_fallback(&cfg, "system", "diff")
+        .expect_err("only the broken preset is tried")
+        .to_string();
+    assert!(err.contains("broken (HTTP 500)"));
+    assert!(!err.contains("working"));
+
+    // Without a cooldown nothing is recorded; with one, the preset that just failed is skipped
+    cfg.fallback_max_attempts = 0;
+    cfg.fallback_cooldown_mins = 10;
+    for _ in 0..2 {
+        let (msg, preset) =
+            provider::call_llm_with_fallback(&cfg, "system", "diff").expect("fallback");
+        assert_eq!(msg, "fallback success");
+        assert_eq!(preset.as_deref(), Some("working"));
+    }
+    let cooldowns = auto_commit_rs::cooldown::load().expect("cooldown file");
+    let now = auto_commit_rs::timestamp::now_unix();
+    assert!(cooldowns.cooling_down(0, 10, now));
+    assert!(!cooldowns.cooling_down(1, 10, now));
+
+    primary.assert();
+    broken.assert();
+    working.assert();
+}
+
 #[test]
 fn call_llm_deepseek_provider() {
     let mut server = Server::new();
@@ -508,3 +651,137 @@ fn call_llm_with_multiple_custom_headers() {
     assert_eq!(msg, "headers ok");
     mock.assert();
 }
+
+#[test]
+fn published_registry_matches_the_builtin_defaults() {
+    let registry =
+        auto_commit_rs::registry::parse(include_str!("../providers.toml")).expect("parse");
+    for (name, entry) in &registry.providers {
+        assert!(
+            provider::is_builtin_provider(name),
+            "{name} is not built in"
+        );
+        assert_eq!(
+            entry.default_model.as_deref(),
+            Some(provider::default_model_for(name).as_str()),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+#[serial]
+fn cached_registry_updates_models_and_adds_providers() {
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let mut server = Server::new();
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"model":"new-model"}"#.into(),
+        ))
+        .with_status(200)
+        .with_body(r#"{"choices":[{"message":{"content":"from registry"}}]}"#)
+        .create();
+
+    let path = auto_commit_rs::registry::registry_path().expect("registry path");
+    fs::create_dir_all(path.parent().unwrap()).expect("create config dir");
+    fs::write(
+        &path,
+        format!(
+            "[providers.openai]\ndefault_model = \"gpt-next\"\n\n\
+             [providers.newcomer]\napi_url = \"{}/v1/chat/completions\"\n\
+             default_model = \"new-model\"\n",
+            server.url()
+        ),
+    )
+    .expect("write registry");
+
+    assert_eq!(provider::default_model_for("openai"), "gpt-next");
+    assert!(provider::is_builtin_provider("newcomer"));
+    assert!(provider::provider_names().ends_with(&["newcomer".to_string()]));
+
+    let mut cfg = cfg_for("newcomer", String::new());
+    cfg.model = provider::default_model_for("newcomer");
+    let msg = provider::call_llm(&cfg, "system", "diff").expect("llm call");
+    assert_eq!(msg, "from registry");
+    mock.assert();
+}
+
+#[test]
+#[serial]
+fn usage_is_recorded_against_the_monthly_budget() {
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let mut server = Server::new();
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_body(
+            r#"{"choices":[{"message":{"content":"feat: add"}}],
+                "usage":{"prompt_tokens":1000000,"completion_tokens":0}}"#,
+        )
+        .expect(2)
+        .create();
+
+    let mut cfg = cfg_for("openai", format!("{}/v1/chat/completions", server.url()));
+    cfg.model = "gpt-4o-mini".into();
+    provider::call_llm(&cfg, "system", "diff").expect("llm call");
+    assert_eq!(
+        usage::this_month().expect("usage"),
+        usage::MonthUsage::default(),
+        "nothing is recorded without a budget"
+    );
+
+    cfg.monthly_budget_usd = "0.20".into();
+    provider::call_llm(&cfg, "system", "diff").expect("llm call");
+    let month = usage::this_month().expect("usage");
+    assert_eq!(month.requests, 1);
+    assert_eq!(month.input_tokens, 1_000_000);
+    assert!((month.cost_usd - 0.15).abs() < 1e-9);
+    mock.assert();
+
+    assert!(usage::check_budget(&cfg, Some(0.01))
+        .expect("check")
+        .is_none());
+    let warning = usage::check_budget(&cfg, Some(0.10))
+        .expect("check")
+        .expect("over budget");
+    assert_eq!(warning.budget, 0.20);
+    assert!(usage::check_budget(&cfg, None).expect("check").is_none());
+}
+
+#[test]
+#[serial]
+fn usage_metrics_are_recorded_only_when_enabled() {
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let mut server = Server::new();
+    let _ok = server
+        .mock("POST", "/ok")
+        .with_status(200)
+        .with_body(
+            r#"{"choices":[{"message":{"content":"fix: typo"}}],
+                "usage":{"prompt_tokens":80,"completion_tokens":5}}"#,
+        )
+        .create();
+    usage::take_requests();
+
+    let mut cfg = cfg_for("custom", format!("{}/ok", server.url()));
+    provider::call_llm(&cfg, "system", "diff").expect("llm call");
+    usage::record_run(&cfg, usage::Outcome::Accepted, None).expect("record");
+    assert!(usage::load_metrics().expect("load").runs.is_empty());
+
+    cfg.usage_metrics = true;
+    provider::call_llm(&cfg, "system", "diff").expect("llm call");
+    usage::record_run(&cfg, usage::Outcome::Edited, None).expect("record");
+    let runs = usage::load_metrics().expect("load").runs;
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].outcome, usage::Outcome::Edited);
+    assert_eq!(runs[0].requests[0].provider, "custom");
+    assert_eq!(runs[0].requests[0].input_tokens, 80);
+    assert!(runs[0].requests[0].success);
+
+    usage::clear_metrics().expect("clear");
+    assert!(usage::load_metrics().expect("load").runs.is_empty());
+}
@@ -0,0 +1,185 @@
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::scope::{
+    apply_scope, detect_scope, files_in_diff, scope_from_map, scope_from_packages,
+    workspace_packages,
+};
+use std::fs;
+use std::path::Path;
+
+fn map(entries: &[&str]) -> Vec<String> {
+    entries.iter().map(|s| s.to_string()).collect()
+}
+
+fn files(paths: &[&str]) -> Vec<String> {
+    paths.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn files_in_diff_lists_changed_paths() {
+    let diff = "diff --git a/crates/api/src/lib.rs b/crates/api/src/lib.rs\n+x\ndiff --git a/README.md b/README.md\n+y\n";
+    assert_eq!(
+        files_in_diff(diff),
+        vec!["crates/api/src/lib.rs", "README.md"]
+    );
+}
+
+#[test]
+fn scope_from_map_picks_dominant_scope() {
+    let entries = map(&["crates/api/**=api", "crates/cli/**=cli", "docs/**=docs"]);
+    let changed = files(&[
+        "crates/api/src/lib.rs",
+        "crates/cli/src/main.rs",
+        "crates/cli/src/args.rs",
+        "README.md",
+    ]);
+    assert_eq!(scope_from_map(&entries, &changed), Some("cli".into()));
+}
+
+#[test]
+fn scope_from_map_breaks_ties_by_map_order_and_ignores_bad_entries() {
+    let entries = map(&[
+        "no-equals-sign",
+        "crates/cli/**=cli",
+        "crates/api/**=api",
+        "x/**=",
+    ]);
+    let changed = files(&["crates/api/a.rs", "crates/cli/b.rs"]);
+    assert_eq!(scope_from_map(&entries, &changed), Some("cli".into()));
+
+    assert_eq!(scope_from_map(&entries, &files(&["other/file.rs"])), None);
+    assert_eq!(scope_from_map(&[], &changed), None);
+}
+
+#[test]
+fn detect_scope_uses_config_map() {
+    let mut cfg = AppConfig::default();
+    cfg.auto_scope = false;
+    let diff = "diff --git a/docs/guide.md b/docs/guide.md\n+z\n";
+    assert_eq!(detect_scope(&cfg, diff), None);
+
+    cfg.scope_map = map(&["docs/**=docs"]);
+    assert_eq!(detect_scope(&cfg, diff), Some("docs".into()));
+}
+
+#[test]
+fn detect_scope_respects_allowed_scopes() {
+    let mut cfg = AppConfig::default();
+    cfg.auto_scope = false;
+    cfg.scope_map = map(&["docs/**=Docs"]);
+    let diff = "diff --git a/docs/guide.md b/docs/guide.md\n+z\n";
+
+    cfg.allowed_scopes = map(&["docs", "api"]);
+    assert_eq!(detect_scope(&cfg, diff), Some("docs".into()));
+
+    cfg.allowed_scopes = map(&["api", "web"]);
+    assert_eq!(detect_scope(&cfg, diff), None);
+}
+
+#[test]
+fn apply_scope_replaces_or_inserts_scope() {
+    assert_eq!(apply_scope("feat: add x", Some("api")), "feat(api): add x");
+    assert_eq!(
+        apply_scope("feat(web)!: add x\n\nbody", Some("api")),
+        "feat(api)!: add x\n\nbody"
+    );
+    assert_eq!(apply_scope("✨ fix(a,b): y", Some("cli")), "✨ fix(cli): y");
+    assert_eq!(
+        apply_scope("not conventional", Some("api")),
+        "not conventional"
+    );
+    assert_eq!(apply_scope("feat(web): add x", None), "feat(web): add x");
+}
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).expect("create dirs");
+    fs::write(path, content).expect("write file");
+}
+
+fn sorted(mut packages: Vec<(String, String)>) -> Vec<(String, String)> {
+    packages.sort();
+    packages
+}
+
+#[test]
+fn workspace_packages_reads_cargo_members() {
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    let root = dir.path();
+    write(
+        root,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"crates/*\", \"tools/xtask\"]\n",
+    );
+    write(root, "crates/api/Cargo.toml", "[package]\nname = \"api\"\n");
+    write(root, "crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+    write(
+        root,
+        "tools/xtask/Cargo.toml",
+        "[package]\nname = \"xtask\"\n",
+    );
+
+    assert_eq!(
+        sorted(workspace_packages(root)),
+        vec![
+            ("crates/api".to_string(), "api".to_string()),
+            ("crates/cli".to_string(), "cli".to_string()),
+            ("tools/xtask".to_string(), "xtask".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn workspace_packages_reads_npm_pnpm_and_nx() {
+    let dir = tempfile::TempDir::new().expect("tempdir");
+    let root = dir.path();
+    write(
+        root,
+        "package.json",
+        r#"{"workspaces": {"packages": ["packages/*"]}}"#,
+    );
+    write(
+        root,
+        "packages/web/package.json",
+        r#"{"name": "@acme/web"}"#,
+    );
+    write(root, "pnpm-workspace.yaml", "packages:\n  - 'services/*'\n");
+    write(
+        root,
+        "services/auth/package.json",
+        r#"{"name": "auth-service"}"#,
+    );
+    write(root, "nx.json", "{}");
+    write(root, "apps/admin/project.json", r#"{"name": "admin"}"#);
+    write(
+        root,
+        "node_modules/dep/project.json",
+        r#"{"name": "ignored"}"#,
+    );
+
+    assert_eq!(
+        sorted(workspace_packages(root)),
+        vec![
+            ("apps/admin".to_string(), "admin".to_string()),
+            ("packages/web".to_string(), "web".to_string()),
+            ("services/auth".to_string(), "auth-service".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn scope_from_packages_uses_longest_prefix_and_majority() {
+    let packages = vec![
+        ("crates".to_string(), "all".to_string()),
+        ("crates/api".to_string(), "api".to_string()),
+        ("crates/cli".to_string(), "cli".to_string()),
+    ];
+    let changed = files(&[
+        "crates/api/src/a.rs",
+        "crates/api/src/b.rs",
+        "crates/cli/src/main.rs",
+        "crates/apiary/x.rs",
+        "README.md",
+    ]);
+    assert_eq!(scope_from_packages(&packages, &changed), Some("api".into()));
+    assert_eq!(scope_from_packages(&packages, &files(&["README.md"])), None);
+}
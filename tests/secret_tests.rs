@@ -0,0 +1,29 @@
+use auto_commit_rs::secret::{decrypt_with, encrypt_with, is_encrypted, ENCRYPTED_PREFIX};
+
+#[test]
+fn encrypted_secret_round_trips_and_rejects_wrong_passphrase() {
+    let sealed = encrypt_with("sk-test-123", "correct horse").expect("encrypt");
+    assert!(sealed.starts_with(ENCRYPTED_PREFIX));
+    assert!(is_encrypted(&sealed));
+    assert!(!sealed.contains("sk-test-123"));
+
+    assert_eq!(
+        decrypt_with(&sealed, "correct horse").expect("decrypt"),
+        "sk-test-123"
+    );
+    assert!(decrypt_with(&sealed, "wrong").is_err());
+}
+
+#[test]
+fn encryption_uses_a_fresh_salt_and_nonce() {
+    let a = encrypt_with("same", "pass").expect("encrypt");
+    let b = encrypt_with("same", "pass").expect("encrypt");
+    assert_ne!(a, b);
+}
+
+#[test]
+fn malformed_encrypted_values_are_rejected() {
+    assert!(decrypt_with("sk-plain", "pass").is_err());
+    assert!(decrypt_with("enc:v1:not base64!", "pass").is_err());
+    assert!(decrypt_with("enc:v1:AAAA", "pass").is_err());
+}
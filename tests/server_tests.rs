@@ -0,0 +1,81 @@
+mod common;
+
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::server;
+use common::EnvGuard;
+use mockito::{Matcher, Server};
+use serde_json::Value;
+use serial_test::serial;
+
+#[test]
+fn generate_from_diff_returns_the_finished_message() {
+    let mut llm = Server::new();
+    let mock = llm
+        .mock("POST", "/v1")
+        .match_body(Matcher::Regex("src/lib.rs".into()))
+        .with_status(200)
+        .with_body(r#"{"choices":[{"message":{"content":"```\nfeat: add server mode\n```"}}]}"#)
+        .create();
+    let mut cfg = AppConfig::default();
+    cfg.provider = "custom".into();
+    cfg.model = "test-model".into();
+    cfg.api_key = "test-key".into();
+    cfg.api_url = format!("{}/v1", llm.url());
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": "generate-from-diff",
+        "params": { "diff": "diff --git a/src/lib.rs b/src/lib.rs\n+pub mod server;\n" },
+    });
+    let response = server::handle_line(&cfg, &request.to_string()).expect("a response");
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["result"]["message"], "feat: add server mode");
+    assert_eq!(response["result"]["fallback"], Value::Null);
+    mock.assert();
+
+    // Without a key the request fails before reaching the provider
+    cfg.api_key.clear();
+    let response = server::handle_line(&cfg, &request.to_string()).expect("a response");
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["error"]["code"], -32000);
+}
+
+#[test]
+#[serial]
+fn generate_refuses_requests_over_the_monthly_budget() {
+    let cfg_dir = tempfile::TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", cfg_dir.path().to_string_lossy().as_ref())]);
+    let mut llm = Server::new();
+    let mock = llm
+        .mock("POST", "/v1")
+        .with_status(200)
+        .with_body(r#"{"choices":[{"message":{"content":"feat: add x"}}]}"#)
+        .expect(1)
+        .create();
+    let mut cfg = AppConfig::default();
+    cfg.provider = "custom".into();
+    cfg.model = "gpt-4o-mini".into();
+    cfg.api_key = "test-key".into();
+    cfg.api_url = format!("{}/v1", llm.url());
+    cfg.monthly_budget_usd = "0.0000001".into();
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "generate-from-diff",
+        "params": { "diff": "diff --git a/x b/x\n+x\n" },
+    });
+    // `confirm` cannot ask over RPC, so the request is refused
+    let response = server::handle_line(&cfg, &request.to_string()).expect("a response");
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["error"]["code"], -32000);
+
+    // `warn` only warns and goes ahead
+    cfg.budget_action = "warn".into();
+    let response = server::handle_line(&cfg, &request.to_string()).expect("a response");
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["result"]["message"], "feat: add x");
+    mock.assert();
+}
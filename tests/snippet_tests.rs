@@ -0,0 +1,79 @@
+use auto_commit_rs::snippet::{filter, load_dir, load_file, PromptSnippet};
+
+fn named(name: &str) -> PromptSnippet {
+    PromptSnippet {
+        source: format!("test {name}.md"),
+        name: name.into(),
+        text: "text".into(),
+    }
+}
+
+#[test]
+fn load_dir_reads_markdown_files_in_name_order() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join("b-style.md"), "Be terse.\n").unwrap();
+    std::fs::write(
+        dir.path().join("a-scopes.md"),
+        "Scopes come from CODEOWNERS.",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("blank.md"), "  \n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+    let snippets = load_dir(dir.path(), "repo .cgen");
+    assert_eq!(
+        snippets,
+        vec![
+            PromptSnippet {
+                source: "repo .cgen/a-scopes.md".into(),
+                name: "a-scopes".into(),
+                text: "Scopes come from CODEOWNERS.".into(),
+            },
+            PromptSnippet {
+                source: "repo .cgen/b-style.md".into(),
+                name: "b-style".into(),
+                text: "Be terse.".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn load_dir_returns_nothing_for_missing_directory() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    assert!(load_dir(&dir.path().join("missing"), "repo .cgen").is_empty());
+}
+
+#[test]
+fn load_file_errors_on_missing_or_empty_file() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("extra.md");
+    assert!(load_file(&path).is_err());
+
+    std::fs::write(&path, "\n").unwrap();
+    assert!(load_file(&path).is_err());
+
+    std::fs::write(&path, "Mention the ticket.").unwrap();
+    let snippet = load_file(&path).expect("load");
+    assert_eq!(snippet.name, "extra");
+    assert!(snippet.source.starts_with("--prompt-file "));
+}
+
+#[test]
+fn filter_applies_include_then_exclude_globs() {
+    let all = vec![named("team-scopes"), named("team-draft"), named("style")];
+    let names = |v: Vec<PromptSnippet>| v.into_iter().map(|s| s.name).collect::<Vec<_>>();
+
+    assert_eq!(
+        names(filter(all.clone(), &[], &[])),
+        vec!["team-scopes", "team-draft", "style"]
+    );
+    assert_eq!(
+        names(filter(all.clone(), &["team-*".into()], &[])),
+        vec!["team-scopes", "team-draft"]
+    );
+    assert_eq!(
+        names(filter(all, &["team-*".into()], &["*-draft".into()])),
+        vec!["team-scopes"]
+    );
+}
@@ -1,16 +1,25 @@
-use auto_commit_rs::update::{current_version, parse_semver, print_update_warning, VersionCheck};
+mod common;
+
+use auto_commit_rs::config::{self, ConfigScope};
+use auto_commit_rs::update::{
+    current_version, load_reminders, parse_semver, print_update_warning, skip_version, snooze,
+    warning_suppressed, SemVer, VersionCheck,
+};
+use common::EnvGuard;
+use serial_test::serial;
+use tempfile::TempDir;
 
 #[test]
 fn parse_semver_handles_plain_versions() {
-    assert_eq!(parse_semver("1.0.0"), Some((1, 0, 0)));
-    assert_eq!(parse_semver("0.1.0"), Some((0, 1, 0)));
-    assert_eq!(parse_semver("12.34.56"), Some((12, 34, 56)));
+    assert_eq!(parse_semver("1.0.0"), Some(SemVer::new(1, 0, 0)));
+    assert_eq!(parse_semver("0.1.0"), Some(SemVer::new(0, 1, 0)));
+    assert_eq!(parse_semver("12.34.56"), Some(SemVer::new(12, 34, 56)));
 }
 
 #[test]
 fn parse_semver_strips_v_prefix() {
-    assert_eq!(parse_semver("v1.0.0"), Some((1, 0, 0)));
-    assert_eq!(parse_semver("v0.2.3"), Some((0, 2, 3)));
+    assert_eq!(parse_semver("v1.0.0"), Some(SemVer::new(1, 0, 0)));
+    assert_eq!(parse_semver("v0.2.3"), Some(SemVer::new(0, 2, 3)));
 }
 
 #[test]
@@ -35,8 +44,8 @@ fn semver_comparison_works_for_update_detection() {
     assert!(newer_patch > current);
     assert!(newer_minor > current);
     assert!(newer_major > current);
-    assert!(!(same > current));
-    assert!(!(older > current));
+    assert!(same <= current);
+    assert!(older <= current);
 }
 
 #[test]
@@ -81,14 +90,17 @@ fn version_check_no_update_available() {
 
 #[test]
 fn parse_semver_large_numbers() {
-    assert_eq!(parse_semver("100.200.300"), Some((100, 200, 300)));
-    assert_eq!(parse_semver("0.0.1"), Some((0, 0, 1)));
+    assert_eq!(
+        parse_semver("100.200.300"),
+        Some(SemVer::new(100, 200, 300))
+    );
+    assert_eq!(parse_semver("0.0.1"), Some(SemVer::new(0, 0, 1)));
 }
 
 #[test]
 fn parse_semver_with_leading_zeros() {
     // Leading zeros in version numbers should still parse
-    assert_eq!(parse_semver("01.02.03"), Some((1, 2, 3)));
+    assert_eq!(parse_semver("01.02.03"), Some(SemVer::new(1, 2, 3)));
 }
 
 #[test]
@@ -111,6 +123,14 @@ fn semver_less_than_comparison() {
     assert!(v1 < v4);
 }
 
+#[test]
+fn parse_semver_keeps_pre_release_below_release() {
+    let rc = parse_semver("v2.0.0-rc.1").unwrap();
+    assert_eq!(rc.pre, vec!["rc", "1"]);
+    assert!(rc > parse_semver("1.9.9").unwrap());
+    assert!(rc < parse_semver("2.0.0").unwrap());
+}
+
 #[test]
 fn print_update_warning_does_not_panic() {
     // Just verify it doesn't panic
@@ -118,3 +138,28 @@ fn print_update_warning_does_not_panic() {
     print_update_warning("v1.5.0");
     print_update_warning("999.999.999");
 }
+
+#[test]
+#[serial]
+fn skip_and_snooze_stay_out_of_the_config() {
+    let dir = TempDir::new().expect("tempdir");
+    let _env = EnvGuard::set(&[("ACR_CONFIG_HOME", dir.path().to_string_lossy().as_ref())]);
+    config::save_setting("MODEL", "gpt-4o", ConfigScope::Global).expect("seed config");
+
+    skip_version("v9.9.9").expect("skip");
+    snooze(3).expect("snooze");
+
+    let reminders = load_reminders();
+    assert_eq!(reminders.skipped_version.as_deref(), Some("v9.9.9"));
+    assert!(warning_suppressed(&reminders, "v9.9.10", 0));
+
+    let path = config::global_config_path().expect("config path");
+    let content = std::fs::read_to_string(path).expect("read config");
+    assert!(!content.contains("skipped_version"));
+    assert!(!content.contains("snoozed"));
+    let issues = auto_commit_rs::validate::validate_all();
+    assert!(
+        issues.iter().all(|i| !i.key.contains("snoozed")),
+        "{issues:?}"
+    );
+}
@@ -0,0 +1,146 @@
+use auto_commit_rs::config::AppConfig;
+use auto_commit_rs::validate::{
+    validate_combinations, validate_env_vars, validate_toml, validate_value,
+};
+
+fn origin(suffix: &str) -> (String, String) {
+    ("test".to_string(), format!("ACR_{suffix}"))
+}
+
+#[test]
+fn values_are_checked_per_setting() {
+    assert!(validate_value("ONE_LINER", "true").is_ok());
+    assert!(validate_value("ONE_LINER", "yes").is_err());
+    assert!(validate_value("ENCRYPT_SECRETS", "1").is_ok());
+    assert!(validate_value("ENCRYPT_SECRETS", "maybe").is_err());
+    assert!(validate_value("GITMOJI_FORMAT", "shortcode").is_ok());
+    assert!(validate_value("GITMOJI_FORMAT", "ascii").is_err());
+    assert!(validate_value("SUBJECT_MAX_LENGTH", "-1").is_err());
+    assert!(validate_value("LOCALE", "pt_BR").is_ok());
+    assert!(validate_value("API_URL", "api.example.com").is_err());
+    assert!(validate_value("API_URL", "$BASE_URL/v1").is_ok());
+    assert!(validate_value("API_HEADERS", "Authorization: Bearer $ACR_API_KEY, x-v: 1").is_ok());
+    assert!(validate_value("API_HEADERS", "Bearer token").is_err());
+    assert!(validate_value("DIFF_EXCLUDE_GLOBS", "*.lock,dist/[").is_err());
+    assert!(validate_value("SCOPE_MAP", "src/api/**=api,docs/**").is_err());
+    assert!(validate_value("TYPE_TEMPLATES", "fix=$msg;feat").is_err());
+}
+
+#[test]
+fn global_toml_reports_unknown_keys_and_wrong_types() {
+    let mut issues = Vec::new();
+    let found = validate_toml(
+        "config.toml",
+        "config_version = 1\nmodel = \"gpt-4o\"\none_liner = \"1\"\nmodle = \"x\"\nmessage_style = \"long\"\n",
+        &mut issues,
+    );
+
+    assert_eq!(
+        found,
+        vec![
+            ("MESSAGE_STYLE", "message_style".to_string()),
+            ("MODEL", "model".to_string())
+        ]
+    );
+    let keys: Vec<&str> = issues.iter().map(|i| i.key.as_str()).collect();
+    assert_eq!(keys, vec!["message_style", "modle", "one_liner"]);
+    assert!(issues
+        .iter()
+        .all(|i| i.source == "config.toml" && !i.warning));
+    assert!(issues[2]
+        .message
+        .contains("expected a boolean, found a string"));
+
+    let mut issues = Vec::new();
+    validate_toml("config.toml", "model = ", &mut issues);
+    assert!(issues[0].message.starts_with("invalid TOML"));
+}
+
+#[test]
+fn directory_profile_sections_are_checked() {
+    let mut issues = Vec::new();
+    let found = validate_toml(
+        "config.toml",
+        "config_version = 1\nmodel = \"m\"\n[profile.\"~/work/**\"]\npreset = \"Corp\"\n\
+         one_liner = \"no\"\napi_key = \"sk\"\nmodle = \"x\"\n",
+        &mut issues,
+    );
+
+    assert_eq!(found, vec![("MODEL", "model".to_string())]);
+    let keys: Vec<&str> = issues.iter().map(|i| i.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        vec![
+            "profile.\"~/work/**\".api_key",
+            "profile.\"~/work/**\".modle",
+            "profile.\"~/work/**\".one_liner"
+        ]
+    );
+}
+
+#[test]
+fn env_vars_report_the_broken_variable() {
+    let vars = vec![
+        ("ACR_PROVIDER".to_string(), "openai".to_string()),
+        ("ACR_POST_COMMIT_PUSH".to_string(), "sometimes".to_string()),
+        ("ACR_MODLE".to_string(), "gpt-4o".to_string()),
+        ("ACR_AUTO_UPDATE".to_string(), "1".to_string()),
+        ("PATH".to_string(), "/bin".to_string()),
+    ];
+    let mut issues = Vec::new();
+    let found = validate_env_vars(".env", &vars, true, &mut issues);
+
+    assert_eq!(
+        found,
+        vec![
+            ("PROVIDER", "ACR_PROVIDER".to_string()),
+            ("POST_COMMIT_PUSH", "ACR_POST_COMMIT_PUSH".to_string())
+        ]
+    );
+    let keys: Vec<(&str, bool)> = issues.iter().map(|i| (i.key.as_str(), i.warning)).collect();
+    assert_eq!(
+        keys,
+        vec![
+            ("ACR_POST_COMMIT_PUSH", false),
+            ("ACR_MODLE", false),
+            ("ACR_AUTO_UPDATE", true)
+        ]
+    );
+}
+
+#[test]
+fn every_global_only_setting_is_flagged_in_repo_layers() {
+    let vars = vec![
+        ("ACR_TEMPLATE_COMMANDS".to_string(), "1".to_string()),
+        ("ACR_UPDATE_CHANNEL".to_string(), "beta".to_string()),
+        ("ACR_ENCRYPT_SECRETS".to_string(), "1".to_string()),
+    ];
+    let mut issues = Vec::new();
+    let found = validate_env_vars(".env", &vars, true, &mut issues);
+
+    assert!(found.is_empty());
+    assert_eq!(issues.len(), 3);
+    assert!(issues.iter().all(|i| i.warning));
+}
+
+#[test]
+fn combinations_flag_custom_provider_without_url_and_mismatched_model() {
+    let mut cfg = AppConfig::default();
+    assert!(validate_combinations(&cfg, origin).is_empty());
+
+    cfg.provider = "my-llm".into();
+    let issues = validate_combinations(&cfg, origin);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].key, "ACR_PROVIDER");
+    assert!(!issues[0].warning);
+
+    cfg.provider = "openai".into();
+    cfg.model = "claude-sonnet-4-20250514".into();
+    let issues = validate_combinations(&cfg, origin);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].key, "ACR_MODEL");
+    assert!(issues[0].warning);
+
+    cfg.provider = "openrouter".into();
+    assert!(validate_combinations(&cfg, origin).is_empty());
+}